@@ -0,0 +1,688 @@
+//! 多平台账号 HTTP API
+//!
+//! `POST /api/{platform}/accounts` 批量导入 OpenAI / Cursor / Windsurf / Antigravity 账号，
+//! 复用各平台 Tauri 命令的导入逻辑（含重复检测），响应结构与 `/api/import/sessions` 一致。
+//!
+//! 管理接口（始终要求 Codex 管理员 Key）同样只是对 Tauri 命令的封装：
+//! - `GET    /api/{platform}/accounts`：账号列表与当前账号
+//! - `GET    /api/{platform}/accounts/{id}`：单个账号
+//! - `DELETE /api/{platform}/accounts/{id}`：删除账号
+//! - `POST   /api/{platform}/accounts/{id}/switch`：切换当前账号
+//! - `POST   /api/{platform}/accounts/{id}/quota`：刷新单个账号配额
+//! - `POST   /api/{platform}/quotas/refresh`：刷新全部账号配额
+//!
+//! 修改类操作会发送与界面操作相同的 `{platform}-accounts-updated` 事件，保持已打开的界面同步。
+
+use crate::core::api_error::{ApiError, ApiErrorCode};
+use crate::core::api_server::{
+    BatchImportResult, ImportResult, SimpleImportResult, default_detailed_response, mask_session,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use warp::Rejection;
+use warp::http::StatusCode;
+
+/// 单次请求最多导入的账号数
+const MAX_IMPORT_ACCOUNTS: usize = 100;
+
+/// 支持 HTTP API 的平台（路径参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountPlatform {
+    OpenAI,
+    Cursor,
+    Windsurf,
+    Antigravity,
+}
+
+impl AccountPlatform {
+    pub const ALL: [AccountPlatform; 4] = [
+        AccountPlatform::OpenAI,
+        AccountPlatform::Cursor,
+        AccountPlatform::Windsurf,
+        AccountPlatform::Antigravity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountPlatform::OpenAI => "openai",
+            AccountPlatform::Cursor => "cursor",
+            AccountPlatform::Windsurf => "windsurf",
+            AccountPlatform::Antigravity => "antigravity",
+        }
+    }
+
+    /// 前端账号列表监听的刷新事件
+    pub fn updated_event(&self) -> &'static str {
+        match self {
+            AccountPlatform::OpenAI => "openai-accounts-updated",
+            AccountPlatform::Cursor => "cursor-accounts-updated",
+            AccountPlatform::Windsurf => "windsurf-accounts-updated",
+            AccountPlatform::Antigravity => "antigravity-accounts-updated",
+        }
+    }
+}
+
+impl FromStr for AccountPlatform {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "openai" => Ok(AccountPlatform::OpenAI),
+            "cursor" => Ok(AccountPlatform::Cursor),
+            "windsurf" => Ok(AccountPlatform::Windsurf),
+            "antigravity" => Ok(AccountPlatform::Antigravity),
+            other => Err(format!("Unsupported platform: {}", other)),
+        }
+    }
+}
+
+/// OpenAI 导入凭据类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAITokenType {
+    /// access token 或 session JSON（`openai_add_account_with_access_token`）
+    #[default]
+    AccessToken,
+    /// refresh token（`openai_add_account`）
+    RefreshToken,
+}
+
+/// 批量账号导入请求
+#[derive(Debug, Deserialize)]
+pub struct AccountImportRequest {
+    /// OpenAI access/refresh token、Cursor session token、Windsurf Devin auth1 token
+    /// 或 Antigravity refresh token
+    pub tokens: Vec<String>,
+    /// 仅 OpenAI 使用
+    #[serde(default)]
+    pub token_type: OpenAITokenType,
+    /// 仅 Windsurf 使用：Devin 组织 ID
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default = "default_detailed_response")]
+    pub detailed_response: bool,
+}
+
+fn to_json<T: Serialize>(account: T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(account).map_err(|e| format!("Failed to serialize account: {}", e))
+}
+
+/// 调用对应平台的导入逻辑，返回保存后的账号
+async fn import_account(
+    platform: AccountPlatform,
+    app: &AppHandle,
+    token: String,
+    token_type: OpenAITokenType,
+    org_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    match platform {
+        AccountPlatform::OpenAI => match token_type {
+            OpenAITokenType::AccessToken => to_json(
+                crate::openai::openai_add_account_with_access_token(app.clone(), token).await?,
+            ),
+            OpenAITokenType::RefreshToken => {
+                to_json(crate::openai::openai_add_account(app.clone(), token).await?)
+            }
+        },
+        AccountPlatform::Cursor => to_json(
+            crate::cursor::commands::add_account_with_session_internal(app, token, true).await?,
+        ),
+        AccountPlatform::Windsurf => to_json(
+            crate::windsurf::windsurf_add_account_by_devin_auth1(app.clone(), token, org_id)
+                .await?,
+        ),
+        AccountPlatform::Antigravity => to_json(
+            crate::antigravity::antigravity_add_account(app.clone(), String::new(), token).await?,
+        ),
+    }
+}
+
+/// 通知前端刷新对应平台的账号列表
+pub(crate) fn emit_accounts_updated(
+    app: &AppHandle,
+    platform: AccountPlatform,
+    source: &str,
+    account_ids: &[&str],
+) {
+    let payload = json!({
+        "source": source,
+        "account_ids": account_ids,
+        "timestamp": chrono::Utc::now().timestamp()
+    });
+    if let Err(e) = app.emit(platform.updated_event(), payload) {
+        eprintln!(
+            "⚠️  Failed to emit {} event: {}",
+            platform.updated_event(),
+            e
+        );
+    }
+}
+
+/// 去除首尾空白，并标记请求内重复的凭据（保留首次出现）
+pub(crate) fn dedupe_tokens(tokens: &[String]) -> Vec<(String, bool)> {
+    let mut seen = HashSet::new();
+    tokens
+        .iter()
+        .map(|token| {
+            let token = token.trim().to_string();
+            let duplicate = !token.is_empty() && !seen.insert(token.clone());
+            (token, duplicate)
+        })
+        .collect()
+}
+
+fn failed_result(error: String, token: &str) -> ImportResult {
+    ImportResult {
+        success: false,
+        token_data: None,
+        account: None,
+        error: Some(error),
+        session_preview: Some(mask_session(token)),
+    }
+}
+
+/// 导入单个凭据（`duplicate` 表示请求内已出现过），供批量导入与导入任务复用
+pub(crate) async fn import_account_item(
+    platform: AccountPlatform,
+    app: &AppHandle,
+    token: String,
+    duplicate: bool,
+    token_type: OpenAITokenType,
+    org_id: Option<String>,
+) -> ImportResult {
+    if token.is_empty() {
+        return failed_result("Token cannot be empty".to_string(), &token);
+    }
+    if duplicate {
+        return failed_result("Duplicate token in request".to_string(), &token);
+    }
+
+    match import_account(platform, app, token.clone(), token_type, org_id).await {
+        Ok(account) => ImportResult {
+            success: true,
+            token_data: None,
+            account: Some(account),
+            error: None,
+            session_preview: Some(mask_session(&token)),
+        },
+        Err(e) => failed_result(e, &token),
+    }
+}
+
+/// 多平台批量导入处理器
+pub(crate) async fn import_accounts_handler(
+    platform: AccountPlatform,
+    request: AccountImportRequest,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    println!(
+        "📥 API: Importing {} {} accounts",
+        request.tokens.len(),
+        platform.as_str()
+    );
+
+    if request.tokens.is_empty() {
+        return Ok(ApiError::new(ApiErrorCode::EmptyArray, "Tokens array cannot be empty").reply());
+    }
+
+    if request.tokens.len() > MAX_IMPORT_ACCOUNTS {
+        return Ok(ApiError::new(
+            ApiErrorCode::TooManyTokens,
+            format!("Too many tokens (max {})", MAX_IMPORT_ACCOUNTS),
+        )
+        .reply());
+    }
+
+    // 使用 Semaphore 限制并发
+    let semaphore = Arc::new(Semaphore::new(5));
+    let mut tasks = Vec::new();
+
+    for (token, duplicate) in dedupe_tokens(&request.tokens) {
+        let app = state.app_handle.clone();
+        let semaphore = semaphore.clone();
+        let token_type = request.token_type;
+        let org_id = request.org_id.clone();
+
+        let task = tokio::spawn(async move {
+            let _permit = semaphore.acquire().await.unwrap();
+            import_account_item(platform, &app, token, duplicate, token_type, org_id).await
+        });
+
+        tasks.push(task);
+    }
+
+    // 等待所有任务完成
+    let mut results = Vec::new();
+    for task in tasks {
+        match task.await {
+            Ok(result) => results.push(result),
+            Err(e) => results.push(ImportResult {
+                success: false,
+                token_data: None,
+                account: None,
+                error: Some(format!("Task error: {}", e)),
+                session_preview: None,
+            }),
+        }
+    }
+
+    let successful = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - successful;
+
+    println!(
+        "✅ API: {} import completed - {}/{} successful",
+        platform.as_str(),
+        successful,
+        results.len()
+    );
+
+    // 有成功导入的账号时通知前端刷新
+    if successful > 0 {
+        let account_ids: Vec<&str> = results
+            .iter()
+            .filter_map(|r| r.account.as_ref())
+            .filter_map(|account| account.get("id").and_then(|id| id.as_str()))
+            .collect();
+        emit_accounts_updated(&state.app_handle, platform, "api-import", &account_ids);
+    }
+
+    if request.detailed_response {
+        let batch_result = BatchImportResult {
+            total: results.len(),
+            successful,
+            failed,
+            results,
+        };
+        Ok(warp::reply::with_status(
+            warp::reply::json(&batch_result),
+            StatusCode::OK,
+        ))
+    } else {
+        let result = SimpleImportResult {
+            success: true,
+            message: Some(format!(
+                "{} of {} accounts imported successfully",
+                successful,
+                results.len()
+            )),
+            error: None,
+            code: None,
+        };
+        Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        ))
+    }
+}
+
+// ==================== 账号管理 ====================
+
+/// 切换账号的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct SwitchAccountQuery {
+    /// 仅 Cursor 使用：是否使用账号绑定的机器码
+    #[serde(default)]
+    pub use_bound_machine_id: Option<bool>,
+}
+
+/// 账号 JSON 中的凭据字段（各平台 token、会话 token 与 API Key）
+const SECRET_FIELDS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "google_access_token",
+    "workos_cursor_session_token",
+    "WorkosCursorSessionToken",
+    "cursorAuth/accessToken",
+    "cursorAuth/refreshToken",
+    "api_key",
+    "key",
+];
+
+/// 递归移除凭据字段。管理密钥会分发给代理用户且可从局域网访问，
+/// 读取类接口只返回账号信息和配额，不返回凭据。
+fn strip_secrets(mut value: serde_json::Value) -> serde_json::Value {
+    fn strip(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.retain(|key, _| !SECRET_FIELDS.contains(&key.as_str()));
+                map.values_mut().for_each(strip);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    strip(&mut value);
+    value
+}
+
+/// 将命令结果转换为 HTTP 响应；"not found" 类错误返回 404
+fn operation_reply(
+    result: Result<serde_json::Value, String>,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(value) => warp::reply::with_status(warp::reply::json(&value), StatusCode::OK),
+        Err(e) => {
+            let code = if e.to_ascii_lowercase().contains("not found") {
+                ApiErrorCode::AccountNotFound
+            } else if e.to_ascii_lowercase().contains("not supported") {
+                ApiErrorCode::NotSupported
+            } else {
+                ApiErrorCode::OperationFailed
+            };
+            ApiError::new(code, e).reply()
+        }
+    }
+}
+
+async fn list_accounts(
+    platform: AccountPlatform,
+    app: &AppHandle,
+) -> Result<serde_json::Value, String> {
+    match platform {
+        AccountPlatform::OpenAI => {
+            let accounts = crate::openai::openai_list_accounts(app.clone()).await?;
+            let current_account_id =
+                crate::openai::openai_get_current_account_id(app.clone()).await?;
+            Ok(json!({
+                "accounts": accounts,
+                "current_account_id": current_account_id
+            }))
+        }
+        AccountPlatform::Cursor => to_json(crate::cursor::cursor_list_accounts(app.clone()).await?),
+        AccountPlatform::Windsurf => {
+            to_json(crate::windsurf::windsurf_list_accounts(app.clone()).await?)
+        }
+        AccountPlatform::Antigravity => {
+            let accounts = crate::antigravity::antigravity_list_accounts(app.clone()).await?;
+            let current_account_id =
+                crate::antigravity::modules::account::get_current_account_id(app).await?;
+            Ok(json!({
+                "accounts": accounts,
+                "current_account_id": current_account_id
+            }))
+        }
+    }
+}
+
+async fn load_account(
+    platform: AccountPlatform,
+    app: &AppHandle,
+    account_id: &str,
+) -> Result<serde_json::Value, String> {
+    match platform {
+        AccountPlatform::OpenAI => {
+            to_json(crate::openai::openai_load_account(app.clone(), account_id.to_string()).await?)
+        }
+        AccountPlatform::Cursor => {
+            to_json(crate::cursor::modules::storage::load_account(app, account_id).await?)
+        }
+        AccountPlatform::Windsurf => {
+            to_json(crate::windsurf::modules::storage::load_account(app, account_id).await?)
+        }
+        AccountPlatform::Antigravity => {
+            to_json(crate::antigravity::modules::storage::load_account(app, account_id).await?)
+        }
+    }
+}
+
+async fn delete_account(
+    platform: AccountPlatform,
+    state: &crate::AppState,
+    account_id: &str,
+) -> Result<(), String> {
+    let app = state.app_handle.clone();
+    let account_id = account_id.to_string();
+    match platform {
+        AccountPlatform::OpenAI => {
+            if !crate::openai::openai_delete_account(app.clone(), account_id.clone()).await? {
+                return Err(format!("Account not found: {}", account_id));
+            }
+            // 同步移出 Codex 号池
+            let pool = state.codex_pool.lock().unwrap().clone();
+            if let Some(pool) = pool {
+                pool.remove_account(&account_id).await;
+            }
+            Ok(())
+        }
+        AccountPlatform::Cursor => crate::cursor::cursor_delete_account(app, account_id).await,
+        AccountPlatform::Windsurf => {
+            crate::windsurf::windsurf_delete_account(app, account_id).await
+        }
+        AccountPlatform::Antigravity => {
+            crate::antigravity::antigravity_delete_account(app, account_id).await
+        }
+    }
+}
+
+async fn switch_account(
+    platform: AccountPlatform,
+    app: &AppHandle,
+    account_id: &str,
+    query: SwitchAccountQuery,
+) -> Result<String, String> {
+    let app = app.clone();
+    let account_id = account_id.to_string();
+    match platform {
+        AccountPlatform::OpenAI => crate::openai::openai_switch_account(app, account_id)
+            .await
+            .map(|_| "Account switched successfully".to_string()),
+        AccountPlatform::Cursor => {
+            crate::cursor::cursor_switch_account(app, account_id, query.use_bound_machine_id)
+                .await
+                .map(|response| response.message)
+        }
+        AccountPlatform::Windsurf => crate::windsurf::windsurf_switch_account(app, account_id)
+            .await
+            .map(|response| response.message),
+        AccountPlatform::Antigravity => {
+            crate::antigravity::antigravity_switch_account(app, account_id).await
+        }
+    }
+}
+
+async fn refresh_account_quota(
+    platform: AccountPlatform,
+    app: &AppHandle,
+    account_id: &str,
+) -> Result<serde_json::Value, String> {
+    let app = app.clone();
+    let account_id = account_id.to_string();
+    match platform {
+        AccountPlatform::OpenAI => {
+            to_json(crate::openai::openai_fetch_quota(app, account_id).await?)
+        }
+        AccountPlatform::Windsurf => {
+            to_json(crate::windsurf::windsurf_fetch_quota(app, account_id).await?)
+        }
+        AccountPlatform::Antigravity => {
+            to_json(crate::antigravity::antigravity_fetch_quota(app, account_id).await?)
+        }
+        AccountPlatform::Cursor => Err("Quota refresh is not supported for cursor".to_string()),
+    }
+}
+
+async fn refresh_all_quotas(
+    platform: AccountPlatform,
+    app: &AppHandle,
+) -> Result<serde_json::Value, String> {
+    let app = app.clone();
+    match platform {
+        AccountPlatform::OpenAI => to_json(crate::openai::openai_refresh_all_quotas(app).await?),
+        AccountPlatform::Windsurf => {
+            let accounts = crate::windsurf::windsurf_fetch_all_quotas(app).await?;
+            Ok(json!({ "total": accounts.len(), "accounts": accounts }))
+        }
+        AccountPlatform::Antigravity => {
+            to_json(crate::antigravity::antigravity_refresh_all_quotas(app).await?)
+        }
+        AccountPlatform::Cursor => Err("Quota refresh is not supported for cursor".to_string()),
+    }
+}
+
+/// GET /api/{platform}/accounts
+pub(crate) async fn list_accounts_handler(
+    platform: AccountPlatform,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    Ok(operation_reply(
+        list_accounts(platform, &state.app_handle)
+            .await
+            .map(strip_secrets),
+    ))
+}
+
+/// GET /api/{platform}/accounts/{id}
+pub(crate) async fn get_account_handler(
+    platform: AccountPlatform,
+    account_id: String,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    Ok(operation_reply(
+        load_account(platform, &state.app_handle, &account_id)
+            .await
+            .map(strip_secrets),
+    ))
+}
+
+/// DELETE /api/{platform}/accounts/{id}
+pub(crate) async fn delete_account_handler(
+    platform: AccountPlatform,
+    account_id: String,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    println!(
+        "🗑️  API: Deleting {} account {}",
+        platform.as_str(),
+        account_id
+    );
+    let result = delete_account(platform, &state, &account_id).await;
+    if result.is_ok() {
+        emit_accounts_updated(&state.app_handle, platform, "api-delete", &[&account_id]);
+    }
+    Ok(operation_reply(result.map(
+        |_| json!({ "success": true, "account_id": account_id }),
+    )))
+}
+
+/// POST /api/{platform}/accounts/{id}/switch
+pub(crate) async fn switch_account_handler(
+    platform: AccountPlatform,
+    account_id: String,
+    query: SwitchAccountQuery,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    println!(
+        "🔄 API: Switching {} account to {}",
+        platform.as_str(),
+        account_id
+    );
+    let result = switch_account(platform, &state.app_handle, &account_id, query).await;
+    if result.is_ok() {
+        emit_accounts_updated(&state.app_handle, platform, "api-switch", &[&account_id]);
+    }
+    Ok(operation_reply(result.map(
+        |message| json!({ "success": true, "account_id": account_id, "message": message }),
+    )))
+}
+
+/// POST /api/{platform}/accounts/{id}/quota
+pub(crate) async fn refresh_account_quota_handler(
+    platform: AccountPlatform,
+    account_id: String,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    let result = refresh_account_quota(platform, &state.app_handle, &account_id).await;
+    if result.is_ok() {
+        emit_accounts_updated(&state.app_handle, platform, "api-quota", &[&account_id]);
+    }
+    Ok(operation_reply(result.map(strip_secrets)))
+}
+
+/// POST /api/{platform}/quotas/refresh
+pub(crate) async fn refresh_all_quotas_handler(
+    platform: AccountPlatform,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    println!("🔄 API: Refreshing all {} quotas", platform.as_str());
+    let result = refresh_all_quotas(platform, &state.app_handle).await;
+    if result.is_ok() {
+        emit_accounts_updated(&state.app_handle, platform, "api-quota", &[]);
+    }
+    Ok(operation_reply(result.map(strip_secrets)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AccountImportRequest, AccountPlatform, OpenAITokenType, dedupe_tokens, strip_secrets,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn platform_path_and_request_parse() {
+        assert_eq!(
+            "OpenAI".parse::<AccountPlatform>(),
+            Ok(AccountPlatform::OpenAI)
+        );
+        assert_eq!(
+            "windsurf"
+                .parse::<AccountPlatform>()
+                .unwrap()
+                .updated_event(),
+            "windsurf-accounts-updated"
+        );
+        assert!("augment".parse::<AccountPlatform>().is_err());
+
+        let request: AccountImportRequest =
+            serde_json::from_str(r#"{"tokens":["rt-1"],"token_type":"refresh_token"}"#).unwrap();
+        assert_eq!(request.token_type, OpenAITokenType::RefreshToken);
+        assert!(request.detailed_response);
+
+        let deduped = dedupe_tokens(&[
+            " tok-a ".to_string(),
+            "tok-b".to_string(),
+            "tok-a".to_string(),
+            "".to_string(),
+        ]);
+        let flags: Vec<bool> = deduped.iter().map(|(_, duplicate)| *duplicate).collect();
+        assert_eq!(flags, vec![false, false, true, false]);
+        assert_eq!(deduped[0].0, "tok-a");
+    }
+
+    #[test]
+    fn read_responses_omit_credentials() {
+        let listed = strip_secrets(json!({
+            "accounts": [{
+                "id": "acc-1",
+                "email": "a@example.com",
+                "key": "sk-api",
+                "token": {
+                    "access_token": "at",
+                    "refresh_token": "rt",
+                    "id_token": "it",
+                    "expires_at": 100
+                },
+                "machine_info": {"cursorAuth/accessToken": "at"}
+            }],
+            "current_account_id": "acc-1"
+        }));
+        assert_eq!(
+            listed,
+            json!({
+                "accounts": [{
+                    "id": "acc-1",
+                    "email": "a@example.com",
+                    "token": {"expires_at": 100},
+                    "machine_info": {}
+                }],
+                "current_account_id": "acc-1"
+            })
+        );
+    }
+}
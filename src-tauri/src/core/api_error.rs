@@ -0,0 +1,282 @@
+//! 本地 HTTP API 的统一错误码
+//!
+//! 所有路由的错误响应都由 [`ApiErrorCode`] 生成：
+//! - `/api/*` 与健康检查返回 `{"error": "...", "code": "..."}`（[`ApiErrorResponse`]）
+//! - Codex 路由（`/v1/*`、`/backend-api/codex/*`、`/pool/status`、`/metrics`）保持 OpenAI 兼容的
+//!   `{"error": {"message", "type", "code"}}`，其中 code 与前者取值相同
+//!
+//! OpenAPI 文档中的错误码枚举与各状态码说明也由此生成，避免文档与实现不一致。
+
+use crate::core::api_server::ApiErrorResponse;
+use serde_json::json;
+use warp::http::StatusCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiErrorCode {
+    // 导入
+    InvalidSession,
+    DuplicateEmail,
+    ImportError,
+    StorageError,
+    EmptyArray,
+    TooManySessions,
+    TooManyTokens,
+    TooManyItems,
+    // 账号管理
+    AccountNotFound,
+    NotSupported,
+    OperationFailed,
+    // 导入任务
+    JobNotFound,
+    JobFinished,
+    // 通用
+    InvalidJson,
+    PayloadTooLarge,
+    MethodNotAllowed,
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    InternalError,
+    // Codex 转发
+    InvalidRequest,
+    TranslationError,
+    UpstreamTimeout,
+    ExecutionError,
+    RateLimited,
+    PoolCoolingDown,
+    NoAvailableAccount,
+    ServiceUnavailable,
+}
+
+impl ApiErrorCode {
+    pub const ALL: &'static [ApiErrorCode] = &[
+        ApiErrorCode::InvalidSession,
+        ApiErrorCode::DuplicateEmail,
+        ApiErrorCode::ImportError,
+        ApiErrorCode::StorageError,
+        ApiErrorCode::EmptyArray,
+        ApiErrorCode::TooManySessions,
+        ApiErrorCode::TooManyTokens,
+        ApiErrorCode::TooManyItems,
+        ApiErrorCode::AccountNotFound,
+        ApiErrorCode::NotSupported,
+        ApiErrorCode::OperationFailed,
+        ApiErrorCode::JobNotFound,
+        ApiErrorCode::JobFinished,
+        ApiErrorCode::InvalidJson,
+        ApiErrorCode::PayloadTooLarge,
+        ApiErrorCode::MethodNotAllowed,
+        ApiErrorCode::NotFound,
+        ApiErrorCode::Unauthorized,
+        ApiErrorCode::Forbidden,
+        ApiErrorCode::InternalError,
+        ApiErrorCode::InvalidRequest,
+        ApiErrorCode::TranslationError,
+        ApiErrorCode::UpstreamTimeout,
+        ApiErrorCode::ExecutionError,
+        ApiErrorCode::RateLimited,
+        ApiErrorCode::PoolCoolingDown,
+        ApiErrorCode::NoAvailableAccount,
+        ApiErrorCode::ServiceUnavailable,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiErrorCode::InvalidSession => "INVALID_SESSION",
+            ApiErrorCode::DuplicateEmail => "DUPLICATE_EMAIL",
+            ApiErrorCode::ImportError => "IMPORT_ERROR",
+            ApiErrorCode::StorageError => "STORAGE_ERROR",
+            ApiErrorCode::EmptyArray => "EMPTY_ARRAY",
+            ApiErrorCode::TooManySessions => "TOO_MANY_SESSIONS",
+            ApiErrorCode::TooManyTokens => "TOO_MANY_TOKENS",
+            ApiErrorCode::TooManyItems => "TOO_MANY_ITEMS",
+            ApiErrorCode::AccountNotFound => "ACCOUNT_NOT_FOUND",
+            ApiErrorCode::NotSupported => "NOT_SUPPORTED",
+            ApiErrorCode::OperationFailed => "OPERATION_FAILED",
+            ApiErrorCode::JobNotFound => "JOB_NOT_FOUND",
+            ApiErrorCode::JobFinished => "JOB_FINISHED",
+            ApiErrorCode::InvalidJson => "INVALID_JSON",
+            ApiErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ApiErrorCode::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+            ApiErrorCode::NotFound => "NOT_FOUND",
+            ApiErrorCode::Unauthorized => "UNAUTHORIZED",
+            ApiErrorCode::Forbidden => "FORBIDDEN",
+            ApiErrorCode::InternalError => "INTERNAL_ERROR",
+            ApiErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ApiErrorCode::TranslationError => "TRANSLATION_ERROR",
+            ApiErrorCode::UpstreamTimeout => "UPSTREAM_TIMEOUT",
+            ApiErrorCode::ExecutionError => "EXECUTION_ERROR",
+            ApiErrorCode::RateLimited => "RATE_LIMITED",
+            ApiErrorCode::PoolCoolingDown => "POOL_COOLING_DOWN",
+            ApiErrorCode::NoAvailableAccount => "NO_AVAILABLE_ACCOUNT",
+            ApiErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiErrorCode::InvalidSession
+            | ApiErrorCode::EmptyArray
+            | ApiErrorCode::TooManySessions
+            | ApiErrorCode::TooManyTokens
+            | ApiErrorCode::TooManyItems
+            | ApiErrorCode::NotSupported
+            | ApiErrorCode::InvalidJson
+            | ApiErrorCode::InvalidRequest
+            | ApiErrorCode::TranslationError => StatusCode::BAD_REQUEST,
+            ApiErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ApiErrorCode::AccountNotFound | ApiErrorCode::JobNotFound | ApiErrorCode::NotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ApiErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ApiErrorCode::DuplicateEmail | ApiErrorCode::JobFinished => StatusCode::CONFLICT,
+            ApiErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiErrorCode::ImportError => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiErrorCode::RateLimited | ApiErrorCode::PoolCoolingDown => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            ApiErrorCode::StorageError
+            | ApiErrorCode::OperationFailed
+            | ApiErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiErrorCode::ExecutionError => StatusCode::BAD_GATEWAY,
+            ApiErrorCode::NoAvailableAccount | ApiErrorCode::ServiceUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ApiErrorCode::UpstreamTimeout => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// OpenAI 兼容错误体中的 type 字段
+    pub fn openai_type(&self) -> &'static str {
+        match self {
+            ApiErrorCode::TranslationError => "translation_error",
+            ApiErrorCode::Unauthorized => "unauthorized",
+            ApiErrorCode::Forbidden => "permission_error",
+            ApiErrorCode::RateLimited | ApiErrorCode::PoolCoolingDown => "rate_limit_error",
+            ApiErrorCode::UpstreamTimeout => "upstream_timeout",
+            ApiErrorCode::ExecutionError => "execution_error",
+            ApiErrorCode::NoAvailableAccount => "no_available_account",
+            ApiErrorCode::ServiceUnavailable => "service_unavailable",
+            code if code.status().is_server_error() => "internal_error",
+            _ => "invalid_request_error",
+        }
+    }
+
+    /// OpenAPI 文档中的说明
+    pub fn description(&self) -> &'static str {
+        match self {
+            ApiErrorCode::InvalidSession => "Session string is empty or malformed",
+            ApiErrorCode::DuplicateEmail => "An account with the same email already exists",
+            ApiErrorCode::ImportError => "The platform rejected the credential",
+            ApiErrorCode::StorageError => "The imported account could not be saved",
+            ApiErrorCode::EmptyArray => "The import list is empty",
+            ApiErrorCode::TooManySessions => "Too many sessions in one request",
+            ApiErrorCode::TooManyTokens => "Too many tokens in one request",
+            ApiErrorCode::TooManyItems => "Too many items in one import job",
+            ApiErrorCode::AccountNotFound => "No account with the given id",
+            ApiErrorCode::NotSupported => "The operation is not supported for this platform",
+            ApiErrorCode::OperationFailed => "The underlying account operation failed",
+            ApiErrorCode::JobNotFound => "No import job with the given id",
+            ApiErrorCode::JobFinished => "The import job has already finished",
+            ApiErrorCode::InvalidJson => "Request body is not valid JSON for this endpoint",
+            ApiErrorCode::PayloadTooLarge => "Request body exceeds the size limit",
+            ApiErrorCode::MethodNotAllowed => "HTTP method not allowed on this path",
+            ApiErrorCode::NotFound => "Endpoint not found",
+            ApiErrorCode::Unauthorized => "Missing, invalid, disabled or expired API key",
+            ApiErrorCode::Forbidden => "Client address or API key is not permitted",
+            ApiErrorCode::InternalError => "Unexpected server error",
+            ApiErrorCode::InvalidRequest => "Invalid request for the Codex upstream",
+            ApiErrorCode::TranslationError => "Request could not be translated to Responses",
+            ApiErrorCode::UpstreamTimeout => "Codex upstream timed out",
+            ApiErrorCode::ExecutionError => "Codex upstream request failed",
+            ApiErrorCode::RateLimited => "Client key rate limit or budget exceeded",
+            ApiErrorCode::PoolCoolingDown => "Every pool account is cooling down",
+            ApiErrorCode::NoAvailableAccount => "No available account in the Codex pool",
+            ApiErrorCode::ServiceUnavailable => "Codex server is disabled or not ready",
+        }
+    }
+}
+
+/// 带错误码的 API 错误，也可作为 warp 拒绝在过滤器间传递
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: ApiErrorCode,
+    pub message: String,
+}
+
+impl warp::reject::Reject for ApiError {}
+
+impl ApiError {
+    pub fn new(code: ApiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    pub fn body(&self) -> ApiErrorResponse {
+        ApiErrorResponse {
+            error: self.message.clone(),
+            code: self.code.as_str().to_string(),
+        }
+    }
+
+    /// `{error, code}` 响应
+    pub fn reply(&self) -> warp::reply::WithStatus<warp::reply::Json> {
+        warp::reply::with_status(warp::reply::json(&self.body()), self.status())
+    }
+
+    /// OpenAI 兼容错误体
+    pub fn openai_body(&self) -> serde_json::Value {
+        json!({
+            "error": {
+                "message": self.message,
+                "type": self.code.openai_type(),
+                "code": self.code.as_str()
+            }
+        })
+    }
+
+    /// OpenAI 兼容响应（Codex 路由）
+    pub fn openai_reply(&self) -> warp::reply::WithStatus<warp::reply::Json> {
+        warp::reply::with_status(warp::reply::json(&self.openai_body()), self.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiError, ApiErrorCode};
+    use std::collections::HashSet;
+    use warp::http::StatusCode;
+
+    #[test]
+    fn error_codes_are_unique_and_map_to_both_envelopes() {
+        let codes: HashSet<&str> = ApiErrorCode::ALL.iter().map(|c| c.as_str()).collect();
+        assert_eq!(codes.len(), ApiErrorCode::ALL.len());
+        assert!(
+            ApiErrorCode::ALL
+                .iter()
+                .all(|c| c.status().is_client_error() || c.status().is_server_error())
+        );
+
+        let error = ApiError::new(ApiErrorCode::PoolCoolingDown, "cooling");
+        assert_eq!(error.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error.body().code, "POOL_COOLING_DOWN");
+        let openai = error.openai_body();
+        assert_eq!(openai["error"]["type"], "rate_limit_error");
+        assert_eq!(openai["error"]["code"], "POOL_COOLING_DOWN");
+        assert_eq!(
+            ApiErrorCode::OperationFailed.openai_type(),
+            "internal_error"
+        );
+        assert_eq!(
+            ApiErrorCode::NotFound.openai_type(),
+            "invalid_request_error"
+        );
+    }
+}
@@ -1,13 +1,20 @@
 use crate::AppState;
+use crate::core::api_accounts::{self, AccountPlatform};
+use crate::core::api_error::{ApiError, ApiErrorCode};
+use crate::core::api_server_config::{AllowList, load_api_server_config_internal};
+use crate::core::import_jobs;
+use crate::core::openapi;
 use crate::features::mail::outlook::OutlookManager;
 use crate::storage::{TokenData, TokenStorage};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use tauri::Emitter;
 use tauri::State;
 use tokio::sync::{Semaphore, oneshot};
 use uuid::Uuid;
+use warp::filters::BoxedFilter;
+use warp::http::HeaderMap;
 use warp::{Filter, Rejection, Reply};
 
 // ==================== 数据结构定义 ====================
@@ -29,29 +36,32 @@ pub struct ImportSessionsRequest {
 }
 
 /// 默认返回详细响应
-fn default_detailed_response() -> bool {
+pub(crate) fn default_detailed_response() -> bool {
     true
 }
 
 /// 单个导入结果
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportResult {
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_data: Option<TokenData>,
+    /// 非 Augment 平台导入的账号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_preview: Option<String>,
 }
 
-/// 批量导入结果
+/// 批量导入结果（导入任务的最终报告使用不含账号内容的条目）
 #[derive(Debug, Serialize)]
-pub struct BatchImportResult {
+pub struct BatchImportResult<T = ImportResult> {
     pub total: usize,
     pub successful: usize,
     pub failed: usize,
-    pub results: Vec<ImportResult>,
+    pub results: Vec<T>,
 }
 
 /// 健康检查响应
@@ -83,11 +93,10 @@ pub async fn get_api_server_status(state: State<'_, AppState>) -> Result<ApiServ
     let server_guard = state.api_server.lock().unwrap();
 
     if let Some(server) = server_guard.as_ref() {
-        let port = server.get_port();
         Ok(ApiServerStatus {
             running: true,
-            port: Some(port),
-            address: Some(format!("http://127.0.0.1:{}", port)),
+            port: Some(server.get_port()),
+            address: Some(server.get_address().to_string()),
         })
     } else {
         Ok(ApiServerStatus {
@@ -137,6 +146,9 @@ pub async fn start_api_server_cmd(
             codex_logger: state.codex_logger.clone(),
             codex_server: state.codex_server.clone(),
             codex_unsupported_params: state.codex_unsupported_params.clone(),
+            codex_client_keys: state.codex_client_keys.clone(),
+            codex_model_prices: state.codex_model_prices.clone(),
+            codex_model_catalog: state.codex_model_catalog.clone(),
             codex_server_config: state.codex_server_config.clone(),
             codex_log_storage: state.codex_log_storage.clone(),
             proxy_config: state.proxy_config.clone(),
@@ -204,6 +216,7 @@ pub struct SimpleImportResult {
 pub struct ApiServer {
     shutdown_tx: Option<oneshot::Sender<()>>,
     port: u16,
+    address: String,
 }
 
 impl ApiServer {
@@ -212,6 +225,7 @@ impl ApiServer {
         ApiServer {
             port,
             shutdown_tx: None,
+            address: format!("http://127.0.0.1:{}", port),
         }
     }
 
@@ -219,6 +233,11 @@ impl ApiServer {
         self.port
     }
 
+    /// 本机访问地址（含协议）
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
     pub fn shutdown(&mut self) {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
@@ -236,7 +255,7 @@ impl Drop for ApiServer {
 // ==================== 辅助函数 ====================
 
 /// 脱敏 session 字符串（只显示前4位和后1位）
-fn mask_session(session: &str) -> String {
+pub(crate) fn mask_session(session: &str) -> String {
     if session.len() <= 5 {
         return "***".to_string();
     }
@@ -278,14 +297,7 @@ async fn import_session_handler(
 
     // 验证 session
     if let Err(e) = validate_session(&request.session) {
-        let error_response = ApiErrorResponse {
-            error: e,
-            code: "INVALID_SESSION".to_string(),
-        };
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+        return Ok(ApiError::new(ApiErrorCode::InvalidSession, e).reply());
     }
 
     // 调用内部函数导入
@@ -318,17 +330,11 @@ async fn import_session_handler(
                                 }
                             }) {
                                 println!("⚠️  API: Duplicate email detected: {}", email_note);
-                                let error_response = ApiErrorResponse {
-                                    error: format!(
-                                        "Token with email '{}' already exists",
-                                        email_note
-                                    ),
-                                    code: "DUPLICATE_EMAIL".to_string(),
-                                };
-                                return Ok(warp::reply::with_status(
-                                    warp::reply::json(&error_response),
-                                    warp::http::StatusCode::CONFLICT,
-                                ));
+                                return Ok(ApiError::new(
+                                    ApiErrorCode::DuplicateEmail,
+                                    format!("Token with email '{}' already exists", email_note),
+                                )
+                                .reply());
                             }
                         }
                         Err(e) => {
@@ -397,6 +403,7 @@ async fn import_session_handler(
                         let result = ImportResult {
                             success: true,
                             token_data: Some(token_data),
+                            account: None,
                             error: None,
                             session_preview: Some(mask_session(&request.session)),
                         };
@@ -420,28 +427,155 @@ async fn import_session_handler(
                 }
                 Err(e) => {
                     eprintln!("❌ API: Failed to save token: {}", e);
-                    let error_response = ApiErrorResponse {
-                        error: format!("Failed to save token: {}", e),
-                        code: "STORAGE_ERROR".to_string(),
-                    };
-                    Ok(warp::reply::with_status(
-                        warp::reply::json(&error_response),
-                        warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    ))
+                    Ok(ApiError::new(
+                        ApiErrorCode::StorageError,
+                        format!("Failed to save token: {}", e),
+                    )
+                    .reply())
                 }
             }
         }
         Err(e) => {
             eprintln!("❌ API: Failed to import session: {}", e);
-            let error_response = ApiErrorResponse {
-                error: e,
-                code: "IMPORT_ERROR".to_string(),
+            Ok(ApiError::new(ApiErrorCode::ImportError, e).reply())
+        }
+    }
+}
+
+/// 导入单个 session 并保存（含格式校验与 email 重复检测），供批量导入与导入任务复用
+pub(crate) async fn import_session_item(
+    session: String,
+    state: Arc<crate::AppState>,
+) -> ImportResult {
+    // 验证 session
+    if let Err(e) = validate_session(&session) {
+        return ImportResult {
+            success: false,
+            token_data: None,
+            account: None,
+            error: Some(e),
+            session_preview: Some(mask_session(&session)),
+        };
+    }
+
+    // 导入 session
+    match crate::platforms::augment::oauth::add_token_from_session_internal_with_cache(
+        &session, &state,
+    )
+    .await
+    {
+        Ok(response) => {
+            // 检查重复 email（与前端逻辑保持一致）
+            if let Some(ref email_note) = response.email {
+                let email_to_check = email_note.trim().to_lowercase();
+
+                // 从 storage_manager 加载现有 tokens
+                let storage_manager = {
+                    let guard = state.storage_manager.lock().unwrap();
+                    guard.clone()
+                };
+
+                if let Some(storage) = storage_manager {
+                    match storage.load_tokens().await {
+                        Ok(existing_tokens) => {
+                            // 检查是否存在相同的 email
+                            if existing_tokens.iter().any(|token| {
+                                if let Some(ref existing_email) = token.email_note {
+                                    existing_email.trim().to_lowercase() == email_to_check
+                                } else {
+                                    false
+                                }
+                            }) {
+                                println!(
+                                    "⚠️  API: Duplicate email detected in batch: {}",
+                                    email_note
+                                );
+                                return ImportResult {
+                                    success: false,
+                                    token_data: None,
+                                    account: None,
+                                    error: Some(format!(
+                                        "Token with email '{}' already exists",
+                                        email_note
+                                    )),
+                                    session_preview: Some(mask_session(&session)),
+                                };
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "⚠️  API: Failed to load existing tokens for duplicate check: {}",
+                                e
+                            );
+                            // 继续导入，不因为加载失败而阻止导入
+                        }
+                    }
+                }
+            }
+
+            // 使用 UUID 生成唯一 ID（与前端逻辑保持一致）
+            let id = Uuid::new_v4().to_string();
+
+            let now = chrono::Utc::now();
+            let token_data = TokenData {
+                id,
+                tenant_url: response.tenant_url.clone(),
+                access_token: response.access_token.clone(),
+                created_at: now,
+                updated_at: now,
+                portal_url: None, // Session 导入不再获取 portal_url
+                email_note: response.email.clone(),
+                tag_name: None,
+                tag_color: None,
+                ban_status: Some(serde_json::Value::String("ACTIVE".to_string())), // Session 导入默认设置为 ACTIVE
+                portal_info: None, // Session 导入不再获取 portal_info
+                auth_session: Some(session.clone()),
+                suspensions: None, // Session 导入不再获取 suspensions
+                balance_color_mode: None,
+                skip_check: Some(false), // 与前端保持一致，默认不跳过检测
+                session_updated_at: Some(now), // 设置 session 初始更新时间
+                version: 0,              // 本地创建时版本号为0，由数据库分配
             };
-            Ok(warp::reply::with_status(
-                warp::reply::json(&error_response),
-                warp::http::StatusCode::UNPROCESSABLE_ENTITY,
-            ))
+
+            // 保存到存储
+            let storage = {
+                let storage_guard = state.storage_manager.lock().unwrap();
+                storage_guard.as_ref().cloned()
+            };
+
+            let storage_result = if let Some(storage) = storage {
+                storage
+                    .save_token(&token_data)
+                    .await
+                    .map_err(|e| e.to_string())
+            } else {
+                Err("Storage manager not initialized".to_string())
+            };
+
+            match storage_result {
+                Ok(_) => ImportResult {
+                    success: true,
+                    token_data: Some(token_data),
+                    account: None,
+                    error: None,
+                    session_preview: Some(mask_session(&session)),
+                },
+                Err(e) => ImportResult {
+                    success: false,
+                    token_data: None,
+                    account: None,
+                    error: Some(format!("Storage error: {}", e)),
+                    session_preview: Some(mask_session(&session)),
+                },
+            }
         }
+        Err(e) => ImportResult {
+            success: false,
+            token_data: None,
+            account: None,
+            error: Some(e),
+            session_preview: Some(mask_session(&session)),
+        },
     }
 }
 
@@ -454,25 +588,15 @@ async fn import_sessions_handler(
 
     // 验证请求
     if request.sessions.is_empty() {
-        let error_response = ApiErrorResponse {
-            error: "Sessions array cannot be empty".to_string(),
-            code: "EMPTY_ARRAY".to_string(),
-        };
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+        return Ok(
+            ApiError::new(ApiErrorCode::EmptyArray, "Sessions array cannot be empty").reply(),
+        );
     }
 
     if request.sessions.len() > 100 {
-        let error_response = ApiErrorResponse {
-            error: "Too many sessions (max 100)".to_string(),
-            code: "TOO_MANY_SESSIONS".to_string(),
-        };
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+        return Ok(
+            ApiError::new(ApiErrorCode::TooManySessions, "Too many sessions (max 100)").reply(),
+        );
     }
 
     // 使用 Semaphore 限制并发
@@ -486,132 +610,7 @@ async fn import_sessions_handler(
 
         let task = tokio::spawn(async move {
             let _permit = semaphore.acquire().await.unwrap();
-
-            // 验证 session
-            if let Err(e) = validate_session(&session) {
-                return ImportResult {
-                    success: false,
-                    token_data: None,
-                    error: Some(e),
-                    session_preview: Some(mask_session(&session)),
-                };
-            }
-
-            // 导入 session
-            match crate::platforms::augment::oauth::add_token_from_session_internal_with_cache(
-                &session, &state,
-            )
-            .await
-            {
-                Ok(response) => {
-                    // 检查重复 email（与前端逻辑保持一致）
-                    if let Some(ref email_note) = response.email {
-                        let email_to_check = email_note.trim().to_lowercase();
-
-                        // 从 storage_manager 加载现有 tokens
-                        let storage_manager = {
-                            let guard = state.storage_manager.lock().unwrap();
-                            guard.clone()
-                        };
-
-                        if let Some(storage) = storage_manager {
-                            match storage.load_tokens().await {
-                                Ok(existing_tokens) => {
-                                    // 检查是否存在相同的 email
-                                    if existing_tokens.iter().any(|token| {
-                                        if let Some(ref existing_email) = token.email_note {
-                                            existing_email.trim().to_lowercase() == email_to_check
-                                        } else {
-                                            false
-                                        }
-                                    }) {
-                                        println!(
-                                            "⚠️  API: Duplicate email detected in batch: {}",
-                                            email_note
-                                        );
-                                        return ImportResult {
-                                            success: false,
-                                            token_data: None,
-                                            error: Some(format!(
-                                                "Token with email '{}' already exists",
-                                                email_note
-                                            )),
-                                            session_preview: Some(mask_session(&session)),
-                                        };
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "⚠️  API: Failed to load existing tokens for duplicate check: {}",
-                                        e
-                                    );
-                                    // 继续导入，不因为加载失败而阻止导入
-                                }
-                            }
-                        }
-                    }
-
-                    // 使用 UUID 生成唯一 ID（与前端逻辑保持一致）
-                    let id = Uuid::new_v4().to_string();
-
-                    let now = chrono::Utc::now();
-                    let token_data = TokenData {
-                        id,
-                        tenant_url: response.tenant_url.clone(),
-                        access_token: response.access_token.clone(),
-                        created_at: now,
-                        updated_at: now,
-                        portal_url: None, // Session 导入不再获取 portal_url
-                        email_note: response.email.clone(),
-                        tag_name: None,
-                        tag_color: None,
-                        ban_status: Some(serde_json::Value::String("ACTIVE".to_string())), // Session 导入默认设置为 ACTIVE
-                        portal_info: None, // Session 导入不再获取 portal_info
-                        auth_session: Some(session.clone()),
-                        suspensions: None, // Session 导入不再获取 suspensions
-                        balance_color_mode: None,
-                        skip_check: Some(false), // 与前端保持一致，默认不跳过检测
-                        session_updated_at: Some(now), // 设置 session 初始更新时间
-                        version: 0,              // 本地创建时版本号为0，由数据库分配
-                    };
-
-                    // 保存到存储
-                    let storage = {
-                        let storage_guard = state.storage_manager.lock().unwrap();
-                        storage_guard.as_ref().cloned()
-                    };
-
-                    let storage_result = if let Some(storage) = storage {
-                        storage
-                            .save_token(&token_data)
-                            .await
-                            .map_err(|e| e.to_string())
-                    } else {
-                        Err("Storage manager not initialized".to_string())
-                    };
-
-                    match storage_result {
-                        Ok(_) => ImportResult {
-                            success: true,
-                            token_data: Some(token_data),
-                            error: None,
-                            session_preview: Some(mask_session(&session)),
-                        },
-                        Err(e) => ImportResult {
-                            success: false,
-                            token_data: None,
-                            error: Some(format!("Storage error: {}", e)),
-                            session_preview: Some(mask_session(&session)),
-                        },
-                    }
-                }
-                Err(e) => ImportResult {
-                    success: false,
-                    token_data: None,
-                    error: Some(e),
-                    session_preview: Some(mask_session(&session)),
-                },
-            }
+            import_session_item(session, state).await
         });
 
         tasks.push(task);
@@ -626,6 +625,7 @@ async fn import_sessions_handler(
                 results.push(ImportResult {
                     success: false,
                     token_data: None,
+                    account: None,
                     error: Some(format!("Task error: {}", e)),
                     session_preview: None,
                 });
@@ -682,6 +682,52 @@ async fn import_sessions_handler(
     }
 }
 
+// ==================== 访问控制 ====================
+
+/// 按 CIDR 白名单校验客户端来源
+async fn check_remote_addr(
+    remote: Option<SocketAddr>,
+    allow_list: Arc<AllowList>,
+) -> Result<(), Rejection> {
+    match remote {
+        Some(addr) if allow_list.allows(addr.ip()) => Ok(()),
+        Some(addr) => {
+            eprintln!("🚫 API request from {} rejected by allow-list", addr.ip());
+            Err(warp::reject::custom(ApiError::new(
+                ApiErrorCode::Forbidden,
+                format!("Client address {} is not allowed", addr.ip()),
+            )))
+        }
+        None => Err(warp::reject::custom(ApiError::new(
+            ApiErrorCode::Forbidden,
+            "Client address unavailable",
+        ))),
+    }
+}
+
+/// 校验 Codex 管理员 Key（`require_key` 为 false 时直接放行）
+async fn check_api_key(
+    require_key: bool,
+    headers: HeaderMap,
+    state: Arc<crate::AppState>,
+) -> Result<(), Rejection> {
+    if !require_key {
+        return Ok(());
+    }
+
+    match crate::platforms::openai::codex::server::check_admin_key(&state, &headers) {
+        Some(true) => Ok(()),
+        Some(false) => Err(warp::reject::custom(ApiError::new(
+            ApiErrorCode::Unauthorized,
+            "Invalid or missing API key",
+        ))),
+        None => Err(warp::reject::custom(ApiError::new(
+            ApiErrorCode::Unauthorized,
+            "API key not configured; set the Codex API key to use this endpoint",
+        ))),
+    }
+}
+
 // ==================== 服务器启动 ====================
 
 /// 启动 API 服务器（固定端口）
@@ -690,14 +736,16 @@ pub async fn start_api_server(state: Arc<crate::AppState>, port: u16) -> Result<
 
     match try_bind_server(state.clone(), port).await {
         Ok(server) => {
-            println!(
-                "✅ API Server started successfully on http://127.0.0.1:{}",
-                port
-            );
+            let address = server.get_address();
+            println!("✅ API Server started successfully on {}", address);
             println!("📡 Available endpoints:");
-            println!("   - GET  http://127.0.0.1:{}/api/health", port);
-            println!("   - POST http://127.0.0.1:{}/api/import/session", port);
-            println!("   - POST http://127.0.0.1:{}/api/import/sessions", port);
+            println!("   - GET  {}/api/health", address);
+            println!("   - GET  {}/api/openapi.json", address);
+            println!("   - POST {}/api/import/session", address);
+            println!("   - POST {}/api/import/sessions", address);
+            println!("   - POST {}/api/{{platform}}/accounts", address);
+            println!("   - GET  {}/api/{{platform}}/accounts[/{{id}}]", address);
+            println!("   - POST {}/api/import/jobs", address);
             Ok(server)
         }
         Err(e) => Err(format!(
@@ -707,24 +755,147 @@ pub async fn start_api_server(state: Arc<crate::AppState>, port: u16) -> Result<
     }
 }
 
+/// 路由标记：参数为 "method path" 形式的路由模板（与 OpenAPI 路由表一致）
+pub(crate) type RouteTag = fn(&'static str) -> BoxedFilter<()>;
+
+/// 不做任何处理的路由标记
+pub(crate) fn untagged_route(_route: &'static str) -> BoxedFilter<()> {
+    warp::any().boxed()
+}
+
 /// 尝试在指定端口绑定服务器
 async fn try_bind_server(state: Arc<crate::AppState>, port: u16) -> Result<ApiServer, String> {
     let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
+    // 监听地址、TLS 与白名单配置
+    let config = load_api_server_config_internal(&state.app_handle).unwrap_or_else(|e| {
+        eprintln!("Failed to load API server config, using defaults: {}", e);
+        Default::default()
+    });
+    let bind_addr = config.socket_addr(port)?;
+    let allow_list = Arc::new(config.allow_list()?);
+    let require_import_key = !config.is_loopback();
+    if require_import_key {
+        println!(
+            "🌐 API Server listening on {}, import endpoints require the Codex API key",
+            bind_addr
+        );
+    }
+
     // 克隆 state 用于各个路由
     let state_for_filters = state.clone();
-    let state_filter = warp::any().map(move || state_for_filters.clone());
+    let state_filter = warp::any().map(move || state_for_filters.clone()).boxed();
+
+    let access_filter = warp::addr::remote()
+        .and(warp::any().map(move || allow_list.clone()))
+        .and_then(check_remote_addr)
+        .untuple_one();
+
+    // API 子路由
+    let api_routes = api_routes(
+        state_filter.clone(),
+        port,
+        config.base_url(port),
+        require_import_key,
+        untagged_route,
+    )
+    .boxed();
+
+    // Codex /v1/* 路由（复用同一个 HTTP 监听器）
+    let codex_routes =
+        crate::platforms::openai::codex::server::codex_routes(state_filter, untagged_route).boxed();
+
+    let cors = warp::cors()
+        .allow_any_origin() // 允许任何来源（对外监听时由白名单与 API Key 把关）
+        .allow_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
+        .allow_headers(vec![
+            "Content-Type",
+            "Authorization",
+            "X-API-Key",
+            "Accept",
+            "Accept-Encoding",
+        ]);
+
+    // 组合所有路由（先校验来源白名单）
+    let routes = access_filter
+        .and(api_routes.or(codex_routes))
+        .with(cors)
+        .recover(handle_rejection);
+
+    let shutdown = async {
+        shutdown_rx.await.ok();
+    };
+
+    // 尝试绑定端口，配置了证书时启用 TLS
+    if let Some((cert_path, key_path)) = config.tls_paths() {
+        let (_addr, server) = warp::serve(routes)
+            .tls()
+            .cert_path(cert_path)
+            .key_path(key_path)
+            .try_bind_with_graceful_shutdown(bind_addr, shutdown)
+            .map_err(|e| format!("Failed to bind TLS server to {}: {}", bind_addr, e))?;
+        tokio::spawn(server);
+    } else {
+        let (_addr, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(bind_addr, shutdown)
+            .map_err(|e| format!("Failed to bind to {}: {}", bind_addr, e))?;
+        tokio::spawn(server);
+    }
+
+    Ok(ApiServer {
+        shutdown_tx: Some(shutdown_tx),
+        port,
+        address: config.base_url(port),
+    })
+}
+
+/// 本地 API 的 `/api/*` 路由
+///
+/// `tag` 生成的过滤器插在每条路由的路径与方法匹配之后，生产环境传 [`untagged_route`]；
+/// 测试借此确认 OpenAPI 路由表中的每个接口都命中了对应的真实路由。
+pub(crate) fn api_routes(
+    state_filter: BoxedFilter<(Arc<AppState>,)>,
+    port: u16,
+    server_url: String,
+    require_import_key: bool,
+    tag: RouteTag,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let port_filter = warp::any().map(move || port);
 
+    // 非回环监听时，导入接口要求携带 Codex 管理员 Key
+    let import_auth = warp::any()
+        .map(move || require_import_key)
+        .and(warp::header::headers_cloned())
+        .and(state_filter.clone())
+        .and_then(check_api_key)
+        .untuple_one();
+    // 账号管理接口始终要求 Codex 管理员 Key
+    let admin_auth = warp::any()
+        .map(|| true)
+        .and(warp::header::headers_cloned())
+        .and(state_filter.clone())
+        .and_then(check_api_key)
+        .untuple_one();
+
     // 健康检查路由
     let health_route = warp::path!("api" / "health")
         .and(warp::get())
+        .and(tag("get /api/health"))
         .and(port_filter.clone())
         .and_then(health_handler);
 
+    // OpenAPI 文档
+    let openapi_route = warp::path!("api" / "openapi.json")
+        .and(warp::get())
+        .and(tag("get /api/openapi.json"))
+        .and(warp::any().map(move || server_url.clone()))
+        .and_then(openapi::openapi_handler);
+
     // 单个 session 导入路由
     let import_session_route = warp::path!("api" / "import" / "session")
         .and(warp::post())
+        .and(tag("post /api/import/session"))
+        .and(import_auth.clone())
         .and(warp::body::content_length_limit(1024 * 1024)) // 1MB 限制
         .and(warp::body::json())
         .and(state_filter.clone())
@@ -733,164 +904,151 @@ async fn try_bind_server(state: Arc<crate::AppState>, port: u16) -> Result<ApiSe
     // 批量 session 导入路由
     let import_sessions_route = warp::path!("api" / "import" / "sessions")
         .and(warp::post())
+        .and(tag("post /api/import/sessions"))
+        .and(import_auth.clone())
         .and(warp::body::content_length_limit(1024 * 1024)) // 1MB 限制
         .and(warp::body::json())
         .and(state_filter.clone())
         .and_then(import_sessions_handler);
 
-    // API 子路由
-    let api_routes = health_route
-        .or(import_session_route)
-        .or(import_sessions_route)
-        .boxed();
+    // 多平台账号导入路由：/api/{platform}/accounts
+    let import_accounts_route = warp::path!("api" / AccountPlatform / "accounts")
+        .and(warp::post())
+        .and(tag("post /api/{platform}/accounts"))
+        .and(import_auth.clone())
+        .and(warp::body::content_length_limit(1024 * 1024)) // 1MB 限制
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(api_accounts::import_accounts_handler);
 
-    // Codex /v1/* 路由（复用同一个 HTTP 监听器）
-    let codex_routes =
-        crate::platforms::openai::codex::server::codex_routes_from_state(state).boxed();
+    // 异步导入任务路由
+    let create_session_job_route = warp::path!("api" / "import" / "jobs")
+        .and(warp::post())
+        .and(tag("post /api/import/jobs"))
+        .and(import_auth.clone())
+        .and(warp::body::content_length_limit(4 * 1024 * 1024)) // 4MB 限制
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(import_jobs::create_session_job_handler);
 
-    let cors = warp::cors()
-        .allow_any_origin() // 允许任何来源（因为只监听 localhost）
-        .allow_methods(vec!["GET", "POST", "OPTIONS"])
-        .allow_headers(vec![
-            "Content-Type",
-            "Authorization",
-            "X-API-Key",
-            "Accept",
-            "Accept-Encoding",
-        ]);
+    let create_account_job_route = warp::path!("api" / AccountPlatform / "accounts" / "jobs")
+        .and(warp::post())
+        .and(tag("post /api/{platform}/accounts/jobs"))
+        .and(import_auth.clone())
+        .and(warp::body::content_length_limit(4 * 1024 * 1024)) // 4MB 限制
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(import_jobs::create_account_job_handler);
 
-    // 组合所有路由
-    let routes = api_routes
-        .or(codex_routes)
-        .with(cors)
-        .recover(handle_rejection);
+    let list_jobs_route = warp::path!("api" / "import" / "jobs")
+        .and(warp::get())
+        .and(tag("get /api/import/jobs"))
+        .and(import_auth.clone())
+        .and(state_filter.clone())
+        .and_then(import_jobs::list_jobs_handler);
 
-    // 尝试绑定端口
-    let (_addr, server) = warp::serve(routes)
-        .try_bind_with_graceful_shutdown(([127, 0, 0, 1], port), async {
-            shutdown_rx.await.ok();
-        })
-        .map_err(|e| format!("Failed to bind to port {}: {}", port, e))?;
+    let get_job_route = warp::path!("api" / "import" / "jobs" / String)
+        .and(warp::get())
+        .and(tag("get /api/import/jobs/{id}"))
+        .and(import_auth.clone())
+        .and(state_filter.clone())
+        .and_then(import_jobs::get_job_handler);
 
-    // 在后台启动服务器
-    tokio::spawn(server);
+    let cancel_job_route = warp::path!("api" / "import" / "jobs" / String)
+        .and(warp::delete())
+        .and(tag("delete /api/import/jobs/{id}"))
+        .and(import_auth.clone())
+        .and(state_filter.clone())
+        .and_then(import_jobs::cancel_job_handler);
 
-    Ok(ApiServer {
-        shutdown_tx: Some(shutdown_tx),
-        port,
-    })
+    let job_routes = create_session_job_route
+        .or(create_account_job_route)
+        .or(list_jobs_route)
+        .or(get_job_route)
+        .or(cancel_job_route)
+        .boxed();
+
+    // 账号管理路由
+    let list_accounts_route = warp::path!("api" / AccountPlatform / "accounts")
+        .and(warp::get())
+        .and(tag("get /api/{platform}/accounts"))
+        .and(admin_auth.clone())
+        .and(state_filter.clone())
+        .and_then(api_accounts::list_accounts_handler);
+
+    let get_account_route = warp::path!("api" / AccountPlatform / "accounts" / String)
+        .and(warp::get())
+        .and(tag("get /api/{platform}/accounts/{id}"))
+        .and(admin_auth.clone())
+        .and(state_filter.clone())
+        .and_then(api_accounts::get_account_handler);
+
+    let delete_account_route = warp::path!("api" / AccountPlatform / "accounts" / String)
+        .and(warp::delete())
+        .and(tag("delete /api/{platform}/accounts/{id}"))
+        .and(admin_auth.clone())
+        .and(state_filter.clone())
+        .and_then(api_accounts::delete_account_handler);
+
+    let switch_account_route =
+        warp::path!("api" / AccountPlatform / "accounts" / String / "switch")
+            .and(warp::post())
+            .and(tag("post /api/{platform}/accounts/{id}/switch"))
+            .and(admin_auth.clone())
+            .and(warp::query::<api_accounts::SwitchAccountQuery>())
+            .and(state_filter.clone())
+            .and_then(api_accounts::switch_account_handler);
+
+    let refresh_quota_route = warp::path!("api" / AccountPlatform / "accounts" / String / "quota")
+        .and(warp::post())
+        .and(tag("post /api/{platform}/accounts/{id}/quota"))
+        .and(admin_auth.clone())
+        .and(state_filter.clone())
+        .and_then(api_accounts::refresh_account_quota_handler);
+
+    let refresh_all_quotas_route = warp::path!("api" / AccountPlatform / "quotas" / "refresh")
+        .and(warp::post())
+        .and(tag("post /api/{platform}/quotas/refresh"))
+        .and(admin_auth.clone())
+        .and(state_filter.clone())
+        .and_then(api_accounts::refresh_all_quotas_handler);
+
+    let account_routes = list_accounts_route
+        .or(get_account_route)
+        .or(delete_account_route)
+        .or(switch_account_route)
+        .or(refresh_quota_route)
+        .or(refresh_all_quotas_route)
+        .boxed();
+
+    health_route
+        .or(openapi_route)
+        .or(import_session_route)
+        .or(import_sessions_route)
+        .or(import_accounts_route)
+        .or(job_routes)
+        .or(account_routes)
 }
 
 /// 处理 warp 拒绝错误
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
-    if let Some(_) = err.find::<warp::reject::MethodNotAllowed>() {
-        let error_response = ApiErrorResponse {
-            error: "Method not allowed".to_string(),
-            code: "METHOD_NOT_ALLOWED".to_string(),
-        };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::METHOD_NOT_ALLOWED,
-        ))
+    // 访问控制优先于 MethodNotAllowed（同一路径的其他方法路由也会产生该拒绝）
+    let error = if let Some(error) = err.find::<ApiError>() {
+        error.clone()
+    } else if let Some(_) = err.find::<warp::reject::MethodNotAllowed>() {
+        ApiError::new(ApiErrorCode::MethodNotAllowed, "Method not allowed")
     } else if let Some(rej) = err.find::<crate::platforms::openai::codex::server::CodexRejection>()
     {
-        let (status, message, code) = match rej {
-            crate::platforms::openai::codex::server::CodexRejection::InvalidRequest(msg) => (
-                warp::http::StatusCode::BAD_REQUEST,
-                msg.as_str(),
-                "invalid_request_error",
-            ),
-            crate::platforms::openai::codex::server::CodexRejection::TranslationError(msg) => (
-                warp::http::StatusCode::BAD_REQUEST,
-                msg.as_str(),
-                "translation_error",
-            ),
-            crate::platforms::openai::codex::server::CodexRejection::ExecutionError(msg)
-                if msg.to_ascii_lowercase().contains("unauthorized") =>
-            {
-                (
-                    warp::http::StatusCode::UNAUTHORIZED,
-                    msg.as_str(),
-                    "unauthorized",
-                )
-            }
-            crate::platforms::openai::codex::server::CodexRejection::ExecutionError(msg)
-                if msg.to_ascii_lowercase().contains("timed out")
-                    || msg.to_ascii_lowercase().contains("timeout") =>
-            {
-                (
-                    warp::http::StatusCode::GATEWAY_TIMEOUT,
-                    msg.as_str(),
-                    "upstream_timeout",
-                )
-            }
-            crate::platforms::openai::codex::server::CodexRejection::ExecutionError(msg) => (
-                warp::http::StatusCode::BAD_GATEWAY,
-                msg.as_str(),
-                "execution_error",
-            ),
-            crate::platforms::openai::codex::server::CodexRejection::NoAvailableAccount => (
-                warp::http::StatusCode::SERVICE_UNAVAILABLE,
-                "No available account in pool",
-                "no_available_account",
-            ),
-            crate::platforms::openai::codex::server::CodexRejection::ServiceUnavailable(msg) => (
-                warp::http::StatusCode::SERVICE_UNAVAILABLE,
-                msg.as_str(),
-                "service_unavailable",
-            ),
-            crate::platforms::openai::codex::server::CodexRejection::InternalError(msg) => (
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-                msg.as_str(),
-                "internal_error",
-            ),
-        };
-
-        Ok(warp::reply::with_status(
-            warp::reply::json(&json!({
-                "error": {
-                    "message": message,
-                    "type": code,
-                    "code": status.as_u16().to_string()
-                }
-            })),
-            status,
-        ))
+        // Codex 路由保持 OpenAI 兼容的错误体
+        return Ok(rej.api_error().openai_reply());
     } else if err.is_not_found() {
-        let error_response = ApiErrorResponse {
-            error: "Endpoint not found".to_string(),
-            code: "NOT_FOUND".to_string(),
-        };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::NOT_FOUND,
-        ))
+        ApiError::new(ApiErrorCode::NotFound, "Endpoint not found")
     } else if let Some(_) = err.find::<warp::filters::body::BodyDeserializeError>() {
-        let error_response = ApiErrorResponse {
-            error: "Invalid JSON body".to_string(),
-            code: "INVALID_JSON".to_string(),
-        };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::BAD_REQUEST,
-        ))
+        ApiError::new(ApiErrorCode::InvalidJson, "Invalid JSON body")
     } else if let Some(_) = err.find::<warp::reject::PayloadTooLarge>() {
-        let error_response = ApiErrorResponse {
-            error: "Request payload too large (max 1MB)".to_string(),
-            code: "PAYLOAD_TOO_LARGE".to_string(),
-        };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::PAYLOAD_TOO_LARGE,
-        ))
+        ApiError::new(ApiErrorCode::PayloadTooLarge, "Request payload too large")
     } else {
-        let error_response = ApiErrorResponse {
-            error: "Internal server error".to_string(),
-            code: "INTERNAL_ERROR".to_string(),
-        };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&error_response),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        ))
-    }
+        ApiError::new(ApiErrorCode::InternalError, "Internal server error")
+    };
+    Ok(error.reply())
 }
@@ -0,0 +1,292 @@
+//! 本地 API 服务器的网络配置
+//!
+//! 默认只监听 127.0.0.1。配置监听地址后可供局域网内其他机器访问，
+//! 可选 TLS 证书/私钥（PEM），并按 CIDR 白名单限制客户端来源（本机回环地址始终放行）。
+//! 配置保存在应用数据目录的 api_server_config.json，重启 API 服务器后生效。
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use tauri::Manager;
+
+const API_SERVER_CONFIG_FILE: &str = "api_server_config.json";
+
+fn default_bind_address() -> String {
+    Ipv4Addr::LOCALHOST.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerConfig {
+    /// 监听地址，"0.0.0.0" / "::" 表示所有网卡
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    /// TLS 证书路径（PEM），与私钥同时配置时启用 HTTPS
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    /// TLS 私钥路径（PEM）
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// 允许访问的客户端网段，如 "192.168.1.0/24"；为空表示不限制
+    #[serde(default)]
+    pub allowed_cidrs: Vec<String>,
+}
+
+impl Default for ApiServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: default_bind_address(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            allowed_cidrs: Vec::new(),
+        }
+    }
+}
+
+impl ApiServerConfig {
+    /// 解析监听地址
+    pub fn bind_ip(&self) -> Result<IpAddr, String> {
+        self.bind_address
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|e| format!("Invalid bind address '{}': {}", self.bind_address, e))
+    }
+
+    pub fn socket_addr(&self, port: u16) -> Result<SocketAddr, String> {
+        Ok(SocketAddr::new(self.bind_ip()?, port))
+    }
+
+    /// 是否只监听本机回环地址
+    pub fn is_loopback(&self) -> bool {
+        self.bind_ip().map(|ip| ip.is_loopback()).unwrap_or(true)
+    }
+
+    /// TLS 证书与私钥路径（两者都配置时）
+    pub fn tls_paths(&self) -> Option<(&str, &str)> {
+        let cert = self.tls_cert_path.as_deref().filter(|p| !p.is_empty())?;
+        let key = self.tls_key_path.as_deref().filter(|p| !p.is_empty())?;
+        Some((cert, key))
+    }
+
+    pub fn allow_list(&self) -> Result<AllowList, String> {
+        let blocks = self
+            .allowed_cidrs
+            .iter()
+            .map(|cidr| CidrBlock::parse(cidr))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AllowList { blocks })
+    }
+
+    /// 本机访问使用的地址（监听所有网卡时用 127.0.0.1）
+    pub fn base_url(&self, port: u16) -> String {
+        let scheme = if self.tls_paths().is_some() { "https" } else { "http" };
+        let host = match self.bind_ip() {
+            Ok(ip) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.to_string(),
+            Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+            Ok(ip) => ip.to_string(),
+            Err(_) => Ipv4Addr::LOCALHOST.to_string(),
+        };
+        format!("{}://{}:{}", scheme, host, port)
+    }
+
+    /// 去除空白项并校验地址、网段与 TLS 配置
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.bind_address = self.bind_address.trim().to_string();
+        if self.bind_address.is_empty() {
+            self.bind_address = default_bind_address();
+        }
+        self.bind_ip()?;
+
+        for path in [&mut self.tls_cert_path, &mut self.tls_key_path] {
+            *path = path
+                .as_deref()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string);
+        }
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err("TLS requires both a certificate and a private key".to_string());
+        }
+
+        self.allowed_cidrs = self
+            .allowed_cidrs
+            .iter()
+            .map(|cidr| cidr.trim().to_string())
+            .filter(|cidr| !cidr.is_empty())
+            .collect();
+        self.allow_list()?;
+        Ok(())
+    }
+}
+
+/// 单个 CIDR 网段；不带前缀长度的地址视为单个主机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CidrBlock {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl CidrBlock {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("Invalid CIDR '{}': {}", value, e))?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max_prefix)
+                .ok_or_else(|| format!("Invalid CIDR prefix in '{}'", value))?,
+            None => max_prefix,
+        };
+        Ok(Self { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 客户端来源白名单
+#[derive(Debug, Clone, Default)]
+pub struct AllowList {
+    blocks: Vec<CidrBlock>,
+}
+
+impl AllowList {
+    /// 白名单为空或来源为本机回环地址时放行
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.blocks.is_empty() || ip.is_loopback() || self.blocks.iter().any(|b| b.contains(ip))
+    }
+}
+
+fn get_api_server_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(app_data_dir.join(API_SERVER_CONFIG_FILE))
+}
+
+/// 从文件加载 API 服务器配置，文件不存在时返回默认配置
+pub fn load_api_server_config_internal(
+    app_handle: &tauri::AppHandle,
+) -> Result<ApiServerConfig, String> {
+    let config_path = get_api_server_config_path(app_handle)?;
+    if !config_path.exists() {
+        return Ok(ApiServerConfig::default());
+    }
+
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse config file: {}", e))
+}
+
+/// 保存 API 服务器配置
+pub fn save_api_server_config_internal(
+    app_handle: &tauri::AppHandle,
+    config: &ApiServerConfig,
+) -> Result<(), String> {
+    let config_path = get_api_server_config_path(app_handle)?;
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, json).map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+#[tauri::command]
+pub async fn load_api_server_config(app: tauri::AppHandle) -> Result<ApiServerConfig, String> {
+    load_api_server_config_internal(&app)
+        .map_err(|e| format!("Failed to load API server config: {}", e))
+}
+
+/// 保存配置，重启 API 服务器后生效
+#[tauri::command]
+pub async fn save_api_server_config(
+    app: tauri::AppHandle,
+    mut config: ApiServerConfig,
+) -> Result<ApiServerConfig, String> {
+    config.normalize()?;
+    save_api_server_config_internal(&app, &config)
+        .map_err(|e| format!("Failed to save API server config: {}", e))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiServerConfig, CidrBlock};
+    use std::net::IpAddr;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn cidr_blocks_match_v4_v6_and_mapped_addresses() {
+        let lan = CidrBlock::parse("192.168.1.0/24").unwrap();
+        assert!(lan.contains(ip("192.168.1.42")));
+        assert!(lan.contains(ip("::ffff:192.168.1.7")));
+        assert!(!lan.contains(ip("192.168.2.1")));
+
+        assert!(CidrBlock::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        assert!(CidrBlock::parse("10.0.0.5").unwrap().contains(ip("10.0.0.5")));
+        assert!(!CidrBlock::parse("10.0.0.5").unwrap().contains(ip("10.0.0.6")));
+        assert!(CidrBlock::parse("fd00::/8").unwrap().contains(ip("fd12::1")));
+        assert!(!CidrBlock::parse("fd00::/8").unwrap().contains(ip("10.0.0.1")));
+
+        assert!(CidrBlock::parse("10.0.0.0/33").is_err());
+        assert!(CidrBlock::parse("lan").is_err());
+    }
+
+    #[test]
+    fn normalize_validates_bind_address_tls_and_allow_list() {
+        let mut config = ApiServerConfig {
+            bind_address: " 0.0.0.0 ".to_string(),
+            tls_cert_path: Some("/etc/atm/cert.pem".to_string()),
+            tls_key_path: Some(" ".to_string()),
+            allowed_cidrs: vec!["10.0.0.0/8".to_string(), " ".to_string()],
+        };
+        assert!(config.normalize().is_err());
+
+        config.tls_key_path = Some("/etc/atm/key.pem".to_string());
+        config.normalize().unwrap();
+        assert!(!config.is_loopback());
+        assert_eq!(config.allowed_cidrs, vec!["10.0.0.0/8"]);
+        assert_eq!(config.base_url(8766), "https://127.0.0.1:8766");
+
+        let allow = config.allow_list().unwrap();
+        assert!(allow.allows(ip("10.1.2.3")));
+        assert!(allow.allows(ip("127.0.0.1")));
+        assert!(!allow.allows(ip("192.168.1.10")));
+
+        assert!(ApiServerConfig::default().is_loopback());
+        let mut invalid = ApiServerConfig {
+            bind_address: "lan-box".to_string(),
+            ..ApiServerConfig::default()
+        };
+        assert!(invalid.normalize().is_err());
+    }
+}
@@ -155,8 +155,10 @@ pub async fn open_internal_browser(
     use std::time::Duration;
     use tauri::webview::PageLoadEvent;
 
-    // 加载代理配置
-    let proxy_config = proxy_config::load_proxy_config(app.clone()).await.ok();
+    // 加载代理配置；无法读取（如代理密码无法解密）时不打开窗口，避免绕过代理直连
+    let proxy_config = proxy_config::load_proxy_config(app.clone())
+        .await
+        .map(Some)?;
 
     let window_label = format!("browser_{}", chrono::Utc::now().timestamp());
     let app_handle = app.clone();
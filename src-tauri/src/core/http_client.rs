@@ -1,13 +1,29 @@
-use crate::proxy_config::{ProxyConfig, ProxyType};
+use crate::proxy_config::{ProxyConfig, ProxyType, read_proxy_config};
 use crate::proxy_helper::ProxyClient;
 use reqwest;
-use serde_json;
 use std::sync::Arc;
 use std::time::Duration;
 
 /// 尝试从配置文件加载代理配置
 /// 路径与 Tauri 的 app_data_dir() 保持一致
-fn try_load_proxy_config() -> Option<ProxyConfig> {
+///
+/// 配置文件存在但无法读取（如代理密码因主密钥未解锁而无法解密）时返回错误，
+/// 调用方不得退回直连，否则流量会绕过已配置的代理
+fn try_load_proxy_config() -> Result<Option<ProxyConfig>, String> {
+    let Some(config_path) = proxy_config_path() else {
+        return Ok(None);
+    };
+
+    if config_path.exists() {
+        // 代理密码已加密，需经 proxy_config 解密
+        return read_proxy_config(&config_path)
+            .map(Some)
+            .map_err(|e| format!("Failed to load proxy config: {}", e));
+    }
+    Ok(None)
+}
+
+fn proxy_config_path() -> Option<std::path::PathBuf> {
     // 根据不同平台构造应用数据目录
     // macOS: ~/Library/Application Support/com.cubezhao.atm
     // Linux: ~/.local/share/atm
@@ -27,14 +43,7 @@ fn try_load_proxy_config() -> Option<ProxyConfig> {
         .join("com.cubezhao.atm")
         .join("proxy_config.json");
 
-    if config_path.exists() {
-        if let Ok(json) = std::fs::read_to_string(&config_path) {
-            if let Ok(config) = serde_json::from_str::<ProxyConfig>(&json) {
-                return Some(config);
-            }
-        }
-    }
-    None
+    Some(config_path)
 }
 
 /// 检查是否配置了 CustomUrl 类型的代理
@@ -53,7 +62,7 @@ pub fn get_custom_proxy_url() -> Option<String> {
 
 /// 获取代理类型
 fn get_proxy_type() -> Option<ProxyType> {
-    if let Some(config) = try_load_proxy_config() {
+    if let Some(config) = try_load_proxy_config().ok().flatten() {
         if config.enabled {
             return Some(config.proxy_type);
         }
@@ -63,7 +72,7 @@ fn get_proxy_type() -> Option<ProxyType> {
 
 /// 获取代理配置
 fn get_proxy_config() -> Option<ProxyConfig> {
-    if let Some(config) = try_load_proxy_config() {
+    if let Some(config) = try_load_proxy_config().ok().flatten() {
         if config.enabled {
             return Some(config);
         }
@@ -73,7 +82,7 @@ fn get_proxy_config() -> Option<ProxyConfig> {
 
 /// 创建代理客户端，自动处理 Edge Function 代理
 pub fn create_proxy_client() -> Result<ProxyClient, String> {
-    let proxy_config = try_load_proxy_config();
+    let proxy_config = try_load_proxy_config()?;
 
     let client = if let Some(config) = &proxy_config {
         config.create_client()?
@@ -101,7 +110,7 @@ pub fn create_proxy_client() -> Result<ProxyClient, String> {
 /// - 不设置全局请求超时，避免长流式响应在固定时长后被中断
 /// - 保留连接超时和代理/自定义 URL 处理逻辑
 pub fn create_proxy_client_for_streaming() -> Result<ProxyClient, String> {
-    let proxy_config = try_load_proxy_config();
+    let proxy_config = try_load_proxy_config()?;
 
     let client = if let Some(config) = &proxy_config {
         create_streaming_client(config)?
@@ -153,7 +162,7 @@ fn create_streaming_client(config: &ProxyConfig) -> Result<reqwest::Client, Stri
 ///
 /// @deprecated 请使用 create_proxy_client() 来获得完整的 Edge Function 支持
 pub fn create_http_client() -> Result<reqwest::Client, String> {
-    let proxy_config = try_load_proxy_config();
+    let proxy_config = try_load_proxy_config()?;
 
     if let Some(config) = proxy_config {
         // 如果是 CustomUrl 类型，记录警告
@@ -174,7 +183,7 @@ pub fn create_http_client() -> Result<reqwest::Client, String> {
 pub fn create_http_client_with_cookies(
     jar: Arc<reqwest::cookie::Jar>,
 ) -> Result<reqwest::Client, String> {
-    let proxy_config = try_load_proxy_config();
+    let proxy_config = try_load_proxy_config()?;
 
     if let Some(config) = proxy_config {
         config.create_client_with_cookies(jar)
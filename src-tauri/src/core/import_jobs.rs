@@ -0,0 +1,760 @@
+//! 异步批量导入任务
+//!
+//! `POST /api/import/jobs`（Augment session）或 `POST /api/{platform}/accounts/jobs` 创建任务后立即
+//! 返回任务 ID，导入在后台执行，不再依赖 HTTP 连接；`GET /api/import/jobs/{id}` 查询进度与逐项结果，
+//! `DELETE /api/import/jobs/{id}` 取消（已开始的条目会执行完）。
+//!
+//! 任务状态保存在应用数据目录的 import_jobs.json，进度通过 `import-job-progress` 事件通知前端。
+//! 应用重启时仍在运行的任务会被标记为 interrupted。
+//!
+//! 任务只记录导入账号的 ID、邮箱和遮盖后的 token 预览，不保存账号内容（access/refresh token 等）。
+
+use crate::core::api_accounts::{
+    AccountImportRequest, AccountPlatform, OpenAITokenType, dedupe_tokens, emit_accounts_updated,
+    import_account_item,
+};
+use crate::core::api_error::{ApiError, ApiErrorCode};
+use crate::core::api_server::{
+    BatchImportResult, ImportResult, ImportSessionsRequest, import_session_item,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Semaphore;
+use warp::Rejection;
+use warp::http::StatusCode;
+
+const IMPORT_JOBS_FILE: &str = "import_jobs.json";
+/// 单个任务最多包含的条目数
+const MAX_JOB_ITEMS: usize = 1000;
+/// 保留的已结束任务数
+const MAX_FINISHED_JOBS: usize = 20;
+const JOB_CONCURRENCY: usize = 5;
+/// 进度落盘的最小间隔（状态变化时立即落盘）
+const PERSIST_INTERVAL_MS: i64 = 2000;
+
+const AUGMENT_PLATFORM: &str = "augment";
+
+static IMPORT_JOBS: OnceLock<ImportJobStore> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportJobStatus {
+    Running,
+    Completed,
+    Cancelled,
+    /// 应用退出时未完成
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: String,
+    /// "augment" 或 openai / cursor / windsurf / antigravity
+    pub platform: String,
+    pub status: ImportJobStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(default)]
+    pub finished_at: Option<i64>,
+    /// 按提交顺序的逐项结果，尚未处理的条目为 null
+    pub results: Vec<Option<ImportJobItem>>,
+}
+
+/// 任务中一个条目的结果，只含标识信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJobItem {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_preview: Option<String>,
+}
+
+impl From<ImportResult> for ImportJobItem {
+    fn from(result: ImportResult) -> Self {
+        let (account_id, email) = match (&result.token_data, &result.account) {
+            (Some(token), _) => (Some(token.id.clone()), token.email_note.clone()),
+            (None, Some(account)) => {
+                let field =
+                    |name: &str| account.get(name).and_then(|v| v.as_str()).map(String::from);
+                (field("id"), field("email"))
+            }
+            (None, None) => (None, None),
+        };
+        Self {
+            success: result.success,
+            account_id,
+            email,
+            error: result.error,
+            session_preview: result.session_preview,
+        }
+    }
+}
+
+/// 任务概要（列表与进度事件使用）
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportJobSummary {
+    pub id: String,
+    pub platform: String,
+    pub status: ImportJobStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// GET 任务详情：运行中返回逐项结果，结束后返回最终报告
+#[derive(Debug, Serialize)]
+pub struct ImportJobDetail {
+    #[serde(flatten)]
+    pub summary: ImportJobSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Vec<Option<ImportJobItem>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<BatchImportResult<ImportJobItem>>,
+}
+
+impl ImportJob {
+    fn new(platform: &str, total: usize, now: i64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            platform: platform.to_string(),
+            status: ImportJobStatus::Running,
+            total,
+            processed: 0,
+            successful: 0,
+            failed: 0,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+            results: vec![None; total],
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.status != ImportJobStatus::Running
+    }
+
+    fn record(&mut self, index: usize, result: ImportResult, now: i64) {
+        let Some(slot) = self.results.get_mut(index) else {
+            return;
+        };
+        if slot.is_some() {
+            return;
+        }
+        if result.success {
+            self.successful += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.processed += 1;
+        self.updated_at = now;
+        *slot = Some(result.into());
+    }
+
+    fn finish(&mut self, cancelled: bool, now: i64) {
+        self.status = if cancelled && self.processed < self.total {
+            ImportJobStatus::Cancelled
+        } else {
+            ImportJobStatus::Completed
+        };
+        self.updated_at = now;
+        self.finished_at = Some(now);
+    }
+
+    /// 最终报告，仅包含已处理的条目（按提交顺序）
+    pub fn report(&self) -> BatchImportResult<ImportJobItem> {
+        let results: Vec<ImportJobItem> = self.results.iter().flatten().cloned().collect();
+        BatchImportResult {
+            total: self.total,
+            successful: self.successful,
+            failed: self.failed,
+            results,
+        }
+    }
+
+    pub fn summary(&self) -> ImportJobSummary {
+        ImportJobSummary {
+            id: self.id.clone(),
+            platform: self.platform.clone(),
+            status: self.status,
+            total: self.total,
+            processed: self.processed,
+            successful: self.successful,
+            failed: self.failed,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            finished_at: self.finished_at,
+        }
+    }
+
+    pub fn detail(&self) -> ImportJobDetail {
+        if self.is_finished() {
+            ImportJobDetail {
+                summary: self.summary(),
+                results: None,
+                report: Some(self.report()),
+            }
+        } else {
+            ImportJobDetail {
+                summary: self.summary(),
+                results: Some(self.results.clone()),
+                report: None,
+            }
+        }
+    }
+}
+
+/// 任务存储：内存中的任务表 + JSON 文件持久化
+pub struct ImportJobStore {
+    path: Option<PathBuf>,
+    jobs: Mutex<HashMap<String, ImportJob>>,
+    cancel_flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    last_persist_ms: AtomicI64,
+    /// 各条目任务并发落盘时依次写入
+    persist_lock: Mutex<()>,
+}
+
+impl ImportJobStore {
+    fn new(path: Option<PathBuf>, jobs: Vec<ImportJob>) -> Self {
+        Self {
+            path,
+            jobs: Mutex::new(jobs.into_iter().map(|job| (job.id.clone(), job)).collect()),
+            cancel_flags: Mutex::new(HashMap::new()),
+            last_persist_ms: AtomicI64::new(0),
+            persist_lock: Mutex::new(()),
+        }
+    }
+
+    /// 从文件加载历史任务，上次未完成的任务标记为 interrupted
+    fn load(path: Option<PathBuf>) -> Self {
+        let mut jobs: Vec<ImportJob> = path
+            .as_ref()
+            .filter(|p| p.exists())
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(jobs) => Some(jobs),
+                Err(e) => {
+                    eprintln!("Failed to parse import jobs file: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        let now = chrono::Utc::now().timestamp();
+        for job in jobs.iter_mut().filter(|job| !job.is_finished()) {
+            job.status = ImportJobStatus::Interrupted;
+            job.updated_at = now;
+            job.finished_at = Some(now);
+        }
+
+        Self::new(path, jobs)
+    }
+
+    fn insert(&self, job: ImportJob) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.cancel_flags
+            .lock()
+            .unwrap()
+            .insert(job.id.clone(), flag.clone());
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(job.id.clone(), job);
+        prune_finished_jobs(&mut jobs, MAX_FINISHED_JOBS);
+        flag
+    }
+
+    pub fn get(&self, id: &str) -> Option<ImportJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// 所有任务概要（新任务在前）
+    pub fn list(&self) -> Vec<ImportJobSummary> {
+        let mut summaries: Vec<ImportJobSummary> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(ImportJob::summary)
+            .collect();
+        summaries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        summaries
+    }
+
+    fn record(&self, id: &str, index: usize, result: ImportResult) -> Option<ImportJobSummary> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id)?;
+        job.record(index, result, chrono::Utc::now().timestamp());
+        Some(job.summary())
+    }
+
+    fn finish(&self, id: &str) -> Option<ImportJob> {
+        let cancelled = self
+            .cancel_flags
+            .lock()
+            .unwrap()
+            .remove(id)
+            .map(|flag| flag.load(Ordering::SeqCst))
+            .unwrap_or(false);
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id)?;
+        job.finish(cancelled, chrono::Utc::now().timestamp());
+        Some(job.clone())
+    }
+
+    /// 请求取消运行中的任务；任务已结束时返回 Ok(false)
+    pub fn cancel(&self, id: &str) -> Result<bool, String> {
+        if !self.jobs.lock().unwrap().contains_key(id) {
+            return Err(format!("Import job not found: {}", id));
+        }
+        match self.cancel_flags.lock().unwrap().get(id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// 写入任务文件；`force` 为 false 时按 PERSIST_INTERVAL_MS 节流
+    fn persist(&self, force: bool) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let _guard = self.persist_lock.lock().unwrap();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if !force && now_ms - self.last_persist_ms.load(Ordering::SeqCst) < PERSIST_INTERVAL_MS {
+            return;
+        }
+        self.last_persist_ms.store(now_ms, Ordering::SeqCst);
+
+        let json = {
+            let jobs = self.jobs.lock().unwrap();
+            let mut list: Vec<&ImportJob> = jobs.values().collect();
+            list.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+            serde_json::to_string(&list)
+        };
+        let json = match json {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to serialize import jobs: {}", e);
+                return;
+            }
+        };
+
+        // 先写临时文件再替换，避免写入中断留下损坏的文件
+        let temp_path = path.with_extension("tmp");
+        if let Err(e) = fs::write(&temp_path, json).and_then(|_| fs::rename(&temp_path, path)) {
+            let _ = fs::remove_file(&temp_path);
+            eprintln!("Failed to write import jobs file: {}", e);
+        }
+    }
+}
+
+/// 只保留最新的 `keep` 个已结束任务（运行中的任务不受影响）
+fn prune_finished_jobs(jobs: &mut HashMap<String, ImportJob>, keep: usize) {
+    let mut finished: Vec<(i64, String)> = jobs
+        .values()
+        .filter(|job| job.is_finished())
+        .map(|job| (job.created_at, job.id.clone()))
+        .collect();
+    if finished.len() <= keep {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() - keep;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+fn get_import_jobs_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(app_data_dir.join(IMPORT_JOBS_FILE))
+}
+
+/// 全局任务存储，首次访问时加载历史任务
+pub fn job_store(app_handle: &AppHandle) -> &'static ImportJobStore {
+    IMPORT_JOBS.get_or_init(|| {
+        let path = get_import_jobs_path(app_handle)
+            .map_err(|e| eprintln!("Import jobs will not be persisted: {}", e))
+            .ok();
+        ImportJobStore::load(path)
+    })
+}
+
+// ==================== 任务执行 ====================
+
+/// 任务的导入来源
+pub(crate) enum ImportJobSource {
+    AugmentSessions(Vec<String>),
+    Accounts {
+        platform: AccountPlatform,
+        tokens: Vec<(String, bool)>,
+        token_type: OpenAITokenType,
+        org_id: Option<String>,
+    },
+}
+
+impl ImportJobSource {
+    fn platform(&self) -> &'static str {
+        match self {
+            ImportJobSource::AugmentSessions(_) => AUGMENT_PLATFORM,
+            ImportJobSource::Accounts { platform, .. } => platform.as_str(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            ImportJobSource::AugmentSessions(sessions) => sessions.len(),
+            ImportJobSource::Accounts { tokens, .. } => tokens.len(),
+        }
+    }
+
+    async fn import_item(&self, index: usize, state: &Arc<crate::AppState>) -> ImportResult {
+        match self {
+            ImportJobSource::AugmentSessions(sessions) => {
+                import_session_item(sessions[index].clone(), state.clone()).await
+            }
+            ImportJobSource::Accounts {
+                platform,
+                tokens,
+                token_type,
+                org_id,
+            } => {
+                let (token, duplicate) = tokens[index].clone();
+                import_account_item(
+                    *platform,
+                    &state.app_handle,
+                    token,
+                    duplicate,
+                    *token_type,
+                    org_id.clone(),
+                )
+                .await
+            }
+        }
+    }
+}
+
+fn emit_job_progress(app: &AppHandle, summary: &ImportJobSummary) {
+    if let Err(e) = app.emit("import-job-progress", summary) {
+        eprintln!("⚠️  Failed to emit import-job-progress event: {}", e);
+    }
+}
+
+/// 创建任务并在后台执行，立即返回任务概要
+pub(crate) fn start_import_job(
+    state: Arc<crate::AppState>,
+    source: ImportJobSource,
+) -> ImportJobSummary {
+    let store = job_store(&state.app_handle);
+    let job = ImportJob::new(
+        source.platform(),
+        source.len(),
+        chrono::Utc::now().timestamp(),
+    );
+    let summary = job.summary();
+    let cancel_flag = store.insert(job);
+    store.persist(true);
+    emit_job_progress(&state.app_handle, &summary);
+
+    println!(
+        "📥 API: Started import job {} ({} {} items)",
+        summary.id, summary.total, summary.platform
+    );
+
+    tokio::spawn(run_import_job(
+        state,
+        summary.id.clone(),
+        Arc::new(source),
+        cancel_flag,
+    ));
+    summary
+}
+
+async fn run_import_job(
+    state: Arc<crate::AppState>,
+    job_id: String,
+    source: Arc<ImportJobSource>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    let store = job_store(&state.app_handle);
+    let semaphore = Arc::new(Semaphore::new(JOB_CONCURRENCY));
+    let mut tasks = Vec::new();
+
+    for index in 0..source.len() {
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        if cancel_flag.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let state = state.clone();
+        let source = source.clone();
+        let job_id = job_id.clone();
+        tasks.push(tokio::spawn(async move {
+            let result = source.import_item(index, &state).await;
+            drop(permit);
+            if let Some(summary) = store.record(&job_id, index, result) {
+                store.persist(false);
+                emit_job_progress(&state.app_handle, &summary);
+            }
+        }));
+    }
+
+    for task in tasks {
+        if let Err(e) = task.await {
+            eprintln!("⚠️  Import job {} task error: {}", job_id, e);
+        }
+    }
+
+    let Some(job) = store.finish(&job_id) else {
+        return;
+    };
+    store.persist(true);
+    emit_job_progress(&state.app_handle, &job.summary());
+
+    println!(
+        "✅ API: Import job {} {:?} - {}/{} successful",
+        job.id, job.status, job.successful, job.total
+    );
+
+    // 有成功导入的条目时通知前端刷新列表
+    if job.successful > 0 {
+        match source.as_ref() {
+            ImportJobSource::AugmentSessions(_) => {
+                if let Err(e) = state.app_handle.emit("tokens-updated", ()) {
+                    eprintln!("⚠️  Failed to emit tokens-updated event: {}", e);
+                }
+            }
+            ImportJobSource::Accounts { platform, .. } => {
+                let account_ids: Vec<&str> = job
+                    .results
+                    .iter()
+                    .flatten()
+                    .filter_map(|r| r.account_id.as_deref())
+                    .collect();
+                emit_accounts_updated(&state.app_handle, *platform, "api-import-job", &account_ids);
+            }
+        }
+    }
+}
+
+// ==================== 路由处理器 ====================
+
+fn validate_job_size(len: usize) -> Result<(), warp::reply::WithStatus<warp::reply::Json>> {
+    if len == 0 {
+        return Err(ApiError::new(ApiErrorCode::EmptyArray, "Import list cannot be empty").reply());
+    }
+    if len > MAX_JOB_ITEMS {
+        return Err(ApiError::new(
+            ApiErrorCode::TooManyItems,
+            format!("Too many items (max {})", MAX_JOB_ITEMS),
+        )
+        .reply());
+    }
+    Ok(())
+}
+
+/// POST /api/import/jobs：Augment session 导入任务
+pub(crate) async fn create_session_job_handler(
+    request: ImportSessionsRequest,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    if let Err(reply) = validate_job_size(request.sessions.len()) {
+        return Ok(reply);
+    }
+    let summary = start_import_job(state, ImportJobSource::AugmentSessions(request.sessions));
+    Ok(warp::reply::with_status(
+        warp::reply::json(&summary),
+        StatusCode::ACCEPTED,
+    ))
+}
+
+/// POST /api/{platform}/accounts/jobs：多平台账号导入任务
+pub(crate) async fn create_account_job_handler(
+    platform: AccountPlatform,
+    request: AccountImportRequest,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    if let Err(reply) = validate_job_size(request.tokens.len()) {
+        return Ok(reply);
+    }
+    let source = ImportJobSource::Accounts {
+        platform,
+        tokens: dedupe_tokens(&request.tokens),
+        token_type: request.token_type,
+        org_id: request.org_id,
+    };
+    let summary = start_import_job(state, source);
+    Ok(warp::reply::with_status(
+        warp::reply::json(&summary),
+        StatusCode::ACCEPTED,
+    ))
+}
+
+/// GET /api/import/jobs
+pub(crate) async fn list_jobs_handler(
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    let jobs = job_store(&state.app_handle).list();
+    Ok(warp::reply::with_status(
+        warp::reply::json(&jobs),
+        StatusCode::OK,
+    ))
+}
+
+/// GET /api/import/jobs/{id}
+pub(crate) async fn get_job_handler(
+    job_id: String,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    match job_store(&state.app_handle).get(&job_id) {
+        Some(job) => Ok(warp::reply::with_status(
+            warp::reply::json(&job.detail()),
+            StatusCode::OK,
+        )),
+        None => Ok(ApiError::new(
+            ApiErrorCode::JobNotFound,
+            format!("Import job not found: {}", job_id),
+        )
+        .reply()),
+    }
+}
+
+/// DELETE /api/import/jobs/{id}：取消任务
+pub(crate) async fn cancel_job_handler(
+    job_id: String,
+    state: Arc<crate::AppState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, Rejection> {
+    let store = job_store(&state.app_handle);
+    match store.cancel(&job_id) {
+        Ok(true) => {
+            println!("🛑 API: Cancelling import job {}", job_id);
+            let summary = store.get(&job_id).map(|job| job.summary());
+            Ok(warp::reply::with_status(
+                warp::reply::json(&summary),
+                StatusCode::ACCEPTED,
+            ))
+        }
+        Ok(false) => Ok(ApiError::new(
+            ApiErrorCode::JobFinished,
+            format!("Import job {} has already finished", job_id),
+        )
+        .reply()),
+        Err(e) => Ok(ApiError::new(ApiErrorCode::JobNotFound, e).reply()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportJob, ImportJobStatus, ImportJobStore, prune_finished_jobs};
+    use crate::core::api_server::ImportResult;
+    use std::collections::HashMap;
+
+    fn result(success: bool) -> ImportResult {
+        ImportResult {
+            success,
+            token_data: None,
+            account: None,
+            error: (!success).then(|| "failed".to_string()),
+            session_preview: Some("abcd***e".to_string()),
+        }
+    }
+
+    #[test]
+    fn job_records_results_in_order_and_reports_on_finish() {
+        let mut job = ImportJob::new("augment", 3, 100);
+        job.record(2, result(false), 101);
+        job.record(0, result(true), 102);
+        job.record(0, result(false), 103);
+        assert_eq!((job.processed, job.successful, job.failed), (2, 1, 1));
+        assert!(job.detail().results.is_some());
+
+        job.finish(true, 104);
+        assert_eq!(job.status, ImportJobStatus::Cancelled);
+        let report = job.report();
+        assert_eq!(report.total, 3);
+        assert_eq!(report.results.len(), 2);
+        assert!(report.results[0].success);
+        assert!(job.detail().report.is_some());
+
+        let mut done = ImportJob::new("openai", 1, 100);
+        let mut imported = result(true);
+        imported.account = Some(serde_json::json!({
+            "id": "acc-1",
+            "email": "a@example.com",
+            "access_token": "secret-access-token"
+        }));
+        done.record(0, imported, 101);
+        done.finish(true, 102);
+        assert_eq!(done.status, ImportJobStatus::Completed);
+        let item = done.results[0].as_ref().unwrap();
+        assert_eq!(item.account_id.as_deref(), Some("acc-1"));
+        assert_eq!(item.email.as_deref(), Some("a@example.com"));
+        // 任务中不保存账号内容
+        assert!(!serde_json::to_string(&done).unwrap().contains("secret-access-token"));
+    }
+
+    #[test]
+    fn store_marks_running_jobs_interrupted_and_prunes_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("import_jobs.json");
+
+        let store = ImportJobStore::new(Some(path.clone()), Vec::new());
+        let running = ImportJob::new("augment", 2, 100);
+        let running_id = running.id.clone();
+        let flag = store.insert(running);
+        assert!(store.cancel(&running_id).unwrap());
+        assert!(flag.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(store.cancel("missing").is_err());
+        store.persist(true);
+
+        let reloaded = ImportJobStore::load(Some(path));
+        let job = reloaded.get(&running_id).unwrap();
+        assert_eq!(job.status, ImportJobStatus::Interrupted);
+        assert!(!reloaded.cancel(&running_id).unwrap());
+
+        let mut jobs = HashMap::new();
+        for created_at in 0..5 {
+            let mut job = ImportJob::new("augment", 0, created_at);
+            job.finish(false, created_at);
+            jobs.insert(job.id.clone(), job);
+        }
+        let live = ImportJob::new("augment", 1, 0);
+        jobs.insert(live.id.clone(), live);
+        prune_finished_jobs(&mut jobs, 2);
+        assert_eq!(jobs.len(), 3);
+        assert!(
+            jobs.values()
+                .any(|job| job.status == ImportJobStatus::Running)
+        );
+        assert!(
+            jobs.values()
+                .filter(|job| job.is_finished())
+                .all(|job| job.created_at >= 3)
+        );
+    }
+}
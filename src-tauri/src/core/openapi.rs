@@ -0,0 +1,783 @@
+//! 本地 HTTP API 的 OpenAPI 3 文档（`GET /api/openapi.json`）
+//!
+//! 文档由下方的路由表生成：错误码枚举、各状态码说明均来自 [`ApiErrorCode`]，
+//! 平台路径参数来自 [`AccountPlatform::ALL`]。新增路由或错误码时同步修改路由表，
+//! 测试会把路由表中的每个接口送进真实的 warp 路由树，缺失或未登记的路由都会失败。
+
+use crate::core::api_accounts::AccountPlatform;
+use crate::core::api_error::ApiErrorCode;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use warp::{Rejection, Reply};
+
+/// 接口鉴权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Auth {
+    /// 无需 Key
+    None,
+    /// 非回环监听时要求 Codex 管理员 Key
+    Import,
+    /// 始终要求 Codex 管理员 Key
+    Admin,
+    /// Codex 管理员 Key 或客户端 Key
+    Client,
+}
+
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    auth: Auth,
+    /// 请求体 schema 名称
+    request: Option<&'static str>,
+    /// 成功状态码与响应 schema 名称（None 表示非 JSON 响应）
+    response: (u16, Option<&'static str>),
+    /// 路由自身可能返回的错误码（访问控制、请求体等公共错误自动补充）
+    errors: &'static [ApiErrorCode],
+}
+
+impl Operation {
+    /// Codex 服务的路由（含号池状态与指标）使用 OpenAI 兼容错误体
+    fn is_codex(&self) -> bool {
+        matches!(self.tag, "codex" | "pool")
+    }
+
+    fn error_codes(&self) -> Vec<ApiErrorCode> {
+        let mut common = vec![ApiErrorCode::Forbidden];
+        if self.auth != Auth::None {
+            common.push(ApiErrorCode::Unauthorized);
+        }
+        if self.request.is_some() && !self.is_codex() {
+            common.push(ApiErrorCode::InvalidJson);
+            common.push(ApiErrorCode::PayloadTooLarge);
+        }
+        common.push(ApiErrorCode::InternalError);
+
+        let mut codes = self.errors.to_vec();
+        for code in common {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+        codes
+    }
+}
+
+/// 与具体路由无关的错误（未知路径、方法不匹配），放在 components.responses 中
+const GLOBAL_ERRORS: &[ApiErrorCode] = &[ApiErrorCode::NotFound, ApiErrorCode::MethodNotAllowed];
+
+const IMPORT_ERRORS: &[ApiErrorCode] = &[
+    ApiErrorCode::InvalidSession,
+    ApiErrorCode::DuplicateEmail,
+    ApiErrorCode::ImportError,
+    ApiErrorCode::StorageError,
+];
+
+const ACCOUNT_ERRORS: &[ApiErrorCode] = &[
+    ApiErrorCode::AccountNotFound,
+    ApiErrorCode::NotSupported,
+    ApiErrorCode::OperationFailed,
+];
+
+const CODEX_ERRORS: &[ApiErrorCode] = &[
+    ApiErrorCode::InvalidRequest,
+    ApiErrorCode::TranslationError,
+    ApiErrorCode::RateLimited,
+    ApiErrorCode::PoolCoolingDown,
+    ApiErrorCode::NoAvailableAccount,
+    ApiErrorCode::ServiceUnavailable,
+    ApiErrorCode::ExecutionError,
+    ApiErrorCode::UpstreamTimeout,
+];
+
+const OPERATIONS: &[Operation] = &[
+    Operation {
+        method: "get",
+        path: "/api/health",
+        tag: "health",
+        summary: "API server health check",
+        auth: Auth::None,
+        request: None,
+        response: (200, Some("HealthResponse")),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/api/openapi.json",
+        tag: "health",
+        summary: "This OpenAPI document",
+        auth: Auth::None,
+        request: None,
+        response: (200, None),
+        errors: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/api/import/session",
+        tag: "import",
+        summary: "Import a single Augment session",
+        auth: Auth::Import,
+        request: Some("ImportSessionRequest"),
+        response: (200, Some("ImportResult")),
+        errors: IMPORT_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/api/import/sessions",
+        tag: "import",
+        summary: "Import Augment sessions in batch",
+        auth: Auth::Import,
+        request: Some("ImportSessionsRequest"),
+        response: (200, Some("BatchImportResult")),
+        errors: &[ApiErrorCode::EmptyArray, ApiErrorCode::TooManySessions],
+    },
+    Operation {
+        method: "post",
+        path: "/api/{platform}/accounts",
+        tag: "import",
+        summary: "Import platform accounts in batch",
+        auth: Auth::Import,
+        request: Some("AccountImportRequest"),
+        response: (200, Some("BatchImportResult")),
+        errors: &[ApiErrorCode::EmptyArray, ApiErrorCode::TooManyTokens],
+    },
+    Operation {
+        method: "post",
+        path: "/api/import/jobs",
+        tag: "import",
+        summary: "Start an asynchronous Augment session import job",
+        auth: Auth::Import,
+        request: Some("ImportSessionsRequest"),
+        response: (202, Some("ImportJobSummary")),
+        errors: &[ApiErrorCode::EmptyArray, ApiErrorCode::TooManyItems],
+    },
+    Operation {
+        method: "post",
+        path: "/api/{platform}/accounts/jobs",
+        tag: "import",
+        summary: "Start an asynchronous platform account import job",
+        auth: Auth::Import,
+        request: Some("AccountImportRequest"),
+        response: (202, Some("ImportJobSummary")),
+        errors: &[ApiErrorCode::EmptyArray, ApiErrorCode::TooManyItems],
+    },
+    Operation {
+        method: "get",
+        path: "/api/import/jobs",
+        tag: "import",
+        summary: "List import jobs",
+        auth: Auth::Import,
+        request: None,
+        response: (200, Some("ImportJobList")),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/api/import/jobs/{id}",
+        tag: "import",
+        summary: "Import job progress and report",
+        auth: Auth::Import,
+        request: None,
+        response: (200, Some("ImportJobDetail")),
+        errors: &[ApiErrorCode::JobNotFound],
+    },
+    Operation {
+        method: "delete",
+        path: "/api/import/jobs/{id}",
+        tag: "import",
+        summary: "Cancel a running import job",
+        auth: Auth::Import,
+        request: None,
+        response: (202, Some("ImportJobSummary")),
+        errors: &[ApiErrorCode::JobNotFound, ApiErrorCode::JobFinished],
+    },
+    Operation {
+        method: "get",
+        path: "/api/{platform}/accounts",
+        tag: "accounts",
+        summary: "List accounts and the current account (credentials omitted)",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: &[ApiErrorCode::OperationFailed],
+    },
+    Operation {
+        method: "get",
+        path: "/api/{platform}/accounts/{id}",
+        tag: "accounts",
+        summary: "Get a single account (credentials omitted)",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: ACCOUNT_ERRORS,
+    },
+    Operation {
+        method: "delete",
+        path: "/api/{platform}/accounts/{id}",
+        tag: "accounts",
+        summary: "Delete an account",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: ACCOUNT_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/api/{platform}/accounts/{id}/switch",
+        tag: "accounts",
+        summary: "Switch the current account",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: ACCOUNT_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/api/{platform}/accounts/{id}/quota",
+        tag: "accounts",
+        summary: "Refresh the quota of an account",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: ACCOUNT_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/api/{platform}/quotas/refresh",
+        tag: "accounts",
+        summary: "Refresh the quota of every account",
+        auth: Auth::Admin,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: &[ApiErrorCode::NotSupported, ApiErrorCode::OperationFailed],
+    },
+    Operation {
+        method: "get",
+        path: "/health",
+        tag: "pool",
+        summary: "Codex server health check",
+        auth: Auth::None,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/pool/status",
+        tag: "pool",
+        summary: "Codex account pool status",
+        auth: Auth::None,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: &[ApiErrorCode::ServiceUnavailable],
+    },
+    Operation {
+        method: "get",
+        path: "/metrics",
+        tag: "pool",
+        summary: "Prometheus metrics",
+        auth: Auth::Client,
+        request: None,
+        response: (200, None),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/v1/models",
+        tag: "codex",
+        summary: "Models available to the Codex pool",
+        auth: Auth::None,
+        request: None,
+        response: (200, Some("JsonObject")),
+        errors: &[ApiErrorCode::ServiceUnavailable],
+    },
+    Operation {
+        method: "post",
+        path: "/v1/responses",
+        tag: "codex",
+        summary: "OpenAI Responses API passthrough",
+        auth: Auth::Client,
+        request: Some("JsonObject"),
+        response: (200, Some("JsonObject")),
+        errors: CODEX_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/v1/chat/completions",
+        tag: "codex",
+        summary: "Chat Completions, translated to the Responses API",
+        auth: Auth::Client,
+        request: Some("JsonObject"),
+        response: (200, Some("JsonObject")),
+        errors: CODEX_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/v1/messages",
+        tag: "codex",
+        summary: "Anthropic Messages, translated to the Responses API",
+        auth: Auth::Client,
+        request: Some("JsonObject"),
+        response: (200, Some("JsonObject")),
+        errors: CODEX_ERRORS,
+    },
+    Operation {
+        method: "post",
+        path: "/backend-api/codex/responses",
+        tag: "codex",
+        summary: "ChatGPT Codex backend passthrough",
+        auth: Auth::Client,
+        request: Some("JsonObject"),
+        response: (200, Some("JsonObject")),
+        errors: CODEX_ERRORS,
+    },
+];
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let schema = if name == "platform" {
+                schema_ref("Platform")
+            } else {
+                json!({ "type": "string" })
+            };
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect()
+}
+
+/// 按状态码合并错误码，生成错误响应
+fn error_responses(op: &Operation) -> BTreeMap<String, Value> {
+    let mut by_status: BTreeMap<u16, Vec<ApiErrorCode>> = BTreeMap::new();
+    for code in op.error_codes() {
+        by_status
+            .entry(code.status().as_u16())
+            .or_default()
+            .push(code);
+    }
+
+    let schema = if op.is_codex() {
+        schema_ref("OpenAIError")
+    } else {
+        schema_ref("ApiError")
+    };
+    by_status
+        .into_iter()
+        .map(|(status, codes)| {
+            let description = codes
+                .iter()
+                .map(|c| format!("`{}`: {}", c.as_str(), c.description()))
+                .collect::<Vec<_>>()
+                .join("; ");
+            let response = json!({
+                "description": description,
+                "content": json_content(schema.clone()),
+            });
+            (status.to_string(), response)
+        })
+        .collect()
+}
+
+fn operation_object(op: &Operation) -> Value {
+    let mut responses = Map::new();
+    let (status, schema) = op.response;
+    let success = match schema {
+        Some(name) => json!({ "description": "OK", "content": json_content(schema_ref(name)) }),
+        None => json!({ "description": "OK" }),
+    };
+    responses.insert(status.to_string(), success);
+    for (status, response) in error_responses(op) {
+        responses.insert(status, response);
+    }
+
+    let mut object = json!({
+        "tags": [op.tag],
+        "summary": op.summary,
+        "operationId": format!("{} {}", op.method, op.path),
+        "responses": responses,
+    });
+    let mut parameters = path_parameters(op.path);
+    if op.path.ends_with("/switch") {
+        parameters.push(json!({
+            "name": "use_bound_machine_id",
+            "in": "query",
+            "required": false,
+            "schema": { "type": "boolean" }
+        }));
+    }
+    if !parameters.is_empty() {
+        object["parameters"] = Value::Array(parameters);
+    }
+    if let Some(name) = op.request {
+        object["requestBody"] = json!({
+            "required": true,
+            "content": json_content(schema_ref(name)),
+        });
+    }
+    match op.auth {
+        Auth::None => {}
+        Auth::Import => {
+            object["description"] = json!(
+                "Requires the admin API key when the server listens on a non-loopback address"
+            );
+            object["security"] = json!([{ "bearerAuth": [] }, { "apiKeyAuth": [] }, {}]);
+        }
+        Auth::Admin | Auth::Client => {
+            object["security"] = json!([{ "bearerAuth": [] }, { "apiKeyAuth": [] }]);
+        }
+    }
+    object
+}
+
+fn component_schemas() -> Value {
+    let error_codes: Vec<&str> = ApiErrorCode::ALL.iter().map(|c| c.as_str()).collect();
+    let platforms: Vec<&str> = AccountPlatform::ALL.iter().map(|p| p.as_str()).collect();
+    json!({
+        "ErrorCode": { "type": "string", "enum": error_codes },
+        "Platform": { "type": "string", "enum": platforms },
+        "ApiError": {
+            "type": "object",
+            "required": ["error", "code"],
+            "properties": {
+                "error": { "type": "string" },
+                "code": schema_ref("ErrorCode")
+            }
+        },
+        "OpenAIError": {
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": {
+                    "type": "object",
+                    "required": ["message", "type", "code"],
+                    "properties": {
+                        "message": { "type": "string" },
+                        "type": { "type": "string" },
+                        "code": schema_ref("ErrorCode")
+                    }
+                }
+            }
+        },
+        "JsonObject": { "type": "object", "additionalProperties": true },
+        "HealthResponse": {
+            "type": "object",
+            "properties": {
+                "status": { "type": "string" },
+                "version": { "type": "string" },
+                "port": { "type": "integer" }
+            }
+        },
+        "ImportSessionRequest": {
+            "type": "object",
+            "required": ["session"],
+            "properties": {
+                "session": { "type": "string" },
+                "detailed_response": { "type": "boolean", "default": true }
+            }
+        },
+        "ImportSessionsRequest": {
+            "type": "object",
+            "required": ["sessions"],
+            "properties": {
+                "sessions": { "type": "array", "items": { "type": "string" } },
+                "detailed_response": { "type": "boolean", "default": true }
+            }
+        },
+        "AccountImportRequest": {
+            "type": "object",
+            "required": ["tokens"],
+            "properties": {
+                "tokens": { "type": "array", "items": { "type": "string" } },
+                "token_type": {
+                    "type": "string",
+                    "enum": ["access_token", "refresh_token"],
+                    "default": "access_token",
+                    "description": "OpenAI only"
+                },
+                "org_id": { "type": "string", "description": "Windsurf only" },
+                "detailed_response": { "type": "boolean", "default": true }
+            }
+        },
+        "ImportResult": {
+            "type": "object",
+            "required": ["success"],
+            "properties": {
+                "success": { "type": "boolean" },
+                "token_data": schema_ref("JsonObject"),
+                "account": schema_ref("JsonObject"),
+                "error": { "type": "string" },
+                "session_preview": { "type": "string" }
+            }
+        },
+        "BatchImportResult": {
+            "type": "object",
+            "properties": {
+                "total": { "type": "integer" },
+                "successful": { "type": "integer" },
+                "failed": { "type": "integer" },
+                "results": { "type": "array", "items": schema_ref("ImportResult") }
+            }
+        },
+        "ImportJobSummary": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "platform": { "type": "string" },
+                "status": {
+                    "type": "string",
+                    "enum": ["running", "completed", "cancelled", "interrupted"]
+                },
+                "total": { "type": "integer" },
+                "processed": { "type": "integer" },
+                "successful": { "type": "integer" },
+                "failed": { "type": "integer" },
+                "created_at": { "type": "integer" },
+                "updated_at": { "type": "integer" },
+                "finished_at": { "type": "integer", "nullable": true }
+            }
+        },
+        "ImportJobList": { "type": "array", "items": schema_ref("ImportJobSummary") },
+        "ImportJobItem": {
+            "type": "object",
+            "required": ["success"],
+            "properties": {
+                "success": { "type": "boolean" },
+                "account_id": { "type": "string" },
+                "email": { "type": "string" },
+                "error": { "type": "string" },
+                "session_preview": { "type": "string" }
+            }
+        },
+        "ImportJobBatchResult": {
+            "description": "BatchImportResult envelope whose items omit account contents",
+            "type": "object",
+            "properties": {
+                "total": { "type": "integer" },
+                "successful": { "type": "integer" },
+                "failed": { "type": "integer" },
+                "results": { "type": "array", "items": schema_ref("ImportJobItem") }
+            }
+        },
+        "ImportJobDetail": {
+            "allOf": [
+                schema_ref("ImportJobSummary"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "results": {
+                            "type": "array",
+                            "items": { "allOf": [schema_ref("ImportJobItem")], "nullable": true }
+                        },
+                        "report": schema_ref("ImportJobBatchResult")
+                    }
+                }
+            ]
+        }
+    })
+}
+
+fn global_responses() -> Value {
+    let responses: Map<String, Value> = GLOBAL_ERRORS
+        .iter()
+        .map(|code| {
+            let response = json!({
+                "description": format!(
+                    "HTTP {}, `{}`: {}",
+                    code.status().as_u16(),
+                    code.as_str(),
+                    code.description()
+                ),
+                "content": json_content(schema_ref("ApiError")),
+            });
+            (code.as_str().to_string(), response)
+        })
+        .collect();
+    Value::Object(responses)
+}
+
+/// 生成 OpenAPI 文档
+pub fn build_openapi_spec(server_url: &str) -> Value {
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let item = paths
+            .entry(op.path.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[op.method] = operation_object(op);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "ATM Local API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Account import, management and Codex passthrough. \
+                Errors on /api routes use ApiError; Codex routes use the OpenAI error envelope."
+        },
+        "servers": [{ "url": server_url }],
+        "tags": [
+            { "name": "health" },
+            { "name": "import" },
+            { "name": "accounts" },
+            { "name": "pool" },
+            { "name": "codex" }
+        ],
+        "paths": paths,
+        "components": {
+            "schemas": component_schemas(),
+            "responses": global_responses(),
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" },
+                "apiKeyAuth": { "type": "apiKey", "in": "header", "name": "X-API-Key" }
+            }
+        }
+    })
+}
+
+/// GET /api/openapi.json
+pub(crate) async fn openapi_handler(server_url: String) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&build_openapi_spec(&server_url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GLOBAL_ERRORS, OPERATIONS, build_openapi_spec};
+    use crate::AppState;
+    use crate::core::api_accounts::AccountPlatform;
+    use crate::core::api_error::ApiErrorCode;
+    use crate::core::api_server::api_routes;
+    use crate::platforms::openai::codex::server::{
+        PASSTHROUGH_ROUTE, codex_routes, is_supported_proxy_path,
+    };
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use warp::filters::BoxedFilter;
+    use warp::{Filter, Rejection};
+
+    /// 请求命中的路由（测试中由路由标记直接拒绝，不会执行处理函数）
+    #[derive(Debug)]
+    struct MatchedRoute(&'static str);
+
+    impl warp::reject::Reject for MatchedRoute {}
+
+    #[test]
+    fn spec_covers_every_error_code_and_route_status() {
+        let spec = build_openapi_spec("http://127.0.0.1:8766");
+        let enum_values: Vec<&str> = spec["components"]["schemas"]["ErrorCode"]["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        let expected: Vec<&str> = ApiErrorCode::ALL.iter().map(|c| c.as_str()).collect();
+        assert_eq!(enum_values, expected);
+
+        // 每个错误码至少出现在一个路由上，且挂在与其状态码一致的响应下
+        let mut documented: HashSet<ApiErrorCode> = GLOBAL_ERRORS.iter().copied().collect();
+        for code in GLOBAL_ERRORS {
+            assert!(spec["components"]["responses"][code.as_str()].is_object());
+        }
+        for op in OPERATIONS {
+            let responses = &spec["paths"][op.path][op.method]["responses"];
+            assert!(responses.is_object(), "missing {} {}", op.method, op.path);
+            for code in op.error_codes() {
+                let status = code.status().as_u16().to_string();
+                let description = responses[&status]["description"].as_str().unwrap();
+                assert!(description.contains(code.as_str()));
+                documented.insert(code);
+            }
+        }
+        assert!(ApiErrorCode::ALL.iter().all(|c| documented.contains(c)));
+
+        let codex = &spec["paths"]["/v1/responses"]["post"]["responses"]["429"];
+        assert_eq!(
+            codex["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/OpenAIError"
+        );
+        let platform = &spec["paths"]["/api/{platform}/accounts"]["post"]["parameters"][0];
+        assert_eq!(platform["name"], "platform");
+    }
+
+    /// 构建路由时登记的全部路由标记
+    static DECLARED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    fn reject_with_route(route: &'static str) -> BoxedFilter<()> {
+        DECLARED.lock().unwrap().push(route);
+        warp::any()
+            .and_then(move || async move {
+                Err::<(), Rejection>(warp::reject::custom(MatchedRoute(route)))
+            })
+            .untuple_one()
+            .boxed()
+    }
+
+    #[tokio::test]
+    async fn operations_match_the_real_route_tree() {
+        let state_filter = warp::any()
+            .and_then(|| async { Err::<Arc<AppState>, Rejection>(warp::reject::not_found()) })
+            .boxed();
+        let routes = api_routes(
+            state_filter.clone(),
+            8766,
+            "http://127.0.0.1:8766".to_string(),
+            true,
+            reject_with_route,
+        )
+        .or(codex_routes(state_filter, reject_with_route));
+
+        // 每个文档接口都命中同名路由（透传接口命中统一入口且路径受支持）
+        for op in OPERATIONS {
+            let expected = format!("{} {}", op.method, op.path);
+            for platform in AccountPlatform::ALL {
+                let path = op
+                    .path
+                    .replace("{platform}", platform.as_str())
+                    .replace("{id}", "acc-1");
+                let rejection = match warp::test::request()
+                    .method(&op.method.to_uppercase())
+                    .path(&path)
+                    .filter(&routes)
+                    .await
+                {
+                    Ok(_) => panic!("{} reached a handler", expected),
+                    Err(rejection) => rejection,
+                };
+                let matched = rejection
+                    .find::<MatchedRoute>()
+                    .unwrap_or_else(|| panic!("{} matches no route", expected))
+                    .0;
+                if matched == PASSTHROUGH_ROUTE {
+                    assert!(
+                        op.is_codex() && is_supported_proxy_path(op.path),
+                        "{}",
+                        expected
+                    );
+                } else {
+                    assert_eq!(matched, expected);
+                }
+            }
+        }
+
+        // 每条具名路由都出现在文档中
+        let documented: HashSet<String> = OPERATIONS
+            .iter()
+            .map(|op| format!("{} {}", op.method, op.path))
+            .collect();
+        for route in DECLARED.lock().unwrap().iter() {
+            assert!(
+                *route == PASSTHROUGH_ROUTE || documented.contains(*route),
+                "{} is not documented",
+                route
+            );
+        }
+    }
+}
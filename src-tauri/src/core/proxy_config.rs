@@ -1,6 +1,7 @@
+use crate::core::secret_store;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tauri::Manager;
 
@@ -171,25 +172,14 @@ impl ProxyConfigManager {
     #[allow(dead_code)]
     pub fn save_config(&self, config: &ProxyConfig) -> Result<(), String> {
         let path = self.get_config_path()?;
-        let json = serde_json::to_string_pretty(config)
-            .map_err(|e| format!("Failed to serialize proxy config: {}", e))?;
-
-        fs::write(&path, json).map_err(|e| format!("Failed to write proxy config: {}", e))?;
-
-        Ok(())
+        write_proxy_config(&path, config)
     }
 
     #[allow(dead_code)]
     pub fn load_config(&self) -> Result<ProxyConfig, String> {
         let path = self.get_config_path()?;
         if path.exists() {
-            let json = std::fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read proxy config: {}", e))?;
-
-            let config: ProxyConfig = serde_json::from_str(&json)
-                .map_err(|e| format!("Failed to parse proxy config: {}", e))?;
-
-            Ok(config)
+            read_proxy_config(&path)
         } else {
             Ok(ProxyConfig::default())
         }
@@ -244,6 +234,45 @@ fn get_proxy_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, Strin
     Ok(app_data_dir.join("proxy_config.json"))
 }
 
+/// 写入代理配置，密码用主密钥加密
+fn write_proxy_config(path: &Path, config: &ProxyConfig) -> Result<(), String> {
+    let mut stored = config.clone();
+    stored.password = config
+        .password
+        .as_deref()
+        .map(secret_store::encrypt_secret)
+        .transpose()?;
+
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+/// 读取代理配置并解密密码，旧版本的明文密码会重新加密写回
+pub(crate) fn read_proxy_config(path: &Path) -> Result<ProxyConfig, String> {
+    let json =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
+    let mut config: ProxyConfig =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse config file: {}", e))?;
+
+    let stored_password = config.password.take();
+    config.password = stored_password
+        .as_deref()
+        .map(secret_store::decrypt_secret)
+        .transpose()?;
+
+    if stored_password
+        .as_deref()
+        .is_some_and(secret_store::needs_migration)
+    {
+        if let Err(e) = write_proxy_config(path, &config) {
+            eprintln!("Failed to migrate proxy password: {}", e);
+        }
+    }
+
+    Ok(config)
+}
+
 /// 保存代理配置到文件
 pub fn save_proxy_config_internal(
     app_handle: &tauri::AppHandle,
@@ -255,26 +284,17 @@ pub fn save_proxy_config_internal(
     let mut final_config = config.clone();
     if config_path.exists() && config.password.as_ref().map_or(true, |p| p.is_empty()) {
         // 尝试加载现有配置以获取原密码
-        if let Ok(existing_json) = fs::read_to_string(&config_path) {
-            if let Ok(existing_config) = serde_json::from_str::<ProxyConfig>(&existing_json) {
-                // 如果新密码为空但旧密码不为空，使用旧密码
-                if config.password.as_ref().map_or(true, |p| p.is_empty())
-                    && existing_config.password.is_some()
-                {
-                    final_config.password = existing_config.password;
-                }
+        if let Ok(existing_config) = read_proxy_config(&config_path) {
+            // 如果新密码为空但旧密码不为空，使用旧密码
+            if config.password.as_ref().map_or(true, |p| p.is_empty())
+                && existing_config.password.is_some()
+            {
+                final_config.password = existing_config.password;
             }
         }
     }
 
-    // 序列化配置
-    let json = serde_json::to_string_pretty(&final_config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-
-    // 写入文件
-    fs::write(&config_path, json).map_err(|e| format!("Failed to write config file: {}", e))?;
-
-    Ok(())
+    write_proxy_config(&config_path, &final_config)
 }
 
 /// 从文件加载代理配置
@@ -286,15 +306,7 @@ pub fn load_proxy_config_internal(app_handle: &tauri::AppHandle) -> Result<Proxy
         return Ok(ProxyConfig::default());
     }
 
-    // 读取文件
-    let json = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
-
-    // 反序列化配置
-    let config: ProxyConfig =
-        serde_json::from_str(&json).map_err(|e| format!("Failed to parse config file: {}", e))?;
-
-    Ok(config)
+    read_proxy_config(&config_path)
 }
 
 /// 删除代理配置文件
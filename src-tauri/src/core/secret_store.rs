@@ -0,0 +1,602 @@
+//! 本地配置中敏感字段的主密钥管理
+//!
+//! 每次安装生成一个随机的 256 位主密钥，优先保存在系统密钥库：
+//! - Windows: DPAPI 保护后写入应用数据目录
+//! - macOS: Keychain
+//! - Linux: Secret Service
+//!
+//! 系统密钥库不可用时，主密钥用口令派生的密钥（PBKDF2-HMAC-SHA256）加密后保存在 master_key.json，
+//! 每次启动后需要输入口令解锁。
+//!
+//! 主密钥曾保存在系统密钥库的安装会留下 master_key.keyring 标记。之后密钥库暂时不可用时
+//! 保持密钥库方式并报告错误，不会改用口令生成新的主密钥，否则已加密的值将无法解密。
+//!
+//! 数据库密码、代理密码、Telegram Bot Token、Raindrop Token 等字段用主密钥以 AES-256-GCM 加密，
+//! 密文格式为 `local:v1:` + base64(nonce || ciphertext)。未加前缀的值视为旧版本数据，
+//! 读取时原样返回，解锁后由各配置在加载时重新加密写回。
+
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit},
+};
+use base64::{Engine as _, engine::general_purpose};
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+pub const SECRET_PREFIX: &str = "local:v1:";
+const MASTER_KEY_FILE: &str = "master_key.json";
+/// 主密钥已保存在系统密钥库的标记
+const KEYRING_MARKER_FILE: &str = "master_key.keyring";
+const KEYRING_ATTEMPTS: u32 = 3;
+const KEYRING_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// 主密钥的保存方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MasterKeyBackend {
+    /// 系统密钥库
+    Keyring,
+    /// 口令保护的本地文件
+    File,
+}
+
+struct MasterKeyState {
+    key: Option<[u8; 32]>,
+    backend: MasterKeyBackend,
+    data_dir: Option<PathBuf>,
+    /// 最近一次读取系统密钥库失败的原因
+    keyring_error: Option<String>,
+}
+
+static MASTER_KEY: OnceLock<RwLock<MasterKeyState>> = OnceLock::new();
+
+fn key_state() -> &'static RwLock<MasterKeyState> {
+    MASTER_KEY.get_or_init(|| {
+        RwLock::new(MasterKeyState {
+            key: None,
+            backend: MasterKeyBackend::File,
+            data_dir: None,
+            keyring_error: None,
+        })
+    })
+}
+
+fn generate_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    let cipher = Aes256Gcm::new_from_slice(key).expect("key length is 32 bytes");
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    // AES-GCM 只会在明文超过 64GB 时失败
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+        .expect("AES-GCM encryption of in-memory data");
+
+    let mut data = nonce_bytes.to_vec();
+    data.extend_from_slice(&ciphertext);
+    data
+}
+
+fn open(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 12 {
+        return Err("Invalid encrypted data".to_string());
+    }
+    let (nonce_bytes, ciphertext) = data.split_at(12);
+    let cipher = Aes256Gcm::new_from_slice(key).expect("key length is 32 bytes");
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| "Failed to decrypt data".to_string())
+}
+
+// ==================== 加密/解密 ====================
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SECRET_PREFIX)
+}
+
+pub fn is_unlocked() -> bool {
+    key_state().read().unwrap().key.is_some()
+}
+
+/// 旧版本写入的值（明文或旧密钥加密）在解锁后需要重新加密
+pub fn needs_migration(value: &str) -> bool {
+    !value.is_empty() && !is_sealed(value) && is_unlocked()
+}
+
+fn master_key() -> Result<[u8; 32], String> {
+    key_state().read().unwrap().key.ok_or_else(|| {
+        "Secret store is locked; enter the master passphrase to unlock it".to_string()
+    })
+}
+
+/// 用主密钥加密字段（空字符串原样返回）
+pub fn encrypt_secret(plaintext: &str) -> Result<String, String> {
+    if plaintext.is_empty() {
+        return Ok(String::new());
+    }
+    let key = master_key()?;
+    Ok(format!(
+        "{}{}",
+        SECRET_PREFIX,
+        general_purpose::STANDARD.encode(seal(&key, plaintext.as_bytes()))
+    ))
+}
+
+/// 解密字段；未加前缀的旧值原样返回
+pub fn decrypt_secret(value: &str) -> Result<String, String> {
+    let Some(encoded) = value.strip_prefix(SECRET_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let key = master_key()?;
+    let data = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid encrypted secret: {}", e))?;
+    let plaintext = open(&key, &data).map_err(|e| format!("Failed to decrypt secret: {}", e))?;
+    String::from_utf8(plaintext).map_err(|e| format!("Invalid decrypted secret: {}", e))
+}
+
+// ==================== 口令保护的密钥文件 ====================
+
+#[derive(Serialize, Deserialize)]
+struct WrappedMasterKey {
+    salt: String,
+    iterations: u32,
+    key: String,
+}
+
+fn derive_wrapping_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn wrap_master_key(key: &[u8; 32], passphrase: &str, iterations: u32) -> WrappedMasterKey {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let wrapping_key = derive_wrapping_key(passphrase, &salt, iterations);
+    WrappedMasterKey {
+        salt: general_purpose::STANDARD.encode(salt),
+        iterations,
+        key: general_purpose::STANDARD.encode(seal(&wrapping_key, key)),
+    }
+}
+
+fn unwrap_master_key(wrapped: &WrappedMasterKey, passphrase: &str) -> Result<[u8; 32], String> {
+    let salt = general_purpose::STANDARD
+        .decode(&wrapped.salt)
+        .map_err(|e| format!("Invalid master key file: {}", e))?;
+    let data = general_purpose::STANDARD
+        .decode(&wrapped.key)
+        .map_err(|e| format!("Invalid master key file: {}", e))?;
+    let wrapping_key = derive_wrapping_key(passphrase, &salt, wrapped.iterations.max(1));
+    open(&wrapping_key, &data)
+        .map_err(|_| "Wrong master passphrase".to_string())?
+        .try_into()
+        .map_err(|_| "Invalid master key length".to_string())
+}
+
+fn read_master_key_file(path: &Path) -> Result<WrappedMasterKey, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read master key file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse master key file: {}", e))
+}
+
+fn write_master_key_file(path: &Path, wrapped: &WrappedMasterKey) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(wrapped)
+        .map_err(|e| format!("Failed to serialize master key file: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write master key file: {}", e))
+}
+
+fn master_key_file_path() -> Result<PathBuf, String> {
+    key_state()
+        .read()
+        .unwrap()
+        .data_dir
+        .as_ref()
+        .map(|dir| dir.join(MASTER_KEY_FILE))
+        .ok_or_else(|| "Secret store is not initialized".to_string())
+}
+
+// ==================== 初始化 ====================
+
+/// 读取一次系统密钥库；成功后留下标记
+fn try_load_keyring_key(app_data_dir: &Path) -> Result<[u8; 32], String> {
+    let key = platform::load_or_create_master_key(app_data_dir)?;
+
+    let marker = app_data_dir.join(KEYRING_MARKER_FILE);
+    if !marker.exists() {
+        fs::write(&marker, "")
+            .unwrap_or_else(|e| eprintln!("Failed to write secret store marker: {}", e));
+    }
+    Ok(key)
+}
+
+/// 读取系统密钥库，失败时短暂等待后重试（会阻塞当前线程）
+fn load_keyring_key(app_data_dir: &Path) -> Result<[u8; 32], String> {
+    let mut attempt = 1;
+    loop {
+        match try_load_keyring_key(app_data_dir) {
+            Ok(key) => return Ok(key),
+            Err(e) if attempt >= KEYRING_ATTEMPTS => return Err(e),
+            Err(e) => {
+                eprintln!(
+                    "System secret store attempt {} failed: {}, retrying",
+                    attempt, e
+                );
+                std::thread::sleep(KEYRING_RETRY_DELAY);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// 应用启动时调用：从系统密钥库读取（或创建）主密钥
+///
+/// 已存在口令保护的密钥文件时直接使用文件方式，等待用户解锁。
+/// 启动时只读取一次密钥库，失败后的重试在后台线程中进行，不阻塞启动。
+pub fn init(app_data_dir: PathBuf) {
+    let mut retry = false;
+    let (key, backend, keyring_error) = if app_data_dir.join(MASTER_KEY_FILE).exists() {
+        (None, MasterKeyBackend::File, None)
+    } else {
+        match try_load_keyring_key(&app_data_dir) {
+            Ok(key) => (Some(key), MasterKeyBackend::Keyring, None),
+            // 主密钥在密钥库中，只是暂时读不到：保持锁定，等待重试
+            Err(e) if app_data_dir.join(KEYRING_MARKER_FILE).exists() => {
+                eprintln!(
+                    "System secret store unavailable, retrying in background: {}",
+                    e
+                );
+                retry = true;
+                (None, MasterKeyBackend::Keyring, Some(e))
+            }
+            Err(e) => {
+                eprintln!(
+                    "System secret store unavailable, a master passphrase may be required: {}",
+                    e
+                );
+                retry = true;
+                (None, MasterKeyBackend::File, Some(e))
+            }
+        }
+    };
+
+    {
+        let mut state = key_state().write().unwrap();
+        state.key = key;
+        state.backend = backend;
+        state.data_dir = Some(app_data_dir.clone());
+        state.keyring_error = keyring_error;
+    }
+
+    if retry {
+        retry_keyring_in_background(app_data_dir);
+    }
+}
+
+/// 启动时密钥库读取失败后在后台继续重试，成功时解锁
+///
+/// 用户在此期间已解锁或设置了口令文件时停止，不覆盖用户的选择。
+fn retry_keyring_in_background(app_data_dir: PathBuf) {
+    std::thread::spawn(move || {
+        for attempt in 2..=KEYRING_ATTEMPTS {
+            std::thread::sleep(KEYRING_RETRY_DELAY);
+            if is_unlocked() || app_data_dir.join(MASTER_KEY_FILE).exists() {
+                return;
+            }
+
+            match try_load_keyring_key(&app_data_dir) {
+                Ok(key) => {
+                    let mut state = key_state().write().unwrap();
+                    if state.key.is_none() && !app_data_dir.join(MASTER_KEY_FILE).exists() {
+                        state.key = Some(key);
+                        state.backend = MasterKeyBackend::Keyring;
+                        state.keyring_error = None;
+                        println!("System secret store unlocked on attempt {}", attempt);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    eprintln!("System secret store attempt {} failed: {}", attempt, e);
+                    key_state().write().unwrap().keyring_error = Some(e);
+                }
+            }
+        }
+    });
+}
+
+/// 测试中使用固定主密钥
+#[cfg(test)]
+pub(crate) fn install_test_key() {
+    key_state().write().unwrap().key = Some([7u8; 32]);
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+const KEYRING_SERVICE: &str = "Augment Token Manager";
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+const KEYRING_ACCOUNT: &str = "master-key";
+
+#[cfg(windows)]
+mod platform {
+    use crate::platforms::windsurf::modules::crypto::platform::{dpapi_decrypt, dpapi_encrypt};
+    use std::fs;
+    use std::path::Path;
+
+    const DPAPI_KEY_FILE: &str = "master_key.dpapi";
+
+    /// Windows: 主密钥经 DPAPI 保护后保存在应用数据目录
+    pub fn load_or_create_master_key(app_data_dir: &Path) -> Result<[u8; 32], String> {
+        let path = app_data_dir.join(DPAPI_KEY_FILE);
+        if path.exists() {
+            let blob = fs::read(&path).map_err(|e| format!("Failed to read master key: {}", e))?;
+            return dpapi_decrypt(&blob)?
+                .try_into()
+                .map_err(|_| "Invalid master key length".to_string());
+        }
+
+        let key = super::generate_key();
+        let blob = dpapi_encrypt(&key)?;
+        fs::write(&path, blob).map_err(|e| format!("Failed to write master key: {}", e))?;
+        Ok(key)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{KEYRING_ACCOUNT, KEYRING_SERVICE};
+    use std::path::Path;
+
+    /// errSecItemNotFound
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    /// macOS: 主密钥保存在 Keychain
+    pub fn load_or_create_master_key(_app_data_dir: &Path) -> Result<[u8; 32], String> {
+        use security_framework::passwords::{get_generic_password, set_generic_password};
+
+        match get_generic_password(KEYRING_SERVICE, KEYRING_ACCOUNT) {
+            Ok(stored) => stored
+                .try_into()
+                .map_err(|_| "Invalid master key length in Keychain".to_string()),
+            Err(e) if e.code() == ITEM_NOT_FOUND => {
+                let key = super::generate_key();
+                set_generic_password(KEYRING_SERVICE, KEYRING_ACCOUNT, &key)
+                    .map_err(|e| format!("Keychain write failed: {:?}", e))?;
+                Ok(key)
+            }
+            Err(e) => Err(format!("Keychain read failed: {:?}", e)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{KEYRING_ACCOUNT, KEYRING_SERVICE};
+    use std::path::Path;
+
+    /// Linux: 主密钥保存在 Secret Service
+    pub fn load_or_create_master_key(_app_data_dir: &Path) -> Result<[u8; 32], String> {
+        // 在独立线程中运行，避免在已有 tokio 运行时中 block_on
+        std::thread::spawn(|| {
+            let rt = tokio::runtime::Runtime::new()
+                .map_err(|e| format!("Failed to create runtime: {}", e))?;
+            rt.block_on(load_or_create_master_key_async())
+        })
+        .join()
+        .map_err(|_| "Secret Service thread panicked".to_string())?
+    }
+
+    async fn load_or_create_master_key_async() -> Result<[u8; 32], String> {
+        use secret_service::{EncryptionType, SecretService};
+        use std::collections::HashMap;
+
+        let ss = SecretService::connect(EncryptionType::Dh)
+            .await
+            .map_err(|e| format!("Failed to connect to Secret Service: {:?}", e))?;
+
+        let collection = ss
+            .get_default_collection()
+            .await
+            .map_err(|e| format!("Failed to get default collection: {:?}", e))?;
+
+        if collection.is_locked().await.unwrap_or(true) {
+            collection
+                .unlock()
+                .await
+                .map_err(|e| format!("Failed to unlock collection: {:?}", e))?;
+        }
+
+        let attributes: HashMap<&str, &str> = [
+            ("application", "augment-token-mng"),
+            ("account", KEYRING_ACCOUNT),
+        ]
+        .into_iter()
+        .collect();
+
+        let items = collection
+            .search_items(attributes.clone())
+            .await
+            .map_err(|e| format!("Failed to search items: {:?}", e))?;
+
+        if let Some(item) = items.first() {
+            let secret = item
+                .get_secret()
+                .await
+                .map_err(|e| format!("Failed to get secret: {:?}", e))?;
+            return secret
+                .try_into()
+                .map_err(|_| "Invalid master key length in Secret Service".to_string());
+        }
+
+        let key = super::generate_key();
+        collection
+            .create_item(
+                KEYRING_SERVICE,
+                attributes,
+                &key,
+                false,
+                "application/octet-stream",
+            )
+            .await
+            .map_err(|e| format!("Failed to create secret: {:?}", e))?;
+
+        Ok(key)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    use std::path::Path;
+
+    pub fn load_or_create_master_key(_app_data_dir: &Path) -> Result<[u8; 32], String> {
+        Err("No system secret store on this platform".to_string())
+    }
+}
+
+// ==================== 命令 ====================
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretStoreStatus {
+    pub backend: MasterKeyBackend,
+    /// 主密钥已可用
+    pub unlocked: bool,
+    /// 已设置口令保护的密钥文件
+    pub passphrase_set: bool,
+    /// 系统密钥库读取失败的原因
+    pub keyring_error: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_secret_store_status() -> Result<SecretStoreStatus, String> {
+    let passphrase_set = master_key_file_path().is_ok_and(|path| path.exists());
+    let state = key_state().read().unwrap();
+    Ok(SecretStoreStatus {
+        backend: state.backend,
+        unlocked: state.key.is_some(),
+        passphrase_set,
+        keyring_error: state.keyring_error.clone(),
+    })
+}
+
+/// 重新读取系统密钥库中的主密钥（启动时密钥库暂时不可用的情况）
+#[tauri::command]
+pub async fn retry_system_secret_store() -> Result<SecretStoreStatus, String> {
+    let data_dir = key_state()
+        .read()
+        .unwrap()
+        .data_dir
+        .clone()
+        .ok_or("Secret store is not initialized")?;
+    if data_dir.join(MASTER_KEY_FILE).exists() {
+        return Err("Master key is protected by a passphrase".to_string());
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || load_keyring_key(&data_dir))
+        .await
+        .map_err(|e| format!("Secret store task failed: {}", e))?;
+    {
+        let mut state = key_state().write().unwrap();
+        match &result {
+            Ok(key) => {
+                state.key = Some(*key);
+                state.backend = MasterKeyBackend::Keyring;
+                state.keyring_error = None;
+            }
+            Err(e) => state.keyring_error = Some(e.clone()),
+        }
+    }
+    result.map_err(|e| format!("System secret store unavailable: {}", e))?;
+    get_secret_store_status().await
+}
+
+/// 系统密钥库不可用时，用口令解锁主密钥文件
+#[tauri::command]
+pub async fn unlock_secret_store(passphrase: String) -> Result<SecretStoreStatus, String> {
+    let path = master_key_file_path()?;
+    if !path.exists() {
+        return Err("Master passphrase is not set".to_string());
+    }
+    // PBKDF2 派生较慢，放到阻塞线程池中执行
+    let key = tauri::async_runtime::spawn_blocking(move || {
+        unwrap_master_key(&read_master_key_file(&path)?, &passphrase)
+    })
+    .await
+    .map_err(|e| format!("Secret store task failed: {}", e))??;
+    key_state().write().unwrap().key = Some(key);
+    get_secret_store_status().await
+}
+
+/// 设置或更换主密钥文件的口令
+///
+/// 首次设置时生成新的主密钥；更换口令时需要提供当前口令，主密钥本身不变。
+#[tauri::command]
+pub async fn set_secret_store_passphrase(
+    new_passphrase: String,
+    current_passphrase: Option<String>,
+) -> Result<SecretStoreStatus, String> {
+    if key_state().read().unwrap().backend == MasterKeyBackend::Keyring {
+        return Err("Master key is stored in the system secret store".to_string());
+    }
+    if new_passphrase.chars().count() < 8 {
+        return Err("Master passphrase must be at least 8 characters".to_string());
+    }
+
+    let path = master_key_file_path()?;
+    if !path.exists() && path.with_file_name(KEYRING_MARKER_FILE).exists() {
+        // 已有值用系统密钥库中的主密钥加密，生成新密钥会使它们无法解密
+        return Err(
+            "Master key is stored in the system secret store; retry it instead of setting a passphrase"
+                .to_string(),
+        );
+    }
+
+    // PBKDF2 派生较慢，放到阻塞线程池中执行
+    let key = tauri::async_runtime::spawn_blocking(move || {
+        let key = if path.exists() {
+            let current = current_passphrase.ok_or("Current master passphrase is required")?;
+            unwrap_master_key(&read_master_key_file(&path)?, &current)?
+        } else {
+            generate_key()
+        };
+        write_master_key_file(
+            &path,
+            &wrap_master_key(&key, &new_passphrase, PBKDF2_ITERATIONS),
+        )?;
+        Ok::<_, String>(key)
+    })
+    .await
+    .map_err(|e| format!("Secret store task failed: {}", e))??;
+    key_state().write().unwrap().key = Some(key);
+    get_secret_store_status().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_and_master_key_unwraps_with_passphrase() {
+        install_test_key();
+        let sealed = encrypt_secret("bot-token").unwrap();
+        assert!(is_sealed(&sealed));
+        assert_ne!(sealed, encrypt_secret("bot-token").unwrap());
+        assert_eq!(decrypt_secret(&sealed).unwrap(), "bot-token");
+        // 旧版本的明文值原样返回
+        assert_eq!(decrypt_secret("plain-token").unwrap(), "plain-token");
+        assert_eq!(encrypt_secret("").unwrap(), "");
+
+        let key = generate_key();
+        let wrapped = wrap_master_key(&key, "correct horse", 1000);
+        assert_eq!(unwrap_master_key(&wrapped, "correct horse").unwrap(), key);
+        assert!(unwrap_master_key(&wrapped, "wrong horse").is_err());
+    }
+}
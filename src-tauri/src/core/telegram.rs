@@ -1,3 +1,4 @@
+use crate::core::secret_store;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -71,12 +72,26 @@ impl TelegramConfigManager {
         let content = fs::read_to_string(&self.config_path)
             .map_err(|e| format!("Failed to read config: {}", e))?;
 
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse config: {}", e))
+        let mut config: TelegramConfig =
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse config: {}", e))?;
+        let stored_token = std::mem::take(&mut config.bot_token);
+        config.bot_token = secret_store::decrypt_secret(&stored_token)?;
+
+        // 旧版本的明文 Bot Token 重新加密写回
+        if secret_store::needs_migration(&stored_token) {
+            if let Err(e) = self.save_config(&config) {
+                eprintln!("Failed to migrate Telegram bot token: {}", e);
+            }
+        }
+
+        Ok(config)
     }
 
-    /// 保存配置
+    /// 保存配置（Bot Token 用主密钥加密）
     pub fn save_config(&self, config: &TelegramConfig) -> Result<(), String> {
-        let content = serde_json::to_string_pretty(config)
+        let mut stored = config.clone();
+        stored.bot_token = secret_store::encrypt_secret(&config.bot_token)?;
+        let content = serde_json::to_string_pretty(&stored)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
         fs::write(&self.config_path, content).map_err(|e| format!("Failed to write config: {}", e))
@@ -1,5 +1,5 @@
 use crate::data::bookmark::models::Bookmark;
-use crate::data::storage::common::{AccountStorage, StorageError, SyncableLocalStorage};
+use crate::data::storage::common::{AccountStorage, StorageError, SyncState, SyncableLocalStorage};
 use rusqlite::{params, Connection};
 use std::path::PathBuf;
 use std::sync::Mutex;
@@ -354,4 +354,19 @@ impl SyncableLocalStorage<Bookmark> for BookmarkLocalStorage {
 
         Ok(ids)
     }
+
+    fn load_sync_state(&self) -> Result<SyncState<Bookmark>, StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let conn = self.get_connection()?;
+        match self.get_meta(&conn, "sync_state")? {
+            Some(raw) if !raw.is_empty() => Ok(serde_json::from_str(&raw)?),
+            _ => Ok(SyncState::default()),
+        }
+    }
+
+    fn save_sync_state(&self, state: &SyncState<Bookmark>) -> Result<(), StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let conn = self.get_connection()?;
+        self.set_meta(&conn, "sync_state", &serde_json::to_string(state)?)
+    }
 }
@@ -5,6 +5,8 @@ pub mod config;
 pub mod connection;
 pub mod cursor;
 pub mod openai;
+pub mod sync_crypto;
+pub mod sync_listener;
 pub mod windsurf;
 
 pub use config::*;
@@ -161,9 +163,18 @@ pub async fn save_database_config(
                         .await
                         .map_err(|e| format!("Failed to update Bookmark tables: {}", e))?;
                 }
+
+                // 数据库已加密时用本地缓存的密钥解锁，失败不影响连接
+                if let Err(e) = sync_crypto::activate(&app, &client).await {
+                    eprintln!("Sync encryption is locked: {}", e);
+                }
+                if let Err(e) = sync_listener::install_notify_triggers(&client).await {
+                    eprintln!("Failed to install realtime sync triggers: {}", e);
+                }
             }
 
-            *state.database_manager.lock().unwrap() = Some(Arc::new(db_manager));
+            let db_manager = Arc::new(db_manager);
+            *state.database_manager.lock().unwrap() = Some(db_manager.clone());
 
             initialize_storage_manager(&app, &state)
                 .await
@@ -184,6 +195,8 @@ pub async fn save_database_config(
                 .await
                 .map_err(|e| format!("Failed to initialize Bookmark storage: {}", e))?;
 
+            sync_listener::start(app.clone(), db_manager);
+
             Ok(())
         }
         Err(e) => Err(format!("Failed to connect to database: {}", e)),
@@ -234,7 +247,9 @@ pub async fn delete_database_config(
         .delete_config()
         .map_err(|e| format!("Failed to delete config: {}", e))?;
 
+    sync_listener::stop();
     *state.database_manager.lock().unwrap() = None;
+    sync_crypto::deactivate();
 
     initialize_storage_manager(&app, &state)
         .await
@@ -1,5 +1,3 @@
 pub mod migrations;
-pub mod operations;
 
 pub use migrations::*;
-pub use operations::*;
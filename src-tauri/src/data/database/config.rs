@@ -1,8 +1,8 @@
+use crate::core::secret_store;
 use aes_gcm::{
     Aes256Gcm, Nonce,
     aead::{Aead, KeyInit},
 };
-use rand::{RngCore, rngs::OsRng};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -154,6 +154,13 @@ impl DatabaseConfigManager {
         let mut config: DatabaseConfig = serde_json::from_str(&content)?;
         config.decrypt_password()?;
 
+        // 旧版本配置（固定密钥加密）在主密钥可用后重新加密
+        if secret_store::needs_migration(&config.password_encrypted) {
+            if let Err(e) = self.save_config(&config) {
+                eprintln!("Failed to migrate database password: {}", e);
+            }
+        }
+
         Ok(config)
     }
 
@@ -161,7 +168,10 @@ impl DatabaseConfigManager {
         &self,
         config: &DatabaseConfig,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let json = serde_json::to_string_pretty(config)?;
+        // 写入前重新加密，主密钥未解锁时报错而不是写入空密码
+        let mut config = config.clone();
+        config.password_encrypted = encrypt_password(&config.password)?;
+        let json = serde_json::to_string_pretty(&config)?;
         fs::write(&self.config_path, json)?;
         Ok(())
     }
@@ -175,9 +185,8 @@ impl DatabaseConfigManager {
 }
 
 // 密码加密/解密功能
-fn get_encryption_key() -> [u8; 32] {
-    // 在实际应用中，这应该是一个更安全的密钥派生方法
-    // 这里使用固定密钥仅用于演示
+/// 旧版本使用的固定密钥，仅用于读取并迁移旧配置
+fn legacy_encryption_key() -> [u8; 32] {
     let mut key = [0u8; 32];
     key[..16].copy_from_slice(b"augment_token_mg");
     key[16..].copy_from_slice(b"r_encryption_key");
@@ -185,24 +194,18 @@ fn get_encryption_key() -> [u8; 32] {
 }
 
 fn encrypt_password(password: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let key = Aes256Gcm::new_from_slice(&get_encryption_key())
-        .map_err(|e| format!("Failed to create encryption key: {}", e))?;
-
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
-    let ciphertext = key
-        .encrypt(nonce, password.as_bytes())
-        .map_err(|e| format!("Failed to encrypt password: {}", e))?;
-
-    let mut result = nonce_bytes.to_vec();
-    result.extend_from_slice(&ciphertext);
+    Ok(secret_store::encrypt_secret(password)?)
+}
 
-    Ok(hex::encode(result))
+fn decrypt_password(encrypted: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if secret_store::is_sealed(encrypted) {
+        return Ok(secret_store::decrypt_secret(encrypted)?);
+    }
+    decrypt_legacy_password(encrypted)
 }
 
-fn decrypt_password(
+/// 解密旧版本的密码（hex(nonce || ciphertext)，固定密钥）
+fn decrypt_legacy_password(
     encrypted_hex: &str,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let encrypted_data = hex::decode(encrypted_hex)?;
@@ -214,7 +217,7 @@ fn decrypt_password(
     let (nonce_bytes, ciphertext) = encrypted_data.split_at(12);
     let nonce = Nonce::from_slice(nonce_bytes);
 
-    let key = Aes256Gcm::new_from_slice(&get_encryption_key())
+    let key = Aes256Gcm::new_from_slice(&legacy_encryption_key())
         .map_err(|e| format!("Failed to create decryption key: {}", e))?;
     let plaintext = key
         .decrypt(nonce, ciphertext)
@@ -229,14 +232,30 @@ mod tests {
 
     #[test]
     fn test_password_encryption() {
+        secret_store::install_test_key();
         let password = "test_password_123";
         let encrypted = encrypt_password(password).unwrap();
         let decrypted = decrypt_password(&encrypted).unwrap();
         assert_eq!(password, decrypted);
     }
 
+    #[test]
+    fn test_legacy_password_is_still_readable() {
+        let key = Aes256Gcm::new_from_slice(&legacy_encryption_key()).unwrap();
+        let nonce_bytes = [1u8; 12];
+        let ciphertext = key
+            .encrypt(Nonce::from_slice(&nonce_bytes), b"legacy_password".as_ref())
+            .unwrap();
+        let mut data = nonce_bytes.to_vec();
+        data.extend_from_slice(&ciphertext);
+
+        let decrypted = decrypt_password(&hex::encode(data)).unwrap();
+        assert_eq!(decrypted, "legacy_password");
+    }
+
     #[test]
     fn test_database_config() {
+        secret_store::install_test_key();
         let config = DatabaseConfig::new(
             "localhost".to_string(),
             5432,
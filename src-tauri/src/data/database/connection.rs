@@ -2,11 +2,60 @@ use super::config::{DatabaseConfig, SslMode};
 use deadpool_postgres::{Config, Pool, Runtime};
 use rustls::{ClientConfig, RootCertStore};
 use std::sync::Arc;
-use tokio_postgres::NoTls;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, Client, Connection, NoTls, Notification};
 use tokio_postgres_rustls::MakeRustlsConnect;
 
 pub type DbPool = Pool;
 
+fn rustls_connector() -> MakeRustlsConnect {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+
+    let tls_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+
+    MakeRustlsConnect::new(tls_config)
+}
+
+/// 驱动连接并把收到的通知转发到通道，连接断开时通道随之关闭
+fn forward_notifications<S, T>(
+    mut connection: Connection<S, T>,
+) -> mpsc::UnboundedReceiver<Notification>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+                Some(Ok(AsyncMessage::Notification(notification))) => {
+                    if tx.send(notification).is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    eprintln!("Database notification connection error: {}", e);
+                    break;
+                }
+                None => break,
+            }
+        }
+    });
+    rx
+}
+
 #[derive(Debug)]
 pub struct DatabaseManager {
     pool: Option<Arc<DbPool>>,
@@ -33,22 +82,7 @@ impl DatabaseManager {
         let pool = match self.config.ssl_mode {
             SslMode::Disable => cfg.create_pool(Some(Runtime::Tokio1), NoTls)?,
             SslMode::Prefer | SslMode::Require => {
-                let mut root_store = RootCertStore::empty();
-                root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
-                    rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
-                        ta.subject,
-                        ta.spki,
-                        ta.name_constraints,
-                    )
-                }));
-
-                let tls_config = ClientConfig::builder()
-                    .with_safe_defaults()
-                    .with_root_certificates(root_store)
-                    .with_no_client_auth();
-
-                let tls = MakeRustlsConnect::new(tls_config);
-                cfg.create_pool(Some(Runtime::Tokio1), tls)?
+                cfg.create_pool(Some(Runtime::Tokio1), rustls_connector())?
             }
         };
 
@@ -68,6 +102,35 @@ impl DatabaseManager {
         self.pool.is_some()
     }
 
+    /// 建立一条独立于连接池的连接用于 LISTEN/NOTIFY
+    ///
+    /// 连接池中的连接会被复用和回收，收不到异步通知，因此单独连接。
+    /// 返回的 `Client` 被丢弃时连接关闭。
+    pub async fn connect_notifications(
+        &self,
+    ) -> Result<
+        (Client, mpsc::UnboundedReceiver<Notification>),
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        let mut cfg = tokio_postgres::Config::new();
+        cfg.host(&self.config.host)
+            .port(self.config.port)
+            .dbname(&self.config.database)
+            .user(&self.config.username)
+            .password(&self.config.password);
+
+        match self.config.ssl_mode {
+            SslMode::Disable => {
+                let (client, connection) = cfg.connect(NoTls).await?;
+                Ok((client, forward_notifications(connection)))
+            }
+            SslMode::Prefer | SslMode::Require => {
+                let (client, connection) = cfg.connect(rustls_connector()).await?;
+                Ok((client, forward_notifications(connection)))
+            }
+        }
+    }
+
     pub async fn test_connection(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         if let Some(pool) = &self.pool {
             let client = pool.get().await?;
@@ -99,22 +162,7 @@ pub async fn test_database_connection(
     let pool = match config.ssl_mode {
         SslMode::Disable => cfg.create_pool(Some(Runtime::Tokio1), NoTls)?,
         SslMode::Prefer | SslMode::Require => {
-            let mut root_store = RootCertStore::empty();
-            root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
-                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
-                    ta.subject,
-                    ta.spki,
-                    ta.name_constraints,
-                )
-            }));
-
-            let tls_config = ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(root_store)
-                .with_no_client_auth();
-
-            let tls = MakeRustlsConnect::new(tls_config);
-            cfg.create_pool(Some(Runtime::Tokio1), tls)?
+            cfg.create_pool(Some(Runtime::Tokio1), rustls_connector())?
         }
     };
 
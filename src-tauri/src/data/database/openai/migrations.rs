@@ -103,6 +103,13 @@ pub async fn add_new_fields_if_not_exist(
         ("openai_auth_json", "TEXT"),
     ];
 
+    // 添加 Codex 号池分层字段
+    let pool_columns = vec![
+        ("pool_priority", "INTEGER NOT NULL DEFAULT 0"),
+        ("pool_weight", "INTEGER NOT NULL DEFAULT 1"),
+        ("pool_reserved_models", "TEXT"),
+    ];
+
     for column in &quota_columns {
         let check_column = client
             .query_one(
@@ -315,6 +322,38 @@ pub async fn add_new_fields_if_not_exist(
         }
     }
 
+    // 添加 Codex 号池分层字段
+    for (column, data_type) in &pool_columns {
+        let check_column = client
+            .query_one(
+                &format!(
+                    "SELECT EXISTS (
+                    SELECT 1
+                    FROM information_schema.columns
+                    WHERE table_name = 'openai_accounts'
+                    AND column_name = '{}'
+                )",
+                    column
+                ),
+                &[],
+            )
+            .await?;
+
+        let exists: bool = check_column.get(0);
+        if !exists {
+            client
+                .execute(
+                    &format!(
+                        "ALTER TABLE openai_accounts ADD COLUMN {} {}",
+                        column, data_type
+                    ),
+                    &[],
+                )
+                .await?;
+            println!("Added column {} to openai_accounts", column);
+        }
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,668 @@
+//! 同步数据库中账号密钥字段的端到端加密
+//!
+//! 设置同步口令后，access token、refresh token、Augment `auth_session`、Claude `auth_token`
+//! 等字段在写入 PostgreSQL 前用 AES-256-GCM 加密，读取时在 mapper 中解密，数据库中只保存密文。
+//!
+//! - 密钥由口令经 PBKDF2-HMAC-SHA256 在本地派生，盐与迭代次数保存在 `sync_encryption_meta` 表
+//! - 元数据中的 key_check 是固定明文的密文，口令错误时解密失败，不会静默写入错误密钥的数据
+//! - 派生后的密钥用本机主密钥加密后缓存在应用数据目录，其他设备需要输入同一口令
+//! - 数据库已加密但本机未解锁时拒绝写入，读取到密文字段时报错
+//! - 每次写入前在同一事务中核对 key_check，口令在其他设备上更换后本机立即锁定
+//! - 密文格式为 `enc:v1:` + base64(nonce || ciphertext)，未加前缀的值视为旧的明文数据
+
+use crate::AppState;
+use crate::core::secret_store;
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit},
+};
+use base64::{Engine as _, engine::general_purpose};
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Manager, State};
+use tokio_postgres::{Client, GenericClient};
+
+type CryptoError = Box<dyn std::error::Error + Send + Sync>;
+
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const KEY_CHECK_PLAINTEXT: &str = "atm-sync-key-check";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SYNC_KEY_FILE: &str = "sync_encryption_key.json";
+
+/// 需要加密的列（表名，列名）
+pub const SECRET_COLUMNS: &[(&str, &[&str])] = &[
+    ("tokens", &["access_token", "auth_session"]),
+    (
+        "openai_accounts",
+        &[
+            "access_token",
+            "refresh_token",
+            "id_token",
+            "api_key",
+            "openai_auth_json",
+        ],
+    ),
+    (
+        "cursor_accounts",
+        &[
+            "access_token",
+            "refresh_token",
+            "workos_cursor_session_token",
+        ],
+    ),
+    (
+        "windsurf_accounts",
+        &[
+            "access_token",
+            "refresh_token",
+            "api_key",
+            "devin_auth1_token",
+        ],
+    ),
+    (
+        "antigravity_accounts",
+        &["access_token", "refresh_token", "id_token"],
+    ),
+    ("claude_accounts", &["auth_token"]),
+];
+
+/// 口令派生的字段加密器
+#[derive(Clone)]
+pub struct SyncCipher {
+    key: [u8; 32],
+}
+
+impl SyncCipher {
+    pub fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Self {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+        Self { key }
+    }
+
+    pub fn from_key(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let cipher = Aes256Gcm::new_from_slice(&self.key).expect("key length is 32 bytes");
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        // AES-GCM 只会在明文超过 64GB 时失败
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+            .expect("AES-GCM encryption of in-memory data");
+
+        let mut data = nonce_bytes.to_vec();
+        data.extend_from_slice(&ciphertext);
+        format!(
+            "{}{}",
+            ENCRYPTED_PREFIX,
+            general_purpose::STANDARD.encode(data)
+        )
+    }
+
+    /// 解密字段；未加密的值原样返回
+    pub fn decrypt(&self, value: &str) -> Result<String, String> {
+        let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let data = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("Invalid encrypted field: {}", e))?;
+        if data.len() < 12 {
+            return Err("Invalid encrypted field".to_string());
+        }
+
+        let (nonce_bytes, ciphertext) = data.split_at(12);
+        let cipher = Aes256Gcm::new_from_slice(&self.key).expect("key length is 32 bytes");
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+            .map_err(|_| "Failed to decrypt field: wrong sync passphrase".to_string())?;
+        String::from_utf8(plaintext).map_err(|e| format!("Invalid decrypted field: {}", e))
+    }
+
+    pub fn key_check(&self) -> String {
+        self.encrypt(KEY_CHECK_PLAINTEXT)
+    }
+
+    pub fn verify(&self, key_check: &str) -> bool {
+        self.decrypt(key_check)
+            .is_ok_and(|v| v == KEY_CHECK_PLAINTEXT)
+    }
+
+    fn key_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.key)
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+// ==================== 全局状态 ====================
+
+#[derive(Default)]
+struct SyncCryptoState {
+    /// 已解锁的加密器
+    cipher: Option<SyncCipher>,
+    /// 当前数据库已启用加密
+    required: bool,
+}
+
+static SYNC_CRYPTO: OnceLock<RwLock<SyncCryptoState>> = OnceLock::new();
+
+fn sync_state() -> &'static RwLock<SyncCryptoState> {
+    SYNC_CRYPTO.get_or_init(|| RwLock::new(SyncCryptoState::default()))
+}
+
+fn set_state(cipher: Option<SyncCipher>, required: bool) {
+    let mut state = sync_state().write().unwrap();
+    state.cipher = cipher;
+    state.required = required;
+}
+
+fn current_cipher() -> Option<SyncCipher> {
+    sync_state().read().unwrap().cipher.clone()
+}
+
+/// 写入密钥字段前在写入事务中调用
+///
+/// 以 FOR SHARE 读取数据库当前的加密元数据并与本机密钥比对：数据库已加密但本机未解锁、
+/// 或口令已在其他设备上更换时锁定本机并拒绝写入，避免写入其他设备无法解密的数据。
+pub async fn ensure_secrets_writable(client: &impl GenericClient) -> Result<(), CryptoError> {
+    let meta = query_meta(client, &format!("{} FOR SHARE", META_SELECT_SQL)).await?;
+    reconcile_state(meta.as_ref().map(|m| m.key_check.as_str()))
+}
+
+/// 按数据库当前的 key_check 更新本机状态
+fn reconcile_state(key_check: Option<&str>) -> Result<(), CryptoError> {
+    let mut state = sync_state().write().unwrap();
+    let Some(key_check) = key_check else {
+        // 数据库未启用加密，本机缓存的密钥不再适用
+        state.cipher = None;
+        state.required = false;
+        return Ok(());
+    };
+
+    state.required = true;
+    match &state.cipher {
+        Some(cipher) if cipher.verify(key_check) => Ok(()),
+        Some(_) => {
+            state.cipher = None;
+            Err("Sync passphrase was changed on another device; enter the new passphrase".into())
+        }
+        None => Err("Sync database is encrypted; enter the sync passphrase first".into()),
+    }
+}
+
+/// 加密写入数据库的字段（未设置口令时原样返回）
+pub fn encrypt_secret(value: &str) -> String {
+    match current_cipher() {
+        Some(cipher) if !value.is_empty() => cipher.encrypt(value),
+        _ => value.to_string(),
+    }
+}
+
+pub fn encrypt_optional_secret(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(encrypt_secret)
+}
+
+/// 解密从数据库读取的字段
+pub fn decrypt_secret(value: String) -> Result<String, CryptoError> {
+    if !is_encrypted(&value) {
+        return Ok(value);
+    }
+    let cipher =
+        current_cipher().ok_or("Encrypted field found; enter the sync passphrase to decrypt it")?;
+    Ok(cipher.decrypt(&value)?)
+}
+
+pub fn decrypt_optional_secret(value: Option<String>) -> Result<Option<String>, CryptoError> {
+    value.map(decrypt_secret).transpose()
+}
+
+// ==================== 元数据 ====================
+
+#[derive(Debug, Clone)]
+struct SyncEncryptionMeta {
+    salt: Vec<u8>,
+    iterations: u32,
+    key_check: String,
+}
+
+pub async fn create_meta_table(client: &Client) -> Result<(), CryptoError> {
+    client
+        .execute(
+            r#"
+        CREATE TABLE IF NOT EXISTS sync_encryption_meta (
+            id SMALLINT PRIMARY KEY DEFAULT 1,
+            kdf TEXT NOT NULL,
+            salt TEXT NOT NULL,
+            iterations INTEGER NOT NULL,
+            key_check TEXT NOT NULL,
+            updated_at BIGINT NOT NULL
+        )
+        "#,
+            &[],
+        )
+        .await?;
+    Ok(())
+}
+
+const META_SELECT_SQL: &str =
+    "SELECT salt, iterations, key_check FROM sync_encryption_meta WHERE id = 1";
+
+async fn load_meta(client: &impl GenericClient) -> Result<Option<SyncEncryptionMeta>, CryptoError> {
+    query_meta(client, META_SELECT_SQL).await
+}
+
+async fn query_meta(
+    client: &impl GenericClient,
+    sql: &str,
+) -> Result<Option<SyncEncryptionMeta>, CryptoError> {
+    let rows = client.query(sql, &[]).await?;
+    let Some(row) = rows.first() else {
+        return Ok(None);
+    };
+    let salt: String = row.get(0);
+    let iterations: i32 = row.get(1);
+    Ok(Some(SyncEncryptionMeta {
+        salt: general_purpose::STANDARD.decode(salt)?,
+        iterations: iterations.max(1) as u32,
+        key_check: row.get(2),
+    }))
+}
+
+async fn save_meta(
+    client: &impl GenericClient,
+    meta: &SyncEncryptionMeta,
+) -> Result<(), CryptoError> {
+    client
+        .execute(
+            r#"
+            INSERT INTO sync_encryption_meta (id, kdf, salt, iterations, key_check, updated_at)
+            VALUES (1, 'pbkdf2-sha256', $1, $2, $3, $4)
+            ON CONFLICT (id) DO UPDATE SET
+                kdf = EXCLUDED.kdf,
+                salt = EXCLUDED.salt,
+                iterations = EXCLUDED.iterations,
+                key_check = EXCLUDED.key_check,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            &[
+                &general_purpose::STANDARD.encode(&meta.salt),
+                &(meta.iterations as i32),
+                &meta.key_check,
+                &chrono::Utc::now().timestamp(),
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+/// 锁定元数据表直到事务结束，写入方的 FOR SHARE 读取会等待口令变更提交
+async fn lock_meta(client: &impl GenericClient) -> Result<(), CryptoError> {
+    client
+        .batch_execute("LOCK TABLE sync_encryption_meta IN EXCLUSIVE MODE")
+        .await?;
+    Ok(())
+}
+
+/// 用新密钥重写所有密钥字段（旧密钥为 None 表示当前是明文）
+async fn reencrypt_secrets(
+    client: &impl GenericClient,
+    old: Option<&SyncCipher>,
+    new: &SyncCipher,
+) -> Result<usize, CryptoError> {
+    let mut updated = 0;
+    for (table, columns) in SECRET_COLUMNS {
+        let select_sql = format!("SELECT id, {} FROM {}", columns.join(", "), table);
+        let assignments: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ${}", column, i + 2))
+            .collect();
+        let update_sql = format!(
+            "UPDATE {} SET {} WHERE id = $1",
+            table,
+            assignments.join(", ")
+        );
+
+        for row in client.query(&select_sql, &[]).await? {
+            let id: String = row.get(0);
+            let mut values: Vec<Option<String>> = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                let value: Option<String> = row.get(i + 1);
+                let value = match value {
+                    Some(v) if is_encrypted(&v) => {
+                        let old = old.ok_or("Encrypted field found without a sync passphrase")?;
+                        Some(new.encrypt(&old.decrypt(&v)?))
+                    }
+                    Some(v) if !v.is_empty() => Some(new.encrypt(&v)),
+                    other => other,
+                };
+                values.push(value);
+            }
+
+            let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&id];
+            params.extend(
+                values
+                    .iter()
+                    .map(|v| v as &(dyn tokio_postgres::types::ToSql + Sync)),
+            );
+            client.execute(&update_sql, &params).await?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+// ==================== 本地密钥 ====================
+
+#[derive(Serialize, Deserialize)]
+struct StoredSyncKey {
+    key: String,
+}
+
+fn get_sync_key_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join(SYNC_KEY_FILE))
+}
+
+fn load_local_key(app: &AppHandle) -> Result<Option<SyncCipher>, String> {
+    let path = get_sync_key_path(app)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read sync key: {}", e))?;
+    let stored: StoredSyncKey =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse sync key: {}", e))?;
+    let key: [u8; 32] = general_purpose::STANDARD
+        .decode(secret_store::decrypt_secret(&stored.key)?)
+        .map_err(|e| format!("Failed to decode sync key: {}", e))?
+        .try_into()
+        .map_err(|_| "Invalid sync key length".to_string())?;
+    let cipher = SyncCipher::from_key(key);
+
+    // 旧版本以明文保存的密钥用主密钥加密写回
+    if secret_store::needs_migration(&stored.key) {
+        if let Err(e) = save_local_key(app, &cipher) {
+            eprintln!("Failed to migrate sync key: {}", e);
+        }
+    }
+    Ok(Some(cipher))
+}
+
+fn save_local_key(app: &AppHandle, cipher: &SyncCipher) -> Result<(), String> {
+    let path = get_sync_key_path(app)?;
+    let stored = StoredSyncKey {
+        key: secret_store::encrypt_secret(&cipher.key_base64())?,
+    };
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize sync key: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write sync key: {}", e))
+}
+
+// ==================== 初始化与命令 ====================
+
+/// 连接数据库后调用：读取元数据，用本地缓存的密钥解锁
+pub async fn activate(app: &AppHandle, client: &Client) -> Result<(), CryptoError> {
+    create_meta_table(client).await?;
+    let Some(meta) = load_meta(client).await? else {
+        set_state(None, false);
+        return Ok(());
+    };
+
+    match load_local_key(app)? {
+        Some(cipher) if cipher.verify(&meta.key_check) => {
+            set_state(Some(cipher), true);
+            Ok(())
+        }
+        Some(_) => {
+            set_state(None, true);
+            Err("Sync passphrase was changed on another device; enter the new passphrase".into())
+        }
+        None => {
+            set_state(None, true);
+            Err("Sync database is encrypted; enter the sync passphrase".into())
+        }
+    }
+}
+
+/// 断开数据库时清除状态
+pub fn deactivate() {
+    set_state(None, false);
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEncryptionStatus {
+    /// 数据库已启用加密
+    pub enabled: bool,
+    /// 本机已解锁
+    pub unlocked: bool,
+}
+
+fn db_pool(state: &State<'_, AppState>) -> Result<std::sync::Arc<super::DbPool>, String> {
+    state
+        .database_manager
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|db| db.get_pool())
+        .ok_or_else(|| "Database not connected".to_string())
+}
+
+/// 在阻塞线程池中派生密钥，避免 PBKDF2 占用异步运行时线程
+async fn derive_cipher(
+    passphrase: String,
+    salt: Vec<u8>,
+    iterations: u32,
+) -> Result<SyncCipher, String> {
+    tauri::async_runtime::spawn_blocking(move || SyncCipher::derive(&passphrase, &salt, iterations))
+        .await
+        .map_err(|e| format!("Failed to derive sync key: {}", e))
+}
+
+async fn new_meta(passphrase: String) -> Result<(SyncCipher, SyncEncryptionMeta), String> {
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = derive_cipher(passphrase, salt.clone(), PBKDF2_ITERATIONS).await?;
+    let meta = SyncEncryptionMeta {
+        salt,
+        iterations: PBKDF2_ITERATIONS,
+        key_check: cipher.key_check(),
+    };
+    Ok((cipher, meta))
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < 8 {
+        return Err("Sync passphrase must be at least 8 characters".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_sync_encryption_status() -> Result<SyncEncryptionStatus, String> {
+    let state = sync_state().read().unwrap();
+    Ok(SyncEncryptionStatus {
+        enabled: state.required,
+        unlocked: state.cipher.is_some(),
+    })
+}
+
+/// 设置或输入同步口令
+///
+/// 数据库未加密时启用加密并加密已有数据；已加密时校验口令，错误则报错。
+#[tauri::command]
+pub async fn set_sync_passphrase(
+    passphrase: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    let pool = db_pool(&state)?;
+    let mut client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get database client: {}", e))?;
+    create_meta_table(&client)
+        .await
+        .map_err(|e| format!("Failed to create sync encryption table: {}", e))?;
+
+    let meta = load_meta(&**client)
+        .await
+        .map_err(|e| format!("Failed to load sync encryption metadata: {}", e))?;
+    let cipher = match meta {
+        Some(meta) => {
+            let cipher = derive_cipher(passphrase, meta.salt, meta.iterations).await?;
+            if !cipher.verify(&meta.key_check) {
+                return Err("Wrong sync passphrase".to_string());
+            }
+            cipher
+        }
+        None => {
+            validate_passphrase(&passphrase)?;
+            let (cipher, meta) = new_meta(passphrase).await?;
+            let tx = client
+                .transaction()
+                .await
+                .map_err(|e| format!("Failed to start transaction: {}", e))?;
+            lock_meta(&*tx)
+                .await
+                .map_err(|e| format!("Failed to lock sync encryption metadata: {}", e))?;
+            // 等锁期间其他设备可能已启用加密
+            if load_meta(&*tx)
+                .await
+                .map_err(|e| format!("Failed to load sync encryption metadata: {}", e))?
+                .is_some()
+            {
+                return Err(
+                    "Sync encryption was enabled on another device; enter its passphrase"
+                        .to_string(),
+                );
+            }
+            let updated = reencrypt_secrets(&*tx, None, &cipher)
+                .await
+                .map_err(|e| format!("Failed to encrypt existing secrets: {}", e))?;
+            save_meta(&*tx, &meta)
+                .await
+                .map_err(|e| format!("Failed to save sync encryption metadata: {}", e))?;
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+            println!("🔐 Sync encryption enabled, {} rows encrypted", updated);
+            cipher
+        }
+    };
+
+    save_local_key(&app, &cipher)?;
+    set_state(Some(cipher), true);
+    get_sync_encryption_status().await
+}
+
+/// 更换同步口令并用新密钥重写所有密文，其他设备需要重新输入口令
+#[tauri::command]
+pub async fn rekey_sync_passphrase(
+    current_passphrase: String,
+    new_passphrase: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    validate_passphrase(&new_passphrase)?;
+    let pool = db_pool(&state)?;
+    let mut client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get database client: {}", e))?;
+
+    let meta = load_meta(&**client)
+        .await
+        .map_err(|e| format!("Failed to load sync encryption metadata: {}", e))?
+        .ok_or("Sync encryption is not enabled")?;
+    let old_cipher = derive_cipher(current_passphrase, meta.salt, meta.iterations).await?;
+    if !old_cipher.verify(&meta.key_check) {
+        return Err("Wrong sync passphrase".to_string());
+    }
+
+    let (new_cipher, new_meta) = new_meta(new_passphrase).await?;
+    let tx = client
+        .transaction()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    lock_meta(&*tx)
+        .await
+        .map_err(|e| format!("Failed to lock sync encryption metadata: {}", e))?;
+    // 等锁期间口令可能已在其他设备上更换
+    let locked_meta = load_meta(&*tx)
+        .await
+        .map_err(|e| format!("Failed to load sync encryption metadata: {}", e))?;
+    if !locked_meta.is_some_and(|m| old_cipher.verify(&m.key_check)) {
+        return Err("Sync passphrase was changed on another device; try again".to_string());
+    }
+    let updated = reencrypt_secrets(&*tx, Some(&old_cipher), &new_cipher)
+        .await
+        .map_err(|e| format!("Failed to re-encrypt secrets: {}", e))?;
+    save_meta(&*tx, &new_meta)
+        .await
+        .map_err(|e| format!("Failed to save sync encryption metadata: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    println!("🔐 Sync passphrase changed, {} rows re-encrypted", updated);
+
+    save_local_key(&app, &new_cipher)?;
+    set_state(Some(new_cipher), true);
+    get_sync_encryption_status().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SyncCipher, current_cipher, is_encrypted, reconcile_state, set_state};
+
+    #[test]
+    fn cipher_round_trips_and_rejects_wrong_passphrase() {
+        let salt = b"0123456789abcdef";
+        let cipher = SyncCipher::derive("correct horse", salt, 1000);
+        let sealed = cipher.encrypt("refresh-token-value");
+        assert!(is_encrypted(&sealed));
+        assert_ne!(sealed, cipher.encrypt("refresh-token-value"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "refresh-token-value");
+        // 旧的明文数据原样返回
+        assert_eq!(cipher.decrypt("plain-token").unwrap(), "plain-token");
+
+        let key_check = cipher.key_check();
+        assert!(cipher.verify(&key_check));
+        let wrong = SyncCipher::derive("wrong horse", salt, 1000);
+        assert!(!wrong.verify(&key_check));
+        assert!(wrong.decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn stale_key_is_locked_when_passphrase_changes_elsewhere() {
+        let salt = b"0123456789abcdef";
+        let old = SyncCipher::derive("old passphrase", salt, 1000);
+        let new = SyncCipher::derive("new passphrase", salt, 1000);
+
+        set_state(Some(old.clone()), true);
+        assert!(reconcile_state(Some(&old.key_check())).is_ok());
+        assert!(current_cipher().is_some());
+
+        // 其他设备更换口令后拒绝写入并锁定本机
+        assert!(reconcile_state(Some(&new.key_check())).is_err());
+        assert!(current_cipher().is_none());
+        assert!(reconcile_state(Some(&new.key_check())).is_err());
+
+        set_state(None, false);
+    }
+}
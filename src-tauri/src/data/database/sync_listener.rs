@@ -0,0 +1,345 @@
+//! 多设备实时同步
+//!
+//! 账号表插入、更新（包括写入删除标记）时由触发器向 `{表名}_changes` 通道发送通知。
+//! 本机用一条独立连接 LISTEN 这些通道，收到通知后按版本号增量拉取变更写入本地存储，
+//! 再发送与界面操作相同的刷新事件，已打开的账号列表即时更新。
+//!
+//! - 连接断开后按指数退避重连（1 秒起，最长 60 秒）
+//! - 每次连接成功都先补拉一次，覆盖断线期间错过的通知
+//! - 短时间内连续到达的通知合并为一次拉取
+
+use super::DatabaseManager;
+use crate::AppState;
+use crate::core::api_accounts::{AccountPlatform, emit_accounts_updated};
+use crate::data::storage::augment::DualStorage;
+use crate::data::storage::common::{
+    AccountDbMapper, GenericDualStorage, RemoteChangeSet, StorageError, SyncableAccount,
+};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_postgres::Client;
+
+const NOTIFY_FUNCTION: &str = "atm_notify_account_change";
+const CHANNEL_SUFFIX: &str = "_changes";
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// 收到通知后稍等片刻，把同一批写入的通知合并处理
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 通知对应的本地存储
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyncTarget {
+    Augment,
+    Platform(AccountPlatform),
+}
+
+/// 监听的表
+const SYNC_TABLES: [(&str, SyncTarget); 5] = [
+    ("tokens", SyncTarget::Augment),
+    (
+        "openai_accounts",
+        SyncTarget::Platform(AccountPlatform::OpenAI),
+    ),
+    (
+        "cursor_accounts",
+        SyncTarget::Platform(AccountPlatform::Cursor),
+    ),
+    (
+        "windsurf_accounts",
+        SyncTarget::Platform(AccountPlatform::Windsurf),
+    ),
+    (
+        "antigravity_accounts",
+        SyncTarget::Platform(AccountPlatform::Antigravity),
+    ),
+];
+
+/// 触发器发送的通知内容
+#[derive(Debug, Deserialize)]
+struct ChangePayload {
+    version: i64,
+}
+
+static LISTENER: OnceLock<Mutex<Option<JoinHandle<()>>>> = OnceLock::new();
+
+fn listener_slot() -> &'static Mutex<Option<JoinHandle<()>>> {
+    LISTENER.get_or_init(|| Mutex::new(None))
+}
+
+/// 创建（或更新）发送变更通知的触发器，可重复执行
+pub async fn install_notify_triggers(
+    client: &Client,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    client
+        .batch_execute(&format!(
+            r#"
+            CREATE OR REPLACE FUNCTION {}() RETURNS trigger AS $$
+            BEGIN
+                PERFORM pg_notify(
+                    TG_TABLE_NAME || '{}',
+                    json_build_object(
+                        'id', NEW.id,
+                        'version', NEW.version,
+                        'deleted', COALESCE(NEW.deleted, FALSE)
+                    )::text
+                );
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql;
+            "#,
+            NOTIFY_FUNCTION, CHANNEL_SUFFIX
+        ))
+        .await?;
+
+    for (table, _) in SYNC_TABLES {
+        client
+            .batch_execute(&format!(
+                "DROP TRIGGER IF EXISTS {table}_notify_change ON {table};
+                 CREATE TRIGGER {table}_notify_change
+                     AFTER INSERT OR UPDATE ON {table}
+                     FOR EACH ROW EXECUTE PROCEDURE {function}();",
+                table = table,
+                function = NOTIFY_FUNCTION
+            ))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// 启动实时同步监听，已有监听时先停止
+pub fn start(app: AppHandle, db_manager: Arc<DatabaseManager>) {
+    let handle = tauri::async_runtime::spawn(run(app, db_manager));
+    if let Some(previous) = listener_slot().lock().unwrap().replace(handle) {
+        previous.abort();
+    }
+}
+
+/// 停止实时同步监听
+pub fn stop() {
+    if let Some(handle) = listener_slot().lock().unwrap().take() {
+        handle.abort();
+    }
+}
+
+fn next_backoff(current: Duration) -> Duration {
+    (current * 2).min(MAX_BACKOFF)
+}
+
+fn table_for_channel(channel: &str) -> Option<(&'static str, SyncTarget)> {
+    let table = channel.strip_suffix(CHANNEL_SUFFIX)?;
+    SYNC_TABLES.into_iter().find(|(t, _)| *t == table)
+}
+
+/// 通知中的版本号不大于已拉取的版本时无需处理；无法解析的通知一律处理
+fn is_new_change(payload: &str, cursor: Option<i64>) -> bool {
+    match (serde_json::from_str::<ChangePayload>(payload), cursor) {
+        (Ok(change), Some(cursor)) => change.version > cursor,
+        _ => true,
+    }
+}
+
+async fn run(app: AppHandle, db_manager: Arc<DatabaseManager>) {
+    // 每张表已拉取到的远端版本号，跨重连保留
+    let mut cursors: HashMap<&'static str, i64> = HashMap::new();
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        match listen(&app, &db_manager, &mut cursors).await {
+            Ok(()) => {
+                // 连接成功后断开，从最短间隔重新开始
+                backoff = INITIAL_BACKOFF;
+                eprintln!(
+                    "Realtime sync connection closed, reconnecting in {:?}",
+                    backoff
+                );
+            }
+            Err(e) => {
+                eprintln!(
+                    "Realtime sync connection failed: {}, retrying in {:?}",
+                    e, backoff
+                );
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = next_backoff(backoff);
+    }
+}
+
+/// 一次连接的生命周期：LISTEN、补拉，然后逐批处理通知，连接断开时返回
+async fn listen(
+    app: &AppHandle,
+    db_manager: &DatabaseManager,
+    cursors: &mut HashMap<&'static str, i64>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (client, mut notifications) = db_manager.connect_notifications().await?;
+    let statements: String = SYNC_TABLES
+        .iter()
+        .map(|(table, _)| format!("LISTEN {}{};", table, CHANNEL_SUFFIX))
+        .collect();
+    client.batch_execute(&statements).await?;
+    println!("🔄 Realtime sync is listening for database changes");
+
+    for (table, target) in SYNC_TABLES {
+        sync_table(app, table, target, cursors).await;
+    }
+
+    while let Some(first) = notifications.recv().await {
+        tokio::time::sleep(DEBOUNCE).await;
+
+        let mut pending = BTreeSet::new();
+        let mut batch = vec![first];
+        while let Ok(notification) = notifications.try_recv() {
+            batch.push(notification);
+        }
+        for notification in &batch {
+            let Some((table, _)) = table_for_channel(notification.channel()) else {
+                continue;
+            };
+            if is_new_change(notification.payload(), cursors.get(table).copied()) {
+                pending.insert(table);
+            }
+        }
+
+        for (table, target) in SYNC_TABLES {
+            if pending.contains(table) {
+                sync_table(app, table, target, cursors).await;
+            }
+        }
+    }
+
+    drop(client);
+    Ok(())
+}
+
+fn current_storage<S>(slot: &Mutex<Option<Arc<S>>>) -> Option<Arc<S>> {
+    slot.lock().unwrap().clone()
+}
+
+async fn apply_augment(
+    storage: Option<Arc<DualStorage>>,
+    cursor: Option<i64>,
+) -> Result<Option<RemoteChangeSet>, StorageError> {
+    let Some(storage) = storage.filter(|s| s.is_database_available()) else {
+        return Ok(None);
+    };
+    let since_version = match cursor {
+        Some(version) => version,
+        None => storage.local_version().await?,
+    };
+    storage.apply_remote_changes(since_version).await.map(Some)
+}
+
+async fn apply_platform<T, M>(
+    storage: Option<Arc<GenericDualStorage<T, M>>>,
+    cursor: Option<i64>,
+) -> Result<Option<RemoteChangeSet>, StorageError>
+where
+    T: SyncableAccount,
+    M: AccountDbMapper<T>,
+{
+    let Some(storage) = storage.filter(|s| s.is_database_available()) else {
+        return Ok(None);
+    };
+    let since_version = match cursor {
+        Some(version) => version,
+        None => storage.local_version()?,
+    };
+    storage.apply_remote_changes(since_version).await.map(Some)
+}
+
+/// 拉取一张表的增量变更写入本地，有变更时通知前端刷新
+async fn sync_table(
+    app: &AppHandle,
+    table: &'static str,
+    target: SyncTarget,
+    cursors: &mut HashMap<&'static str, i64>,
+) {
+    let state = app.state::<AppState>();
+    let cursor = cursors.get(table).copied();
+    let result = match target {
+        SyncTarget::Augment => apply_augment(current_storage(&state.storage_manager), cursor).await,
+        SyncTarget::Platform(AccountPlatform::OpenAI) => {
+            apply_platform(current_storage(&state.openai_storage_manager), cursor).await
+        }
+        SyncTarget::Platform(AccountPlatform::Cursor) => {
+            apply_platform(current_storage(&state.cursor_storage_manager), cursor).await
+        }
+        SyncTarget::Platform(AccountPlatform::Windsurf) => {
+            apply_platform(current_storage(&state.windsurf_storage_manager), cursor).await
+        }
+        SyncTarget::Platform(AccountPlatform::Antigravity) => {
+            apply_platform(current_storage(&state.antigravity_storage_manager), cursor).await
+        }
+    };
+
+    let changes = match result {
+        Ok(Some(changes)) => changes,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Failed to apply remote changes from {}: {}", table, e);
+            return;
+        }
+    };
+    cursors.insert(table, changes.version);
+    if changes.changed_ids.is_empty() {
+        return;
+    }
+
+    println!(
+        "🔄 Applied {} remote changes from {}",
+        changes.changed_ids.len(),
+        table
+    );
+    match target {
+        SyncTarget::Augment => {
+            if let Err(e) = app.emit("tokens-updated", ()) {
+                eprintln!("⚠️  Failed to emit tokens-updated event: {}", e);
+            }
+        }
+        SyncTarget::Platform(platform) => {
+            let account_ids: Vec<&str> = changes.changed_ids.iter().map(String::as_str).collect();
+            emit_accounts_updated(app, platform, "realtime_sync", &account_ids);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_map_to_tables_and_backoff_is_capped() {
+        assert_eq!(
+            table_for_channel("cursor_accounts_changes"),
+            Some((
+                "cursor_accounts",
+                SyncTarget::Platform(AccountPlatform::Cursor)
+            ))
+        );
+        assert_eq!(
+            table_for_channel("tokens_changes"),
+            Some(("tokens", SyncTarget::Augment))
+        );
+        assert_eq!(table_for_channel("claude_accounts_changes"), None);
+        assert_eq!(table_for_channel("tokens"), None);
+
+        let payload = r#"{"id":"a","version":12,"deleted":true}"#;
+        assert!(is_new_change(payload, None));
+        assert!(is_new_change(payload, Some(11)));
+        assert!(!is_new_change(payload, Some(12)));
+        assert!(is_new_change("not json", Some(100)));
+
+        let mut backoff = INITIAL_BACKOFF;
+        for _ in 0..10 {
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(backoff, MAX_BACKOFF);
+        assert_eq!(next_backoff(INITIAL_BACKOFF), Duration::from_secs(2));
+    }
+}
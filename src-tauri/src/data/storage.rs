@@ -10,7 +10,7 @@ pub use antigravity::{
     AccountSyncStatus, AntigravityDualStorage, AntigravityLocalStorage,
     AntigravityPostgreSQLStorage, ClientAccountChange, ClientAccountDelete,
     ClientAccountSyncRequest, ServerAccountSyncResponse, antigravity_bidirectional_sync_accounts,
-    antigravity_get_sync_status, antigravity_sync_accounts,
+    antigravity_get_sync_status, antigravity_resolve_sync_conflict, antigravity_sync_accounts,
     antigravity_sync_accounts_from_database, antigravity_sync_accounts_to_database,
     initialize_antigravity_storage_manager,
 };
@@ -18,23 +18,24 @@ pub use augment::*;
 pub use claude::{
     ClaudeDualStorage, ClaudeLocalStorage, ClaudePostgreSQLStorage, claude_add,
     claude_bidirectional_sync_accounts, claude_delete, claude_get_sync_status, claude_list,
-    claude_sync_accounts, claude_sync_accounts_from_database, claude_sync_accounts_to_database,
-    claude_update, initialize_claude_storage_manager,
+    claude_resolve_sync_conflict, claude_sync_accounts, claude_sync_accounts_from_database,
+    claude_sync_accounts_to_database, claude_update, initialize_claude_storage_manager,
 };
 pub use cursor::{
     CursorDualStorage, CursorLocalStorage, CursorPostgreSQLStorage,
-    cursor_bidirectional_sync_accounts, cursor_get_sync_status, cursor_sync_accounts,
-    cursor_sync_accounts_from_database, cursor_sync_accounts_to_database,
+    cursor_bidirectional_sync_accounts, cursor_get_sync_status, cursor_resolve_sync_conflict,
+    cursor_sync_accounts, cursor_sync_accounts_from_database, cursor_sync_accounts_to_database,
     initialize_cursor_storage_manager,
 };
 pub use openai::{
     OpenAIDualStorage, OpenAILocalStorage, OpenAIPostgreSQLStorage,
     initialize_openai_storage_manager, openai_bidirectional_sync_accounts, openai_get_sync_status,
-    openai_sync_accounts, openai_sync_accounts_from_database, openai_sync_accounts_to_database,
+    openai_resolve_sync_conflict, openai_sync_accounts, openai_sync_accounts_from_database,
+    openai_sync_accounts_to_database,
 };
 pub use windsurf::{
     WindsurfDualStorage, WindsurfLocalStorage, WindsurfPostgreSQLStorage,
     initialize_windsurf_storage_manager, windsurf_bidirectional_sync_accounts,
-    windsurf_sync_accounts, windsurf_sync_accounts_from_database,
-    windsurf_sync_accounts_to_database,
+    windsurf_get_sync_status, windsurf_resolve_sync_conflict, windsurf_sync_accounts,
+    windsurf_sync_accounts_from_database, windsurf_sync_accounts_to_database,
 };
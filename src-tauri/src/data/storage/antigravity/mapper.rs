@@ -1,4 +1,7 @@
 use crate::data::storage::common::{AccountDbMapper, StorageError};
+use crate::database::sync_crypto::{
+    decrypt_optional_secret, decrypt_secret, encrypt_optional_secret, encrypt_secret,
+};
 use crate::platforms::antigravity::models::{Account, QuotaData, TokenData};
 use tokio_postgres::Row;
 
@@ -25,8 +28,8 @@ impl AccountDbMapper<Account> for AntigravityAccountMapper {
             email: email.clone(),
             name: row.get(2),
             token: TokenData {
-                access_token: row.get(3),
-                refresh_token: row.get(4),
+                access_token: decrypt_secret(row.get(3))?,
+                refresh_token: decrypt_secret(row.get(4))?,
                 expires_in: row.get(5),
                 expiry_timestamp: row.get(6),
                 token_type: row.get(7),
@@ -35,7 +38,7 @@ impl AccountDbMapper<Account> for AntigravityAccountMapper {
                 oauth_client_key: row.get(9),
                 session_id: row.get(10),
                 is_gcp_tos: row.get(11),
-                id_token: row.get(12),
+                id_token: decrypt_optional_secret(row.get(12))?,
             },
             device_profile,
             quota,
@@ -109,8 +112,8 @@ impl AccountDbMapper<Account> for AntigravityAccountMapper {
             Box::new(account.id.clone()),
             Box::new(account.email.clone()),
             Box::new(account.name.clone()),
-            Box::new(account.token.access_token.clone()),
-            Box::new(account.token.refresh_token.clone()),
+            Box::new(encrypt_secret(&account.token.access_token)),
+            Box::new(encrypt_secret(&account.token.refresh_token)),
             Box::new(account.token.expires_in),
             Box::new(account.token.expiry_timestamp),
             Box::new(account.token.token_type.clone()),
@@ -118,7 +121,7 @@ impl AccountDbMapper<Account> for AntigravityAccountMapper {
             Box::new(account.token.oauth_client_key.clone()),
             Box::new(account.token.session_id.clone()),
             Box::new(account.token.is_gcp_tos),
-            Box::new(account.token.id_token.clone()),
+            Box::new(encrypt_optional_secret(&account.token.id_token)),
             Box::new(device_profile_json),
             Box::new(quota_json),
             Box::new(account.tag.clone()),
@@ -31,6 +31,8 @@ pub async fn save_tokens_json(json_string: String, app: tauri::AppHandle) -> Res
 
     serde_json::from_str::<serde_json::Value>(&json_string)
         .map_err(|e| format!("Invalid JSON format: {}", e))?;
+    let json_string = crate::data::storage::common::at_rest::seal(&json_string)
+        .map_err(|e| format!("Failed to encrypt tokens file: {}", e))?;
 
     {
         let mut temp_file = fs::File::create(&temp_path)
@@ -118,6 +120,8 @@ pub async fn load_tokens_json(app: tauri::AppHandle) -> Result<String, String> {
     if new_storage_path.exists() {
         let content = fs::read_to_string(&new_storage_path)
             .map_err(|e| format!("Failed to read tokens file: {}", e))?;
+        let content = crate::data::storage::common::at_rest::open(&content)
+            .map_err(|e| format!("Failed to decrypt tokens file: {}", e))?;
 
         if content.trim().is_empty() {
             return Ok("[]".to_string());
@@ -2,6 +2,7 @@ use super::traits::{
     ClientSyncRequest, ServerSyncResponse, SyncManager, SyncStatus, TokenData, TokenStorage,
 };
 use super::{LocalFileStorage, PostgreSQLStorage};
+use crate::data::storage::common::RemoteChangeSet;
 use chrono::Utc;
 use std::sync::Arc;
 
@@ -289,6 +290,47 @@ impl DualStorage {
         // 回退到本地存储
         self.local_storage.load_tokens().await
     }
+
+    /// 本地 tokens 中最大的版本号
+    pub async fn local_version(&self) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        let tokens = self.local_storage.load_tokens().await?;
+        Ok(tokens.iter().map(|t| t.version).max().unwrap_or(0))
+    }
+
+    /// 实时同步：拉取 `since_version` 之后的远端写入与删除标记并合并到本地
+    pub async fn apply_remote_changes(
+        &self,
+        since_version: i64,
+    ) -> Result<RemoteChangeSet, Box<dyn std::error::Error + Send + Sync>> {
+        let postgres = self
+            .postgres_storage
+            .as_ref()
+            .ok_or("Database storage not available")?;
+
+        let changes = postgres.load_changes_since_version(since_version).await?;
+        let version = changes.new_version;
+
+        let mut changed_ids = Vec::new();
+        for remote_token in changes.upserts {
+            // 本地更新的 token 保留，留给下次同步上传
+            let local_token = self.local_storage.get_token(&remote_token.id).await?;
+            if matches!(local_token, Some(local) if local.updated_at > remote_token.updated_at) {
+                continue;
+            }
+            self.local_storage.save_token(&remote_token).await?;
+            changed_ids.push(remote_token.id);
+        }
+        for token_id in changes.deletions {
+            if self.local_storage.delete_token(&token_id).await? {
+                changed_ids.push(token_id);
+            }
+        }
+
+        Ok(RemoteChangeSet {
+            version,
+            changed_ids,
+        })
+    }
 }
 
 #[async_trait::async_trait]
@@ -1,13 +1,14 @@
 use super::traits::{TokenData, TokenStorage, convert_legacy_token, convert_to_legacy_format};
+use crate::data::storage::common::at_rest;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 pub struct LocalFileStorage {
     storage_path: PathBuf,
-    // 使用Mutex来确保文件操作的线程安全
-    _lock: Mutex<()>,
+    // 使用Mutex来确保文件操作的线程安全，静态加密迁移共用此锁
+    _lock: Arc<Mutex<()>>,
 }
 
 impl LocalFileStorage {
@@ -18,18 +19,37 @@ impl LocalFileStorage {
         fs::create_dir_all(&app_data_dir)?;
 
         let storage_path = app_data_dir.join("tokens.json");
-
-        Ok(Self {
-            storage_path,
-            _lock: Mutex::new(()),
-        })
+        Ok(Self::new_with_path(storage_path))
     }
 
     pub fn new_with_path(storage_path: PathBuf) -> Self {
-        Self {
+        let storage = Self {
             storage_path,
-            _lock: Mutex::new(()),
+            _lock: Arc::new(Mutex::new(())),
+        };
+        let migration = Self {
+            storage_path: storage.storage_path.clone(),
+            _lock: storage._lock.clone(),
+        };
+        at_rest::register_store(&storage.storage_path, move || migration.migrate_at_rest());
+        storage
+    }
+
+    /// 按当前加密模式重写文件，返回是否重写
+    fn migrate_at_rest(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let _guard = self._lock.lock().unwrap();
+
+        if !self.storage_path.exists() {
+            return Ok(false);
+        }
+
+        let raw = fs::read_to_string(&self.storage_path)?;
+        if !at_rest::needs_rewrite(&raw) {
+            return Ok(false);
         }
+
+        self.write_raw(&at_rest::seal(&at_rest::open(&raw)?)?)?;
+        Ok(true)
     }
 
     async fn read_file_content(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
@@ -39,11 +59,20 @@ impl LocalFileStorage {
             return Ok("[]".to_string());
         }
 
-        let content = fs::read_to_string(&self.storage_path)?;
-        if content.trim().is_empty() {
+        let raw = fs::read_to_string(&self.storage_path)?;
+        if raw.trim().is_empty() {
             return Ok("[]".to_string());
         }
 
+        let content = at_rest::open(&raw)?;
+
+        // 与当前加密模式不一致的文件（如旧的明文文件）就地重写
+        if at_rest::needs_rewrite(&raw) {
+            if let Err(e) = at_rest::seal(&content).and_then(|sealed| self.write_raw(&sealed)) {
+                eprintln!("Failed to migrate tokens file: {}", e);
+            }
+        }
+
         Ok(content)
     }
 
@@ -53,6 +82,14 @@ impl LocalFileStorage {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let _guard = self._lock.lock().unwrap();
 
+        // 验证JSON格式
+        serde_json::from_str::<serde_json::Value>(content)?;
+
+        self.write_raw(&at_rest::seal(content)?)
+    }
+
+    /// 原子性写入文件（调用方持有锁）
+    fn write_raw(&self, content: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         // 确保父目录存在
         if let Some(parent) = self.storage_path.parent() {
             fs::create_dir_all(parent)?;
@@ -60,9 +97,6 @@ impl LocalFileStorage {
 
         let temp_path = self.storage_path.with_extension("tmp");
 
-        // 验证JSON格式
-        serde_json::from_str::<serde_json::Value>(content)?;
-
         // 原子性写入
         fs::write(&temp_path, content)?;
 
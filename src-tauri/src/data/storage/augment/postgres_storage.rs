@@ -1,6 +1,11 @@
-use super::traits::{TokenData, TokenStorage};
+use super::traits::{ServerSyncResponse, TokenData, TokenStorage};
+use crate::database::sync_crypto::{
+    decrypt_optional_secret, decrypt_secret, encrypt_optional_secret, encrypt_secret,
+    ensure_secrets_writable,
+};
 use crate::database::{DatabaseManager, DbPool};
 use std::sync::Arc;
+use tokio_postgres::IsolationLevel;
 
 pub struct PostgreSQLStorage {
     pub db_manager: Arc<DatabaseManager>,
@@ -25,12 +30,16 @@ impl TokenStorage for PostgreSQLStorage {
         token: &TokenData,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let pool = self.get_pool().await?;
-        let client = pool.get().await?;
+        let mut client = pool.get().await?;
+        let tx = client.transaction().await?;
+        ensure_secrets_writable(&*tx).await?;
+        let access_token = encrypt_secret(&token.access_token);
+        let auth_session = encrypt_optional_secret(&token.auth_session);
 
         // 使用UPSERT (INSERT ... ON CONFLICT)
         // 注意：我们需要保留传入的 updated_at，而不是让触发器自动更新
         // 因此在 UPDATE 时显式设置 updated_at，触发器会被这个值覆盖
-        client.execute(
+        tx.execute(
             r#"
             INSERT INTO tokens (id, tenant_url, access_token, created_at, updated_at, portal_url, email_note, tag_name, tag_color, ban_status, portal_info, auth_session, suspensions, balance_color_mode, skip_check, session_updated_at)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
@@ -53,7 +62,7 @@ impl TokenStorage for PostgreSQLStorage {
             &[
                 &token.id,
                 &token.tenant_url,
-                &token.access_token,
+                &access_token,
                 &token.created_at,
                 &token.updated_at,
                 &token.portal_url,
@@ -62,13 +71,14 @@ impl TokenStorage for PostgreSQLStorage {
                 &token.tag_color,
                 &token.ban_status,
                 &token.portal_info,
-                &token.auth_session,
+                &auth_session,
                 &token.suspensions,
                 &token.balance_color_mode,
                 &token.skip_check,
                 &token.session_updated_at,
             ],
         ).await?;
+        tx.commit().await?;
 
         Ok(())
     }
@@ -89,7 +99,7 @@ impl TokenStorage for PostgreSQLStorage {
             let token = TokenData {
                 id: row.get(0),
                 tenant_url: row.get(1),
-                access_token: row.get(2),
+                access_token: decrypt_secret(row.get(2))?,
                 created_at: row.get(3),
                 updated_at: row.get(4),
                 portal_url: row.get(5),
@@ -98,7 +108,7 @@ impl TokenStorage for PostgreSQLStorage {
                 tag_color: row.get(8),
                 ban_status: row.get(9),
                 portal_info: row.get(10),
-                auth_session: row.get(11),
+                auth_session: decrypt_optional_secret(row.get(11))?,
                 suspensions: row.get(12),
                 balance_color_mode: row.get(13),
                 skip_check: row.get(14),
@@ -116,11 +126,15 @@ impl TokenStorage for PostgreSQLStorage {
         token: &TokenData,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let pool = self.get_pool().await?;
-        let client = pool.get().await?;
+        let mut client = pool.get().await?;
+        let tx = client.transaction().await?;
+        ensure_secrets_writable(&*tx).await?;
+        let access_token = encrypt_secret(&token.access_token);
+        let auth_session = encrypt_optional_secret(&token.auth_session);
 
         // 使用 token 中的 updated_at，而不是自动生成新的时间戳
         // 这样可以保持双向同步时的时间戳一致性
-        let rows_affected = client
+        let rows_affected = tx
             .execute(
                 r#"
             UPDATE tokens SET
@@ -143,7 +157,7 @@ impl TokenStorage for PostgreSQLStorage {
                 &[
                     &token.id,
                     &token.tenant_url,
-                    &token.access_token,
+                    &access_token,
                     &token.updated_at,
                     &token.portal_url,
                     &token.email_note,
@@ -151,7 +165,7 @@ impl TokenStorage for PostgreSQLStorage {
                     &token.tag_color,
                     &token.ban_status,
                     &token.portal_info,
-                    &token.auth_session,
+                    &auth_session,
                     &token.suspensions,
                     &token.balance_color_mode,
                     &token.skip_check,
@@ -163,6 +177,7 @@ impl TokenStorage for PostgreSQLStorage {
         if rows_affected == 0 {
             return Err("Token not found for update".into());
         }
+        tx.commit().await?;
 
         Ok(())
     }
@@ -192,7 +207,7 @@ impl TokenStorage for PostgreSQLStorage {
             let token = TokenData {
                 id: row.get(0),
                 tenant_url: row.get(1),
-                access_token: row.get(2),
+                access_token: decrypt_secret(row.get(2))?,
                 created_at: row.get(3),
                 updated_at: row.get(4),
                 portal_url: row.get(5),
@@ -201,7 +216,7 @@ impl TokenStorage for PostgreSQLStorage {
                 tag_color: row.get(8),
                 ban_status: row.get(9),
                 portal_info: row.get(10),
-                auth_session: row.get(11),
+                auth_session: decrypt_optional_secret(row.get(11))?,
                 suspensions: row.get(12),
                 balance_color_mode: row.get(13),
                 skip_check: row.get(14),
@@ -259,7 +274,7 @@ impl PostgreSQLStorage {
             let token = TokenData {
                 id: row.get(0),
                 tenant_url: row.get(1),
-                access_token: row.get(2),
+                access_token: decrypt_secret(row.get(2))?,
                 created_at: row.get(3),
                 updated_at: row.get(4),
                 portal_url: row.get(5),
@@ -268,7 +283,7 @@ impl PostgreSQLStorage {
                 tag_color: row.get(8),
                 ban_status: row.get(9),
                 portal_info: row.get(10),
-                auth_session: row.get(11),
+                auth_session: decrypt_optional_secret(row.get(11))?,
                 suspensions: row.get(12),
                 balance_color_mode: row.get(13),
                 skip_check: row.get(14),
@@ -316,7 +331,7 @@ impl PostgreSQLStorage {
             let token = TokenData {
                 id: row.get(0),
                 tenant_url: row.get(1),
-                access_token: row.get(2),
+                access_token: decrypt_secret(row.get(2))?,
                 created_at: row.get(3),
                 updated_at: row.get(4),
                 portal_url: row.get(5),
@@ -325,7 +340,7 @@ impl PostgreSQLStorage {
                 tag_color: row.get(8),
                 ban_status: row.get(9),
                 portal_info: row.get(10),
-                auth_session: row.get(11),
+                auth_session: decrypt_optional_secret(row.get(11))?,
                 suspensions: row.get(12),
                 balance_color_mode: row.get(13),
                 skip_check: row.get(14),
@@ -361,6 +376,71 @@ impl PostgreSQLStorage {
         Ok(ids)
     }
 
+    /// 在同一快照中读取 `since_version` 之后的 tokens 和 tombstones
+    ///
+    /// 返回的版本号取自实际读到的行，读取期间提交的写入留给下一次拉取。
+    pub async fn load_changes_since_version(
+        &self,
+        since_version: i64,
+    ) -> Result<ServerSyncResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let pool = self.get_pool().await?;
+        let mut client = pool.get().await?;
+        let tx = client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()
+            .await?;
+
+        let rows = tx.query(
+            "SELECT id, tenant_url, access_token, created_at, updated_at, portal_url, email_note, tag_name, tag_color, ban_status, portal_info, auth_session, suspensions, balance_color_mode, skip_check, session_updated_at, version FROM tokens WHERE version > $1 AND deleted IS NOT TRUE ORDER BY version",
+            &[&since_version],
+        ).await?;
+        let tombstone_rows = tx
+            .query(
+                "SELECT id, version FROM tokens WHERE deleted IS TRUE AND version > $1",
+                &[&since_version],
+            )
+            .await?;
+        tx.commit().await?;
+
+        let mut tokens = Vec::new();
+        for row in rows {
+            let token = TokenData {
+                id: row.get(0),
+                tenant_url: row.get(1),
+                access_token: decrypt_secret(row.get(2))?,
+                created_at: row.get(3),
+                updated_at: row.get(4),
+                portal_url: row.get(5),
+                email_note: row.get(6),
+                tag_name: row.get(7),
+                tag_color: row.get(8),
+                ban_status: row.get(9),
+                portal_info: row.get(10),
+                auth_session: decrypt_optional_secret(row.get(11))?,
+                suspensions: row.get(12),
+                balance_color_mode: row.get(13),
+                skip_check: row.get(14),
+                session_updated_at: row.get(15),
+                version: row.get(16),
+            };
+            tokens.push(token);
+        }
+
+        let new_version = tokens
+            .iter()
+            .map(|token| token.version)
+            .chain(tombstone_rows.iter().map(|row| row.get::<_, i64>(1)))
+            .fold(since_version, i64::max);
+
+        Ok(ServerSyncResponse {
+            upserts: tokens,
+            deletions: tombstone_rows.iter().map(|row| row.get(0)).collect(),
+            new_version,
+        })
+    }
+
     /// 获取当前最大版本号
     pub async fn get_max_version(&self) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
         let pool = self.get_pool().await?;
@@ -385,12 +465,17 @@ impl PostgreSQLStorage {
         token: &TokenData,
     ) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
         let pool = self.get_pool().await?;
-        let client = pool.get().await?;
+        let mut client = pool.get().await?;
         let new_version = self.get_next_version().await?;
 
+        let tx = client.transaction().await?;
+        ensure_secrets_writable(&*tx).await?;
+        let access_token = encrypt_secret(&token.access_token);
+        let auth_session = encrypt_optional_secret(&token.auth_session);
+
         let deleted = false;
 
-        client.execute(
+        tx.execute(
             r#"
             INSERT INTO tokens (id, tenant_url, access_token, created_at, updated_at, portal_url, email_note, tag_name, tag_color, ban_status, portal_info, auth_session, suspensions, balance_color_mode, skip_check, session_updated_at, deleted, version)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
@@ -415,7 +500,7 @@ impl PostgreSQLStorage {
             &[
                 &token.id,
                 &token.tenant_url,
-                &token.access_token,
+                &access_token,
                 &token.created_at,
                 &token.updated_at,
                 &token.portal_url,
@@ -424,7 +509,7 @@ impl PostgreSQLStorage {
                 &token.tag_color,
                 &token.ban_status,
                 &token.portal_info,
-                &token.auth_session,
+                &auth_session,
                 &token.suspensions,
                 &token.balance_color_mode,
                 &token.skip_check,
@@ -433,6 +518,7 @@ impl PostgreSQLStorage {
                 &new_version,
             ],
         ).await?;
+        tx.commit().await?;
 
         Ok(new_version)
     }
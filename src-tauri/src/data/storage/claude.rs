@@ -7,7 +7,7 @@ pub use traits::*;
 use crate::AppState;
 use crate::data::storage::common::AccountStorage as CommonAccountStorage;
 use crate::data::storage::common::{
-    GenericDualStorage, GenericLocalStorage, GenericPostgreSQLStorage,
+    ConflictSide, GenericDualStorage, GenericLocalStorage, GenericPostgreSQLStorage,
 };
 use crate::platforms::claude::Account;
 use serde::{Deserialize, Serialize};
@@ -236,6 +236,27 @@ pub async fn claude_get_sync_status(
         .map_err(|e| format!("Failed to get sync status: {}", e))
 }
 
+/// 为同步冲突的字段选择保留本地（`local`）或远端（`remote`）的值
+#[tauri::command]
+pub async fn claude_resolve_sync_conflict(
+    account_id: String,
+    field: String,
+    side: ConflictSide,
+    state: State<'_, AppState>,
+) -> Result<Account, String> {
+    let storage_manager = {
+        let guard = state.claude_storage_manager.lock().unwrap();
+        guard
+            .clone()
+            .ok_or("Claude storage manager not initialized")?
+    };
+
+    storage_manager
+        .resolve_sync_conflict(&account_id, &field, side)
+        .await
+        .map_err(|e| format!("Failed to resolve sync conflict: {}", e))
+}
+
 pub async fn initialize_claude_storage_manager(
     app: &tauri::AppHandle,
     state: &State<'_, AppState>,
@@ -1,4 +1,5 @@
 use crate::data::storage::common::{AccountDbMapper, StorageError};
+use crate::database::sync_crypto::{decrypt_secret, encrypt_secret};
 use crate::platforms::claude::Account;
 use tokio_postgres::Row;
 
@@ -18,7 +19,7 @@ impl AccountDbMapper<Account> for ClaudeAccountMapper {
             tag_color: row.try_get(7).ok().flatten(),
             notes: row.try_get(8).ok().flatten(),
             base_url: row.get(9),
-            auth_token: row.get(10),
+            auth_token: decrypt_secret(row.get(10))?,
             default_opus_model: row.get(11),
             default_sonnet_model: row.get(12),
             default_haiku_model: row.get(13),
@@ -81,7 +82,7 @@ impl AccountDbMapper<Account> for ClaudeAccountMapper {
             Box::new(account.tag_color.clone()),
             Box::new(account.notes.clone()),
             Box::new(account.base_url.clone()),
-            Box::new(account.auth_token.clone()),
+            Box::new(encrypt_secret(&account.auth_token)),
             Box::new(account.default_opus_model.clone()),
             Box::new(account.default_sonnet_model.clone()),
             Box::new(account.default_haiku_model.clone()),
@@ -0,0 +1,227 @@
+//! 本地账号存储的静态加密
+//!
+//! 启用后，`GenericLocalStorage`、`GenericSQLiteStorage` 与 Augment `LocalFileStorage` 写入的内容
+//! 用本机主密钥（见 `core::secret_store`）加密，文件中只保存 `local:v1:` 前缀的密文。
+//!
+//! - 读取时自动识别明文与密文，因此开关切换前后的文件都能读取
+//! - 文件格式与当前模式不一致时（如启用后遇到旧的明文文件），在读取时重写，完成迁移
+//! - 切换模式或解锁时，已创建的存储（JSON 文件和 SQLite 数据库）通过各自加锁的写入路径立即迁移
+//! - 主密钥未解锁时无法读取已加密的存储，启动后需要先解锁
+//! - 设置保存在应用数据目录的 storage_encryption.json
+
+use super::traits::StorageError;
+use crate::core::secret_store;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tauri::{AppHandle, Emitter};
+
+const STORAGE_ENCRYPTION_CONFIG_FILE: &str = "storage_encryption.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageEncryptionConfig {
+    #[serde(default)]
+    enabled: bool,
+}
+
+#[derive(Default)]
+struct AtRestState {
+    enabled: bool,
+    config_path: Option<PathBuf>,
+}
+
+/// 按当前模式重写一个存储，返回是否有内容被重写
+type StoreMigration = Arc<dyn Fn() -> Result<bool, StorageError> + Send + Sync>;
+
+static AT_REST: OnceLock<RwLock<AtRestState>> = OnceLock::new();
+/// 已创建的存储，切换模式时统一迁移；同一路径重新创建时替换为新的实例
+static STORES: OnceLock<Mutex<BTreeMap<PathBuf, StoreMigration>>> = OnceLock::new();
+
+fn at_rest_state() -> &'static RwLock<AtRestState> {
+    AT_REST.get_or_init(|| RwLock::new(AtRestState::default()))
+}
+
+fn stores() -> &'static Mutex<BTreeMap<PathBuf, StoreMigration>> {
+    STORES.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// 应用启动时调用：读取静态加密设置
+pub fn init(app_data_dir: &Path) {
+    let config_path = app_data_dir.join(STORAGE_ENCRYPTION_CONFIG_FILE);
+    let config = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<StorageEncryptionConfig>(&content).ok())
+        .unwrap_or_default();
+
+    if config.enabled && !secret_store::is_unlocked() {
+        eprintln!("Local account storage is encrypted; unlock the secret store to read it");
+    }
+
+    let mut state = at_rest_state().write().unwrap();
+    state.enabled = config.enabled;
+    state.config_path = Some(config_path);
+}
+
+pub fn is_enabled() -> bool {
+    at_rest_state().read().unwrap().enabled
+}
+
+/// 记录存储及其迁移方法，迁移须持有存储自身的写锁
+pub fn register_store<F>(path: &Path, migrate: F)
+where
+    F: Fn() -> Result<bool, StorageError> + Send + Sync + 'static,
+{
+    stores()
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), Arc::new(migrate));
+}
+
+fn seal_with(enabled: bool, content: &str) -> Result<String, StorageError> {
+    if !enabled {
+        return Ok(content.to_string());
+    }
+    Ok(secret_store::encrypt_secret(content)?)
+}
+
+fn rewrite_needed(enabled: bool, unlocked: bool, raw: &str) -> bool {
+    unlocked && !raw.trim().is_empty() && enabled != secret_store::is_sealed(raw)
+}
+
+/// 按当前模式编码写入存储的内容
+pub fn seal(content: &str) -> Result<String, StorageError> {
+    seal_with(is_enabled(), content)
+}
+
+/// 解码从存储读取的内容（明文原样返回）
+pub fn open(raw: &str) -> Result<String, StorageError> {
+    if !secret_store::is_sealed(raw) {
+        return Ok(raw.to_string());
+    }
+    secret_store::decrypt_secret(raw)
+        .map_err(|e| format!("Failed to decrypt local storage: {}", e).into())
+}
+
+/// 内容格式与当前模式不一致且主密钥可用时需要重写
+pub fn needs_rewrite(raw: &str) -> bool {
+    rewrite_needed(is_enabled(), secret_store::is_unlocked(), raw)
+}
+
+/// 迁移已记录的存储，返回重写的存储数
+pub fn migrate_registered_stores() -> Result<usize, StorageError> {
+    let registered: Vec<(PathBuf, StoreMigration)> = stores()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, migrate)| (path.clone(), migrate.clone()))
+        .collect();
+    let mut migrated = 0;
+    for (path, migrate) in registered {
+        if migrate().map_err(|e| format!("{}: {}", path.display(), e))? {
+            migrated += 1;
+        }
+    }
+    Ok(migrated)
+}
+
+fn save_setting(enabled: bool) -> Result<(), String> {
+    let config_path = at_rest_state()
+        .read()
+        .unwrap()
+        .config_path
+        .clone()
+        .ok_or("Storage encryption is not initialized")?;
+    let json = serde_json::to_string_pretty(&StorageEncryptionConfig { enabled })
+        .map_err(|e| format!("Failed to serialize storage encryption config: {}", e))?;
+    fs::write(&config_path, json)
+        .map_err(|e| format!("Failed to write storage encryption config: {}", e))?;
+    at_rest_state().write().unwrap().enabled = enabled;
+    Ok(())
+}
+
+// ==================== 命令 ====================
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEncryptionStatus {
+    pub enabled: bool,
+    /// 主密钥已可用，可以读写加密的存储
+    pub unlocked: bool,
+}
+
+#[tauri::command]
+pub async fn get_storage_encryption_status() -> Result<StorageEncryptionStatus, String> {
+    Ok(StorageEncryptionStatus {
+        enabled: is_enabled(),
+        unlocked: secret_store::is_unlocked(),
+    })
+}
+
+/// 启用或关闭本地存储的静态加密，并立即迁移已有文件
+#[tauri::command]
+pub async fn set_storage_encryption(
+    enabled: bool,
+    app: AppHandle,
+) -> Result<StorageEncryptionStatus, String> {
+    if !secret_store::is_unlocked() {
+        return Err("Secret store is locked; unlock it before changing storage encryption".into());
+    }
+    save_setting(enabled)?;
+
+    let migrated = migrate_registered_stores()
+        .map_err(|e| format!("Failed to migrate local storage: {}", e))?;
+    println!(
+        "🔐 Local storage encryption set to {}, {} stores rewritten",
+        enabled, migrated
+    );
+
+    let _ = app.emit("local-storage-encryption-changed", enabled);
+    get_storage_encryption_status().await
+}
+
+/// 启动时的解锁步骤：用主口令解锁后迁移本地存储，并通知前端重新加载账号
+#[tauri::command]
+pub async fn unlock_local_storage(
+    passphrase: String,
+    app: AppHandle,
+) -> Result<StorageEncryptionStatus, String> {
+    secret_store::unlock_secret_store(passphrase).await?;
+
+    let migrated = migrate_registered_stores()
+        .map_err(|e| format!("Failed to migrate local storage: {}", e))?;
+    if migrated > 0 {
+        println!("🔐 Migrated {} local stores", migrated);
+    }
+
+    let _ = app.emit("local-storage-unlocked", ());
+    get_storage_encryption_status().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_content_round_trips_and_mismatched_files_are_rewritten() {
+        secret_store::install_test_key();
+        let content = r#"{"accounts":[]}"#;
+
+        let sealed = seal_with(true, content).unwrap();
+        assert!(secret_store::is_sealed(&sealed));
+        assert_eq!(open(&sealed).unwrap(), content);
+        assert_eq!(seal_with(false, content).unwrap(), content);
+        assert_eq!(open(content).unwrap(), content);
+
+        // 启用后旧的明文文件需要迁移，关闭后密文文件改回明文
+        assert!(rewrite_needed(true, true, content));
+        assert!(!rewrite_needed(true, true, &sealed));
+        assert!(rewrite_needed(false, true, &sealed));
+        assert!(!rewrite_needed(false, true, content));
+        // 未解锁时不做迁移
+        assert!(!rewrite_needed(true, false, content));
+        assert!(!rewrite_needed(true, true, "  "));
+    }
+}
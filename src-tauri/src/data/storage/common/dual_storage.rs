@@ -1,6 +1,10 @@
+use super::merge::{
+    ConflictSide, MergeReport, SyncState, apply_choice, merge_account, merge_account_lists,
+    same_content,
+};
 use super::traits::{
     AccountStorage, AccountSyncManager, AccountSyncStatus, ClientAccountSyncRequest,
-    ServerAccountSyncResponse, StorageError, SyncableAccount,
+    RemoteChangeSet, ServerAccountSyncResponse, StorageError, SyncableAccount,
 };
 use super::{AccountDbMapper, GenericLocalStorage, GenericPostgreSQLStorage};
 use chrono::Utc;
@@ -54,30 +58,112 @@ where
         self.local_storage.set_current_account_id(account_id).await
     }
 
-    fn resolve_conflicts_impl(local: Vec<T>, remote: Vec<T>) -> Vec<T> {
-        let local_map: HashMap<String, &T> =
-            local.iter().map(|a| (a.id().to_string(), a)).collect();
-        let mut resolved: HashMap<String, T> = HashMap::new();
+    /// 本地记录的同步版本号
+    pub fn local_version(&self) -> Result<i64, StorageError> {
+        self.local_storage.get_local_version()
+    }
 
-        for mut r in remote {
-            if let Some(l) = local_map.get(r.id()) {
-                r.merge_missing_fields(l);
-            }
-            resolved.insert(r.id().to_string(), r);
+    /// 实时同步：拉取 `since_version` 之后的远端写入与删除标记并合并到本地
+    ///
+    /// 本地已有的账号与远端做字段级三方合并，本地未上传的修改保留到下次同步。
+    /// 合并在本地存储的锁内完成，不会覆盖同时发生的界面修改。
+    pub async fn apply_remote_changes(
+        &self,
+        since_version: i64,
+    ) -> Result<RemoteChangeSet, StorageError> {
+        let postgres = self
+            .postgres_storage
+            .as_ref()
+            .ok_or("Database storage not available")?;
+
+        let changes = postgres.load_changes_since_version(since_version).await?;
+        let version = changes.new_version;
+        if changes.upserts.is_empty() && changes.deletions.is_empty() {
+            return Ok(RemoteChangeSet {
+                version,
+                changed_ids: Vec::new(),
+            });
         }
 
-        for mut l in local {
-            if let Some(r) = resolved.get(l.id()) {
-                if l.updated_at() > r.updated_at() {
-                    l.merge_missing_fields(r);
-                    resolved.insert(l.id().to_string(), l);
+        let upserts = changes.upserts;
+        let changed_ids = self.local_storage.apply_remote_changes(
+            &changes.deletions,
+            version,
+            |accounts, state| {
+                let mut changed_ids = Vec::new();
+                for remote in upserts {
+                    // 远端记录即双方当前的共同版本
+                    let mut base = remote.clone();
+                    match accounts.iter_mut().find(|a| a.id() == remote.id()) {
+                        Some(local) => {
+                            base.merge_missing_fields(local);
+                            let outcome =
+                                merge_account(state.bases.get(remote.id()), local, &remote)?;
+                            if !same_content(&outcome.account, local)? {
+                                changed_ids.push(remote.id().to_string());
+                            }
+                            state.apply_merge(outcome.report);
+                            *local = outcome.account;
+                        }
+                        None => {
+                            changed_ids.push(remote.id().to_string());
+                            accounts.push(remote);
+                        }
+                    }
+                    state.record_base(&base);
                 }
-            } else {
-                resolved.insert(l.id().to_string(), l);
-            }
+                Ok(changed_ids)
+            },
+        )?;
+
+        Ok(RemoteChangeSet {
+            version,
+            changed_ids,
+        })
+    }
+
+    /// 以上次同步的记录为基准逐字段合并，返回合并结果和冲突字段的变化
+    fn resolve_conflicts_impl(
+        local: Vec<T>,
+        remote: Vec<T>,
+        state: &SyncState<T>,
+    ) -> Result<(Vec<T>, MergeReport), StorageError> {
+        merge_account_lists(local, remote, &state.bases)
+    }
+
+    /// 为同步冲突选择保留的一侧，写入本地和数据库
+    pub async fn resolve_sync_conflict(
+        &self,
+        account_id: &str,
+        field: &str,
+        side: ConflictSide,
+    ) -> Result<T, StorageError> {
+        let postgres = self
+            .postgres_storage
+            .as_ref()
+            .ok_or("Database storage not available")?;
+
+        if !postgres.is_available().await {
+            return Err("Database not available".into());
         }
 
-        resolved.into_values().collect()
+        let mut state = self.local_storage.load_sync_state()?;
+        let conflict = state
+            .take_conflict(account_id, field)
+            .ok_or_else(|| format!("No sync conflict on {} for account {}", field, account_id))?;
+        let account = self
+            .local_storage
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+        let resolved = apply_choice(&account, &conflict, side)?;
+        self.local_storage.save_account(&resolved).await?;
+        postgres.save_account(&resolved).await?;
+        state.record_base(&resolved);
+        self.local_storage.save_sync_state(&state)?;
+
+        Ok(resolved)
     }
 }
 
@@ -157,6 +243,7 @@ where
         }
 
         let local_accounts = self.local_storage.load_accounts().await?;
+        let mut state = self.local_storage.load_sync_state()?;
         let mut synced_count = 0;
         let mut errors = Vec::new();
 
@@ -164,9 +251,11 @@ where
             if let Err(e) = postgres.save_account(account).await {
                 errors.push(format!("Failed to sync account {}: {}", account.id(), e));
             } else {
+                state.record_base(account);
                 synced_count += 1;
             }
         }
+        self.local_storage.save_sync_state(&state)?;
 
         let status = if errors.is_empty() {
             "success"
@@ -184,6 +273,7 @@ where
                 Some(errors.join("; "))
             },
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
@@ -233,9 +323,18 @@ where
             None
         };
 
+        let mut state = self.local_storage.load_sync_state()?;
+        for account in &remote_accounts {
+            state.record_base(account);
+        }
+        for id in &deletions {
+            state.forget(id);
+        }
+
         self.local_storage
             .replace_all(remote_accounts, deletions, new_version, selected_current)
             .await?;
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -243,6 +342,7 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: remote_accounts_len as i32,
+            conflicts: state.conflicts,
         })
     }
 
@@ -258,9 +358,9 @@ where
 
         let local_accounts = self.local_storage.load_accounts().await?;
         let remote_accounts = postgres.load_accounts().await?;
-        let resolved_accounts = self
-            .resolve_conflicts(local_accounts.clone(), remote_accounts.clone())
-            .await?;
+        let mut state = self.local_storage.load_sync_state()?;
+        let (resolved_accounts, report) =
+            Self::resolve_conflicts_impl(local_accounts, remote_accounts, &state)?;
 
         let mut synced_count = 0;
         for account in &resolved_accounts {
@@ -272,6 +372,7 @@ where
             }
 
             if postgres.save_account(account).await.is_ok() {
+                state.record_base(account);
                 remote_ok = true;
             }
 
@@ -279,6 +380,8 @@ where
                 synced_count += 1;
             }
         }
+        state.apply_merge(report);
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -286,6 +389,7 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
@@ -303,9 +407,9 @@ where
         }
 
         let remote_accounts = postgres.load_accounts().await?;
-        let resolved_accounts = self
-            .resolve_conflicts(local_accounts.clone(), remote_accounts.clone())
-            .await?;
+        let mut state = self.local_storage.load_sync_state()?;
+        let (resolved_accounts, report) =
+            Self::resolve_conflicts_impl(local_accounts, remote_accounts, &state)?;
 
         let mut synced_count = 0;
         for account in &resolved_accounts {
@@ -317,6 +421,7 @@ where
             }
 
             if postgres.save_account(account).await.is_ok() {
+                state.record_base(account);
                 remote_ok = true;
             }
 
@@ -324,6 +429,8 @@ where
                 synced_count += 1;
             }
         }
+        state.apply_merge(report);
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -331,11 +438,25 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
+    /// 存在未解决的字段冲突时返回冲突列表
     async fn get_sync_status(&self) -> Result<Option<AccountSyncStatus>, StorageError> {
-        Ok(None)
+        let state = self.local_storage.load_sync_state()?;
+        if state.conflicts.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(AccountSyncStatus {
+            last_sync_at: None,
+            sync_direction: "bidirectional".to_string(),
+            status: "conflict".to_string(),
+            error_message: None,
+            accounts_synced: 0,
+            conflicts: state.conflicts,
+        }))
     }
 
     async fn resolve_conflicts(
@@ -343,7 +464,8 @@ where
         local: Vec<T>,
         remote: Vec<T>,
     ) -> Result<Vec<T>, StorageError> {
-        Ok(Self::resolve_conflicts_impl(local, remote))
+        let state = self.local_storage.load_sync_state()?;
+        Ok(Self::resolve_conflicts_impl(local, remote, &state)?.0)
     }
 
     async fn sync_accounts(
@@ -359,20 +481,25 @@ where
             return Err("Database not available".into());
         }
 
-        // 处理客户端上传的变更
+        // 处理客户端上传的变更：与服务端记录逐字段三方合并
+        let mut state = self.local_storage.load_sync_state()?;
         for change in &req.upserts {
             let account = &change.account;
             if let Ok(Some(existing)) = postgres.get_account(account.id()).await {
-                if account.updated_at() > existing.updated_at() {
-                    let mut merged = account.clone();
-                    merged.merge_missing_fields(&existing);
-                    if let Err(e) = postgres.save_account_with_version(&merged).await {
-                        eprintln!(
-                            "Failed to save account {} to postgres: {:?}",
-                            account.id(),
-                            e
-                        );
-                    }
+                let outcome = merge_account(state.bases.get(account.id()), account, &existing)?;
+                state.apply_merge(outcome.report);
+
+                let mut current = existing;
+                current.merge_missing_fields(account);
+                if same_content(&outcome.account, &current)? {
+                    continue;
+                }
+                if let Err(e) = postgres.save_account_with_version(&outcome.account).await {
+                    eprintln!(
+                        "Failed to save account {} to postgres: {:?}",
+                        account.id(),
+                        e
+                    );
                 }
             } else {
                 if let Err(e) = postgres.save_account_with_version(account).await {
@@ -468,6 +595,13 @@ where
             None
         };
 
+        for account in &all_accounts {
+            state.record_base(account);
+        }
+        for id in &all_deletions {
+            state.forget(id);
+        }
+
         if let Err(e) = self
             .local_storage
             .replace_all(all_accounts, all_deletions, new_version, selected_current)
@@ -475,6 +609,9 @@ where
         {
             eprintln!("Failed to replace local accounts: {}", e);
         }
+        if let Err(e) = self.local_storage.save_sync_state(&state) {
+            eprintln!("Failed to save sync state: {}", e);
+        }
 
         Ok(ServerAccountSyncResponse {
             upserts: server_upserts,
@@ -1,9 +1,11 @@
+use super::at_rest;
+use super::merge::SyncState;
 use super::traits::{AccountStorage, StorageError, SyncableAccount};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::marker::PhantomData;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 const SCHEMA_VERSION: i32 = 2;
@@ -28,6 +30,8 @@ struct AccountStore<T> {
     accounts: Vec<T>,
     #[serde(default)]
     deletions: Vec<DeletedRecord>,
+    #[serde(default, skip_serializing_if = "SyncState::is_empty")]
+    sync_state: SyncState<T>,
 }
 
 impl<T> Default for AccountStore<T> {
@@ -38,6 +42,7 @@ impl<T> Default for AccountStore<T> {
             current_account_id: None,
             accounts: Vec::new(),
             deletions: Vec::new(),
+            sync_state: SyncState::default(),
         }
     }
 }
@@ -52,7 +57,8 @@ impl<T> AccountStore<T> {
 /// 通用本地文件存储
 pub struct GenericLocalStorage<T: SyncableAccount> {
     storage_path: PathBuf,
-    lock: Mutex<()>,
+    /// 与静态加密迁移共用的写锁
+    lock: Arc<Mutex<()>>,
     _phantom: PhantomData<T>,
 }
 
@@ -61,33 +67,63 @@ impl<T: SyncableAccount> GenericLocalStorage<T> {
         let app_data_dir = app_handle.path().app_data_dir()?;
         fs::create_dir_all(&app_data_dir)?;
 
-        Ok(Self {
-            storage_path: app_data_dir.join(T::storage_file_name()),
-            lock: Mutex::new(()),
-            _phantom: PhantomData,
-        })
+        let storage_path = app_data_dir.join(T::storage_file_name());
+        Ok(Self::new_with_path(storage_path))
     }
 
     pub fn new_with_path(storage_path: PathBuf) -> Self {
-        Self {
+        let storage = Self {
             storage_path,
-            lock: Mutex::new(()),
+            lock: Arc::new(Mutex::new(())),
+            _phantom: PhantomData,
+        };
+        storage.register_at_rest();
+        storage
+    }
+
+    fn register_at_rest(&self) {
+        let storage = Self {
+            storage_path: self.storage_path.clone(),
+            lock: self.lock.clone(),
             _phantom: PhantomData,
+        };
+        at_rest::register_store(&self.storage_path, move || storage.migrate_at_rest());
+    }
+
+    /// 按当前加密模式重写文件，返回是否重写
+    fn migrate_at_rest(&self) -> Result<bool, StorageError> {
+        let _guard = self.lock.lock().unwrap();
+
+        if !self.storage_path.exists() {
+            return Ok(false);
         }
+
+        let raw = fs::read_to_string(&self.storage_path)?;
+        if !at_rest::needs_rewrite(&raw) {
+            return Ok(false);
+        }
+
+        self.write_content(&at_rest::open(&raw)?)?;
+        Ok(true)
     }
 
     fn read_store(&self) -> Result<AccountStore<T>, StorageError> {
         let _guard = self.lock.lock().unwrap();
+        self.read_store_locked()
+    }
 
+    /// 读取存储（调用方持有锁）
+    fn read_store_locked(&self) -> Result<AccountStore<T>, StorageError> {
         if !self.storage_path.exists() {
             return Ok(AccountStore::default());
         }
 
-        let content = fs::read_to_string(&self.storage_path)?;
-        if content.trim().is_empty() {
+        let raw = fs::read_to_string(&self.storage_path)?;
+        if raw.trim().is_empty() {
             return Ok(AccountStore::default());
         }
 
+        let content = at_rest::open(&raw)?;
         let store: AccountStore<T> = serde_json::from_str(&content)?;
         if store.schema_version != SCHEMA_VERSION {
             return Err(format!(
@@ -98,20 +134,45 @@ impl<T: SyncableAccount> GenericLocalStorage<T> {
             .into());
         }
 
+        // 与当前加密模式不一致的文件（如旧的明文文件）就地重写
+        if at_rest::needs_rewrite(&raw) {
+            if let Err(e) = self.write_content(&content) {
+                eprintln!(
+                    "Failed to migrate {} local storage: {}",
+                    T::platform_name(),
+                    e
+                );
+            }
+        }
+
         Ok(store)
     }
 
     fn write_store(&self, store: &AccountStore<T>) -> Result<(), StorageError> {
         let _guard = self.lock.lock().unwrap();
+        self.write_content(&serde_json::to_string_pretty(store)?)
+    }
+
+    /// 持有锁完成读取、修改和写回，并发写入不会互相覆盖
+    fn modify_store<R>(
+        &self,
+        modify: impl FnOnce(&mut AccountStore<T>) -> Result<R, StorageError>,
+    ) -> Result<R, StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut store = self.read_store_locked()?;
+        let result = modify(&mut store)?;
+        self.write_content(&serde_json::to_string_pretty(&store)?)?;
+        Ok(result)
+    }
 
+    /// 原子写入文件（调用方持有锁），内容按加密模式编码
+    fn write_content(&self, content: &str) -> Result<(), StorageError> {
         if let Some(parent) = self.storage_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let temp_path = self.storage_path.with_extension("tmp");
-        let content = serde_json::to_string_pretty(store)?;
-
-        fs::write(&temp_path, content)?;
+        fs::write(&temp_path, at_rest::seal(content)?)?;
 
         match fs::rename(&temp_path, &self.storage_path) {
             Ok(_) => Ok(()),
@@ -128,9 +189,10 @@ impl<T: SyncableAccount> GenericLocalStorage<T> {
     }
 
     pub async fn set_current_account_id(&self, id: Option<String>) -> Result<(), StorageError> {
-        let mut store = self.read_store()?;
-        store.current_account_id = id;
-        self.write_store(&store)
+        self.modify_store(|store| {
+            store.current_account_id = id;
+            Ok(())
+        })
     }
 
     pub async fn replace_all(
@@ -144,18 +206,62 @@ impl<T: SyncableAccount> GenericLocalStorage<T> {
             account.set_deleted(false);
         }
 
-        let store = AccountStore {
-            schema_version: SCHEMA_VERSION,
-            version,
-            current_account_id,
-            accounts,
-            deletions: deletions
-                .into_iter()
-                .map(|id| DeletedRecord { id, version: 0 })
-                .collect(),
-        };
+        // 同步基准和未解决的冲突不随账号列表替换
+        self.modify_store(|store| {
+            *store = AccountStore {
+                schema_version: SCHEMA_VERSION,
+                version,
+                current_account_id,
+                accounts,
+                deletions: deletions
+                    .into_iter()
+                    .map(|id| DeletedRecord { id, version: 0 })
+                    .collect(),
+                sync_state: std::mem::take(&mut store.sync_state),
+            };
+            Ok(())
+        })
+    }
 
-        self.write_store(&store)
+    /// 实时同步：在锁内把远端变更合并进本地，期间的界面写入会等待而不是被覆盖
+    ///
+    /// `merge_upserts` 就地合并远端写入并返回内容有变化的账号 ID；
+    /// `tombstones` 中的账号被移除并记入删除列表，本地版本号推进到 `version`。
+    pub fn apply_remote_changes(
+        &self,
+        tombstones: &[String],
+        version: i64,
+        merge_upserts: impl FnOnce(&mut Vec<T>, &mut SyncState<T>) -> Result<Vec<String>, StorageError>,
+    ) -> Result<Vec<String>, StorageError> {
+        self.modify_store(|store| {
+            let mut changed_ids = merge_upserts(&mut store.accounts, &mut store.sync_state)?;
+
+            store.accounts.retain(|a| {
+                if tombstones.iter().any(|id| id == a.id()) {
+                    changed_ids.push(a.id().to_string());
+                    false
+                } else {
+                    true
+                }
+            });
+            for id in tombstones {
+                store.sync_state.forget(id);
+                if !store.deletions.iter().any(|d| &d.id == id) {
+                    store.deletions.push(DeletedRecord {
+                        id: id.clone(),
+                        version: 0,
+                    });
+                }
+            }
+            if let Some(current) = store.current_account_id.as_deref() {
+                if !store.accounts.iter().any(|a| a.id() == current) {
+                    store.current_account_id = None;
+                }
+            }
+            store.version = store.version.max(version);
+
+            Ok(changed_ids)
+        })
     }
 
     pub fn get_local_version(&self) -> Result<i64, StorageError> {
@@ -167,27 +273,38 @@ impl<T: SyncableAccount> GenericLocalStorage<T> {
         let store = self.read_store()?;
         Ok(store.deletions.iter().map(|d| d.id.clone()).collect())
     }
+
+    pub fn load_sync_state(&self) -> Result<SyncState<T>, StorageError> {
+        Ok(self.read_store()?.sync_state)
+    }
+
+    pub fn save_sync_state(&self, state: &SyncState<T>) -> Result<(), StorageError> {
+        self.modify_store(|store| {
+            store.sync_state = state.clone();
+            Ok(())
+        })
+    }
 }
 
 #[async_trait::async_trait]
 impl<T: SyncableAccount> AccountStorage<T> for GenericLocalStorage<T> {
     async fn save_account(&self, account: &T) -> Result<(), StorageError> {
-        let mut store = self.read_store()?;
         let mut account = account.clone();
         account.set_deleted(false);
 
-        if account.version() <= 0 {
-            account.set_version(store.next_version());
-        } else if account.version() > store.version {
-            store.version = account.version();
-        }
-
-        let account_id = account.id().to_string();
-        store.accounts.retain(|a| a.id() != account_id);
-        store.accounts.push(account);
-        store.deletions.retain(|d| d.id != account_id);
+        self.modify_store(|store| {
+            if account.version() <= 0 {
+                account.set_version(store.next_version());
+            } else if account.version() > store.version {
+                store.version = account.version();
+            }
 
-        self.write_store(&store)
+            let account_id = account.id().to_string();
+            store.accounts.retain(|a| a.id() != account_id);
+            store.accounts.push(account);
+            store.deletions.retain(|d| d.id != account_id);
+            Ok(())
+        })
     }
 
     async fn load_accounts(&self) -> Result<Vec<T>, StorageError> {
@@ -212,24 +329,24 @@ impl<T: SyncableAccount> AccountStorage<T> for GenericLocalStorage<T> {
     }
 
     async fn delete_account(&self, id: &str) -> Result<bool, StorageError> {
-        let mut store = self.read_store()?;
-        let initial_len = store.accounts.len();
+        self.modify_store(|store| {
+            let initial_len = store.accounts.len();
 
-        store.accounts.retain(|a| a.id() != id);
-        store.deletions.retain(|d| d.id != id);
+            store.accounts.retain(|a| a.id() != id);
+            store.deletions.retain(|d| d.id != id);
 
-        let version = store.next_version();
-        store.deletions.push(DeletedRecord {
-            id: id.to_string(),
-            version,
-        });
+            let version = store.next_version();
+            store.deletions.push(DeletedRecord {
+                id: id.to_string(),
+                version,
+            });
 
-        if store.current_account_id.as_deref() == Some(id) {
-            store.current_account_id = store.accounts.first().map(|a| a.id().to_string());
-        }
+            if store.current_account_id.as_deref() == Some(id) {
+                store.current_account_id = store.accounts.first().map(|a| a.id().to_string());
+            }
 
-        self.write_store(&store)?;
-        Ok(store.accounts.len() < initial_len)
+            Ok(store.accounts.len() < initial_len)
+        })
     }
 
     async fn clear_all_accounts(&self) -> Result<(), StorageError> {
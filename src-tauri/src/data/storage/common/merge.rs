@@ -0,0 +1,415 @@
+//! 同步冲突的字段级三方合并
+//!
+//! 以上次同步时双方一致的记录为基准（共同祖先），逐个顶层字段比较本地与远端：
+//! - 只有一侧修改的字段取修改后的值
+//! - 两侧改成相同值的字段直接采用
+//! - 两侧都修改且结果不同的字段是真正的冲突：暂取修改时间较新一侧的值，
+//!   同时记录下来，由用户选择保留哪一侧
+//! - 程序自动刷新的字段（token、额度等，见 `SyncableAccount::machine_maintained_fields`）
+//!   各设备会各自更新，两侧都修改时直接取较新一侧，不记录冲突
+//! - 之前记录的冲突字段在远端又被修改且顺利合并时，旧冲突随之清除
+//!
+//! 没有基准（如首次同步）时退回整条记录按修改时间取较新的一侧。
+
+use super::traits::{StorageError, SyncableAccount};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// 同步元数据字段，不参与字段合并
+const BOOKKEEPING_FIELDS: [&str; 3] = ["updated_at", "version", "deleted"];
+
+/// 两侧都修改了同一字段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldConflict {
+    pub account_id: String,
+    pub field: String,
+    /// 上次同步时的值
+    pub base: Value,
+    pub local: Value,
+    pub remote: Value,
+}
+
+/// 解决冲突时保留的一侧
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Local,
+    Remote,
+}
+
+/// 本地保存的同步状态：三方合并的基准记录和未解决的冲突
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncState<T> {
+    #[serde(default)]
+    pub bases: HashMap<String, T>,
+    #[serde(default)]
+    pub conflicts: Vec<FieldConflict>,
+}
+
+impl<T> Default for SyncState<T> {
+    fn default() -> Self {
+        Self {
+            bases: HashMap::new(),
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+impl<T> SyncState<T> {
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty() && self.conflicts.is_empty()
+    }
+}
+
+impl<T: SyncableAccount> SyncState<T> {
+    /// 记录双方已一致的记录，作为下次合并的基准
+    pub fn record_base(&mut self, account: &T) {
+        self.bases.insert(account.id().to_string(), account.clone());
+    }
+
+    /// 账号删除后清除其基准和冲突
+    pub fn forget(&mut self, account_id: &str) {
+        self.bases.remove(account_id);
+        self.conflicts.retain(|c| c.account_id != account_id);
+    }
+
+    /// 记录一次合并的结果：清除已顺利合并字段的旧冲突，加入新冲突
+    pub fn apply_merge(&mut self, report: MergeReport) {
+        self.conflicts.retain(|c| {
+            !report
+                .settled
+                .iter()
+                .any(|(account_id, field)| *account_id == c.account_id && *field == c.field)
+        });
+        self.add_conflicts(report.conflicts);
+    }
+
+    /// 同一账号同一字段的新冲突替换旧记录
+    pub fn add_conflicts(&mut self, conflicts: Vec<FieldConflict>) {
+        for conflict in conflicts {
+            self.conflicts
+                .retain(|c| !(c.account_id == conflict.account_id && c.field == conflict.field));
+            self.conflicts.push(conflict);
+        }
+    }
+
+    pub fn take_conflict(&mut self, account_id: &str, field: &str) -> Option<FieldConflict> {
+        let index = self
+            .conflicts
+            .iter()
+            .position(|c| c.account_id == account_id && c.field == field)?;
+        Some(self.conflicts.remove(index))
+    }
+}
+
+/// 一次合并中字段冲突的变化
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// 两侧都修改了的用户字段
+    pub conflicts: Vec<FieldConflict>,
+    /// 远端修改后顺利合并的字段（账号 ID，字段名），其旧冲突已失效
+    pub settled: Vec<(String, String)>,
+}
+
+impl MergeReport {
+    fn extend(&mut self, other: MergeReport) {
+        self.conflicts.extend(other.conflicts);
+        self.settled.extend(other.settled);
+    }
+}
+
+/// 合并结果
+#[derive(Debug)]
+pub struct MergeOutcome<T> {
+    pub account: T,
+    pub report: MergeReport,
+}
+
+fn to_fields<T: SyncableAccount>(account: &T) -> Result<Map<String, Value>, StorageError> {
+    match serde_json::to_value(account)? {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(format!("{} account is not a JSON object", T::platform_name()).into()),
+    }
+}
+
+/// 整条记录取修改时间较新的一侧
+fn newer_of<T: SyncableAccount>(local: &T, remote: T) -> T {
+    if local.updated_at() > remote.updated_at() {
+        let mut local = local.clone();
+        local.merge_missing_fields(&remote);
+        local
+    } else {
+        remote
+    }
+}
+
+/// 以 `base` 为共同祖先合并本地与远端的同一账号
+pub fn merge_account<T: SyncableAccount>(
+    base: Option<&T>,
+    local: &T,
+    remote: &T,
+) -> Result<MergeOutcome<T>, StorageError> {
+    // 远端不保存的本地字段（如额度）不算远端修改
+    let mut remote = remote.clone();
+    remote.merge_missing_fields(local);
+
+    let Some(base) = base else {
+        return Ok(MergeOutcome {
+            account: newer_of(local, remote),
+            report: MergeReport::default(),
+        });
+    };
+
+    let base_fields = to_fields(base)?;
+    let local_fields = to_fields(local)?;
+    let remote_fields = to_fields(&remote)?;
+    let local_newer = local.updated_at() > remote.updated_at();
+
+    // 元数据字段沿用较新的一侧
+    let mut merged = if local_newer {
+        local_fields.clone()
+    } else {
+        remote_fields.clone()
+    };
+    let mut report = MergeReport::default();
+    let keys: BTreeSet<&String> = local_fields.keys().chain(remote_fields.keys()).collect();
+    for key in keys {
+        if BOOKKEEPING_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let base_value = base_fields.get(key).unwrap_or(&Value::Null);
+        let local_value = local_fields.get(key).unwrap_or(&Value::Null);
+        let remote_value = remote_fields.get(key).unwrap_or(&Value::Null);
+        let newer_value = if local_newer {
+            local_value
+        } else {
+            remote_value
+        };
+
+        let value = if local_value == remote_value || remote_value == base_value {
+            local_value
+        } else if local_value == base_value {
+            remote_value
+        } else if T::machine_maintained_fields().contains(&key.as_str()) {
+            newer_value
+        } else {
+            report.conflicts.push(FieldConflict {
+                account_id: local.id().to_string(),
+                field: key.clone(),
+                base: base_value.clone(),
+                local: local_value.clone(),
+                remote: remote_value.clone(),
+            });
+            merged.insert(key.clone(), newer_value.clone());
+            continue;
+        };
+        // 远端在上次同步后又改动了该字段且顺利合并（如其他设备已选择或两侧改成相同值），
+        // 之前记录的冲突已失效；远端未改动时保留冲突等待用户选择
+        if remote_value != base_value {
+            report.settled.push((local.id().to_string(), key.clone()));
+        }
+        merged.insert(key.clone(), value.clone());
+    }
+
+    Ok(MergeOutcome {
+        account: serde_json::from_value(Value::Object(merged))?,
+        report,
+    })
+}
+
+/// 合并两侧的账号列表：两侧都有的账号做三方合并，只在一侧的账号原样保留
+pub fn merge_account_lists<T: SyncableAccount>(
+    local: Vec<T>,
+    remote: Vec<T>,
+    bases: &HashMap<String, T>,
+) -> Result<(Vec<T>, MergeReport), StorageError> {
+    let mut remote_map: HashMap<String, T> = remote
+        .into_iter()
+        .map(|a| (a.id().to_string(), a))
+        .collect();
+    let mut accounts = Vec::new();
+    let mut report = MergeReport::default();
+
+    for l in local {
+        match remote_map.remove(l.id()) {
+            Some(r) => {
+                let outcome = merge_account(bases.get(l.id()), &l, &r)?;
+                accounts.push(outcome.account);
+                report.extend(outcome.report);
+            }
+            None => accounts.push(l),
+        }
+    }
+    accounts.extend(remote_map.into_values());
+
+    Ok((accounts, report))
+}
+
+/// 两条记录除同步元数据外内容相同
+pub fn same_content<T: SyncableAccount>(a: &T, b: &T) -> Result<bool, StorageError> {
+    let mut a = to_fields(a)?;
+    let mut b = to_fields(b)?;
+    for field in BOOKKEEPING_FIELDS {
+        a.remove(field);
+        b.remove(field);
+    }
+    Ok(a == b)
+}
+
+/// 按用户的选择写入冲突字段，并更新修改时间
+pub fn apply_choice<T: SyncableAccount>(
+    account: &T,
+    conflict: &FieldConflict,
+    side: ConflictSide,
+) -> Result<T, StorageError> {
+    let mut fields = to_fields(account)?;
+    let value = match side {
+        ConflictSide::Local => conflict.local.clone(),
+        ConflictSide::Remote => conflict.remote.clone(),
+    };
+    fields.insert(conflict.field.clone(), value);
+    let updated_at = chrono::Utc::now().timestamp().max(account.updated_at());
+    fields.insert("updated_at".to_string(), Value::from(updated_at));
+    Ok(serde_json::from_value(Value::Object(fields))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct TestAccount {
+        id: String,
+        tag: Option<String>,
+        quota: Option<i64>,
+        note: String,
+        updated_at: i64,
+        version: i64,
+        deleted: bool,
+    }
+
+    impl SyncableAccount for TestAccount {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        fn email(&self) -> &str {
+            ""
+        }
+        fn updated_at(&self) -> i64 {
+            self.updated_at
+        }
+        fn version(&self) -> i64 {
+            self.version
+        }
+        fn set_version(&mut self, version: i64) {
+            self.version = version;
+        }
+        fn is_deleted(&self) -> bool {
+            self.deleted
+        }
+        fn set_deleted(&mut self, deleted: bool) {
+            self.deleted = deleted;
+        }
+        fn platform_name() -> &'static str {
+            "test"
+        }
+        fn merge_missing_fields(&mut self, source: &Self) {
+            if self.quota.is_none() {
+                self.quota = source.quota;
+            }
+        }
+        fn machine_maintained_fields() -> &'static [&'static str] {
+            &["quota"]
+        }
+    }
+
+    fn account(tag: &str, quota: Option<i64>, note: &str, updated_at: i64) -> TestAccount {
+        TestAccount {
+            id: "a".to_string(),
+            tag: Some(tag.to_string()),
+            quota,
+            note: note.to_string(),
+            updated_at,
+            version: 1,
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn edits_to_different_fields_are_both_kept_and_same_field_edits_conflict() {
+        let base = account("old", Some(10), "n", 100);
+        // 本地刷新了额度，远端改了标签
+        let local = account("old", Some(5), "n", 300);
+        let remote = account("team", Some(10), "n", 200);
+
+        let outcome = merge_account(Some(&base), &local, &remote).unwrap();
+        assert_eq!(outcome.account.tag.as_deref(), Some("team"));
+        assert_eq!(outcome.account.quota, Some(5));
+        assert_eq!(outcome.account.updated_at, 300);
+        assert!(outcome.report.conflicts.is_empty());
+
+        // 两侧都改了备注
+        let local = account("old", Some(10), "mine", 300);
+        let remote = account("old", Some(10), "theirs", 200);
+        let outcome = merge_account(Some(&base), &local, &remote).unwrap();
+        assert_eq!(outcome.account.note, "mine");
+        assert_eq!(outcome.report.conflicts.len(), 1);
+        let conflict = &outcome.report.conflicts[0];
+        assert_eq!(conflict.field, "note");
+        assert_eq!(conflict.base, Value::from("n"));
+
+        let resolved = apply_choice(&outcome.account, conflict, ConflictSide::Remote).unwrap();
+        assert_eq!(resolved.note, "theirs");
+        assert!(resolved.updated_at >= 300);
+
+        // 远端未保存额度时不视为远端修改
+        let remote = account("old", None, "n", 200);
+        let outcome = merge_account(Some(&base), &local, &remote).unwrap();
+        assert!(outcome.report.conflicts.is_empty());
+        assert_eq!(outcome.account.quota, Some(10));
+
+        // 没有基准时整条记录取较新的一侧
+        let outcome = merge_account(None, &local, &account("x", None, "y", 400)).unwrap();
+        assert_eq!(outcome.account.tag.as_deref(), Some("x"));
+        assert_eq!(outcome.account.quota, Some(10));
+        assert!(outcome.report.conflicts.is_empty());
+
+        let mut state = SyncState::<TestAccount>::default();
+        state.add_conflicts(vec![conflict.clone(), conflict.clone()]);
+        assert_eq!(state.conflicts.len(), 1);
+        assert!(state.take_conflict("a", "note").is_some());
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn machine_fields_take_newer_side_and_stale_conflicts_are_cleared() {
+        let base = account("old", Some(10), "n", 100);
+        // 两台设备各自刷新了额度，不算冲突
+        let local = account("old", Some(5), "n", 300);
+        let remote = account("old", Some(7), "n", 200);
+        let outcome = merge_account(Some(&base), &local, &remote).unwrap();
+        assert_eq!(outcome.account.quota, Some(5));
+        assert!(outcome.report.conflicts.is_empty());
+
+        let mut state = SyncState::<TestAccount>::default();
+        let local = account("old", Some(10), "mine", 300);
+        let remote = account("old", Some(10), "theirs", 200);
+        let outcome = merge_account(Some(&base), &local, &remote).unwrap();
+        state.apply_merge(outcome.report);
+        assert_eq!(state.conflicts.len(), 1);
+
+        // 同步后两侧都是自动选择的值，冲突仍等待用户处理
+        let synced = outcome.account;
+        let outcome = merge_account(Some(&synced), &synced, &synced).unwrap();
+        state.apply_merge(outcome.report);
+        assert_eq!(state.conflicts.len(), 1);
+
+        // 之后两侧改成了相同的值，旧冲突清除
+        let agreed = account("old", Some(10), "agreed", 400);
+        let outcome = merge_account(Some(&synced), &agreed, &agreed).unwrap();
+        assert!(outcome.report.conflicts.is_empty());
+        state.apply_merge(outcome.report);
+        assert!(state.conflicts.is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod at_rest;
 pub mod dual_storage;
 pub mod local_storage;
+pub mod merge;
 pub mod postgres_storage;
 pub mod sqlite_dual_storage;
 pub mod sqlite_storage;
@@ -7,6 +9,7 @@ pub mod traits;
 
 pub use dual_storage::*;
 pub use local_storage::*;
+pub use merge::*;
 pub use postgres_storage::*;
 pub use sqlite_dual_storage::*;
 pub use sqlite_storage::*;
@@ -1,8 +1,9 @@
-use super::traits::{AccountStorage, StorageError, SyncableAccount};
+use super::traits::{AccountStorage, ServerAccountSyncResponse, StorageError, SyncableAccount};
 use crate::database::DatabaseManager;
+use crate::database::sync_crypto;
 use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio_postgres::Row;
+use tokio_postgres::{IsolationLevel, Row};
 
 /// 平台特定的数据库映射器 trait
 pub trait AccountDbMapper<T: SyncableAccount>: Send + Sync + 'static {
@@ -111,18 +112,67 @@ where
         Ok(rows.iter().map(|r| r.get(0)).collect())
     }
 
+    /// 在同一快照中读取 `since_version` 之后的写入和删除标记
+    ///
+    /// 返回的版本号取自实际读到的行，读取期间提交的写入留给下一次拉取。
+    pub async fn load_changes_since_version(
+        &self,
+        since_version: i64,
+    ) -> Result<ServerAccountSyncResponse<T>, StorageError> {
+        let pool = self.get_pool().await?;
+        let mut client = pool.get().await?;
+        let tx = client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()
+            .await?;
+
+        let sql = format!(
+            "SELECT {} FROM {} WHERE version > $1 AND deleted IS NOT TRUE ORDER BY version",
+            M::select_columns(),
+            T::table_name()
+        );
+        let mut upserts = Vec::new();
+        for row in tx.query(&sql, &[&since_version]).await? {
+            upserts.push(M::from_row(&row)?);
+        }
+
+        let sql = format!(
+            "SELECT id, version FROM {} WHERE deleted IS TRUE AND version > $1",
+            T::table_name()
+        );
+        let tombstone_rows = tx.query(&sql, &[&since_version]).await?;
+        tx.commit().await?;
+
+        let new_version = upserts
+            .iter()
+            .map(|account| account.version())
+            .chain(tombstone_rows.iter().map(|row| row.get::<_, i64>(1)))
+            .fold(since_version, i64::max);
+        Ok(ServerAccountSyncResponse {
+            upserts,
+            deletions: tombstone_rows.iter().map(|row| row.get(0)).collect(),
+            new_version,
+        })
+    }
+
     pub async fn save_account_with_version(&self, account: &T) -> Result<i64, StorageError> {
         let pool = self.get_pool().await?;
-        let client = pool.get().await?;
+        let mut client = pool.get().await?;
         let new_version = self.get_next_version().await?;
 
+        // 校验与写入在同一事务中，避免与其他设备更换口令交错
+        let tx = client.transaction().await?;
+        sync_crypto::ensure_secrets_writable(&*tx).await?;
         let params = M::to_params(account, new_version);
         let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = params
             .iter()
             .map(|p| p.as_ref() as &(dyn tokio_postgres::types::ToSql + Sync))
             .collect();
 
-        client.execute(M::insert_sql(), &params_refs).await?;
+        tx.execute(M::insert_sql(), &params_refs).await?;
+        tx.commit().await?;
         Ok(new_version)
     }
 
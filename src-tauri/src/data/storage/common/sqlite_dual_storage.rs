@@ -1,10 +1,13 @@
+use super::merge::{
+    ConflictSide, MergeReport, SyncState, apply_choice, merge_account, merge_account_lists,
+    same_content,
+};
 use super::traits::{
     AccountStorage, AccountSyncManager, AccountSyncStatus, ClientAccountSyncRequest,
     ServerAccountSyncResponse, StorageError, SyncableAccount, SyncableLocalStorage,
 };
 use super::{AccountDbMapper, GenericPostgreSQLStorage};
 use chrono::Utc;
-use std::collections::HashMap;
 use std::sync::Arc;
 
 /// SQLite 本地 + PostgreSQL 远端 双向存储
@@ -47,24 +50,48 @@ where
         self.local_storage.set_current_account_id(account_id).await
     }
 
-    fn resolve_conflicts_impl(local: Vec<T>, remote: Vec<T>) -> Vec<T> {
-        let mut resolved: HashMap<String, T> = HashMap::new();
+    /// 以上次同步的记录为基准逐字段合并，返回合并结果和冲突字段的变化
+    fn resolve_conflicts_impl(
+        local: Vec<T>,
+        remote: Vec<T>,
+        state: &SyncState<T>,
+    ) -> Result<(Vec<T>, MergeReport), StorageError> {
+        merge_account_lists(local, remote, &state.bases)
+    }
 
-        for r in remote {
-            resolved.insert(r.id().to_string(), r);
-        }
+    /// 为同步冲突选择保留的一侧，写入本地和数据库
+    pub async fn resolve_sync_conflict(
+        &self,
+        account_id: &str,
+        field: &str,
+        side: ConflictSide,
+    ) -> Result<T, StorageError> {
+        let postgres = self
+            .postgres_storage
+            .as_ref()
+            .ok_or("Database storage not available")?;
 
-        for l in local {
-            if let Some(r) = resolved.get(l.id()) {
-                if l.updated_at() > r.updated_at() {
-                    resolved.insert(l.id().to_string(), l);
-                }
-            } else {
-                resolved.insert(l.id().to_string(), l);
-            }
+        if !postgres.is_available().await {
+            return Err("Database not available".into());
         }
 
-        resolved.into_values().collect()
+        let mut state = self.local_storage.load_sync_state()?;
+        let conflict = state
+            .take_conflict(account_id, field)
+            .ok_or_else(|| format!("No sync conflict on {} for account {}", field, account_id))?;
+        let account = self
+            .local_storage
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+        let resolved = apply_choice(&account, &conflict, side)?;
+        self.local_storage.save_account(&resolved).await?;
+        postgres.save_account(&resolved).await?;
+        state.record_base(&resolved);
+        self.local_storage.save_sync_state(&state)?;
+
+        Ok(resolved)
     }
 }
 
@@ -136,6 +163,7 @@ where
         }
 
         let local_accounts = self.local_storage.load_accounts().await?;
+        let mut state = self.local_storage.load_sync_state()?;
         let mut synced_count = 0;
         let mut errors = Vec::new();
 
@@ -143,9 +171,11 @@ where
             if let Err(e) = postgres.save_account(account).await {
                 errors.push(format!("Failed to sync account {}: {}", account.id(), e));
             } else {
+                state.record_base(account);
                 synced_count += 1;
             }
         }
+        self.local_storage.save_sync_state(&state)?;
 
         let status = if errors.is_empty() {
             "success"
@@ -163,6 +193,7 @@ where
                 Some(errors.join("; "))
             },
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
@@ -200,9 +231,18 @@ where
             None
         };
 
+        let mut state = self.local_storage.load_sync_state()?;
+        for account in &remote_accounts {
+            state.record_base(account);
+        }
+        for id in &deletions {
+            state.forget(id);
+        }
+
         self.local_storage
             .replace_all(remote_accounts, deletions, new_version, selected_current)
             .await?;
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -210,6 +250,7 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: remote_accounts_len as i32,
+            conflicts: state.conflicts,
         })
     }
 
@@ -225,9 +266,9 @@ where
 
         let local_accounts = self.local_storage.load_accounts().await?;
         let remote_accounts = postgres.load_accounts().await?;
-        let resolved_accounts = self
-            .resolve_conflicts(local_accounts.clone(), remote_accounts.clone())
-            .await?;
+        let mut state = self.local_storage.load_sync_state()?;
+        let (resolved_accounts, report) =
+            Self::resolve_conflicts_impl(local_accounts, remote_accounts, &state)?;
 
         let mut synced_count = 0;
         for account in &resolved_accounts {
@@ -239,6 +280,7 @@ where
             }
 
             if postgres.save_account(account).await.is_ok() {
+                state.record_base(account);
                 remote_ok = true;
             }
 
@@ -246,6 +288,8 @@ where
                 synced_count += 1;
             }
         }
+        state.apply_merge(report);
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -253,6 +297,7 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
@@ -270,9 +315,9 @@ where
         }
 
         let remote_accounts = postgres.load_accounts().await?;
-        let resolved_accounts = self
-            .resolve_conflicts(local_accounts.clone(), remote_accounts.clone())
-            .await?;
+        let mut state = self.local_storage.load_sync_state()?;
+        let (resolved_accounts, report) =
+            Self::resolve_conflicts_impl(local_accounts, remote_accounts, &state)?;
 
         let mut synced_count = 0;
         for account in &resolved_accounts {
@@ -284,6 +329,7 @@ where
             }
 
             if postgres.save_account(account).await.is_ok() {
+                state.record_base(account);
                 remote_ok = true;
             }
 
@@ -291,6 +337,8 @@ where
                 synced_count += 1;
             }
         }
+        state.apply_merge(report);
+        self.local_storage.save_sync_state(&state)?;
 
         Ok(AccountSyncStatus {
             last_sync_at: Some(Utc::now()),
@@ -298,11 +346,25 @@ where
             status: "success".to_string(),
             error_message: None,
             accounts_synced: synced_count,
+            conflicts: state.conflicts,
         })
     }
 
+    /// 存在未解决的字段冲突时返回冲突列表
     async fn get_sync_status(&self) -> Result<Option<AccountSyncStatus>, StorageError> {
-        Ok(None)
+        let state = self.local_storage.load_sync_state()?;
+        if state.conflicts.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(AccountSyncStatus {
+            last_sync_at: None,
+            sync_direction: "bidirectional".to_string(),
+            status: "conflict".to_string(),
+            error_message: None,
+            accounts_synced: 0,
+            conflicts: state.conflicts,
+        }))
     }
 
     async fn resolve_conflicts(
@@ -310,7 +372,8 @@ where
         local: Vec<T>,
         remote: Vec<T>,
     ) -> Result<Vec<T>, StorageError> {
-        Ok(Self::resolve_conflicts_impl(local, remote))
+        let state = self.local_storage.load_sync_state()?;
+        Ok(Self::resolve_conflicts_impl(local, remote, &state)?.0)
     }
 
     async fn sync_accounts(
@@ -326,18 +389,25 @@ where
             return Err("Database not available".into());
         }
 
-        // 处理客户端上传的变更
+        // 处理客户端上传的变更：与服务端记录逐字段三方合并
+        let mut state = self.local_storage.load_sync_state()?;
         for change in &req.upserts {
             let account = &change.account;
             if let Ok(Some(existing)) = postgres.get_account(account.id()).await {
-                if account.updated_at() > existing.updated_at() {
-                    if let Err(e) = postgres.save_account_with_version(account).await {
-                        eprintln!(
-                            "Failed to save account {} to postgres: {:?}",
-                            account.id(),
-                            e
-                        );
-                    }
+                let outcome = merge_account(state.bases.get(account.id()), account, &existing)?;
+                state.apply_merge(outcome.report);
+
+                let mut current = existing;
+                current.merge_missing_fields(account);
+                if same_content(&outcome.account, &current)? {
+                    continue;
+                }
+                if let Err(e) = postgres.save_account_with_version(&outcome.account).await {
+                    eprintln!(
+                        "Failed to save account {} to postgres: {:?}",
+                        account.id(),
+                        e
+                    );
                 }
             } else {
                 if let Err(e) = postgres.save_account_with_version(account).await {
@@ -420,6 +490,13 @@ where
             None
         };
 
+        for account in &all_accounts {
+            state.record_base(account);
+        }
+        for id in &all_deletions {
+            state.forget(id);
+        }
+
         if let Err(e) = self
             .local_storage
             .replace_all(all_accounts, all_deletions, new_version, selected_current)
@@ -427,6 +504,9 @@ where
         {
             eprintln!("Failed to replace local accounts: {}", e);
         }
+        if let Err(e) = self.local_storage.save_sync_state(&state) {
+            eprintln!("Failed to save sync state: {}", e);
+        }
 
         Ok(ServerAccountSyncResponse {
             upserts: server_upserts,
@@ -1,9 +1,11 @@
+use super::at_rest;
+use super::merge::SyncState;
 use super::traits::{AccountStorage, StorageError, SyncableAccount, SyncableLocalStorage};
 use rusqlite::{Connection, params};
 use serde_json;
 use std::marker::PhantomData;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 const SCHEMA_VERSION: i64 = 1;
@@ -12,7 +14,8 @@ const SCHEMA_VERSION: i64 = 1;
 /// 使用 JSON 序列化存储每条记录，配合 SQLite 索引实现高效查询
 pub struct GenericSQLiteStorage<T: SyncableAccount> {
     db_path: PathBuf,
-    lock: Mutex<()>,
+    /// 与静态加密迁移共用的写锁
+    lock: Arc<Mutex<()>>,
     _phantom: PhantomData<T>,
 }
 
@@ -25,12 +28,19 @@ impl<T: SyncableAccount> GenericSQLiteStorage<T> {
 
         let storage = Self {
             db_path,
-            lock: Mutex::new(()),
+            lock: Arc::new(Mutex::new(())),
             _phantom: PhantomData,
         };
 
         storage.init_db()?;
 
+        let migration = Self {
+            db_path: storage.db_path.clone(),
+            lock: storage.lock.clone(),
+            _phantom: PhantomData,
+        };
+        at_rest::register_store(&storage.db_path, move || migration.migrate_at_rest());
+
         Ok(storage)
     }
 
@@ -96,9 +106,50 @@ impl<T: SyncableAccount> GenericSQLiteStorage<T> {
             [],
         )?;
 
+        self.rewrite_at_rest(&conn)?;
+
         Ok(())
     }
 
+    /// 持有写锁按当前加密模式重写，返回是否有记录被重写
+    fn migrate_at_rest(&self) -> Result<bool, StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let conn = self.get_connection()?;
+        Ok(self.rewrite_at_rest(&conn)? > 0)
+    }
+
+    /// 重写格式与当前加密模式不一致的记录（如启用加密后的旧明文记录），返回重写的条数
+    fn rewrite_at_rest(&self, conn: &Connection) -> Result<usize, StorageError> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, data FROM {}_accounts",
+            T::platform_name()
+        ))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let rows: Vec<(String, String)> = rows
+            .into_iter()
+            .filter(|(_, data)| at_rest::needs_rewrite(data))
+            .collect();
+
+        let mut rewritten = 0;
+        for (id, data) in rows {
+            let data = at_rest::seal(&at_rest::open(&data)?)?;
+            conn.execute(
+                &format!(
+                    "UPDATE {}_accounts SET data = ?1 WHERE id = ?2",
+                    T::platform_name()
+                ),
+                params![data, id],
+            )?;
+            rewritten += 1;
+        }
+
+        Ok(rewritten)
+    }
+
     fn get_meta(&self, conn: &Connection, key: &str) -> Result<Option<String>, StorageError> {
         let mut stmt = conn.prepare(&format!(
             "SELECT value FROM {}_meta WHERE key = ?1",
@@ -171,7 +222,7 @@ impl<T: SyncableAccount> GenericSQLiteStorage<T> {
 
         for account in &mut accounts {
             account.set_deleted(false);
-            let data = serde_json::to_string(account)?;
+            let data = at_rest::seal(&serde_json::to_string(account)?)?;
             conn.execute(
                 &format!(
                     "INSERT INTO {}_accounts (id, data, version, deleted, updated_at) VALUES (?1, ?2, ?3, 0, ?4)",
@@ -223,6 +274,23 @@ impl<T: SyncableAccount> GenericSQLiteStorage<T> {
 
         Ok(ids)
     }
+
+    /// 同步基准和未解决的冲突以 JSON 保存在 meta 表，按加密模式编码
+    pub fn load_sync_state(&self) -> Result<SyncState<T>, StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let conn = self.get_connection()?;
+        match self.get_meta(&conn, "sync_state")? {
+            Some(raw) if !raw.is_empty() => Ok(serde_json::from_str(&at_rest::open(&raw)?)?),
+            _ => Ok(SyncState::default()),
+        }
+    }
+
+    pub fn save_sync_state(&self, state: &SyncState<T>) -> Result<(), StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let conn = self.get_connection()?;
+        let data = at_rest::seal(&serde_json::to_string(state)?)?;
+        self.set_meta(&conn, "sync_state", &data)
+    }
 }
 
 #[async_trait::async_trait]
@@ -244,7 +312,7 @@ impl<T: SyncableAccount> AccountStorage<T> for GenericSQLiteStorage<T> {
             }
         }
 
-        let data = serde_json::to_string(&account)?;
+        let data = at_rest::seal(&serde_json::to_string(&account)?)?;
         let id = account.id().to_string();
 
         conn.execute(
@@ -276,15 +344,24 @@ impl<T: SyncableAccount> AccountStorage<T> for GenericSQLiteStorage<T> {
             T::platform_name()
         ))?;
 
-        let accounts: Vec<T> = stmt
+        let rows = stmt
             .query_map([], |row| {
                 let data: String = row.get(0)?;
                 Ok(data)
             })?
-            .filter_map(|r| r.ok())
-            .filter_map(|data| serde_json::from_str::<T>(&data).ok())
-            .filter(|a| !a.is_deleted())
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 无法解密（如主密钥未解锁）时返回错误，而不是空列表
+        let mut accounts = Vec::with_capacity(rows.len());
+        for data in rows {
+            let data = at_rest::open(&data)?;
+            let Ok(account) = serde_json::from_str::<T>(&data) else {
+                continue;
+            };
+            if !account.is_deleted() {
+                accounts.push(account);
+            }
+        }
 
         Ok(accounts)
     }
@@ -305,7 +382,7 @@ impl<T: SyncableAccount> AccountStorage<T> for GenericSQLiteStorage<T> {
 
         match result {
             Ok(data) => {
-                let account: T = serde_json::from_str(&data)?;
+                let account: T = serde_json::from_str(&at_rest::open(&data)?)?;
                 if account.is_deleted() {
                     Ok(None)
                 } else {
@@ -423,4 +500,12 @@ impl<T: SyncableAccount> SyncableLocalStorage<T> for GenericSQLiteStorage<T> {
     fn get_deletions(&self) -> Result<Vec<String>, StorageError> {
         GenericSQLiteStorage::get_deletions(self)
     }
+
+    fn load_sync_state(&self) -> Result<SyncState<T>, StorageError> {
+        GenericSQLiteStorage::load_sync_state(self)
+    }
+
+    fn save_sync_state(&self, state: &SyncState<T>) -> Result<(), StorageError> {
+        GenericSQLiteStorage::save_sync_state(self, state)
+    }
 }
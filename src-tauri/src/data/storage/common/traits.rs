@@ -1,3 +1,4 @@
+use super::merge::{FieldConflict, SyncState};
 use chrono::{DateTime, Utc};
 use serde::{Serialize, de::DeserializeOwned};
 
@@ -21,6 +22,12 @@ pub trait SyncableAccount: Clone + Send + Sync + Serialize + DeserializeOwned +
     /// locally-computed data (e.g. quota, auth metadata).
     fn merge_missing_fields(&mut self, _source: &Self) {}
 
+    /// 由程序自动刷新的字段（如 token、额度），各设备会各自更新。
+    /// 同步合并时两侧都修改了这些字段直接取较新的一侧，不作为冲突交给用户。
+    fn machine_maintained_fields() -> &'static [&'static str] {
+        &[]
+    }
+
     /// 存储文件名
     fn storage_file_name() -> String {
         format!("{}_accounts.json", Self::platform_name())
@@ -45,6 +52,18 @@ pub struct AccountSyncStatus {
     pub status: String,
     pub error_message: Option<String>,
     pub accounts_synced: i32,
+    /// 尚未解决的字段冲突
+    #[serde(default)]
+    pub conflicts: Vec<FieldConflict>,
+}
+
+/// 实时同步时一次增量拉取的结果
+#[derive(Debug, Clone, Default)]
+pub struct RemoteChangeSet {
+    /// 远端当前最大版本号，作为下次拉取的起点
+    pub version: i64,
+    /// 写入或删除的本地账号 ID
+    pub changed_ids: Vec<String>,
 }
 
 /// 客户端账号变更
@@ -102,6 +121,8 @@ pub trait SyncableLocalStorage<T: SyncableAccount>: AccountStorage<T> {
     ) -> Result<(), StorageError>;
     fn get_local_version(&self) -> Result<i64, StorageError>;
     fn get_deletions(&self) -> Result<Vec<String>, StorageError>;
+    fn load_sync_state(&self) -> Result<SyncState<T>, StorageError>;
+    fn save_sync_state(&self, state: &SyncState<T>) -> Result<(), StorageError>;
 }
 
 /// 通用同步管理器 trait
@@ -6,8 +6,8 @@ pub use crate::platforms::cursor::modules::mapper::CursorAccountMapper;
 
 use crate::AppState;
 use crate::data::storage::common::{
-    AccountSyncManager as CommonAccountSyncManager, GenericDualStorage, GenericLocalStorage,
-    GenericPostgreSQLStorage,
+    AccountSyncManager as CommonAccountSyncManager, ConflictSide, GenericDualStorage,
+    GenericLocalStorage, GenericPostgreSQLStorage,
 };
 use crate::platforms::cursor::models::Account;
 use std::sync::Arc;
@@ -121,6 +121,27 @@ pub async fn cursor_get_sync_status(
         .map_err(|e| format!("Failed to get sync status: {}", e))
 }
 
+/// 为同步冲突的字段选择保留本地（`local`）或远端（`remote`）的值
+#[tauri::command]
+pub async fn cursor_resolve_sync_conflict(
+    account_id: String,
+    field: String,
+    side: ConflictSide,
+    state: State<'_, AppState>,
+) -> Result<Account, String> {
+    let storage_manager = {
+        let guard = state.cursor_storage_manager.lock().unwrap();
+        guard
+            .clone()
+            .ok_or("Cursor storage manager not initialized")?
+    };
+
+    storage_manager
+        .resolve_sync_conflict(&account_id, &field, side)
+        .await
+        .map_err(|e| format!("Failed to resolve sync conflict: {}", e))
+}
+
 pub async fn initialize_cursor_storage_manager(
     app: &tauri::AppHandle,
     state: &State<'_, AppState>,
@@ -6,8 +6,8 @@ pub use traits::*;
 
 use crate::AppState;
 use crate::data::storage::common::{
-    AccountSyncManager as CommonAccountSyncManager, GenericDualStorage, GenericLocalStorage,
-    GenericPostgreSQLStorage,
+    AccountSyncManager as CommonAccountSyncManager, ConflictSide, GenericDualStorage,
+    GenericLocalStorage, GenericPostgreSQLStorage,
 };
 use crate::platforms::openai::models::Account;
 use std::sync::Arc;
@@ -111,6 +111,27 @@ pub async fn openai_get_sync_status(
         .map_err(|e| format!("Failed to get sync status: {}", e))
 }
 
+/// 为同步冲突的字段选择保留本地（`local`）或远端（`remote`）的值
+#[tauri::command]
+pub async fn openai_resolve_sync_conflict(
+    account_id: String,
+    field: String,
+    side: ConflictSide,
+    state: State<'_, AppState>,
+) -> Result<Account, String> {
+    let storage_manager = {
+        let guard = state.openai_storage_manager.lock().unwrap();
+        guard
+            .clone()
+            .ok_or("OpenAI storage manager not initialized")?
+    };
+
+    storage_manager
+        .resolve_sync_conflict(&account_id, &field, side)
+        .await
+        .map_err(|e| format!("Failed to resolve sync conflict: {}", e))
+}
+
 pub async fn initialize_openai_storage_manager(
     app: &tauri::AppHandle,
     state: &State<'_, AppState>,
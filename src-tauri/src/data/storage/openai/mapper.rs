@@ -1,4 +1,7 @@
 use crate::data::storage::common::{AccountDbMapper, StorageError};
+use crate::database::sync_crypto::{
+    decrypt_optional_secret, decrypt_secret, encrypt_optional_secret, encrypt_secret,
+};
 use crate::platforms::openai::models::{Account, AccountType, ApiConfig, QuotaData, TokenData};
 use tokio_postgres::Row;
 
@@ -17,9 +20,9 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
         // 读取 token 数据（仅 OAuth 账号）
         let token = if account_type == AccountType::OAuth {
             Some(TokenData {
-                access_token: row.get(2),
-                refresh_token: row.get(3),
-                id_token: row.get(4),
+                access_token: decrypt_secret(row.get(2))?,
+                refresh_token: decrypt_optional_secret(row.get(3))?,
+                id_token: decrypt_optional_secret(row.get(4))?,
                 expires_in: row.get(5),
                 expires_at: row.get(6),
                 token_type: row.get(7),
@@ -35,7 +38,7 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
             let model_reasoning_effort: Option<String> = row.try_get(29).ok().flatten();
             let wire_api: Option<String> = row.try_get(30).ok().flatten();
             let base_url: Option<String> = row.try_get(31).ok().flatten();
-            let api_key: Option<String> = row.try_get(32).ok().flatten();
+            let api_key = decrypt_optional_secret(row.try_get(32).ok().flatten())?;
 
             if model_provider.is_some()
                 || model.is_some()
@@ -91,17 +94,30 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
         let rt_invalid: bool = row.try_get(35).unwrap_or(false);
         let rt_invalid_reason: Option<String> = row.try_get(36).ok().flatten();
 
+        // Codex 号池分层（预留模型以 JSON 数组存储）
+        let pool_priority: i32 = row.try_get(38).unwrap_or(0);
+        let pool_weight: i32 = row.try_get(39).unwrap_or(1);
+        let pool_reserved_models: Vec<String> = row
+            .try_get::<_, Option<String>>(40)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
         Ok(Account {
             id: row.get(0),
             email: row.get(1),
             reverse_proxy_enabled: row.try_get(37).unwrap_or(true),
+            pool_priority,
+            pool_weight: pool_weight.max(0) as u32,
+            pool_reserved_models,
             account_type,
             token,
             api_config,
             chatgpt_account_id: row.get(8),
             chatgpt_user_id: row.get(9),
             organization_id: row.get(10),
-            openai_auth_json: row.try_get(33).ok().flatten(),
+            openai_auth_json: decrypt_optional_secret(row.try_get(33).ok().flatten())?,
             quota,
             tag: row.try_get(16).ok().flatten(),
             tag_color: row.try_get(17).ok().flatten(),
@@ -123,7 +139,8 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
          codex_7d_used_percent, codex_7d_reset_after_seconds, codex_7d_window_minutes, \
          codex_primary_over_secondary_percent, codex_usage_updated_at, \
          account_type, model_provider, model, model_reasoning_effort, wire_api, base_url, api_key, \
-         openai_auth_json, is_forbidden, rt_invalid, rt_invalid_reason, reverse_proxy_enabled"
+         openai_auth_json, is_forbidden, rt_invalid, rt_invalid_reason, reverse_proxy_enabled, \
+         pool_priority, pool_weight, pool_reserved_models"
     }
 
     fn insert_sql() -> &'static str {
@@ -134,8 +151,9 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
              version, deleted, tag, tag_color, codex_5h_used_percent, codex_5h_reset_after_seconds, codex_5h_window_minutes,
              codex_7d_used_percent, codex_7d_reset_after_seconds, codex_7d_window_minutes,
              codex_primary_over_secondary_percent, codex_usage_updated_at, account_type,
-             model_provider, model, model_reasoning_effort, wire_api, base_url, api_key, openai_auth_json, is_forbidden, rt_invalid, rt_invalid_reason, reverse_proxy_enabled)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35, $36, $37, $38)
+             model_provider, model, model_reasoning_effort, wire_api, base_url, api_key, openai_auth_json, is_forbidden, rt_invalid, rt_invalid_reason, reverse_proxy_enabled,
+             pool_priority, pool_weight, pool_reserved_models)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35, $36, $37, $38, $39, $40, $41)
         ON CONFLICT (id) DO UPDATE SET
             email = EXCLUDED.email,
             access_token = EXCLUDED.access_token,
@@ -172,7 +190,10 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
             is_forbidden = EXCLUDED.is_forbidden,
             rt_invalid = EXCLUDED.rt_invalid,
             rt_invalid_reason = EXCLUDED.rt_invalid_reason,
-            reverse_proxy_enabled = EXCLUDED.reverse_proxy_enabled
+            reverse_proxy_enabled = EXCLUDED.reverse_proxy_enabled,
+            pool_priority = EXCLUDED.pool_priority,
+            pool_weight = EXCLUDED.pool_weight,
+            pool_reserved_models = EXCLUDED.pool_reserved_models
         "#
     }
 
@@ -246,6 +267,12 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
                 (None, None, None, None, None, None)
             };
 
+        let pool_reserved_models = if account.pool_reserved_models.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&account.pool_reserved_models).ok()
+        };
+
         // 获取 is_forbidden
         let is_forbidden = account
             .quota
@@ -256,9 +283,9 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
         vec![
             Box::new(account.id.clone()),
             Box::new(account.email.clone()),
-            Box::new(access_token),
-            Box::new(refresh_token),
-            Box::new(id_token),
+            Box::new(encrypt_secret(&access_token)),
+            Box::new(encrypt_optional_secret(&refresh_token)),
+            Box::new(encrypt_optional_secret(&id_token)),
             Box::new(expires_in),
             Box::new(expires_at),
             Box::new(token_type),
@@ -286,12 +313,15 @@ impl AccountDbMapper<Account> for OpenAIAccountMapper {
             Box::new(model_reasoning_effort),
             Box::new(wire_api),
             Box::new(base_url),
-            Box::new(api_key),
-            Box::new(account.openai_auth_json.clone()),
+            Box::new(encrypt_optional_secret(&api_key)),
+            Box::new(encrypt_optional_secret(&account.openai_auth_json)),
             Box::new(is_forbidden),
             Box::new(account.rt_invalid),
             Box::new(account.rt_invalid_reason.clone()),
             Box::new(account.reverse_proxy_enabled),
+            Box::new(account.pool_priority),
+            Box::new(account.pool_weight.min(i32::MAX as u32) as i32),
+            Box::new(pool_reserved_models),
         ]
     }
 }
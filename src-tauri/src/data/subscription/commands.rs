@@ -1,7 +1,7 @@
 use crate::AppState;
 use crate::data::storage::common::{
     AccountStorage, AccountSyncManager as CommonAccountSyncManager, AccountSyncStatus,
-    ClientAccountSyncRequest, ServerAccountSyncResponse,
+    ClientAccountSyncRequest, ConflictSide, ServerAccountSyncResponse,
 };
 use crate::data::subscription::Subscription;
 use crate::data::subscription::storage::{
@@ -221,3 +221,33 @@ pub async fn subscription_bidirectional_sync(
         .await
         .map_err(|e| format!("Sync failed: {}", e))
 }
+
+#[tauri::command]
+pub async fn subscription_get_sync_status(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<AccountSyncStatus>, String> {
+    let storage_manager = get_subscription_storage_manager(&app, &state).await?;
+
+    storage_manager
+        .get_sync_status()
+        .await
+        .map_err(|e| format!("Failed to get sync status: {}", e))
+}
+
+/// 为同步冲突的字段选择保留本地（`local`）或远端（`remote`）的值
+#[tauri::command]
+pub async fn subscription_resolve_sync_conflict(
+    subscription_id: String,
+    field: String,
+    side: ConflictSide,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Subscription, String> {
+    let storage_manager = get_subscription_storage_manager(&app, &state).await?;
+
+    storage_manager
+        .resolve_sync_conflict(&subscription_id, &field, side)
+        .await
+        .map_err(|e| format!("Failed to resolve sync conflict: {}", e))
+}
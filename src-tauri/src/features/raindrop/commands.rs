@@ -1,3 +1,4 @@
+use crate::core::secret_store;
 use crate::data::bookmark::{Bookmark, BookmarkLocalStorage};
 use crate::data::storage::common::AccountStorage;
 use super::client::RaindropClient;
@@ -23,15 +24,26 @@ fn load_config(app: &tauri::AppHandle) -> Result<Option<RaindropConfig>, String>
     }
     let json = std::fs::read_to_string(&path)
         .map_err(|e| format!("Failed to read raindrop config: {}", e))?;
-    let config: RaindropConfig =
+    let mut config: RaindropConfig =
         serde_json::from_str(&json).map_err(|e| format!("Failed to parse raindrop config: {}", e))?;
+    let stored_token = std::mem::take(&mut config.token);
+    config.token = secret_store::decrypt_secret(&stored_token)?;
+
+    // 旧版本的明文 token 重新加密写回
+    if secret_store::needs_migration(&stored_token) {
+        if let Err(e) = save_config(app, &config) {
+            eprintln!("Failed to migrate raindrop token: {}", e);
+        }
+    }
     Ok(Some(config))
 }
 
-/// 保存配置
+/// 保存配置（token 用主密钥加密）
 fn save_config(app: &tauri::AppHandle, config: &RaindropConfig) -> Result<(), String> {
     let path = config_path(app)?;
-    let json = serde_json::to_string_pretty(config)
+    let mut stored = config.clone();
+    stored.token = secret_store::encrypt_secret(&config.token)?;
+    let json = serde_json::to_string_pretty(&stored)
         .map_err(|e| format!("Failed to serialize raindrop config: {}", e))?;
     std::fs::write(&path, json)
         .map_err(|e| format!("Failed to write raindrop config: {}", e))?;
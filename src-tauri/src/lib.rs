@@ -13,12 +13,18 @@ pub mod features {
 }
 
 pub mod core {
+    pub mod api_accounts;
+    pub mod api_error;
     pub mod api_server;
+    pub mod api_server_config;
     pub mod app_commands;
     pub mod http_client;
+    pub mod import_jobs;
+    pub mod openapi;
     pub mod path_manager;
     pub mod proxy_config;
     pub mod proxy_helper;
+    pub mod secret_store;
     pub mod spotlight;
     pub mod subscription_monitor;
     pub mod telegram;
@@ -103,6 +109,9 @@ pub struct AppState {
     codex_server: Arc<Mutex<Option<CodexServer>>>,
     pub codex_unsupported_params:
         Arc<crate::platforms::openai::codex::server::UnsupportedParamCache>,
+    pub codex_client_keys: Arc<crate::platforms::openai::codex::client_keys::ClientKeyStore>,
+    pub codex_model_prices: Arc<crate::platforms::openai::codex::pricing::ModelPriceTable>,
+    pub codex_model_catalog: Arc<crate::platforms::openai::codex::catalog::ModelCatalog>,
     pub codex_server_config: Arc<Mutex<Option<CodexServerConfig>>>,
     pub codex_log_storage: Arc<Mutex<Option<Arc<CodexLogStorage>>>>,
     pub proxy_config: Arc<Mutex<Option<crate::core::proxy_config::ProxyConfig>>>,
@@ -138,6 +147,10 @@ pub fn run() {
         .setup(|app| {
             let app_data_dir = app.handle().path().app_data_dir()
                 .unwrap_or_else(|_| std::path::PathBuf::from("."));
+            // 读取本地配置前先加载主密钥
+            let _ = std::fs::create_dir_all(&app_data_dir);
+            core::secret_store::init(app_data_dir.clone());
+            data::storage::common::at_rest::init(&app_data_dir);
             let app_state = AppState {
                 augment_oauth_state: Mutex::new(None),
                 openai_oauth_sessions: Arc::new(Mutex::new(HashMap::new())),
@@ -178,6 +191,15 @@ pub fn run() {
                 codex_unsupported_params: Arc::new(
                     crate::platforms::openai::codex::server::UnsupportedParamCache::load(&app_data_dir),
                 ),
+                codex_client_keys: Arc::new(
+                    crate::platforms::openai::codex::client_keys::ClientKeyStore::load(&app_data_dir),
+                ),
+                codex_model_prices: Arc::new(
+                    crate::platforms::openai::codex::pricing::ModelPriceTable::load(&app_data_dir),
+                ),
+                codex_model_catalog: Arc::new(
+                    crate::platforms::openai::codex::catalog::ModelCatalog::load(&app_data_dir),
+                ),
                 codex_server_config: Arc::new(Mutex::new(None)),
                 codex_log_storage: Arc::new(Mutex::new(None)),
                 proxy_config: Arc::new(Mutex::new(None)),
@@ -337,6 +359,12 @@ pub fn run() {
                                                                 eprintln!("Failed to check Bookmark tables on startup: {}", e);
                                                             }
                                                         }
+                                                        if let Err(e) = database::sync_crypto::activate(&app_handle, &client).await {
+                                                            eprintln!("Sync encryption is locked on startup: {}", e);
+                                                        }
+                                                        if let Err(e) = database::sync_listener::install_notify_triggers(&client).await {
+                                                            eprintln!("Failed to install realtime sync triggers on startup: {}", e);
+                                                        }
                                                 }
                                                 Err(e) => {
                                                     eprintln!("Failed to get database client on startup: {}", e);
@@ -380,6 +408,12 @@ pub fn run() {
                     eprintln!("Failed to initialize Bookmark storage manager: {}", e);
                 }
 
+                // 存储管理器就绪后开始监听其他设备的变更
+                let db_manager = state.database_manager.lock().unwrap().clone();
+                if let Some(db_manager) = db_manager {
+                    database::sync_listener::start(app_handle.clone(), db_manager);
+                }
+
                 // 初始化 Codex 日志存储
                 if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
                     match CodexLogStorage::new(app_data_dir.to_path_buf()) {
@@ -445,6 +479,9 @@ pub fn run() {
                         codex_logger: state.codex_logger.clone(),
                         codex_server: state.codex_server.clone(),
                         codex_unsupported_params: state.codex_unsupported_params.clone(),
+                        codex_client_keys: state.codex_client_keys.clone(),
+                        codex_model_prices: state.codex_model_prices.clone(),
+                        codex_model_catalog: state.codex_model_catalog.clone(),
                         codex_server_config: state.codex_server_config.clone(),
                         codex_log_storage: state.codex_log_storage.clone(),
                         proxy_config: state.proxy_config.clone(),
@@ -665,6 +702,8 @@ pub fn run() {
             data::storage::windsurf::windsurf_sync_accounts_from_database,
             data::storage::windsurf::windsurf_bidirectional_sync_accounts,
             data::storage::windsurf::windsurf_sync_accounts,
+            data::storage::windsurf::windsurf_get_sync_status,
+            data::storage::windsurf::windsurf_resolve_sync_conflict,
 
             // Cursor 管理命令
             cursor::cursor_get_user_info_from_session,
@@ -697,6 +736,8 @@ pub fn run() {
             data::bookmark::bookmark_sync_to_database,
             data::bookmark::bookmark_sync_from_database,
             data::bookmark::bookmark_bidirectional_sync,
+            data::bookmark::bookmark_get_sync_status,
+            data::bookmark::bookmark_resolve_sync_conflict,
             // 书签 CRUD 命令
             data::bookmark::bookmark_load_local,
             data::bookmark::bookmark_list,
@@ -728,24 +769,29 @@ pub fn run() {
             storage::antigravity::antigravity_bidirectional_sync_accounts,
             storage::antigravity::antigravity_sync_accounts,
             storage::antigravity::antigravity_get_sync_status,
+            storage::antigravity::antigravity_resolve_sync_conflict,
             // OpenAI 同步命令
             storage::openai::openai_sync_accounts_to_database,
             storage::openai::openai_sync_accounts_from_database,
             storage::openai::openai_bidirectional_sync_accounts,
             storage::openai::openai_sync_accounts,
             storage::openai::openai_get_sync_status,
+            storage::openai::openai_resolve_sync_conflict,
             // Cursor 同步命令
             storage::cursor::cursor_sync_accounts_to_database,
             storage::cursor::cursor_sync_accounts_from_database,
             storage::cursor::cursor_bidirectional_sync_accounts,
             storage::cursor::cursor_sync_accounts,
             storage::cursor::cursor_get_sync_status,
+            storage::cursor::cursor_resolve_sync_conflict,
 
             // 订阅同步命令
             data::subscription::subscription_sync_accounts,
             data::subscription::subscription_sync_to_database,
             data::subscription::subscription_sync_from_database,
             data::subscription::subscription_bidirectional_sync,
+            data::subscription::subscription_get_sync_status,
+            data::subscription::subscription_resolve_sync_conflict,
             // 订阅 CRUD 命令
             data::subscription::subscription_load_local,
             data::subscription::subscription_list,
@@ -763,6 +809,8 @@ pub fn run() {
             storage::claude::claude_sync_accounts_to_database,
             storage::claude::claude_sync_accounts_from_database,
             storage::claude::claude_bidirectional_sync_accounts,
+            storage::claude::claude_get_sync_status,
+            storage::claude::claude_resolve_sync_conflict,
             storage::claude::claude_switch_account,
             storage::claude::claude_get_current_account_id,
 
@@ -770,6 +818,8 @@ pub fn run() {
             api_server::get_api_server_status,
             api_server::start_api_server_cmd,
             api_server::stop_api_server,
+            core::api_server_config::load_api_server_config,
+            core::api_server_config::save_api_server_config,
 
             // Outlook 邮箱管理命令
             outlook::outlook_save_credentials,
@@ -816,6 +866,16 @@ pub fn run() {
             database::load_database_config,
             database::test_database_connection_cmd,
             database::delete_database_config,
+            database::sync_crypto::get_sync_encryption_status,
+            database::sync_crypto::set_sync_passphrase,
+            database::sync_crypto::rekey_sync_passphrase,
+            core::secret_store::get_secret_store_status,
+            core::secret_store::unlock_secret_store,
+            core::secret_store::set_secret_store_passphrase,
+            core::secret_store::retry_system_secret_store,
+            data::storage::common::at_rest::get_storage_encryption_status,
+            data::storage::common::at_rest::set_storage_encryption,
+            data::storage::common::at_rest::unlock_local_storage,
 
             // 代理配置命令
             proxy_config::save_proxy_config,
@@ -865,14 +925,33 @@ pub fn run() {
             crate::platforms::openai::codex::commands::clear_codex_logs,
             crate::platforms::openai::codex::commands::set_codex_pool_strategy,
             crate::platforms::openai::codex::commands::set_codex_selected_account,
+            crate::platforms::openai::codex::commands::set_codex_account_pool_tier,
             crate::platforms::openai::codex::commands::get_codex_access_config,
             crate::platforms::openai::codex::commands::set_codex_access_config,
             crate::platforms::openai::codex::commands::get_codex_runtime_settings,
             crate::platforms::openai::codex::commands::set_codex_runtime_settings,
+            crate::platforms::openai::codex::commands::list_codex_client_keys,
+            crate::platforms::openai::codex::commands::create_codex_client_key,
+            crate::platforms::openai::codex::commands::update_codex_client_key,
+            crate::platforms::openai::codex::commands::regenerate_codex_client_key,
+            crate::platforms::openai::codex::commands::delete_codex_client_key,
+            crate::platforms::openai::codex::commands::get_codex_model_prices,
+            crate::platforms::openai::codex::commands::save_codex_model_prices,
+            crate::platforms::openai::codex::commands::reset_codex_model_prices,
+            crate::platforms::openai::codex::commands::get_codex_model_catalog,
+            crate::platforms::openai::codex::commands::save_codex_model_catalog,
+            crate::platforms::openai::codex::commands::reset_codex_model_catalog,
+            crate::platforms::openai::codex::commands::get_codex_capture_enabled,
+            crate::platforms::openai::codex::commands::set_codex_capture_enabled,
+            crate::platforms::openai::codex::commands::list_codex_captures,
+            crate::platforms::openai::codex::commands::get_codex_capture,
+            crate::platforms::openai::codex::commands::clear_codex_captures,
+            crate::platforms::openai::codex::commands::replay_codex_capture,
             // Codex 日志存储命令
             crate::platforms::openai::codex::commands::query_codex_logs_from_storage,
             crate::platforms::openai::codex::commands::get_codex_model_stats_from_storage,
             crate::platforms::openai::codex::commands::get_codex_period_stats_from_storage,
+            crate::platforms::openai::codex::commands::get_codex_client_stats_from_storage,
             crate::platforms::openai::codex::commands::get_codex_daily_stats_from_storage,
             crate::platforms::openai::codex::commands::clear_codex_logs_in_storage,
             crate::platforms::openai::codex::commands::delete_codex_logs_before,
@@ -37,7 +37,8 @@ pub async fn antigravity_load_accounts_json(app: AppHandle) -> Result<String, St
             return Ok(r#"{"accounts":[],"current_account_id":null}"#.to_string());
         }
 
-        Ok(content)
+        crate::data::storage::common::at_rest::open(&content)
+            .map_err(|e| format!("Failed to decrypt accounts file: {}", e))
     } else {
         Ok(r#"{"accounts":[],"current_account_id":null}"#.to_string())
     }
@@ -65,6 +65,10 @@ impl SyncableAccount for Account {
         "antigravity"
     }
 
+    fn machine_maintained_fields() -> &'static [&'static str] {
+        &["token", "quota", "last_used"]
+    }
+
     fn merge_missing_fields(&mut self, source: &Self) {
         if self.device_profile.is_none() {
             self.device_profile = source.device_profile.clone();
@@ -362,10 +362,33 @@ pub async fn cursor_refresh_account_tokens(
 pub async fn cursor_add_account_with_session(
     app: AppHandle,
     session_token: String,
+) -> Result<Account, String> {
+    add_account_with_session_internal(&app, session_token, false).await
+}
+
+/// session 导入的内部实现；`reject_duplicate_email` 为 true 时在保存前检查邮箱重复（供 HTTP API 使用）
+pub async fn add_account_with_session_internal(
+    app: &AppHandle,
+    session_token: String,
+    reject_duplicate_email: bool,
 ) -> Result<Account, String> {
     // 1. 获取用户信息（使用 session_token + Cookie 认证）
     let user_info = auth::get_user_info(&session_token).await?;
 
+    if reject_duplicate_email {
+        let email_to_check = user_info.email.trim().to_lowercase();
+        let existing_accounts = storage::list_accounts(app).await?;
+        if existing_accounts
+            .iter()
+            .any(|account| account.email.trim().to_lowercase() == email_to_check)
+        {
+            return Err(format!(
+                "Account with email '{}' already exists",
+                user_info.email
+            ));
+        }
+    }
+
     // 2. 获取 accessToken
     let token_response = auth::get_access_token_from_session(&session_token).await?;
 
@@ -392,7 +415,7 @@ pub async fn cursor_add_account_with_session(
     account.name = user_info.name;
 
     // 6. 保存账号
-    storage::save_account(&app, &account).await?;
+    storage::save_account(app, &account).await?;
 
     Ok(account)
 }
@@ -129,6 +129,15 @@ impl SyncableAccount for Account {
     fn platform_name() -> &'static str {
         "cursor"
     }
+
+    fn machine_maintained_fields() -> &'static [&'static str] {
+        &[
+            "token",
+            "individual_usage",
+            "membership_type",
+            "last_used",
+        ]
+    }
 }
 
 impl Account {
@@ -1,4 +1,7 @@
 use crate::data::storage::common::{AccountDbMapper, StorageError};
+use crate::database::sync_crypto::{
+    decrypt_optional_secret, decrypt_secret, encrypt_optional_secret, encrypt_secret,
+};
 use crate::platforms::cursor::models::{Account, MachineInfo, TokenData};
 use tokio_postgres::Row;
 
@@ -22,11 +25,11 @@ impl AccountDbMapper<Account> for CursorAccountMapper {
             email: row.get(1),
             name: row.get(2),
             token: TokenData {
-                access_token: row.get(3),
-                refresh_token: row.get(4),
+                access_token: decrypt_secret(row.get(3))?,
+                refresh_token: decrypt_secret(row.get(4))?,
                 expiry_timestamp: row.get(5),
                 user_id: row.get(6),
-                workos_cursor_session_token: row.get(7),
+                workos_cursor_session_token: decrypt_optional_secret(row.get(7))?,
                 session_expiry_timestamp: row.get(8),
             },
             tag: row.get(9),
@@ -106,11 +109,13 @@ impl AccountDbMapper<Account> for CursorAccountMapper {
             Box::new(account.id.clone()),
             Box::new(account.email.clone()),
             Box::new(account.name.clone()),
-            Box::new(account.token.access_token.clone()),
-            Box::new(account.token.refresh_token.clone()),
+            Box::new(encrypt_secret(&account.token.access_token)),
+            Box::new(encrypt_secret(&account.token.refresh_token)),
             Box::new(account.token.expiry_timestamp),
             Box::new(account.token.user_id.clone()),
-            Box::new(account.token.workos_cursor_session_token.clone()),
+            Box::new(encrypt_optional_secret(
+                &account.token.workos_cursor_session_token,
+            )),
             Box::new(account.token.session_expiry_timestamp),
             Box::new(account.tag.clone()),
             Box::new(account.tag_color.clone()),
@@ -0,0 +1,274 @@
+//! Codex 请求抓包
+//!
+//! 开启抓包后，每个转发到上游的请求都会记录规范化后的请求体、上游状态码与响应头，
+//! 以及重组后的响应体（流式响应取自 SseMetricsExtractor 消费的同一份上游字节流）。
+//! Authorization 等凭据头与请求体中的账号令牌在入库前脱敏，抓包可在之后重放。
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use warp::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+
+use super::models::{RequestCapture, RequestLog};
+
+/// 单个请求体/响应体的最大抓包字节数，超出部分截断
+pub const CAPTURE_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// 抓包表的总容量上限（请求体 + 响应体字节数），超出后删除最旧的抓包
+pub const CAPTURE_STORAGE_LIMIT: i64 = 64 * 1024 * 1024;
+
+/// 脱敏后的占位值
+pub const REDACTED: &str = "[REDACTED]";
+
+/// 直接脱敏的头部
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "api-key",
+    "chatgpt-account-id",
+];
+
+/// 请求体中需要脱敏的 JSON 字段
+const SENSITIVE_FIELDS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "api_key",
+    "authorization",
+];
+
+/// 转发前记录的请求部分（已脱敏）
+#[derive(Debug, Clone)]
+pub struct CaptureRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    pub body_truncated: bool,
+}
+
+impl CaptureRequest {
+    pub fn new(
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Self {
+        let (body, body_truncated) = redact_body(body);
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.map(str::to_string),
+            headers: redact_headers(headers),
+            body,
+            body_truncated,
+        }
+    }
+
+    /// 结合请求日志与上游响应生成抓包记录
+    pub fn finish(
+        self,
+        log: &RequestLog,
+        status: StatusCode,
+        response_headers: &HeaderMap,
+        response: CaptureBuffer,
+    ) -> RequestCapture {
+        let (response_body, response_truncated) = response.into_text();
+        RequestCapture {
+            id: log.id.clone(),
+            timestamp: log.timestamp,
+            account_id: log.account_id.clone(),
+            account_email: log.account_email.clone(),
+            model: log.model.clone(),
+            format: log.format.clone(),
+            method: self.method,
+            path: self.path,
+            query: self.query,
+            request_headers: self.headers,
+            request_body: self.body,
+            request_truncated: self.body_truncated,
+            upstream_status: status.as_u16(),
+            response_headers: redact_headers(response_headers),
+            response_body,
+            response_truncated,
+        }
+    }
+}
+
+/// 按上限累积响应字节
+#[derive(Debug, Default)]
+pub struct CaptureBuffer {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl CaptureBuffer {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut buffer = Self::default();
+        buffer.push(bytes);
+        buffer
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        let remaining = CAPTURE_BODY_LIMIT.saturating_sub(self.bytes.len());
+        if chunk.len() > remaining {
+            self.truncated = true;
+        }
+        self.bytes
+            .extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+    }
+
+    fn into_text(self) -> (String, bool) {
+        (
+            String::from_utf8_lossy(&self.bytes).into_owned(),
+            self.truncated,
+        )
+    }
+}
+
+/// 头部转为有序映射并脱敏凭据
+pub fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let name = name.as_str().to_ascii_lowercase();
+        let value = if is_sensitive_header(&name) {
+            REDACTED.to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        map.entry(name)
+            .and_modify(|existing| {
+                if existing != REDACTED {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+            })
+            .or_insert(value);
+    }
+    map
+}
+
+/// 由抓包的请求头重建重放用的头部（跳过已脱敏的值）
+pub fn replay_headers(headers: &BTreeMap<String, String>) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        if value == REDACTED {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            map.insert(name, value);
+        }
+    }
+    map
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    SENSITIVE_HEADERS.contains(&name) || name.contains("token") || name.contains("secret")
+}
+
+/// 请求体脱敏并按上限截断；返回 (文本, 是否截断)
+fn redact_body(body: &[u8]) -> (String, bool) {
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact_json(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
+    };
+    if text.len() <= CAPTURE_BODY_LIMIT {
+        return (text, false);
+    }
+    let mut end = CAPTURE_BODY_LIMIT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (text[..end].to_string(), true)
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if SENSITIVE_FIELDS.contains(&key.to_ascii_lowercase().as_str()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CAPTURE_BODY_LIMIT, CaptureBuffer, CaptureRequest, REDACTED, replay_headers};
+    use serde_json::{Value, json};
+    use warp::http::HeaderMap;
+
+    #[test]
+    fn redacts_credentials_and_rebuilds_replay_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer sk-secret".parse().unwrap());
+        headers.insert("x-session-token", "abc".parse().unwrap());
+        headers.insert("session_id", "s1".parse().unwrap());
+        headers.append("accept", "text/event-stream".parse().unwrap());
+        headers.append("accept", "application/json".parse().unwrap());
+
+        let body = json!({
+            "model": "gpt-5",
+            "input": [{"role": "user", "content": "hi"}],
+            "metadata": {"Access_Token": "eyJ", "nested": [{"refresh_token": "rt"}]}
+        });
+        let capture = CaptureRequest::new(
+            "POST",
+            "/v1/responses",
+            None,
+            &headers,
+            body.to_string().as_bytes(),
+        );
+
+        assert_eq!(capture.headers["authorization"], REDACTED);
+        assert_eq!(capture.headers["x-session-token"], REDACTED);
+        assert_eq!(
+            capture.headers["accept"],
+            "text/event-stream, application/json"
+        );
+        assert!(!capture.body.contains("eyJ") && !capture.body.contains("\"rt\""));
+        let redacted: Value = serde_json::from_str(&capture.body).unwrap();
+        assert_eq!(redacted["input"], body["input"]);
+
+        let replay = replay_headers(&capture.headers);
+        assert!(replay.get("authorization").is_none());
+        assert_eq!(replay.get("session_id").unwrap(), "s1");
+    }
+
+    #[test]
+    fn bodies_are_truncated_at_the_capture_limit() {
+        let large = "x".repeat(CAPTURE_BODY_LIMIT + 10);
+        let capture = CaptureRequest::new(
+            "POST",
+            "/v1/responses",
+            None,
+            &HeaderMap::new(),
+            large.as_bytes(),
+        );
+        assert!(capture.body_truncated);
+        assert_eq!(capture.body.len(), CAPTURE_BODY_LIMIT);
+
+        let mut buffer = CaptureBuffer::default();
+        buffer.push(&vec![b'a'; CAPTURE_BODY_LIMIT - 1]);
+        buffer.push(b"bc");
+        let (text, truncated) = buffer.into_text();
+        assert!(truncated);
+        assert!(text.ends_with("ab"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Codex 模型目录与别名
+//!
+//! /v1/models 返回的模型由三部分组成：可编辑的模型目录（按号池账号套餐过滤）、
+//! 近期上游成功处理过的模型，以及配置的别名。别名在转发前把请求中的模型改写为目标模型。
+//! 目录与别名持久化到 Codex 配置旁的 JSON 文件，文件不存在时使用内置默认值。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+const MODEL_CATALOG_FILE: &str = "openai_codex_models.json";
+
+/// 别名默认指向的 Codex 模型
+pub const DEFAULT_CODEX_MODEL: &str = "gpt-5-codex";
+
+/// 目录中的一个模型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogModel {
+    pub id: String,
+    /// 可用的套餐（如 plus、pro、team），为空表示所有套餐可用
+    #[serde(default)]
+    pub plans: Vec<String>,
+}
+
+/// 模型别名：请求 `from` 时改写为 `to`，`from` 以 `*` 结尾表示前缀匹配
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelAlias {
+    pub from: String,
+    pub to: String,
+}
+
+/// 模型目录配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelCatalogConfig {
+    #[serde(default)]
+    pub models: Vec<CatalogModel>,
+    #[serde(default)]
+    pub aliases: Vec<ModelAlias>,
+    /// 是否把近期上游成功处理过的模型加入列表
+    #[serde(default = "default_include_observed")]
+    pub include_observed: bool,
+    /// 统计近期模型的天数
+    #[serde(default = "default_observed_days")]
+    pub observed_days: u32,
+}
+
+fn default_include_observed() -> bool {
+    true
+}
+
+fn default_observed_days() -> u32 {
+    7
+}
+
+impl Default for ModelCatalogConfig {
+    fn default() -> Self {
+        let model = |id: &str| CatalogModel {
+            id: id.to_string(),
+            plans: Vec::new(),
+        };
+        let alias = |from: &str| ModelAlias {
+            from: from.to_string(),
+            to: DEFAULT_CODEX_MODEL.to_string(),
+        };
+        Self {
+            models: vec![
+                model("gpt-5"),
+                model("gpt-5-codex"),
+                model("gpt-5-codex-mini"),
+                model("gpt-5.1"),
+                model("gpt-5.1-codex"),
+                model("gpt-5.1-codex-mini"),
+                model("gpt-5.1-codex-max"),
+            ],
+            aliases: vec![alias("gpt-4o"), alias("gpt-4.1")],
+            include_observed: true,
+            observed_days: default_observed_days(),
+        }
+    }
+}
+
+/// 模型目录
+pub struct ModelCatalog {
+    config: RwLock<ModelCatalogConfig>,
+    file_path: PathBuf,
+}
+
+impl ModelCatalog {
+    /// 从 JSON 文件加载，文件不存在或损坏时使用默认目录
+    pub fn load(app_data_dir: &std::path::Path) -> Self {
+        let file_path = app_data_dir.join(MODEL_CATALOG_FILE);
+        let config = std::fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<ModelCatalogConfig>(&s).ok())
+            .unwrap_or_default();
+
+        Self {
+            config: RwLock::new(config),
+            file_path,
+        }
+    }
+
+    /// 当前配置
+    pub fn get(&self) -> ModelCatalogConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// 替换整个目录并持久化
+    pub fn save(&self, config: ModelCatalogConfig) -> Result<ModelCatalogConfig, String> {
+        let mut normalized = ModelCatalogConfig {
+            models: Vec::with_capacity(config.models.len()),
+            aliases: Vec::with_capacity(config.aliases.len()),
+            include_observed: config.include_observed,
+            observed_days: config.observed_days.clamp(1, 90),
+        };
+        for mut model in config.models {
+            model.id = model.id.trim().to_string();
+            if model.id.is_empty() {
+                return Err("Model id is required".to_string());
+            }
+            model.plans = model
+                .plans
+                .iter()
+                .map(|p| p.trim().to_ascii_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect();
+            normalized.models.push(model);
+        }
+        for mut alias in config.aliases {
+            alias.from = alias.from.trim().to_string();
+            alias.to = alias.to.trim().to_string();
+            if alias.from.is_empty() || alias.to.is_empty() {
+                return Err("Alias source and target are required".to_string());
+            }
+            if alias.to.ends_with('*') {
+                return Err(format!("Alias target must be a model id: {}", alias.to));
+            }
+            normalized.aliases.push(alias);
+        }
+
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&normalized)
+            .map_err(|e| format!("Failed to serialize model catalog: {}", e))?;
+        std::fs::write(&self.file_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", MODEL_CATALOG_FILE, e))?;
+
+        *self.config.write().unwrap() = normalized.clone();
+        Ok(normalized)
+    }
+
+    /// 恢复默认目录
+    pub fn reset(&self) -> Result<ModelCatalogConfig, String> {
+        self.save(ModelCatalogConfig::default())
+    }
+
+    /// 按别名改写模型：精确匹配优先，其次取最长的前缀匹配；未命中返回 None
+    pub fn resolve_alias(&self, model: &str) -> Option<String> {
+        let config = self.config.read().unwrap();
+        if let Some(exact) = config
+            .aliases
+            .iter()
+            .find(|a| a.from.eq_ignore_ascii_case(model))
+        {
+            return Some(exact.to.clone());
+        }
+
+        let model = model.to_ascii_lowercase();
+        config
+            .aliases
+            .iter()
+            .filter_map(|a| {
+                let prefix = a.from.strip_suffix('*')?.to_ascii_lowercase();
+                model.starts_with(&prefix).then_some((prefix.len(), a))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, a)| a.to.clone())
+            .filter(|to| !to.eq_ignore_ascii_case(&model))
+    }
+
+    /// 生成 /v1/models 列表。
+    /// `plans` 为号池中可用账号的套餐，`observed` 为近期上游成功处理过的模型。
+    pub fn list_models(&self, plans: &[String], observed: &[String]) -> Vec<String> {
+        let config = self.config.read().unwrap();
+        let plans: Vec<String> = plans.iter().map(|p| p.to_ascii_lowercase()).collect();
+
+        let mut ids: BTreeSet<String> = config
+            .models
+            .iter()
+            .filter(|m| m.plans.is_empty() || m.plans.iter().any(|p| plans.contains(p)))
+            .map(|m| m.id.clone())
+            .collect();
+        if config.include_observed {
+            ids.extend(observed.iter().filter(|m| m.as_str() != "unknown").cloned());
+        }
+        // 别名对客户端也是可用的模型名，但只有目标模型可用时才列出
+        let aliases: Vec<String> = config
+            .aliases
+            .iter()
+            .filter(|a| !a.from.ends_with('*') && ids.contains(&a.to))
+            .map(|a| a.from.clone())
+            .collect();
+        ids.extend(aliases);
+        ids.into_iter().collect()
+    }
+
+    /// 统计近期模型的起始时间戳
+    pub fn observed_since(&self, now_ts: i64) -> Option<i64> {
+        let config = self.config.read().unwrap();
+        config
+            .include_observed
+            .then(|| now_ts - config.observed_days as i64 * 24 * 60 * 60)
+    }
+}
+
+/// 按 OpenAI 格式构造 /v1/models 响应体
+pub fn models_response(ids: &[String]) -> Value {
+    let data: Vec<Value> = ids
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "object": "model",
+                "created": 1728000000,
+                "owned_by": "openai"
+            })
+        })
+        .collect();
+    json!({
+        "object": "list",
+        "data": data
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CatalogModel, DEFAULT_CODEX_MODEL, ModelAlias, ModelCatalog, ModelCatalogConfig};
+
+    #[test]
+    fn aliases_rewrite_exact_then_longest_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let catalog = ModelCatalog::load(dir.path());
+        assert_eq!(
+            catalog.resolve_alias("GPT-4o").as_deref(),
+            Some(DEFAULT_CODEX_MODEL)
+        );
+        assert!(catalog.resolve_alias("gpt-5").is_none());
+
+        let mut config = catalog.get();
+        config.aliases = vec![
+            ModelAlias {
+                from: "claude-*".to_string(),
+                to: "gpt-5".to_string(),
+            },
+            ModelAlias {
+                from: "claude-haiku*".to_string(),
+                to: "gpt-5-codex-mini".to_string(),
+            },
+        ];
+        catalog.save(config).unwrap();
+        assert_eq!(
+            catalog.resolve_alias("claude-sonnet-4").as_deref(),
+            Some("gpt-5")
+        );
+        assert_eq!(
+            catalog.resolve_alias("claude-haiku-4-5").as_deref(),
+            Some("gpt-5-codex-mini")
+        );
+        assert!(catalog.resolve_alias("gpt-4o").is_none());
+    }
+
+    #[test]
+    fn models_are_filtered_by_plan_and_merged_with_observed() {
+        let dir = tempfile::tempdir().unwrap();
+        let catalog = ModelCatalog::load(dir.path());
+        catalog
+            .save(ModelCatalogConfig {
+                models: vec![
+                    CatalogModel {
+                        id: "gpt-5-codex".to_string(),
+                        plans: Vec::new(),
+                    },
+                    CatalogModel {
+                        id: "gpt-5.1-codex-max".to_string(),
+                        plans: vec![" Pro ".to_string()],
+                    },
+                ],
+                aliases: vec![
+                    ModelAlias {
+                        from: "gpt-4o".to_string(),
+                        to: "gpt-5-codex".to_string(),
+                    },
+                    ModelAlias {
+                        from: "o3".to_string(),
+                        to: "gpt-5.1-codex-max".to_string(),
+                    },
+                ],
+                include_observed: true,
+                observed_days: 7,
+            })
+            .unwrap();
+
+        let observed = vec!["gpt-5".to_string(), "unknown".to_string()];
+        assert_eq!(
+            catalog.list_models(&["plus".to_string()], &observed),
+            vec!["gpt-4o", "gpt-5", "gpt-5-codex"]
+        );
+        assert_eq!(
+            catalog.list_models(&["PRO".to_string()], &[]),
+            vec!["gpt-4o", "gpt-5-codex", "gpt-5.1-codex-max", "o3"]
+        );
+
+        // 重新加载后配置保持一致
+        let reloaded = ModelCatalog::load(dir.path());
+        assert_eq!(reloaded.get(), catalog.get());
+        assert_eq!(reloaded.get().models[1].plans, vec!["pro"]);
+    }
+}
@@ -0,0 +1,338 @@
+//! Codex 客户端 API Key 管理
+//!
+//! 为共享代理的每个使用者分配独立的 Key，支持每日/每月 token 预算、RPM 限制、
+//! 模型白名单与过期时间。Key 列表持久化到 JSON 文件，RPM 计数只保存在内存中。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::RwLock;
+
+const CLIENT_KEYS_FILE: &str = "codex_client_keys.json";
+
+/// RPM 统计窗口（毫秒）
+const RATE_WINDOW_MS: i64 = 60 * 1000;
+
+/// 客户端 API Key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientApiKey {
+    pub id: String,
+    pub name: String,
+    pub key: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 每日 token 上限（UTC 自然日）
+    #[serde(default)]
+    pub daily_token_limit: Option<i64>,
+    /// 每月 token 上限（UTC 自然月）
+    #[serde(default)]
+    pub monthly_token_limit: Option<i64>,
+    /// 每分钟请求数上限
+    #[serde(default)]
+    pub rpm_limit: Option<u32>,
+    /// 允许使用的模型，为空表示不限制；以 `*` 结尾表示前缀匹配
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// 过期时间 (Unix timestamp)
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 创建/更新客户端 Key 的参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientApiKeyInput {
+    pub name: String,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub daily_token_limit: Option<i64>,
+    #[serde(default)]
+    pub monthly_token_limit: Option<i64>,
+    #[serde(default)]
+    pub rpm_limit: Option<u32>,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl ClientApiKey {
+    /// 是否已过期
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|ts| ts <= now)
+    }
+
+    /// 模型是否在白名单内
+    pub fn allows_model(&self, model: &str) -> bool {
+        if self.allowed_models.is_empty() {
+            return true;
+        }
+        self.allowed_models.iter().any(|pattern| {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => model
+                    .to_ascii_lowercase()
+                    .starts_with(&prefix.to_ascii_lowercase()),
+                None => model.eq_ignore_ascii_case(pattern),
+            }
+        })
+    }
+
+    fn apply_input(&mut self, input: ClientApiKeyInput) {
+        self.name = input.name.trim().to_string();
+        if let Some(enabled) = input.enabled {
+            self.enabled = enabled;
+        }
+        self.daily_token_limit = input.daily_token_limit.filter(|v| *v > 0);
+        self.monthly_token_limit = input.monthly_token_limit.filter(|v| *v > 0);
+        self.rpm_limit = input.rpm_limit.filter(|v| *v > 0);
+        self.allowed_models = input
+            .allowed_models
+            .into_iter()
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+        self.expires_at = input.expires_at;
+    }
+}
+
+fn generate_key() -> String {
+    format!(
+        "sk-codex-{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// 客户端 Key 存储
+pub struct ClientKeyStore {
+    keys: RwLock<Vec<ClientApiKey>>,
+    file_path: PathBuf,
+    /// key id → 最近一个窗口内的请求时间（毫秒）
+    recent_requests: Mutex<HashMap<String, VecDeque<i64>>>,
+}
+
+impl ClientKeyStore {
+    /// 从 JSON 文件加载，文件不存在或损坏时为空列表
+    pub fn load(app_data_dir: &std::path::Path) -> Self {
+        let file_path = app_data_dir.join(CLIENT_KEYS_FILE);
+        let keys = std::fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<Vec<ClientApiKey>>(&s).ok())
+            .unwrap_or_default();
+
+        if !keys.is_empty() {
+            println!("[Codex] Loaded {} client API keys", keys.len());
+        }
+        Self {
+            keys: RwLock::new(keys),
+            file_path,
+            recent_requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn save(&self, keys: &[ClientApiKey]) -> Result<(), String> {
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(keys)
+            .map_err(|e| format!("Failed to serialize client keys: {}", e))?;
+        std::fs::write(&self.file_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", CLIENT_KEYS_FILE, e))
+    }
+
+    /// 列出所有 Key
+    pub async fn list(&self) -> Vec<ClientApiKey> {
+        self.keys.read().await.clone()
+    }
+
+    /// 是否配置了任何 Key
+    pub async fn has_keys(&self) -> bool {
+        !self.keys.read().await.is_empty()
+    }
+
+    /// 按 Key 明文查找
+    pub async fn find_by_key(&self, key: &str) -> Option<ClientApiKey> {
+        self.keys.read().await.iter().find(|k| k.key == key).cloned()
+    }
+
+    /// 按 id 获取名称（统计展示用）
+    pub async fn names(&self) -> HashMap<String, String> {
+        self.keys
+            .read()
+            .await
+            .iter()
+            .map(|k| (k.id.clone(), k.name.clone()))
+            .collect()
+    }
+
+    /// 创建 Key
+    pub async fn create(&self, input: ClientApiKeyInput) -> Result<ClientApiKey, String> {
+        if input.name.trim().is_empty() {
+            return Err("Client key name is required".to_string());
+        }
+        let mut key = ClientApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: String::new(),
+            key: generate_key(),
+            enabled: true,
+            daily_token_limit: None,
+            monthly_token_limit: None,
+            rpm_limit: None,
+            allowed_models: Vec::new(),
+            expires_at: None,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        key.apply_input(input);
+
+        let mut keys = self.keys.write().await;
+        keys.push(key.clone());
+        self.save(&keys)?;
+        Ok(key)
+    }
+
+    /// 更新 Key 的名称与限制（Key 明文不变）
+    pub async fn update(&self, id: &str, input: ClientApiKeyInput) -> Result<ClientApiKey, String> {
+        if input.name.trim().is_empty() {
+            return Err("Client key name is required".to_string());
+        }
+        let mut keys = self.keys.write().await;
+        let key = keys
+            .iter_mut()
+            .find(|k| k.id == id)
+            .ok_or_else(|| format!("Client key not found: {}", id))?;
+        key.apply_input(input);
+        let updated = key.clone();
+        self.save(&keys)?;
+        Ok(updated)
+    }
+
+    /// 重新生成 Key 明文
+    pub async fn regenerate(&self, id: &str) -> Result<ClientApiKey, String> {
+        let mut keys = self.keys.write().await;
+        let key = keys
+            .iter_mut()
+            .find(|k| k.id == id)
+            .ok_or_else(|| format!("Client key not found: {}", id))?;
+        key.key = generate_key();
+        let updated = key.clone();
+        self.save(&keys)?;
+        Ok(updated)
+    }
+
+    /// 删除 Key
+    pub async fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut keys = self.keys.write().await;
+        let before = keys.len();
+        keys.retain(|k| k.id != id);
+        if keys.len() == before {
+            return Ok(false);
+        }
+        self.save(&keys)?;
+        self.recent_requests.lock().unwrap().remove(id);
+        Ok(true)
+    }
+
+    /// 记录一次请求并检查 RPM 限制，超限时返回需要等待的秒数
+    pub fn check_rate_limit(&self, key: &ClientApiKey, now_ms: i64) -> Result<(), u64> {
+        let Some(limit) = key.rpm_limit.filter(|v| *v > 0) else {
+            return Ok(());
+        };
+
+        let mut recent = self.recent_requests.lock().unwrap();
+        let window = recent.entry(key.id.clone()).or_default();
+        while window.front().is_some_and(|ts| *ts <= now_ms - RATE_WINDOW_MS) {
+            window.pop_front();
+        }
+
+        if window.len() >= limit as usize {
+            let oldest = window.front().copied().unwrap_or(now_ms);
+            let wait_ms = (oldest + RATE_WINDOW_MS - now_ms).max(0);
+            return Err((wait_ms as u64).div_ceil(1000).max(1));
+        }
+
+        window.push_back(now_ms);
+        Ok(())
+    }
+}
+
+/// 当前 UTC 自然日与自然月的起始时间戳
+pub fn budget_period_starts(now_ts: i64) -> (i64, i64) {
+    use chrono::Datelike;
+
+    let now = chrono::DateTime::from_timestamp(now_ts, 0).unwrap_or_else(chrono::Utc::now);
+    let day_start = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .map(|d| d.and_utc().timestamp())
+        .unwrap_or(0);
+    let month_start = now
+        .date_naive()
+        .with_day(1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc().timestamp())
+        .unwrap_or(0);
+    (day_start, month_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClientApiKeyInput, ClientKeyStore, budget_period_starts};
+
+    fn input(name: &str) -> ClientApiKeyInput {
+        ClientApiKeyInput {
+            name: name.to_string(),
+            enabled: None,
+            daily_token_limit: Some(1000),
+            monthly_token_limit: None,
+            rpm_limit: Some(2),
+            allowed_models: vec!["gpt-5*".to_string(), " gpt-4o ".to_string()],
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn client_keys_persist_and_match_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ClientKeyStore::load(dir.path());
+        let created = store.create(input("alice")).await.unwrap();
+        assert!(created.key.starts_with("sk-codex-"));
+        assert_eq!(created.allowed_models, vec!["gpt-5*", "gpt-4o"]);
+
+        let reloaded = ClientKeyStore::load(dir.path());
+        let found = reloaded.find_by_key(&created.key).await.unwrap();
+        assert_eq!(found.id, created.id);
+        assert!(found.allows_model("gpt-5-codex"));
+        assert!(found.allows_model("GPT-4o"));
+        assert!(!found.allows_model("gpt-4.1"));
+
+        assert!(reloaded.delete(&created.id).await.unwrap());
+        assert!(!ClientKeyStore::load(dir.path()).has_keys().await);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_uses_sliding_minute_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ClientKeyStore::load(dir.path());
+        let key = store.create(input("bob")).await.unwrap();
+
+        assert!(store.check_rate_limit(&key, 0).is_ok());
+        assert!(store.check_rate_limit(&key, 10_000).is_ok());
+        assert_eq!(store.check_rate_limit(&key, 30_000), Err(30));
+        assert!(store.check_rate_limit(&key, 60_001).is_ok());
+    }
+
+    #[test]
+    fn budget_periods_start_at_utc_day_and_month() {
+        // 2025-03-15T12:34:56Z
+        let (day, month) = budget_period_starts(1_742_042_096);
+        assert_eq!(day, 1_741_996_800);
+        assert_eq!(month, 1_740_787_200);
+    }
+}
@@ -8,16 +8,27 @@ use serde::{Deserialize, Serialize};
 use tauri::{Emitter, Manager, State};
 use tokio::sync::Mutex as TokioMutex;
 
+use super::capture::{redact_headers, replay_headers};
+use super::catalog::ModelCatalogConfig;
+use super::client_keys::{ClientApiKey, ClientApiKeyInput};
+use super::executor::ForwardRequest;
 use super::logger::RequestLogger;
 use super::models::{
-    DailyStatsResponse, LogPage, LogQuery, ModelTokenStats, PeriodTokenStats, PoolStrategy,
-    RequestLog, TokenStats,
+    CaptureReplayResult, CaptureSummary, ClientTokenStats, CodexPoolAccount, DailyStatsResponse,
+    LogPage, LogQuery, ModelTokenStats, PeriodTokenStats, PoolStrategy, RequestCapture,
+    RequestLog, SmartScoreWeights, TokenStats,
 };
-use super::pool::{CodexServerConfig, CodexServerStatus};
+use super::pool::{
+    CodexServerConfig, CodexServerStatus, RefreshedToken, TOKEN_REFRESH_WINDOW_SECS,
+    TokenRefresher, USAGE_PERSIST_DEBOUNCE_SECS,
+};
+use super::pricing::ModelPrice;
 use crate::AppState;
 use crate::platforms::openai::codex::server::CodexServer;
 static QUOTA_REFRESH_TASK: std::sync::LazyLock<TokioMutex<Option<tokio::task::JoinHandle<()>>>> =
     std::sync::LazyLock::new(|| TokioMutex::new(None));
+static USAGE_PERSIST_TASK: std::sync::LazyLock<TokioMutex<Option<tokio::task::JoinHandle<()>>>> =
+    std::sync::LazyLock::new(|| TokioMutex::new(None));
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodexAccessConfig {
@@ -30,12 +41,24 @@ pub struct CodexRuntimeSettings {
     pub quota_refresh_enabled: bool,
     pub quota_refresh_interval_seconds: u64,
     pub fast_mode_enabled: bool,
+    /// 排队设置；旧版前端不传时保留现有配置
+    #[serde(default)]
+    pub wait_queue_enabled: Option<bool>,
+    #[serde(default)]
+    pub wait_queue_max_wait_seconds: Option<u64>,
+    #[serde(default)]
+    pub wait_queue_max_depth: Option<usize>,
+    /// Smart 策略打分权重；不传时保留现有配置
+    #[serde(default)]
+    pub smart_score_weights: Option<SmartScoreWeights>,
 }
 
 const CODEX_CONFIG_FILE: &str = "openai_codex_config.json";
 const SHARED_API_SERVER_PORT: u16 = 8766;
 const MIN_QUOTA_REFRESH_INTERVAL_SECONDS: u64 = 60;
 const MAX_QUOTA_REFRESH_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+const MAX_WAIT_QUEUE_WAIT_SECONDS: u64 = 10 * 60;
+const MAX_WAIT_QUEUE_DEPTH: usize = 1024;
 
 fn normalize_access_fields(config: &mut CodexServerConfig) {
     config.api_key = config.api_key.as_ref().and_then(|v| {
@@ -67,6 +90,26 @@ fn normalize_runtime_fields(config: &mut CodexServerConfig) {
     if config.quota_refresh_interval_seconds > MAX_QUOTA_REFRESH_INTERVAL_SECONDS {
         config.quota_refresh_interval_seconds = MAX_QUOTA_REFRESH_INTERVAL_SECONDS;
     }
+
+    if config.wait_queue_max_wait_seconds == 0 {
+        config.wait_queue_max_wait_seconds = defaults.wait_queue_max_wait_seconds;
+    }
+    config.wait_queue_max_wait_seconds = config
+        .wait_queue_max_wait_seconds
+        .min(MAX_WAIT_QUEUE_WAIT_SECONDS);
+    if config.wait_queue_max_depth == 0 {
+        config.wait_queue_max_depth = defaults.wait_queue_max_depth;
+    }
+    config.wait_queue_max_depth = config.wait_queue_max_depth.min(MAX_WAIT_QUEUE_DEPTH);
+    config.smart_score_weights = config.smart_score_weights.normalized();
+
+    // 上游地址只保留 origin 部分，非 http(s) 地址回退到默认值
+    let origin = config.upstream_origin.trim().trim_end_matches('/');
+    config.upstream_origin = if origin.starts_with("http://") || origin.starts_with("https://") {
+        origin.to_string()
+    } else {
+        defaults.upstream_origin
+    };
 }
 
 fn runtime_settings_from_config(config: &CodexServerConfig) -> CodexRuntimeSettings {
@@ -74,6 +117,10 @@ fn runtime_settings_from_config(config: &CodexServerConfig) -> CodexRuntimeSetti
         quota_refresh_enabled: config.quota_refresh_enabled,
         quota_refresh_interval_seconds: config.quota_refresh_interval_seconds,
         fast_mode_enabled: config.fast_mode_enabled,
+        wait_queue_enabled: Some(config.wait_queue_enabled),
+        wait_queue_max_wait_seconds: Some(config.wait_queue_max_wait_seconds),
+        wait_queue_max_depth: Some(config.wait_queue_max_depth),
+        smart_score_weights: Some(config.smart_score_weights),
     }
 }
 
@@ -209,6 +256,12 @@ async fn apply_periodic_tasks(app: tauri::AppHandle, state: &AppState, config: &
     } else {
         stop_periodic_quota_refresh().await;
     }
+
+    if config.enabled {
+        start_usage_persist_task(app, state).await;
+    } else {
+        stop_usage_persist_task().await;
+    }
 }
 
 async fn init_codex_runtime(
@@ -229,15 +282,24 @@ async fn init_codex_runtime(
     let strategy = match config.pool_strategy.as_str() {
         "single" => PoolStrategy::Single,
         "smart" => PoolStrategy::Smart,
+        "priority" => PoolStrategy::Priority,
         _ => PoolStrategy::RoundRobin,
     };
     pool.set_strategy(strategy).await;
+    pool.set_smart_weights(config.smart_score_weights).await;
     if let Some(ref account_id) = config.selected_account_id {
         pool.set_selected_account_id(account_id.clone()).await;
     }
+    pool.set_session_affinity_ttl(config.session_affinity_ttl_seconds as i64)
+        .await;
+    pool.set_wait_queue(config.wait_queue()).await;
+    pool.set_token_refresher(Arc::new(AppTokenRefresher { app: app.clone() }))
+        .await;
 
-    let executor =
-        Arc::new(crate::platforms::openai::codex::executor::CodexExecutor::new(pool.clone())?);
+    let executor = Arc::new(crate::platforms::openai::codex::executor::CodexExecutor::new(
+        pool.clone(),
+        config.upstream_origin.clone(),
+    )?);
     *state.codex_executor.lock().unwrap() = Some(executor);
 
     // 初始化 logger
@@ -344,6 +406,14 @@ pub async fn start_codex_server(
         config.quota_refresh_enabled = existing.quota_refresh_enabled;
         config.quota_refresh_interval_seconds = existing.quota_refresh_interval_seconds;
         config.fast_mode_enabled = existing.fast_mode_enabled;
+        config.wait_queue_enabled = existing.wait_queue_enabled;
+        config.wait_queue_max_wait_seconds = existing.wait_queue_max_wait_seconds;
+        config.wait_queue_max_depth = existing.wait_queue_max_depth;
+        config.smart_score_weights = existing.smart_score_weights;
+        // 上游地址与会话粘性时长只能通过配置文件修改，前端对话框不会传入
+        config.upstream_origin = existing.upstream_origin;
+        config.session_affinity_ttl_seconds = existing.session_affinity_ttl_seconds;
+        config.capture_enabled = existing.capture_enabled;
     }
     normalize_access_fields(&mut config);
     normalize_server_port(&mut config);
@@ -484,6 +554,9 @@ pub async fn get_codex_period_stats(
             week_tokens: 0,
             month_requests: 0,
             month_tokens: 0,
+            today_cost: 0.0,
+            week_cost: 0.0,
+            month_cost: 0.0,
         })
     }
 }
@@ -530,6 +603,7 @@ pub async fn set_codex_pool_strategy(
             "round-robin" => PoolStrategy::RoundRobin,
             "single" => PoolStrategy::Single,
             "smart" => PoolStrategy::Smart,
+            "priority" => PoolStrategy::Priority,
             _ => return Err(format!("Invalid strategy: {}", strategy)),
         };
         pool_ref.set_strategy(strategy_enum).await;
@@ -568,6 +642,45 @@ pub async fn set_codex_selected_account(
     }
 }
 
+/// 设置账号在号池中的优先级层级、权重与预留模型
+#[tauri::command]
+pub async fn set_codex_account_pool_tier(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    account_id: String,
+    priority: i32,
+    weight: u32,
+    reserved_models: Vec<String>,
+) -> Result<(), String> {
+    use crate::platforms::openai::modules::storage;
+
+    let mut account = storage::load_account(&app, &account_id).await?;
+    account.pool_priority = priority;
+    account.pool_weight = weight;
+    account.pool_reserved_models = reserved_models
+        .into_iter()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    account.updated_at = chrono::Utc::now().timestamp();
+    storage::save_account(&app, &account).await?;
+
+    let pool = state.codex_pool.lock().unwrap().clone();
+    if let Some(pool_ref) = pool {
+        let accounts = storage::list_accounts(&app).await?;
+        pool_ref.refresh_from_accounts(&accounts).await;
+    }
+    let _ = app.emit(
+        "openai-accounts-updated",
+        serde_json::json!({
+            "source": "codex-pool-tier",
+            "account_ids": [account_id],
+            "timestamp": account.updated_at
+        }),
+    );
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_codex_access_config(
     app: tauri::AppHandle,
@@ -627,16 +740,126 @@ pub async fn set_codex_runtime_settings(
     config.quota_refresh_enabled = settings.quota_refresh_enabled;
     config.quota_refresh_interval_seconds = settings.quota_refresh_interval_seconds;
     config.fast_mode_enabled = settings.fast_mode_enabled;
+    if let Some(enabled) = settings.wait_queue_enabled {
+        config.wait_queue_enabled = enabled;
+    }
+    if let Some(max_wait) = settings.wait_queue_max_wait_seconds {
+        config.wait_queue_max_wait_seconds = max_wait;
+    }
+    if let Some(max_depth) = settings.wait_queue_max_depth {
+        config.wait_queue_max_depth = max_depth;
+    }
+    if let Some(weights) = settings.smart_score_weights {
+        config.smart_score_weights = weights;
+    }
     normalize_access_fields(&mut config);
     normalize_server_port(&mut config);
     normalize_runtime_fields(&mut config);
     *state.codex_server_config.lock().unwrap() = Some(config.clone());
     write_persisted_config(&app, &config)?;
+    let pool = state.codex_pool.lock().unwrap().clone();
+    if let Some(pool) = pool {
+        pool.set_wait_queue(config.wait_queue()).await;
+        pool.set_smart_weights(config.smart_score_weights).await;
+    }
     apply_periodic_tasks(app.clone(), state.inner(), &config).await;
     apply_fast_mode_to_codex_config_toml(&app, settings.fast_mode_enabled)?;
     Ok(runtime_settings_from_config(&config))
 }
 
+/// 列出客户端 API Key
+#[tauri::command]
+pub async fn list_codex_client_keys(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClientApiKey>, String> {
+    Ok(state.codex_client_keys.list().await)
+}
+
+/// 创建客户端 API Key
+#[tauri::command]
+pub async fn create_codex_client_key(
+    state: State<'_, AppState>,
+    input: ClientApiKeyInput,
+) -> Result<ClientApiKey, String> {
+    state.codex_client_keys.create(input).await
+}
+
+/// 更新客户端 API Key 的名称与限制
+#[tauri::command]
+pub async fn update_codex_client_key(
+    state: State<'_, AppState>,
+    id: String,
+    input: ClientApiKeyInput,
+) -> Result<ClientApiKey, String> {
+    state.codex_client_keys.update(&id, input).await
+}
+
+/// 重新生成客户端 API Key
+#[tauri::command]
+pub async fn regenerate_codex_client_key(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ClientApiKey, String> {
+    state.codex_client_keys.regenerate(&id).await
+}
+
+/// 删除客户端 API Key（历史日志保留 key id）
+#[tauri::command]
+pub async fn delete_codex_client_key(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<bool, String> {
+    state.codex_client_keys.delete(&id).await
+}
+
+/// 获取模型价格表
+#[tauri::command]
+pub async fn get_codex_model_prices(state: State<'_, AppState>) -> Result<Vec<ModelPrice>, String> {
+    Ok(state.codex_model_prices.list())
+}
+
+/// 保存模型价格表（整表替换，仅影响之后写入的日志）
+#[tauri::command]
+pub async fn save_codex_model_prices(
+    state: State<'_, AppState>,
+    prices: Vec<ModelPrice>,
+) -> Result<Vec<ModelPrice>, String> {
+    state.codex_model_prices.save(prices)
+}
+
+/// 恢复默认模型价格
+#[tauri::command]
+pub async fn reset_codex_model_prices(
+    state: State<'_, AppState>,
+) -> Result<Vec<ModelPrice>, String> {
+    state.codex_model_prices.reset()
+}
+
+/// 获取模型目录与别名
+#[tauri::command]
+pub async fn get_codex_model_catalog(
+    state: State<'_, AppState>,
+) -> Result<ModelCatalogConfig, String> {
+    Ok(state.codex_model_catalog.get())
+}
+
+/// 保存模型目录与别名（别名对之后的请求立即生效）
+#[tauri::command]
+pub async fn save_codex_model_catalog(
+    state: State<'_, AppState>,
+    catalog: ModelCatalogConfig,
+) -> Result<ModelCatalogConfig, String> {
+    state.codex_model_catalog.save(catalog)
+}
+
+/// 恢复默认模型目录
+#[tauri::command]
+pub async fn reset_codex_model_catalog(
+    state: State<'_, AppState>,
+) -> Result<ModelCatalogConfig, String> {
+    state.codex_model_catalog.reset()
+}
+
 #[tauri::command]
 pub async fn query_codex_logs_from_storage(
     state: State<'_, AppState>,
@@ -657,10 +880,11 @@ pub async fn get_codex_model_stats_from_storage(
     state: State<'_, AppState>,
     start_ts: i64,
     end_ts: i64,
+    client_key_id: Option<String>,
 ) -> Result<Vec<ModelTokenStats>, String> {
     let storage = state.codex_log_storage.lock().unwrap().clone();
     if let Some(s) = storage {
-        s.get_model_stats(start_ts, end_ts)
+        s.get_model_stats(start_ts, end_ts, client_key_id.as_deref())
             .map_err(|e| e.to_string())
     } else {
         Ok(vec![])
@@ -669,11 +893,13 @@ pub async fn get_codex_model_stats_from_storage(
 #[tauri::command]
 pub async fn get_codex_period_stats_from_storage(
     state: State<'_, AppState>,
+    client_key_id: Option<String>,
 ) -> Result<PeriodTokenStats, String> {
     let storage = state.codex_log_storage.lock().unwrap().clone();
     if let Some(s) = storage {
         let now_ts = chrono::Utc::now().timestamp();
-        s.get_period_stats(now_ts).map_err(|e| e.to_string())
+        s.get_period_stats(now_ts, client_key_id.as_deref())
+            .map_err(|e| e.to_string())
     } else {
         Ok(PeriodTokenStats {
             today_requests: 0,
@@ -682,10 +908,35 @@ pub async fn get_codex_period_stats_from_storage(
             week_tokens: 0,
             month_requests: 0,
             month_tokens: 0,
+            today_cost: 0.0,
+            week_cost: 0.0,
+            month_cost: 0.0,
         })
     }
 }
 
+/// 从 SQLite 存储获取按客户端 Key 分组的统计
+#[tauri::command]
+pub async fn get_codex_client_stats_from_storage(
+    state: State<'_, AppState>,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Vec<ClientTokenStats>, String> {
+    let storage = state.codex_log_storage.lock().unwrap().clone();
+    let Some(s) = storage else {
+        return Ok(vec![]);
+    };
+    let mut stats = s.get_client_stats(start_ts, end_ts)?;
+    let names = state.codex_client_keys.names().await;
+    for stat in &mut stats {
+        stat.client_name = stat
+            .client_key_id
+            .as_ref()
+            .and_then(|id| names.get(id).cloned());
+    }
+    Ok(stats)
+}
+
 /// 从 SQLite 存储获取每日统计
 #[tauri::command]
 pub async fn get_codex_daily_stats_from_storage(
@@ -791,6 +1042,193 @@ pub async fn get_codex_all_time_stats(
     }
 }
 
+// ==================== 抓包 ====================
+
+/// 获取是否开启抓包
+#[tauri::command]
+pub async fn get_codex_capture_enabled(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(get_or_load_codex_config(&app, state.inner())?.capture_enabled)
+}
+
+/// 开启/关闭抓包（对之后的请求立即生效）
+#[tauri::command]
+pub async fn set_codex_capture_enabled(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_or_load_codex_config(&app, state.inner())?;
+    config.capture_enabled = enabled;
+    *state.codex_server_config.lock().unwrap() = Some(config.clone());
+    write_persisted_config(&app, &config)
+}
+
+/// 抓包列表
+#[tauri::command]
+pub async fn list_codex_captures(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<CaptureSummary>, String> {
+    let storage = state.codex_log_storage.lock().unwrap().clone();
+    if let Some(s) = storage {
+        s.list_captures(limit.unwrap_or(100), offset.unwrap_or(0))
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// 获取单个抓包详情
+#[tauri::command]
+pub async fn get_codex_capture(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<RequestCapture, String> {
+    let storage = state.codex_log_storage.lock().unwrap().clone();
+    let Some(s) = storage else {
+        return Err("Codex log storage not initialized".to_string());
+    };
+    s.get_capture(&id)?
+        .ok_or_else(|| format!("Capture not found: {}", id))
+}
+
+/// 清空抓包
+#[tauri::command]
+pub async fn clear_codex_captures(state: State<'_, AppState>) -> Result<usize, String> {
+    let storage = state.codex_log_storage.lock().unwrap().clone();
+    if let Some(s) = storage {
+        s.clear_captures()
+    } else {
+        Ok(0)
+    }
+}
+
+/// 通过执行器重放抓包的请求；指定 account_id 时只使用该账号，失败不换号
+#[tauri::command]
+pub async fn replay_codex_capture(
+    state: State<'_, AppState>,
+    id: String,
+    account_id: Option<String>,
+) -> Result<CaptureReplayResult, String> {
+    let capture = get_codex_capture(state.clone(), id).await?;
+    if capture.request_truncated {
+        return Err("Captured request body was truncated and cannot be replayed".to_string());
+    }
+
+    let executor = state
+        .codex_executor
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Codex executor not initialized".to_string())?;
+    if let Some(ref account_id) = account_id {
+        let pool = state
+            .codex_pool
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "Codex pool not initialized".to_string())?;
+        if pool.get_account(account_id).await.is_none() {
+            return Err(format!("Account not in Codex pool: {}", account_id));
+        }
+    }
+
+    let method = reqwest::Method::from_bytes(capture.method.as_bytes())
+        .map_err(|e| format!("Invalid captured method: {}", e))?;
+    let request = ForwardRequest {
+        method,
+        path: capture.path.clone(),
+        query: capture.query.clone(),
+        headers: replay_headers(&capture.request_headers),
+        body: bytes::Bytes::from(capture.request_body),
+        format: capture.format,
+        model: capture.model,
+        client_key_id: None,
+        excluded_account_ids: Default::default(),
+        session_key: None,
+        pinned_account_id: account_id,
+    };
+
+    let (response, meta) = executor.forward(request).await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    let headers = redact_headers(response.headers());
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read replay response: {}", e))?;
+
+    Ok(CaptureReplayResult {
+        capture_id: capture.id,
+        account_id: meta.account_id,
+        account_email: meta.account_email,
+        status,
+        headers,
+        body,
+        duration_ms: meta.started_at.elapsed().as_millis() as i64,
+    })
+}
+
+// ==================== Token 刷新 ====================
+
+/// 号池 token 刷新器：通过 OAuth refresh_token 刷新，并写回账号存储
+struct AppTokenRefresher {
+    app: tauri::AppHandle,
+}
+
+fn refreshed_token_from(token: &crate::platforms::openai::models::TokenData) -> RefreshedToken {
+    RefreshedToken {
+        access_token: token.access_token.clone(),
+        refresh_token: token.refresh_token.clone(),
+        id_token: token.id_token.clone(),
+        expires_at: token.expires_at,
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenRefresher for AppTokenRefresher {
+    async fn refresh(&self, pool_account: &CodexPoolAccount) -> Result<RefreshedToken, String> {
+        use crate::platforms::openai::modules::{account as account_module, storage};
+
+        let mut account = storage::load_account(&self.app, &pool_account.id).await?;
+
+        // 存储中的 token 已被其他途径刷新（定时任务、手动刷新），直接复用，避免重复消耗 refresh_token
+        let now = chrono::Utc::now().timestamp();
+        if let Some(token) = account.token.as_ref().filter(|t| {
+            t.access_token != pool_account.access_token
+                && t.expires_at > now + TOKEN_REFRESH_WINDOW_SECS
+        }) {
+            return Ok(refreshed_token_from(token));
+        }
+
+        let result = account_module::refresh_token_if_needed(&mut account, 0, true).await;
+        if result.is_ok() {
+            account_module::backfill_openai_auth_json_if_missing(&mut account);
+        }
+        // 与定时刷新一致：失败时仍保存（如 refresh_token_reused 已置 rt_invalid）
+        if let Err(e) = storage::save_account(&self.app, &account).await {
+            eprintln!("[Codex] Failed to save refreshed account {}: {}", account.email, e);
+        }
+        let _ = self.app.emit(
+            "openai-accounts-updated",
+            serde_json::json!({
+                "source": "codex-token-refresh",
+                "account_ids": [account.id.clone()],
+                "timestamp": chrono::Utc::now().timestamp()
+            }),
+        );
+        result?;
+
+        account
+            .token
+            .as_ref()
+            .map(refreshed_token_from)
+            .ok_or_else(|| "OAuth account missing token".to_string())
+    }
+}
+
 // ==================== 定时任务 ====================
 
 async fn start_periodic_quota_refresh(
@@ -911,6 +1349,88 @@ async fn start_periodic_quota_refresh(
     *QUOTA_REFRESH_TASK.lock().await = Some(handle);
 }
 
+/// 把响应头中的用量防抖写回账号的 QuotaData
+async fn start_usage_persist_task(app: tauri::AppHandle, state: &AppState) {
+    // 已在运行时保留原任务，避免中止正在写回的批次
+    let mut task = USAGE_PERSIST_TASK.lock().await;
+    if task.as_ref().is_some_and(|handle| !handle.is_finished()) {
+        return;
+    }
+
+    let pool = state.codex_pool.lock().unwrap().clone();
+    let Some(pool_ref) = pool else {
+        return;
+    };
+
+    let handle = tokio::spawn(async move {
+        loop {
+            let batch = pool_ref
+                .next_usage_batch(std::time::Duration::from_secs(USAGE_PERSIST_DEBOUNCE_SECS))
+                .await;
+            if batch.is_empty() {
+                continue;
+            }
+
+            let now = chrono::Utc::now().timestamp();
+            let mut changed_account_ids = Vec::new();
+            for (account_id, usage) in batch {
+                let mut account = match crate::platforms::openai::modules::storage::load_account(
+                    &app,
+                    &account_id,
+                )
+                .await
+                {
+                    Ok(account) => account,
+                    Err(e) => {
+                        eprintln!(
+                            "[Codex] Failed to load account {} for usage update: {}",
+                            account_id, e
+                        );
+                        continue;
+                    }
+                };
+                let quota = account
+                    .quota
+                    .get_or_insert_with(crate::platforms::openai::models::QuotaData::new);
+                // 定时刷新已写入更新的数据时跳过
+                if quota.codex_usage_updated_at > usage.observed_at {
+                    continue;
+                }
+                usage.apply_to_quota(quota, now);
+                match crate::platforms::openai::modules::storage::save_account(&app, &account)
+                    .await
+                {
+                    Ok(()) => changed_account_ids.push(account_id),
+                    Err(e) => {
+                        eprintln!("[Codex] Failed to save usage for {}: {}", account.email, e);
+                    }
+                }
+            }
+
+            if changed_account_ids.is_empty() {
+                continue;
+            }
+            let _ = app.emit(
+                "openai-accounts-updated",
+                serde_json::json!({
+                    "source": "codex-rate-limit-headers",
+                    "account_ids": changed_account_ids,
+                    "timestamp": now
+                }),
+            );
+        }
+    });
+
+    *task = Some(handle);
+}
+
+async fn stop_usage_persist_task() {
+    let mut task = USAGE_PERSIST_TASK.lock().await;
+    if let Some(handle) = task.take() {
+        handle.abort();
+    }
+}
+
 async fn stop_periodic_quota_refresh() {
     let mut task = QUOTA_REFRESH_TASK.lock().await;
     if let Some(handle) = task.take() {
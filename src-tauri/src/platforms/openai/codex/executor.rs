@@ -1,6 +1,8 @@
 //! Codex 透传执行器
 //!
 //! 负责将本地请求透传到 ChatGPT Codex 上游，并使用账号池进行鉴权与失败切换。
+//! token 临近过期时先刷新再转发；上游返回 401 时刷新 token 并用同一账号重试一次。
+//! 每个上游响应头中的 x-codex-* 用量窗口都会同步到号池账号。
 
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -12,7 +14,7 @@ use warp::http::HeaderMap;
 
 use super::pool::CodexPool;
 use crate::http_client::create_proxy_client_for_streaming;
-use crate::platforms::openai::codex::models::{CodexError, CodexPoolAccount};
+use crate::platforms::openai::codex::models::{CodexError, CodexPoolAccount, CodexUsageSnapshot};
 use crate::proxy_helper::ProxyClient;
 
 /// 透传请求上下文
@@ -25,6 +27,14 @@ pub struct ForwardRequest {
     pub body: Bytes,
     pub format: String,
     pub model: String,
+    /// 发起请求的客户端 Key id（管理员 Key 为 None）
+    pub client_key_id: Option<String>,
+    /// 本次请求中已失败（如流式响应中途断开）、不再选用的账号
+    pub excluded_account_ids: HashSet<String>,
+    /// 会话粘性 key，相同 key 的请求优先使用同一账号
+    pub session_key: Option<String>,
+    /// 指定使用的账号（如重放抓包），失败时不换号
+    pub pinned_account_id: Option<String>,
 }
 
 /// 透传执行元数据（供上层记录日志）
@@ -34,6 +44,7 @@ pub struct ForwardMeta {
     pub account_email: String,
     pub format: String,
     pub model: String,
+    pub client_key_id: Option<String>,
     pub started_at: Instant,
 }
 
@@ -45,14 +56,18 @@ pub struct CodexExecutor {
 }
 
 impl CodexExecutor {
-    pub fn new(pool: Arc<CodexPool>) -> Result<Self, String> {
+    pub fn new(pool: Arc<CodexPool>, upstream_origin: String) -> Result<Self, String> {
         let client = create_proxy_client_for_streaming()?;
+        Ok(Self::with_client(pool, client, upstream_origin))
+    }
 
-        Ok(Self {
+    /// 使用指定的 HTTP 客户端创建执行器（测试中用于直连本地 mock 上游）
+    pub fn with_client(pool: Arc<CodexPool>, client: ProxyClient, upstream_origin: String) -> Self {
+        Self {
             pool,
             client,
-            upstream_origin: "https://chatgpt.com".to_string(),
-        })
+            upstream_origin: upstream_origin.trim_end_matches('/').to_string(),
+        }
     }
 
     /// 透传执行：返回上游响应（包含原始状态码与头）
@@ -60,7 +75,21 @@ impl CodexExecutor {
         &self,
         request: ForwardRequest,
     ) -> Result<(reqwest::Response, ForwardMeta), CodexError> {
-        let active_count = self.pool.active_count().await;
+        let pinned_account_id = request.pinned_account_id.as_deref();
+        let model = Some(request.model.as_str()).filter(|m| !m.is_empty());
+        // 只统计能服务该模型的账号：其余账号可用时，预留账号全部冷却也要排队
+        let mut active_count = match pinned_account_id {
+            Some(_) => 1,
+            None => self.pool.active_count_for_model(model).await,
+        };
+        // 全部账号冷却中：按配置排队等待，放弃时带上建议的重试时间
+        if active_count == 0 {
+            match self.pool.wait_for_available_account(model).await {
+                Ok(()) => active_count = self.pool.active_count_for_model(model).await,
+                Err(Some(retry_after)) => return Err(CodexError::PoolCoolingDown(retry_after)),
+                Err(None) => return Err(CodexError::NoAvailableAccount),
+            }
+        }
         if active_count == 0 {
             return Err(CodexError::NoAvailableAccount);
         }
@@ -72,29 +101,63 @@ impl CodexExecutor {
             request.query.as_deref(),
         );
 
-        let mut attempted_ids = HashSet::new();
+        // 已排除的账号视为尝试过，仍计入 active_count
+        let mut attempted_ids = request.excluded_account_ids.clone();
         let mut selection_budget = active_count.saturating_mul(3).max(1);
         let mut last_transport_error: Option<reqwest::Error> = None;
 
+        // 只有首次选号遵循会话粘性，绑定账号失败后按策略换号
+        let mut session_key = request.session_key.as_deref();
+
         while attempted_ids.len() < active_count && selection_budget > 0 {
             selection_budget -= 1;
 
-            let Some(account) = self.pool.next_account().await else {
+            let selected = match pinned_account_id {
+                Some(id) => self
+                    .pool
+                    .get_account(id)
+                    .await
+                    .filter(|a| !a.is_forbidden),
+                None => {
+                    self.pool
+                        .next_account_for_model(session_key.take(), model)
+                        .await
+                }
+            };
+            let Some(account) = selected else {
                 break;
             };
             if !attempted_ids.insert(account.id.clone()) {
                 continue;
             }
 
+            let account = match self.pool.ensure_fresh_token(account.clone(), false).await {
+                Ok(fresh) => fresh,
+                // 刷新失败但旧 token 尚未过期时继续使用旧 token
+                Err(err) if !account.is_expired() => {
+                    eprintln!("[Codex] Token refresh failed for {}: {}", account.email, err);
+                    account
+                }
+                Err(err) => {
+                    eprintln!("[Codex] Token refresh failed for {}: {}", account.email, err);
+                    self.pool.record_failure(&account.id, Some(401)).await;
+                    continue;
+                }
+            };
+
             let meta = ForwardMeta {
                 account_id: account.id.clone(),
                 account_email: account.email.clone(),
                 format: request.format.clone(),
                 model: request.model.clone(),
+                client_key_id: request.client_key_id.clone(),
                 started_at: Instant::now(),
             };
 
-            let response = match self.send_once(&upstream_url, &request, &account).await {
+            let response = match self
+                .send_with_token_retry(&upstream_url, &request, account.clone())
+                .await
+            {
                 Ok(resp) => resp,
                 Err(err) => {
                     self.pool.record_failure(&account.id, None).await;
@@ -102,18 +165,28 @@ impl CodexExecutor {
                         last_transport_error = Some(err);
                         continue;
                     }
-                    return Err(CodexError::ExecutionError(format_transport_error(&err)));
+                    return Err(transport_error(&err));
                 }
             };
 
+            if let Some(usage) =
+                parse_usage_headers(response.headers(), chrono::Utc::now().timestamp())
+            {
+                self.pool.record_rate_limits(&account.id, usage).await;
+            }
+
             let status = response.status();
             if status.is_success() {
                 self.pool.record_success(&account.id).await;
+                if let Some(ref key) = request.session_key {
+                    self.pool.pin_session(key, &account.id).await;
+                }
                 return Ok((response, meta));
             }
 
+            let retry_after = parse_retry_after(response.headers(), chrono::Utc::now());
             self.pool
-                .record_failure(&account.id, Some(status.as_u16()))
+                .record_failure_with_retry_after(&account.id, Some(status.as_u16()), retry_after)
                 .await;
 
             if should_retry_status(status) && attempted_ids.len() < active_count {
@@ -124,12 +197,33 @@ impl CodexExecutor {
         }
 
         if let Some(err) = last_transport_error {
-            return Err(CodexError::ExecutionError(format_transport_error(&err)));
+            return Err(transport_error(&err));
         }
 
         Err(CodexError::NoAvailableAccount)
     }
 
+    /// 发送请求；遇到 401 时强制刷新 token 并用同一账号重试一次
+    async fn send_with_token_retry(
+        &self,
+        url: &str,
+        request: &ForwardRequest,
+        account: CodexPoolAccount,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let response = self.send_once(url, request, &account).await?;
+        if response.status() != StatusCode::UNAUTHORIZED || !self.pool.can_refresh_tokens().await {
+            return Ok(response);
+        }
+
+        match self.pool.ensure_fresh_token(account, true).await {
+            Ok(fresh) => self.send_once(url, request, &fresh).await,
+            Err(err) => {
+                eprintln!("[Codex] Token refresh after 401 failed: {}", err);
+                Ok(response)
+            }
+        }
+    }
+
     async fn send_once(
         &self,
         url: &str,
@@ -241,6 +335,102 @@ fn should_retry_status(status: StatusCode) -> bool {
     )
 }
 
+/// 从上游限流响应头解析重置前的秒数（向上取整）。
+/// 依次尝试 retry-after-ms、retry-after（秒数或 HTTP 日期）、
+/// x-ratelimit-reset（秒数、Unix 时间戳或 "1m30s" 形式的时长）。
+fn parse_retry_after(headers: &HeaderMap, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some((ms / 1000.0).ceil() as i64);
+    }
+    if let Some(value) = header("retry-after") {
+        if let Ok(secs) = value.parse::<f64>() {
+            return Some(secs.ceil() as i64);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some((date.timestamp() - now.timestamp()).max(0));
+        }
+    }
+    let reset = header("x-ratelimit-reset")?;
+    match reset.parse::<f64>() {
+        // 大于 10 年的秒数视为 Unix 时间戳
+        Ok(value) if value > 10.0 * 365.0 * 24.0 * 3600.0 => {
+            Some((value.ceil() as i64 - now.timestamp()).max(0))
+        }
+        Ok(secs) => Some(secs.ceil() as i64),
+        Err(_) => parse_duration_secs(reset).map(|secs| secs.ceil() as i64),
+    }
+}
+
+/// 解析 Codex 上游的用量响应头（x-codex-primary-* / x-codex-secondary-*），
+/// 两个窗口都没有用量百分比时返回 None
+fn parse_usage_headers(headers: &HeaderMap, now: i64) -> Option<CodexUsageSnapshot> {
+    let number = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+    };
+    let reset_at = |window: &str| {
+        number(&format!("x-codex-{window}-reset-at"))
+            .map(|ts| ts as i64)
+            .or_else(|| {
+                number(&format!("x-codex-{window}-reset-after-seconds"))
+                    .map(|secs| now + secs.max(0.0).ceil() as i64)
+            })
+    };
+    let percent = |name: &str| number(name).map(|v| v.clamp(0.0, 100.0));
+
+    let usage = CodexUsageSnapshot {
+        primary_used_percent: percent("x-codex-primary-used-percent"),
+        primary_window_minutes: number("x-codex-primary-window-minutes").map(|v| v as i64),
+        primary_reset_at: reset_at("primary"),
+        secondary_used_percent: percent("x-codex-secondary-used-percent"),
+        secondary_window_minutes: number("x-codex-secondary-window-minutes").map(|v| v as i64),
+        secondary_reset_at: reset_at("secondary"),
+        primary_over_secondary_percent: number("x-codex-primary-over-secondary-limit-percent"),
+        observed_at: now,
+    };
+    if usage.primary_used_percent.is_none() && usage.secondary_used_percent.is_none() {
+        return None;
+    }
+    Some(usage)
+}
+
+/// 解析 "6m0s"、"1h2m"、"250ms" 形式的时长（秒）
+fn parse_duration_secs(value: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .filter(|idx| *idx > 0)?;
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        total += number * factor;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
 fn is_retryable_transport_error(err: &reqwest::Error) -> bool {
     err.is_timeout() || err.is_connect()
 }
@@ -255,3 +445,398 @@ fn format_transport_error(err: &reqwest::Error) -> String {
 
     format!("Request failed: {}", err)
 }
+
+/// 请求超时单独归类，其余传输错误按执行错误处理
+fn transport_error(err: &reqwest::Error) -> CodexError {
+    if err.is_timeout() {
+        CodexError::UpstreamTimeout(format_transport_error(err))
+    } else {
+        CodexError::ExecutionError(format_transport_error(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+    use warp::http::HeaderMap;
+
+    use super::{CodexExecutor, ForwardRequest, parse_retry_after, parse_usage_headers};
+    use crate::platforms::openai::codex::models::CodexError;
+    use crate::platforms::openai::codex::pool::{CodexPool, WaitQueueConfig};
+    use crate::platforms::openai::codex::test_support::{
+        MockRefresher, MockReply, MockUpstream, pool_account,
+    };
+
+    async fn setup(ids: &[&str]) -> (MockUpstream, Arc<CodexPool>, CodexExecutor) {
+        let upstream = MockUpstream::start().await;
+        let pool = Arc::new(CodexPool::new());
+        for id in ids {
+            pool.add_account(pool_account(id)).await;
+        }
+        let executor =
+            CodexExecutor::with_client(pool.clone(), MockUpstream::client(), upstream.origin());
+        (upstream, pool, executor)
+    }
+
+    fn responses_request() -> ForwardRequest {
+        ForwardRequest {
+            method: Method::POST,
+            path: "/v1/responses".to_string(),
+            query: Some("trace=1".to_string()),
+            headers: HeaderMap::new(),
+            body: Bytes::from(json!({"model": "gpt-5", "input": "hi", "stream": true}).to_string()),
+            format: "openai-responses".to_string(),
+            model: "gpt-5".to_string(),
+            client_key_id: None,
+            excluded_account_ids: Default::default(),
+            session_key: None,
+            pinned_account_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn forward_streams_sse_from_configured_origin() {
+        let (upstream, _pool, executor) = setup(&["a"]).await;
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "a");
+        let body = response.text().await.unwrap();
+        assert!(body.contains("response.completed"));
+
+        let requests = upstream.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/backend-api/codex/responses");
+        assert_eq!(requests[0].query.as_deref(), Some("trace=1"));
+        assert_eq!(requests[0].authorization.as_deref(), Some("Bearer access-a"));
+    }
+
+    #[tokio::test]
+    async fn forward_fails_over_on_retryable_statuses() {
+        for status in [401u16, 403, 408, 429, 500, 502, 503, 504] {
+            let (upstream, pool, executor) = setup(&["a", "b"]).await;
+            upstream.script("a", [MockReply::Status(status)]);
+
+            let (response, meta) = executor.forward(responses_request()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "status {status}");
+            assert_eq!(meta.account_id, "b", "status {status}");
+
+            let failed = pool.get_account("a").await.unwrap();
+            assert_eq!(failed.last_error_status, Some(status));
+            let succeeded = pool.get_account("b").await.unwrap();
+            assert_eq!(succeeded.last_error_status, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn forward_skips_excluded_accounts() {
+        let (upstream, _pool, executor) = setup(&["a", "b"]).await;
+
+        let mut request = responses_request();
+        request.excluded_account_ids.insert("a".to_string());
+        for _ in 0..3 {
+            let (_, meta) = executor.forward(request.clone()).await.unwrap();
+            assert_eq!(meta.account_id, "b");
+        }
+        assert!(upstream.requests().iter().all(|r| r.account_id == "b"));
+
+        request.excluded_account_ids.insert("b".to_string());
+        assert!(matches!(
+            executor.forward(request).await,
+            Err(CodexError::NoAvailableAccount)
+        ));
+    }
+
+    #[test]
+    fn retry_after_reads_seconds_dates_and_reset_durations() {
+        let now = chrono::Utc::now();
+        let parse = |name: &'static str, value: String| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, value.parse().unwrap());
+            parse_retry_after(&headers, now)
+        };
+
+        assert_eq!(parse("retry-after", "30".to_string()), Some(30));
+        assert_eq!(parse("retry-after-ms", "1500".to_string()), Some(2));
+        let date = (now + chrono::Duration::seconds(90)).to_rfc2822();
+        assert_eq!(parse("retry-after", date), Some(90));
+        assert_eq!(parse("x-ratelimit-reset", "6m0s".to_string()), Some(360));
+        assert_eq!(parse("x-ratelimit-reset", "1.5s".to_string()), Some(2));
+        let reset_at = (now.timestamp() + 45).to_string();
+        assert_eq!(parse("x-ratelimit-reset", reset_at), Some(45));
+        assert_eq!(parse("x-ratelimit-reset", "soon".to_string()), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn usage_headers_parse_both_windows() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-codex-primary-used-percent", "42.5".parse().unwrap());
+        headers.insert("x-codex-primary-window-minutes", "300".parse().unwrap());
+        headers.insert("x-codex-primary-reset-after-seconds", "120".parse().unwrap());
+        headers.insert("x-codex-secondary-used-percent", "130".parse().unwrap());
+        headers.insert("x-codex-secondary-reset-at", "2000000".parse().unwrap());
+
+        let usage = parse_usage_headers(&headers, 1000).unwrap();
+        assert_eq!(usage.primary_used_percent, Some(42.5));
+        assert_eq!(usage.primary_window_minutes, Some(300));
+        assert_eq!(usage.primary_reset_at, Some(1120));
+        assert_eq!(usage.secondary_used_percent, Some(100.0));
+        assert_eq!(usage.secondary_reset_at, Some(2_000_000));
+        assert_eq!(usage.observed_at, 1000);
+
+        headers.remove("x-codex-primary-used-percent");
+        headers.remove("x-codex-secondary-used-percent");
+        assert!(parse_usage_headers(&headers, 1000).is_none());
+    }
+
+    #[tokio::test]
+    async fn forward_records_usage_headers_on_pool_account() {
+        let (upstream, pool, executor) = setup(&["a"]).await;
+        upstream.set_response_headers(
+            "a",
+            &[
+                ("x-codex-primary-used-percent", "61"),
+                ("x-codex-secondary-used-percent", "12.5"),
+            ],
+        );
+
+        executor.forward(responses_request()).await.unwrap();
+
+        let account = pool.get_account("a").await.unwrap();
+        assert_eq!(account.codex_5h_used_percent, Some(61.0));
+        assert_eq!(account.codex_7d_used_percent, Some(12.5));
+        let batch = pool
+            .next_usage_batch(std::time::Duration::from_millis(1))
+            .await;
+        assert_eq!(batch["a"].primary_used_percent, Some(61.0));
+    }
+
+    #[tokio::test]
+    async fn forward_waits_for_cooldown_or_returns_retry_after() {
+        let (upstream, pool, executor) = setup(&["a"]).await;
+        upstream.script(
+            "a",
+            [MockReply::StatusWithHeader(429, "retry-after", "1".to_string())],
+        );
+
+        // 冷却时长取自上游的 retry-after，而不是固定的 5 分钟
+        let now = chrono::Utc::now().timestamp();
+        let (response, _) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let cooldown = pool.get_account("a").await.unwrap().cooldown_until.unwrap();
+        assert!(cooldown > now && cooldown <= now + 2);
+
+        // 未开启排队时立即返回建议的重试时间
+        assert!(matches!(
+            executor.forward(responses_request()).await,
+            Err(CodexError::PoolCoolingDown(secs)) if secs <= 2
+        ));
+
+        // 开启排队后等到冷却结束再转发
+        pool.set_wait_queue(Some(WaitQueueConfig {
+            max_wait_secs: 5,
+            max_depth: 4,
+        }))
+        .await;
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "a");
+
+        // 冷却超过最长等待时间时不排队
+        upstream.script(
+            "a",
+            [MockReply::StatusWithHeader(429, "x-ratelimit-reset", "10m0s".to_string())],
+        );
+        executor.forward(responses_request()).await.unwrap();
+        let started = std::time::Instant::now();
+        assert!(matches!(
+            executor.forward(responses_request()).await,
+            Err(CodexError::PoolCoolingDown(secs)) if secs >= 590
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn forward_uses_pinned_account_without_failover() {
+        let (upstream, _pool, executor) = setup(&["a", "b"]).await;
+        upstream.script("b", [MockReply::Status(429)]);
+
+        let mut request = responses_request();
+        request.pinned_account_id = Some("b".to_string());
+        let (response, meta) = executor.forward(request.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(meta.account_id, "b");
+        assert_eq!(upstream.requests().len(), 1);
+
+        // 冷却中的指定账号仍会被使用
+        let (response, meta) = executor.forward(request.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "b");
+
+        request.pinned_account_id = Some("missing".to_string());
+        assert!(matches!(
+            executor.forward(request).await,
+            Err(CodexError::NoAvailableAccount)
+        ));
+    }
+
+    #[tokio::test]
+    async fn forward_keeps_sessions_on_one_account_and_repins_on_failure() {
+        let (upstream, pool, executor) = setup(&["a", "b", "c"]).await;
+        let mut request = responses_request();
+        request.session_key = Some("session:abc".to_string());
+
+        let (_, first) = executor.forward(request.clone()).await.unwrap();
+        for _ in 0..3 {
+            let (_, meta) = executor.forward(request.clone()).await.unwrap();
+            assert_eq!(meta.account_id, first.account_id);
+        }
+
+        // 绑定账号限流后换号，并改绑到新账号
+        upstream.script(&first.account_id, [MockReply::Status(429)]);
+        let (_, moved) = executor.forward(request.clone()).await.unwrap();
+        assert_ne!(moved.account_id, first.account_id);
+        assert_eq!(
+            pool.pinned_account_id("session:abc"),
+            Some(moved.account_id.clone())
+        );
+        let (_, meta) = executor.forward(request).await.unwrap();
+        assert_eq!(meta.account_id, moved.account_id);
+    }
+
+    #[tokio::test]
+    async fn forward_applies_account_cooldowns() {
+        let (upstream, pool, executor) = setup(&["unauthorized", "quota", "ok"]).await;
+        upstream.script("unauthorized", [MockReply::Status(401)]);
+        upstream.script("quota", [MockReply::Status(429)]);
+
+        let now = chrono::Utc::now().timestamp();
+        let (_, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(meta.account_id, "ok");
+
+        let unauthorized = pool.get_account("unauthorized").await.unwrap();
+        assert_eq!(unauthorized.unavailable_reason.as_deref(), Some("unauthorized"));
+        assert!(unauthorized.cooldown_until.unwrap() >= now + 30 * 60);
+
+        let quota = pool.get_account("quota").await.unwrap();
+        assert_eq!(quota.unavailable_reason.as_deref(), Some("quota"));
+        let cooldown = quota.cooldown_until.unwrap();
+        assert!(cooldown >= now + 5 * 60 && cooldown < now + 30 * 60);
+
+        // 冷却中的账号不再参与选择
+        assert_eq!(pool.active_count().await, 1);
+        let (_, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(meta.account_id, "ok");
+        assert_eq!(upstream.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn forward_returns_payment_required_and_forbids_account() {
+        let (upstream, pool, executor) = setup(&["a", "b"]).await;
+        upstream.script("a", [MockReply::Status(402)]);
+
+        // 402 不在重试列表中，直接返回给客户端
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(meta.account_id, "a");
+        assert!(pool.get_account("a").await.unwrap().is_forbidden);
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "b");
+    }
+
+    #[tokio::test]
+    async fn forward_does_not_fail_over_on_client_errors() {
+        let (upstream, pool, executor) = setup(&["a", "b"]).await;
+        upstream.script("a", [MockReply::Status(400)]);
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(meta.account_id, "a");
+        assert_eq!(upstream.requests().len(), 1);
+        assert!(pool.get_account("a").await.unwrap().is_available());
+    }
+
+    #[tokio::test]
+    async fn forward_returns_last_response_when_every_account_fails() {
+        let (upstream, _pool, executor) = setup(&["a", "b"]).await;
+        upstream.set_default(MockReply::Status(503));
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(meta.account_id, "b");
+        assert_eq!(upstream.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn forward_refreshes_token_on_401_and_retries_same_account() {
+        let (upstream, pool, executor) = setup(&["a", "b"]).await;
+        let refresher = Arc::new(MockRefresher::default());
+        pool.set_token_refresher(refresher.clone()).await;
+        upstream.script("a", [MockReply::Status(401)]);
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "a");
+        assert_eq!(refresher.calls(), 1);
+
+        let requests = upstream.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].authorization.as_deref(), Some("Bearer access-a"));
+        assert_eq!(requests[1].authorization.as_deref(), Some("Bearer fresh-a-1"));
+
+        let account = pool.get_account("a").await.unwrap();
+        assert_eq!(account.access_token, "fresh-a-1");
+        assert_eq!(account.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(account.last_error_status.is_none());
+    }
+
+    #[tokio::test]
+    async fn forward_fails_over_when_refreshed_token_is_still_rejected() {
+        let (upstream, pool, executor) = setup(&["a", "b"]).await;
+        pool.set_token_refresher(Arc::new(MockRefresher::default())).await;
+        upstream.script("a", [MockReply::Status(401), MockReply::Status(401)]);
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "b");
+        assert_eq!(upstream.requests().len(), 3);
+        let failed = pool.get_account("a").await.unwrap();
+        assert_eq!(failed.unavailable_reason.as_deref(), Some("unauthorized"));
+    }
+
+    #[tokio::test]
+    async fn forward_refreshes_expired_token_before_sending() {
+        let upstream = MockUpstream::start().await;
+        let pool = Arc::new(CodexPool::new());
+        let mut expired = pool_account("a");
+        expired.expires_at = chrono::Utc::now().timestamp() - 60;
+        expired.is_active = false;
+        pool.add_account(expired).await;
+        let executor =
+            CodexExecutor::with_client(pool.clone(), MockUpstream::client(), upstream.origin());
+
+        // 没有刷新器时过期账号不可用
+        assert_eq!(pool.active_count().await, 0);
+
+        let refresher = Arc::new(MockRefresher::default());
+        pool.set_token_refresher(refresher.clone()).await;
+        assert_eq!(pool.active_count().await, 1);
+
+        let (response, meta) = executor.forward(responses_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(meta.account_id, "a");
+        assert_eq!(refresher.calls(), 1);
+        assert_eq!(
+            upstream.requests()[0].authorization.as_deref(),
+            Some("Bearer fresh-a-1")
+        );
+        assert!(pool.get_account("a").await.unwrap().is_available());
+    }
+}
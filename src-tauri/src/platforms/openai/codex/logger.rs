@@ -108,11 +108,17 @@ impl RequestLogger {
                     input_tokens: 0,
                     output_tokens: 0,
                     total_tokens: 0,
+                    cached_tokens: 0,
+                    reasoning_tokens: 0,
+                    estimated_cost: 0.0,
                 });
             entry.requests += 1;
             entry.input_tokens += log.input_tokens.max(0) as u64;
             entry.output_tokens += log.output_tokens.max(0) as u64;
             entry.total_tokens += log.total_tokens.max(0) as u64;
+            entry.cached_tokens += log.cached_tokens.max(0) as u64;
+            entry.reasoning_tokens += log.reasoning_tokens.max(0) as u64;
+            entry.estimated_cost += log.estimated_cost;
         }
 
         let mut models: Vec<_> = per_model.into_values().collect();
@@ -165,23 +171,30 @@ impl RequestLogger {
             week_tokens: 0,
             month_requests: 0,
             month_tokens: 0,
+            today_cost: 0.0,
+            week_cost: 0.0,
+            month_cost: 0.0,
         };
 
         for log in &self.logs {
             let ts = log.timestamp;
             let tokens = log.total_tokens.max(0) as u64;
+            let cost = log.estimated_cost;
 
             if ts >= month_start && ts <= now_ts {
                 stats.month_requests += 1;
                 stats.month_tokens += tokens;
+                stats.month_cost += cost;
             }
             if ts >= week_start && ts <= now_ts {
                 stats.week_requests += 1;
                 stats.week_tokens += tokens;
+                stats.week_cost += cost;
             }
             if ts >= today_start && ts <= now_ts {
                 stats.today_requests += 1;
                 stats.today_tokens += tokens;
+                stats.today_cost += cost;
             }
         }
 
@@ -266,6 +279,13 @@ fn matches_query(log: &RequestLog, query: &LogQuery) -> bool {
             return false;
         }
     }
+    if let Some(client_key_id) = &query.client_key_id {
+        if !client_key_id.trim().is_empty()
+            && log.client_key_id.as_deref() != Some(client_key_id.trim())
+        {
+            return false;
+        }
+    }
 
     true
 }
@@ -0,0 +1,342 @@
+//! Codex Prometheus 指标
+//!
+//! 在内存中累计请求数、token 数、估算成本与上游延迟直方图，
+//! 抓取时结合号池状态与不支持参数缓存渲染为 Prometheus 文本格式（0.0.4）。
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+
+use super::models::{CodexPoolAccount, RequestLog};
+
+/// 进程内共享的指标注册表
+pub static CODEX_METRICS: LazyLock<CodexMetrics> = LazyLock::new(CodexMetrics::default);
+
+/// /metrics 响应的 Content-Type
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 上游延迟直方图的桶上界（秒）
+const LATENCY_BUCKETS: &[f64] = &[0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestLabels {
+    model: String,
+    status: String,
+    format: String,
+    account: String,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// 每个桶内（非累计）的样本数
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS.len()];
+        }
+        if let Some(idx) = LATENCY_BUCKETS.iter().position(|b| value <= *b) {
+            self.buckets[idx] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    requests: BTreeMap<RequestLabels, u64>,
+    /// (model, type) → token 数，type 为 input/output/cached/reasoning
+    tokens: BTreeMap<(String, &'static str), u64>,
+    cost: BTreeMap<String, f64>,
+    latency: BTreeMap<String, Histogram>,
+}
+
+/// Codex 指标注册表
+#[derive(Debug, Default)]
+pub struct CodexMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl CodexMetrics {
+    /// 记录一条请求日志
+    pub fn record_request(&self, log: &RequestLog) {
+        let model = label_or_unknown(&log.model);
+        let account = label_or_unknown(&log.account_email);
+        let mut inner = self.inner.lock().unwrap();
+
+        *inner
+            .requests
+            .entry(RequestLabels {
+                model: model.clone(),
+                status: label_or_unknown(&log.status),
+                format: label_or_unknown(&log.format),
+                account,
+            })
+            .or_default() += 1;
+
+        for (kind, value) in [
+            ("input", log.input_tokens),
+            ("output", log.output_tokens),
+            ("cached", log.cached_tokens),
+            ("reasoning", log.reasoning_tokens),
+        ] {
+            if value > 0 {
+                *inner.tokens.entry((model.clone(), kind)).or_default() += value as u64;
+            }
+        }
+        if log.estimated_cost > 0.0 {
+            *inner.cost.entry(model.clone()).or_default() += log.estimated_cost;
+        }
+
+        // 未选到账号的失败请求没有上游耗时
+        if let Some(ms) = log.request_duration_ms.filter(|ms| *ms >= 0) {
+            inner
+                .latency
+                .entry(model)
+                .or_default()
+                .observe(ms as f64 / 1000.0);
+        }
+    }
+
+    /// 渲染全部指标
+    pub fn render(&self, accounts: &[CodexPoolAccount], unsupported_params: usize) -> String {
+        let mut out = String::new();
+        {
+            let inner = self.inner.lock().unwrap();
+
+            write_header(
+                &mut out,
+                "codex_requests_total",
+                "counter",
+                "Codex proxy requests.",
+            );
+            for (labels, value) in &inner.requests {
+                let _ = writeln!(
+                    out,
+                    "codex_requests_total{{model=\"{}\",status=\"{}\",format=\"{}\",account=\"{}\"}} {}",
+                    escape_label(&labels.model),
+                    escape_label(&labels.status),
+                    escape_label(&labels.format),
+                    escape_label(&labels.account),
+                    value
+                );
+            }
+
+            write_header(
+                &mut out,
+                "codex_tokens_total",
+                "counter",
+                "Tokens reported by the upstream usage object.",
+            );
+            for ((model, kind), value) in &inner.tokens {
+                let _ = writeln!(
+                    out,
+                    "codex_tokens_total{{model=\"{}\",type=\"{}\"}} {}",
+                    escape_label(model),
+                    kind,
+                    value
+                );
+            }
+
+            write_header(
+                &mut out,
+                "codex_estimated_cost_usd_total",
+                "counter",
+                "Estimated API cost from the model price table.",
+            );
+            for (model, value) in &inner.cost {
+                let _ = writeln!(
+                    out,
+                    "codex_estimated_cost_usd_total{{model=\"{}\"}} {}",
+                    escape_label(model),
+                    value
+                );
+            }
+
+            write_header(
+                &mut out,
+                "codex_upstream_latency_seconds",
+                "histogram",
+                "Time from account selection to the end of the upstream response.",
+            );
+            for (model, histogram) in &inner.latency {
+                let model = escape_label(model);
+                let mut cumulative = 0;
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                    cumulative += count;
+                    let _ = writeln!(
+                        out,
+                        "codex_upstream_latency_seconds_bucket{{model=\"{}\",le=\"{}\"}} {}",
+                        model, bound, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "codex_upstream_latency_seconds_bucket{{model=\"{}\",le=\"+Inf\"}} {}",
+                    model, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "codex_upstream_latency_seconds_sum{{model=\"{}\"}} {}",
+                    model, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "codex_upstream_latency_seconds_count{{model=\"{}\"}} {}",
+                    model, histogram.count
+                );
+            }
+        }
+
+        let active = accounts.iter().filter(|a| a.is_available()).count();
+        let cooling_down = accounts
+            .iter()
+            .filter(|a| !a.is_forbidden && a.is_in_cooldown())
+            .count();
+        let forbidden = accounts.iter().filter(|a| a.is_forbidden).count();
+
+        write_header(
+            &mut out,
+            "codex_pool_accounts",
+            "gauge",
+            "Accounts in the Codex pool by state.",
+        );
+        for (state, value) in [
+            ("total", accounts.len()),
+            ("active", active),
+            ("cooling_down", cooling_down),
+            ("forbidden", forbidden),
+        ] {
+            let _ = writeln!(out, "codex_pool_accounts{{state=\"{}\"}} {}", state, value);
+        }
+
+        write_header(
+            &mut out,
+            "codex_account_quota_used_percent",
+            "gauge",
+            "Codex quota usage per account and window.",
+        );
+        for account in accounts {
+            for (window, value) in [
+                ("5h", account.codex_5h_used_percent),
+                ("7d", account.codex_7d_used_percent),
+            ] {
+                if let Some(value) = value {
+                    let _ = writeln!(
+                        out,
+                        "codex_account_quota_used_percent{{account=\"{}\",window=\"{}\"}} {}",
+                        escape_label(&account.email),
+                        window,
+                        value
+                    );
+                }
+            }
+        }
+
+        write_header(
+            &mut out,
+            "codex_unsupported_params",
+            "gauge",
+            "Request parameters cached as unsupported by the upstream.",
+        );
+        let _ = writeln!(out, "codex_unsupported_params {}", unsupported_params);
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn label_or_unknown(value: &str) -> String {
+    let value = value.trim();
+    if value.is_empty() {
+        "unknown".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按 Prometheus 文本格式转义标签值
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodexMetrics;
+    use crate::platforms::openai::codex::models::RequestLog;
+    use crate::platforms::openai::codex::test_support::pool_account;
+
+    fn log(model: &str, status: &str, duration_ms: Option<i64>) -> RequestLog {
+        RequestLog {
+            id: "id".to_string(),
+            timestamp: 0,
+            account_id: "a".to_string(),
+            account_email: "a@example.com".to_string(),
+            model: model.to_string(),
+            format: "openai-responses".to_string(),
+            client_key_id: None,
+            input_tokens: 100,
+            output_tokens: 20,
+            total_tokens: 120,
+            cached_tokens: 80,
+            reasoning_tokens: 0,
+            estimated_cost: 0.5,
+            status: status.to_string(),
+            error_message: None,
+            request_duration_ms: duration_ms,
+        }
+    }
+
+    #[test]
+    fn renders_counters_histograms_and_pool_gauges() {
+        let metrics = CodexMetrics::default();
+        metrics.record_request(&log("gpt-5", "success", Some(400)));
+        metrics.record_request(&log("gpt-5", "success", Some(3_000)));
+        metrics.record_request(&log("gpt-5", "error", None));
+
+        let mut cooling = pool_account("b");
+        cooling.cooldown_until = Some(chrono::Utc::now().timestamp() + 60);
+        let mut forbidden = pool_account("c");
+        forbidden.is_forbidden = true;
+        let mut active = pool_account("a");
+        active.codex_5h_used_percent = Some(42.5);
+
+        let text = metrics.render(&[active, cooling, forbidden], 3);
+        for line in [
+            "codex_requests_total{model=\"gpt-5\",status=\"success\",format=\"openai-responses\",account=\"a@example.com\"} 2",
+            "codex_requests_total{model=\"gpt-5\",status=\"error\",format=\"openai-responses\",account=\"a@example.com\"} 1",
+            "codex_tokens_total{model=\"gpt-5\",type=\"cached\"} 240",
+            "codex_tokens_total{model=\"gpt-5\",type=\"input\"} 300",
+            "codex_estimated_cost_usd_total{model=\"gpt-5\"} 1.5",
+            "codex_upstream_latency_seconds_bucket{model=\"gpt-5\",le=\"0.5\"} 1",
+            "codex_upstream_latency_seconds_bucket{model=\"gpt-5\",le=\"2.5\"} 1",
+            "codex_upstream_latency_seconds_bucket{model=\"gpt-5\",le=\"5\"} 2",
+            "codex_upstream_latency_seconds_bucket{model=\"gpt-5\",le=\"+Inf\"} 2",
+            "codex_upstream_latency_seconds_count{model=\"gpt-5\"} 2",
+            "codex_pool_accounts{state=\"total\"} 3",
+            "codex_pool_accounts{state=\"active\"} 1",
+            "codex_pool_accounts{state=\"cooling_down\"} 1",
+            "codex_pool_accounts{state=\"forbidden\"} 1",
+            "codex_account_quota_used_percent{account=\"a@example.com\",window=\"5h\"} 42.5",
+            "codex_unsupported_params 3",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing line: {line}\n{text}"
+            );
+        }
+        assert!(!text.contains("type=\"reasoning\""));
+    }
+}
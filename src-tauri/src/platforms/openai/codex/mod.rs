@@ -1,18 +1,33 @@
 //! Codex API 模块
 //!
 //! 提供 OpenAI Codex (Responses API) 的反代服务，包括：
-//! - 请求/响应透传（不做协议转换）
-//! - 号池管理（使用现有 OAuth 账号）
+//! - 请求/响应透传（Responses API）
+//! - 动态模型列表（模型目录 + 近期流量 + 套餐过滤）与模型别名
+//! - Chat Completions / Anthropic Messages 与 Responses 之间的协议转换
+//! - 号池管理（使用现有 OAuth 账号），全部冷却时可排队等待并按上游 retry-after 冷却
+//! - 从上游响应头实时同步 5h/7d 用量并防抖写回账号配额
+//! - 号池策略：轮询 / 单个 / 智能打分（权重可配置）/ 分层优先级，账号可预留给指定模型
+//! - 客户端 API Key（预算、限速、模型白名单）
 //! - 请求日志记录（内存 + 持久化）
-//! - Token 使用统计
+//! - Token 使用统计与按模型价格表估算成本
+//! - Prometheus 指标导出（/metrics）
+//! - 可选的请求抓包（脱敏后存入日志库旁表）与重放
 
+pub mod capture;
+pub mod catalog;
+pub mod client_keys;
 pub mod commands;
 pub mod executor;
 pub mod logger;
+pub mod metrics;
 pub mod models;
 pub mod pool;
+pub mod pricing;
 pub mod server;
 pub mod storage;
+#[cfg(test)]
+mod test_support;
+pub mod translator;
 
 pub use executor::CodexExecutor;
 pub use logger::RequestLogger;
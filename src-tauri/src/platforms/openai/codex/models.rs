@@ -3,6 +3,7 @@
 //! 定义请求/响应格式转换所需的数据结构
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // ==================== 号池相关 ====================
 
@@ -65,6 +66,17 @@ pub struct CodexPoolAccount {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub subscription_expires_at: Option<i64>,
 
+    // ===== 分层（Priority 策略与模型预留） =====
+    /// 优先级层级，数值越小越优先
+    #[serde(default)]
+    pub priority: i32,
+    /// 同一层级内的选择权重
+    #[serde(default = "default_pool_weight")]
+    pub weight: u32,
+    /// 仅服务这些模型（支持 `*` 结尾的前缀匹配），为空表示不限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reserved_models: Vec<String>,
+
     // ===== 标签 =====
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tag: Option<String>,
@@ -72,6 +84,10 @@ pub struct CodexPoolAccount {
     pub tag_color: Option<String>,
 }
 
+fn default_pool_weight() -> u32 {
+    1
+}
+
 impl CodexPoolAccount {
     /// 从 OpenAI Account 转换为 CodexPoolAccount
     /// 返回 None 表示账号不可用（API 账号、无 token、被禁用等）
@@ -156,6 +172,9 @@ impl CodexPoolAccount {
             codex_7d_used_percent,
             plan_type,
             subscription_expires_at,
+            priority: account.pool_priority,
+            weight: account.pool_weight,
+            reserved_models: account.pool_reserved_models.clone(),
             tag: account.tag.clone(),
             tag_color: account.tag_color.clone(),
         })
@@ -178,6 +197,24 @@ impl CodexPoolAccount {
         self.is_active && !self.is_forbidden && !self.is_expired() && !self.is_in_cooldown()
     }
 
+    /// token 是否会在 window_secs 秒内过期
+    pub fn needs_token_refresh(&self, window_secs: i64) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        self.expires_at <= now + window_secs
+    }
+
+    /// 检查账号是否可被选中；可刷新 token 时，已过期但持有 refresh_token 的账号也可选（转发前先刷新）
+    pub fn is_selectable(&self, can_refresh: bool) -> bool {
+        if self.is_available() {
+            return true;
+        }
+        can_refresh
+            && self.is_expired()
+            && self.refresh_token.is_some()
+            && !self.is_forbidden
+            && !self.is_in_cooldown()
+    }
+
     fn token_is_expired(token: &crate::platforms::openai::models::TokenData) -> bool {
         let now = chrono::Utc::now().timestamp();
         token.expires_at <= now
@@ -190,65 +227,139 @@ impl CodexPoolAccount {
         self.last_used = Some(chrono::Utc::now().timestamp());
     }
 
-    /// Smart 策略打分 (0-100)
-    pub fn compute_score(&self) -> f64 {
+    /// 账号是否可用于该模型的请求；未知模型只能使用未预留的账号
+    pub fn serves_model(&self, model: Option<&str>) -> bool {
+        if self.reserved_models.is_empty() {
+            return true;
+        }
+        let Some(model) = model else {
+            return false;
+        };
+        self.reserved_models
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => model
+                    .to_ascii_lowercase()
+                    .starts_with(&prefix.to_ascii_lowercase()),
+                None => model.eq_ignore_ascii_case(pattern),
+            })
+    }
+
+    /// Priority 策略层内的选择权重：账号权重乘以订阅计划系数，付费计划分到更多请求。
+    /// 权重为 0 的账号仍只在层内没有其他账号时使用。
+    pub fn effective_weight(&self) -> f64 {
+        let plan = self.plan_type.as_deref().map(str::to_ascii_lowercase);
+        let plan_factor = match plan.as_deref() {
+            Some("pro") => 4.0,
+            Some("plus" | "team" | "business" | "enterprise" | "edu") => 2.0,
+            _ => 1.0, // free 或未知计划
+        };
+        self.weight as f64 * plan_factor
+    }
+
+    /// Smart 策略打分，满分为各项权重之和（默认 100）
+    pub fn compute_score(&self, weights: &SmartScoreWeights) -> f64 {
         let now = chrono::Utc::now().timestamp();
         let mut score = 0.0;
 
-        // 1. 5h 配额剩余 (40 分)
+        // 1. 5h 配额剩余
         score += match self.codex_5h_used_percent {
-            Some(used) => (100.0 - used).max(0.0) / 100.0 * 40.0,
-            None => 20.0, // 无数据给中间值
+            Some(used) => (100.0 - used).max(0.0) / 100.0 * weights.quota_5h,
+            None => weights.quota_5h / 2.0, // 无数据给中间值
         };
 
-        // 2. 7d 配额剩余 (15 分)
+        // 2. 7d 配额剩余
         score += match self.codex_7d_used_percent {
-            Some(used) => (100.0 - used).max(0.0) / 100.0 * 15.0,
-            None => 8.0,
+            Some(used) => (100.0 - used).max(0.0) / 100.0 * weights.quota_7d,
+            None => weights.quota_7d / 2.0,
         };
 
-        // 3. 订阅到期距今 (40 分) - 已过期或快到期的优先消耗
-        score += match self.subscription_expires_at {
+        // 3. 订阅到期距今 - 已过期或快到期的优先消耗
+        let expiry_ratio = match self.subscription_expires_at {
             Some(expires) => {
                 let days_left = (expires - now) as f64 / 86400.0;
                 if days_left <= 0.0 {
                     // 已过期（宽限期内），最高优先级
-                    40.0
+                    1.0
                 } else if days_left <= 3.0 {
-                    38.0
+                    0.95
                 } else if days_left <= 7.0 {
-                    30.0
+                    0.75
                 } else if days_left <= 15.0 {
-                    18.0
+                    0.45
                 } else if days_left <= 30.0 {
-                    8.0
+                    0.2
                 } else {
                     0.0
                 }
             }
             None => 0.0,
         };
+        score += expiry_ratio * weights.subscription_expiry;
 
-        // 4. 最近使用时间 (5 分) - 越久没用分越高，均衡负载
+        // 4. 最近使用时间 - 越久没用分越高，均衡负载
         score += match self.last_used {
             Some(ts) => {
                 let idle_secs = (now - ts).max(0) as f64;
                 // 空闲超过 5 分钟得满分，线性插值
-                (idle_secs / 300.0).min(1.0) * 5.0
+                (idle_secs / 300.0).min(1.0) * weights.idle
             }
-            None => 5.0, // 从未使用，满分
+            None => weights.idle, // 从未使用，满分
         };
 
         score
     }
 }
 
+/// Smart 策略各打分项的满分
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartScoreWeights {
+    /// 5h 配额剩余
+    pub quota_5h: f64,
+    /// 7d 配额剩余
+    pub quota_7d: f64,
+    /// 订阅临近到期
+    pub subscription_expiry: f64,
+    /// 空闲时长
+    pub idle: f64,
+}
+
+impl Default for SmartScoreWeights {
+    fn default() -> Self {
+        Self {
+            quota_5h: 40.0,
+            quota_7d: 15.0,
+            subscription_expiry: 40.0,
+            idle: 5.0,
+        }
+    }
+}
+
+impl SmartScoreWeights {
+    /// 负数或非法值按 0 处理；全部为 0 时回退默认权重
+    pub fn normalized(self) -> Self {
+        let clean = |value: f64| if value.is_finite() { value.max(0.0) } else { 0.0 };
+        let weights = Self {
+            quota_5h: clean(self.quota_5h),
+            quota_7d: clean(self.quota_7d),
+            subscription_expiry: clean(self.subscription_expiry),
+            idle: clean(self.idle),
+        };
+        if weights.quota_5h + weights.quota_7d + weights.subscription_expiry + weights.idle <= 0.0 {
+            return Self::default();
+        }
+        weights
+    }
+}
+
 /// 号池选择策略
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PoolStrategy {
     RoundRobin, // 轮询
     Single,     // 单个（固定账号）
     Smart,      // 智能选号（打分）
+    Priority,   // 分层：优先使用数值最小的层级，层内按权重随机
 }
 
 /// 号池状态
@@ -262,6 +373,79 @@ pub struct PoolStatus {
     pub selected_account_email: Option<String>, // Single 策略时选中的账号邮箱
 }
 
+/// 上游响应头携带的用量窗口（primary 对应 5h，secondary 对应 7d）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodexUsageSnapshot {
+    pub primary_used_percent: Option<f64>,
+    pub primary_window_minutes: Option<i64>,
+    /// 重置时间 (Unix timestamp)
+    pub primary_reset_at: Option<i64>,
+    pub secondary_used_percent: Option<f64>,
+    pub secondary_window_minutes: Option<i64>,
+    pub secondary_reset_at: Option<i64>,
+    pub primary_over_secondary_percent: Option<f64>,
+    /// 收到响应的时间
+    pub observed_at: i64,
+}
+
+impl CodexUsageSnapshot {
+    /// 用较新的快照覆盖，新快照缺失的字段保留旧值
+    pub fn merge(&mut self, newer: CodexUsageSnapshot) {
+        self.primary_used_percent = newer.primary_used_percent.or(self.primary_used_percent);
+        self.primary_window_minutes = newer.primary_window_minutes.or(self.primary_window_minutes);
+        self.primary_reset_at = newer.primary_reset_at.or(self.primary_reset_at);
+        self.secondary_used_percent = newer.secondary_used_percent.or(self.secondary_used_percent);
+        self.secondary_window_minutes = newer
+            .secondary_window_minutes
+            .or(self.secondary_window_minutes);
+        self.secondary_reset_at = newer.secondary_reset_at.or(self.secondary_reset_at);
+        self.primary_over_secondary_percent = newer
+            .primary_over_secondary_percent
+            .or(self.primary_over_secondary_percent);
+        self.observed_at = self.observed_at.max(newer.observed_at);
+    }
+
+    /// 更新号池账号中 Smart 打分使用的百分比
+    pub fn apply_to_account(&self, account: &mut CodexPoolAccount) {
+        if let Some(used) = self.primary_used_percent {
+            account.codex_5h_used_percent = Some(used);
+        }
+        if let Some(used) = self.secondary_used_percent {
+            account.codex_7d_used_percent = Some(used);
+        }
+    }
+
+    /// 写回账号的 QuotaData，重置倒计时按 now 计算
+    pub fn apply_to_quota(
+        &self,
+        quota: &mut crate::platforms::openai::models::QuotaData,
+        now: i64,
+    ) {
+        if let Some(used) = self.primary_used_percent {
+            quota.codex_5h_used_percent = Some(used);
+        }
+        if let Some(minutes) = self.primary_window_minutes {
+            quota.codex_5h_window_minutes = Some(minutes);
+        }
+        if let Some(reset_at) = self.primary_reset_at {
+            quota.codex_5h_reset_after_seconds = Some((reset_at - now).max(0));
+        }
+        if let Some(used) = self.secondary_used_percent {
+            quota.codex_7d_used_percent = Some(used);
+        }
+        if let Some(minutes) = self.secondary_window_minutes {
+            quota.codex_7d_window_minutes = Some(minutes);
+        }
+        if let Some(reset_at) = self.secondary_reset_at {
+            quota.codex_7d_reset_after_seconds = Some((reset_at - now).max(0));
+        }
+        if let Some(percent) = self.primary_over_secondary_percent {
+            quota.codex_primary_over_secondary_percent = Some(percent);
+        }
+        quota.codex_usage_updated_at = self.observed_at;
+    }
+}
+
 // ==================== 日志相关 ====================
 
 /// 请求日志
@@ -273,9 +457,21 @@ pub struct RequestLog {
     pub account_email: String,
     pub model: String,
     pub format: String,
+    /// 发起请求的客户端 Key id（管理员 Key 为 None）
+    #[serde(default)]
+    pub client_key_id: Option<String>,
     pub input_tokens: i64,
     pub output_tokens: i64,
     pub total_tokens: i64,
+    /// 命中提示缓存的输入 token（包含在 input_tokens 中）
+    #[serde(default)]
+    pub cached_tokens: i64,
+    /// 推理 token（包含在 output_tokens 中）
+    #[serde(default)]
+    pub reasoning_tokens: i64,
+    /// 按模型价格表估算的成本（美元）
+    #[serde(default)]
+    pub estimated_cost: f64,
     pub status: String,
     #[serde(default)]
     pub error_message: Option<String>,
@@ -308,6 +504,24 @@ pub struct ModelTokenStats {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_tokens: u64,
+    #[serde(default)]
+    pub cached_tokens: u64,
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+    #[serde(default)]
+    pub estimated_cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientTokenStats {
+    /// 客户端 Key id，None 表示管理员 Key 或未鉴权的失败请求
+    pub client_key_id: Option<String>,
+    #[serde(default)]
+    pub client_name: Option<String>,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -318,6 +532,12 @@ pub struct PeriodTokenStats {
     pub week_tokens: u64,
     pub month_requests: u64,
     pub month_tokens: u64,
+    #[serde(default)]
+    pub today_cost: f64,
+    #[serde(default)]
+    pub week_cost: f64,
+    #[serde(default)]
+    pub month_cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -325,11 +545,17 @@ pub struct DailyStats {
     pub date: String,
     pub requests: u64,
     pub tokens: u64,
+    #[serde(default)]
+    pub cached_tokens: u64,
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+    #[serde(default)]
+    pub cost: f64,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::CodexPoolAccount;
+    use super::{CodexPoolAccount, SmartScoreWeights};
     use crate::platforms::openai::models::{Account, TokenData};
 
     fn sample_token() -> TokenData {
@@ -357,6 +583,52 @@ mod tests {
         let pooled = CodexPoolAccount::from_openai_account(&account);
         assert!(pooled.is_none());
     }
+
+    #[test]
+    fn smart_score_uses_configured_weights_and_carries_pool_tier() {
+        let mut account = Account::new_oauth(
+            "user@example.com".to_string(),
+            sample_token(),
+            Some("acct".to_string()),
+            None,
+            None,
+        );
+        account.pool_priority = 2;
+        account.pool_weight = 5;
+        account.pool_reserved_models = vec!["gpt-5*".to_string()];
+
+        let mut pooled = CodexPoolAccount::from_openai_account(&account).unwrap();
+        assert_eq!((pooled.priority, pooled.weight), (2, 5));
+        assert!(pooled.serves_model(Some("gpt-5.1")));
+        assert!(!pooled.serves_model(Some("o3")) && !pooled.serves_model(None));
+
+        assert_eq!(pooled.effective_weight(), 5.0);
+        pooled.plan_type = Some("Pro".to_string());
+        assert_eq!(pooled.effective_weight(), 20.0);
+        pooled.plan_type = Some("plus".to_string());
+        assert_eq!(pooled.effective_weight(), 10.0);
+        pooled.weight = 0;
+        assert_eq!(pooled.effective_weight(), 0.0);
+
+        pooled.codex_5h_used_percent = Some(50.0);
+        pooled.codex_7d_used_percent = Some(0.0);
+        pooled.last_used = Some(chrono::Utc::now().timestamp());
+        let quota_only = SmartScoreWeights {
+            quota_5h: 10.0,
+            quota_7d: 1.0,
+            subscription_expiry: 0.0,
+            idle: 0.0,
+        };
+        assert!((pooled.compute_score(&quota_only) - 6.0).abs() < 1e-9);
+
+        let zero = SmartScoreWeights {
+            quota_5h: -1.0,
+            quota_7d: 0.0,
+            subscription_expiry: 0.0,
+            idle: f64::NAN,
+        };
+        assert_eq!(zero.normalized(), SmartScoreWeights::default());
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -383,6 +655,8 @@ pub struct LogQuery {
     pub status: Option<String>,
     #[serde(default)]
     pub account_id: Option<String>,
+    #[serde(default)]
+    pub client_key_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -391,6 +665,61 @@ pub struct LogPage {
     pub items: Vec<RequestLog>,
 }
 
+// ==================== 抓包相关 ====================
+
+/// 抓包记录（id 与对应的 RequestLog 相同，头部与令牌已脱敏）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestCapture {
+    pub id: String,
+    pub timestamp: i64,
+    pub account_id: String,
+    pub account_email: String,
+    pub model: String,
+    pub format: String,
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: Option<String>,
+    pub request_headers: BTreeMap<String, String>,
+    /// 规范化后实际发往上游的请求体
+    pub request_body: String,
+    /// 请求体超过抓包上限被截断（截断的请求无法重放）
+    #[serde(default)]
+    pub request_truncated: bool,
+    pub upstream_status: u16,
+    pub response_headers: BTreeMap<String, String>,
+    /// 上游响应体；流式响应为拼接后的原始 SSE
+    pub response_body: String,
+    #[serde(default)]
+    pub response_truncated: bool,
+}
+
+/// 抓包列表项（不含请求/响应体）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSummary {
+    pub id: String,
+    pub timestamp: i64,
+    pub account_email: String,
+    pub model: String,
+    pub method: String,
+    pub path: String,
+    pub upstream_status: u16,
+    /// 请求体与响应体的总字节数
+    pub size: i64,
+}
+
+/// 重放抓包的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureReplayResult {
+    pub capture_id: String,
+    pub account_id: String,
+    pub account_email: String,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    pub duration_ms: i64,
+}
+
 // ==================== 错误处理 ====================
 
 #[derive(Debug, thiserror::Error)]
@@ -410,8 +739,14 @@ pub enum CodexError {
     #[error("Execution error: {0}")]
     ExecutionError(String),
 
+    #[error("Upstream timeout: {0}")]
+    UpstreamTimeout(String),
+
     #[error("Token refresh required for account: {0}")]
     TokenRefreshRequired(String),
+
+    #[error("All accounts are cooling down, retry after {0}s")]
+    PoolCoolingDown(u64),
 }
 
 // Warp Rejection implementation
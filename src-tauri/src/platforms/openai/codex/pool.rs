@@ -1,13 +1,74 @@
 //! Codex 号池管理器
 //!
-//! 管理用于 Codex API 请求的 OAuth 账号池，支持轮询和单个选择策略
+//! 管理用于 Codex API 请求的 OAuth 账号池，支持轮询、单个、智能打分与分层优先级策略，
+//! 预留给指定模型的账号只服务这些模型，
+//! 并在 token 临近过期或上游返回 401 时自动刷新（同一账号同时只刷新一次）。
+//! 轮询与智能策略下按会话粘性把同一对话固定到同一账号，账号冷却时再按策略重新选号。
+//! 全部账号冷却时可按先进先出排队，等到最早的冷却结束再转发。
+//! 上游响应头中的 5h/7d 用量即时更新到号池账号，并攒批交给上层写回 QuotaData。
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, RwLock};
 use rand::Rng;
 
-use super::models::{CodexPoolAccount, PoolStatus, PoolStrategy};
+use super::models::{
+    CodexPoolAccount, CodexUsageSnapshot, PoolStatus, PoolStrategy, SmartScoreWeights,
+};
+
+/// token 剩余有效期不足该秒数时，在转发前提前刷新
+pub const TOKEN_REFRESH_WINDOW_SECS: i64 = 5 * 60;
+
+/// 会话粘性默认保持时长（秒），每次成功请求后续期
+pub const DEFAULT_SESSION_AFFINITY_TTL_SECS: i64 = 60 * 60;
+
+/// 响应头用量写回存储前的防抖时长（秒）
+pub const USAGE_PERSIST_DEBOUNCE_SECS: u64 = 30;
+
+/// 冷却时长上限（秒），上游给出的重置时间超过该值时按上限处理
+const MAX_COOLDOWN_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// 号池全部冷却时的排队配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitQueueConfig {
+    /// 单个请求最长等待秒数
+    pub max_wait_secs: u64,
+    /// 最多同时排队的请求数
+    pub max_depth: usize,
+}
+
+/// 占用的排队名额，释放时计数减一
+struct WaitSlot<'a>(&'a AtomicUsize);
+
+impl Drop for WaitSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 会话绑定的账号
+#[derive(Debug, Clone)]
+struct SessionPin {
+    account_id: String,
+    expires_at: i64,
+}
+
+/// 刷新后的账号凭证
+#[derive(Debug, Clone)]
+pub struct RefreshedToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub id_token: Option<String>,
+    pub expires_at: i64,
+}
+
+/// 号池 token 刷新器，由上层实现 OAuth 刷新与持久化
+#[async_trait::async_trait]
+pub trait TokenRefresher: Send + Sync {
+    async fn refresh(&self, account: &CodexPoolAccount) -> Result<RefreshedToken, String>;
+}
 
 /// Codex 号池管理器
 pub struct CodexPool {
@@ -16,6 +77,16 @@ pub struct CodexPool {
     request_counter: Arc<RwLock<u64>>,
     strategy: Arc<RwLock<PoolStrategy>>,
     selected_account_id: Arc<RwLock<Option<String>>>, // Single 策略时选中的账号 ID
+    smart_weights: Arc<RwLock<SmartScoreWeights>>,
+    token_refresher: Arc<RwLock<Option<Arc<dyn TokenRefresher>>>>,
+    refresh_locks: Arc<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>>, // 每个账号一把刷新锁
+    session_pins: Arc<std::sync::Mutex<HashMap<String, SessionPin>>>, // 会话 key → 账号
+    session_ttl_secs: Arc<RwLock<i64>>,                               // 0 表示关闭会话粘性
+    wait_queue: Arc<RwLock<Option<WaitQueueConfig>>>,                 // None 表示不排队
+    wait_turnstile: Arc<Mutex<()>>, // 排队请求按先进先出依次等待（tokio Mutex 是公平锁）
+    waiting: Arc<AtomicUsize>,
+    pending_usage: Arc<std::sync::Mutex<HashMap<String, CodexUsageSnapshot>>>, // 待写回的响应头用量
+    usage_notify: Arc<Notify>,
 }
 
 impl CodexPool {
@@ -27,6 +98,16 @@ impl CodexPool {
             request_counter: Arc::new(RwLock::new(0)),
             strategy: Arc::new(RwLock::new(PoolStrategy::RoundRobin)),
             selected_account_id: Arc::new(RwLock::new(None)),
+            smart_weights: Arc::new(RwLock::new(SmartScoreWeights::default())),
+            token_refresher: Arc::new(RwLock::new(None)),
+            refresh_locks: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            session_pins: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            session_ttl_secs: Arc::new(RwLock::new(DEFAULT_SESSION_AFFINITY_TTL_SECS)),
+            wait_queue: Arc::new(RwLock::new(None)),
+            wait_turnstile: Arc::new(Mutex::new(())),
+            waiting: Arc::new(AtomicUsize::new(0)),
+            pending_usage: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            usage_notify: Arc::new(Notify::new()),
         }
     }
 
@@ -82,24 +163,153 @@ impl CodexPool {
         self.selected_account_id.read().await.clone()
     }
 
-    /// 获取下一个可用账号
-    pub async fn next_account(&self) -> Option<CodexPoolAccount> {
-        let pool = self.accounts.read().await;
+    /// 设置 Smart 策略打分权重
+    pub async fn set_smart_weights(&self, weights: SmartScoreWeights) {
+        *self.smart_weights.write().await = weights.normalized();
+    }
+
+    /// 设置 token 刷新器
+    pub async fn set_token_refresher(&self, refresher: Arc<dyn TokenRefresher>) {
+        *self.token_refresher.write().await = Some(refresher);
+    }
+
+    /// 是否配置了 token 刷新器
+    pub async fn can_refresh_tokens(&self) -> bool {
+        self.token_refresher.read().await.is_some()
+    }
+
+    /// 设置会话粘性保持时长（秒），小于等于 0 时关闭并清空已有绑定
+    pub async fn set_session_affinity_ttl(&self, ttl_secs: i64) {
+        *self.session_ttl_secs.write().await = ttl_secs;
+        if ttl_secs <= 0 {
+            self.session_pins.lock().unwrap().clear();
+        }
+    }
+
+    /// 将会话绑定到账号，已有绑定时改绑并续期
+    pub async fn pin_session(&self, session_key: &str, account_id: &str) {
+        let ttl = *self.session_ttl_secs.read().await;
+        if ttl <= 0 {
+            return;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let mut pins = self.session_pins.lock().unwrap();
+        pins.retain(|_, pin| pin.expires_at > now);
+        pins.insert(
+            session_key.to_string(),
+            SessionPin {
+                account_id: account_id.to_string(),
+                expires_at: now + ttl,
+            },
+        );
+    }
+
+    /// 会话当前绑定的账号 ID
+    pub fn pinned_account_id(&self, session_key: &str) -> Option<String> {
+        let now = chrono::Utc::now().timestamp();
+        self.session_pins
+            .lock()
+            .unwrap()
+            .get(session_key)
+            .filter(|pin| pin.expires_at > now)
+            .map(|pin| pin.account_id.clone())
+    }
+
+    /// 获取下一个可用账号。
+    /// 传入会话 key 且该会话已绑定可用账号时直接返回该账号（Single 策略除外）。
+    pub async fn next_account(&self, session_key: Option<&str>) -> Option<CodexPoolAccount> {
+        self.next_account_for_model(session_key, None).await
+    }
+
+    /// 同 next_account，只在能服务该模型的账号中选择（跳过预留给其他模型的账号）
+    pub async fn next_account_for_model(
+        &self,
+        session_key: Option<&str>,
+        model: Option<&str>,
+    ) -> Option<CodexPoolAccount> {
+        let accounts = self.accounts.read().await;
+        let reserved: Vec<CodexPoolAccount>;
+        let pool: &[CodexPoolAccount] = if accounts.iter().any(|a| !a.reserved_models.is_empty()) {
+            reserved = accounts
+                .iter()
+                .filter(|a| a.serves_model(model))
+                .cloned()
+                .collect();
+            &reserved
+        } else {
+            &accounts
+        };
         if pool.is_empty() {
             return None;
         }
 
         let strategy = *self.strategy.read().await;
+        let can_refresh = self.can_refresh_tokens().await;
+
+        let pinned = match strategy {
+            PoolStrategy::Single => None,
+            _ => self.select_pinned(pool, session_key, can_refresh).await,
+        };
+        if let Some(account) = pinned {
+            let mut counter = self.request_counter.write().await;
+            *counter += 1;
+            return Some(account);
+        }
 
         match strategy {
-            PoolStrategy::RoundRobin => self.select_round_robin(&pool).await,
-            PoolStrategy::Single => self.select_single(&pool).await,
-            PoolStrategy::Smart => self.select_smart(&pool).await,
+            PoolStrategy::RoundRobin => self.select_round_robin(pool, can_refresh).await,
+            PoolStrategy::Single => self.select_single(pool, can_refresh).await,
+            PoolStrategy::Smart => self.select_smart(pool, can_refresh).await,
+            PoolStrategy::Priority => self.select_priority(pool, can_refresh).await,
+        }
+    }
+
+    /// 会话粘性选择：绑定的账号不可用时返回 None，交给策略重新选号
+    async fn select_pinned(
+        &self,
+        pool: &[CodexPoolAccount],
+        session_key: Option<&str>,
+        can_refresh: bool,
+    ) -> Option<CodexPoolAccount> {
+        let session_key = session_key?;
+        if *self.session_ttl_secs.read().await <= 0 {
+            return None;
+        }
+        let account_id = self.pinned_account_id(session_key)?;
+
+        match pool.iter().find(|a| a.id == account_id) {
+            Some(account) if account.is_selectable(can_refresh) => {
+                println!(
+                    "[Codex] Session affinity honoured: {} -> {}",
+                    session_key, account.email
+                );
+                Some(account.clone())
+            }
+            Some(account) => {
+                println!(
+                    "[Codex] Session affinity broken: {} -> {} is unavailable ({}), reselecting",
+                    session_key,
+                    account.email,
+                    account.unavailable_reason.as_deref().unwrap_or("inactive")
+                );
+                None
+            }
+            None => {
+                println!(
+                    "[Codex] Session affinity broken: {} -> account {} left the pool, reselecting",
+                    session_key, account_id
+                );
+                None
+            }
         }
     }
 
     /// 轮询选择
-    async fn select_round_robin(&self, pool: &[CodexPoolAccount]) -> Option<CodexPoolAccount> {
+    async fn select_round_robin(
+        &self,
+        pool: &[CodexPoolAccount],
+        can_refresh: bool,
+    ) -> Option<CodexPoolAccount> {
         let mut index = self.current_index.write().await;
         let len = pool.len();
 
@@ -112,7 +322,7 @@ impl CodexPool {
             let acc = &pool[*index % len];
             *index = (*index + 1) % len;
             tried += 1;
-            if acc.is_available() {
+            if acc.is_selectable(can_refresh) {
                 break acc;
             }
         };
@@ -125,15 +335,23 @@ impl CodexPool {
     }
 
     /// 单个账号选择
-    async fn select_single(&self, pool: &[CodexPoolAccount]) -> Option<CodexPoolAccount> {
+    async fn select_single(
+        &self,
+        pool: &[CodexPoolAccount],
+        can_refresh: bool,
+    ) -> Option<CodexPoolAccount> {
         let selected_id = self.selected_account_id.read().await.clone();
 
         let account = selected_id
             .as_ref()
-            .and_then(|id| pool.iter().find(|a| a.id == *id && a.is_available()).cloned())
+            .and_then(|id| {
+                pool.iter()
+                    .find(|a| a.id == *id && a.is_selectable(can_refresh))
+                    .cloned()
+            })
             .or_else(|| {
                 // 选中账号不可用时，回退到第一个可用账号，避免服务被无声卡死。
-                pool.iter().find(|a| a.is_available()).cloned()
+                pool.iter().find(|a| a.is_selectable(can_refresh)).cloned()
             });
 
         if account.is_some() {
@@ -146,8 +364,15 @@ impl CodexPool {
     }
 
     /// 智能选号：加权随机选择，分数越高被选中概率越大
-    async fn select_smart(&self, pool: &[CodexPoolAccount]) -> Option<CodexPoolAccount> {
-        let available: Vec<&CodexPoolAccount> = pool.iter().filter(|a| a.is_available()).collect();
+    async fn select_smart(
+        &self,
+        pool: &[CodexPoolAccount],
+        can_refresh: bool,
+    ) -> Option<CodexPoolAccount> {
+        let available: Vec<&CodexPoolAccount> = pool
+            .iter()
+            .filter(|a| a.is_selectable(can_refresh))
+            .collect();
 
         if available.is_empty() {
             return None;
@@ -161,26 +386,12 @@ impl CodexPool {
         }
 
         // 计算每个账号的分数，下限 1.0 确保所有账号都有被选中的概率
+        let score_weights = *self.smart_weights.read().await;
         let weights: Vec<f64> = available
             .iter()
-            .map(|a| a.compute_score().max(1.0))
+            .map(|a| a.compute_score(&score_weights).max(1.0))
             .collect();
-        let total_weight: f64 = weights.iter().sum();
-
-        // 加权随机选择（rng 必须在 await 之前 drop，因为 ThreadRng 不是 Send）
-        let pick = {
-            let mut rng = rand::thread_rng();
-            let mut roll = rng.gen_range(0.0..total_weight);
-            let mut idx = available.len() - 1;
-            for (i, w) in weights.iter().enumerate() {
-                roll -= w;
-                if roll <= 0.0 {
-                    idx = i;
-                    break;
-                }
-            }
-            idx
-        };
+        let pick = weighted_pick(&weights);
 
         let mut counter = self.request_counter.write().await;
         *counter += 1;
@@ -188,6 +399,101 @@ impl CodexPool {
         Some(available[pick].clone())
     }
 
+    /// 分层选号：只在可用账号中优先级数值最小的一层里选，层内按账号权重（含计划系数）随机
+    async fn select_priority(
+        &self,
+        pool: &[CodexPoolAccount],
+        can_refresh: bool,
+    ) -> Option<CodexPoolAccount> {
+        let top_tier = pool
+            .iter()
+            .filter(|a| a.is_selectable(can_refresh))
+            .map(|a| a.priority)
+            .min()?;
+        let tier: Vec<&CodexPoolAccount> = pool
+            .iter()
+            .filter(|a| a.priority == top_tier && a.is_selectable(can_refresh))
+            .collect();
+
+        // 权重为 0 的账号仅在层内没有其他账号时使用
+        let weights: Vec<f64> = tier.iter().map(|a| a.effective_weight()).collect();
+        let pick = if weights.iter().sum::<f64>() > 0.0 {
+            weighted_pick(&weights)
+        } else {
+            0
+        };
+
+        let mut counter = self.request_counter.write().await;
+        *counter += 1;
+
+        Some(tier[pick].clone())
+    }
+
+    /// 确保账号 token 可用：临近过期（或 force 时无条件）通过刷新器刷新并写回号池。
+    /// 同一账号的并发刷新会排队，等到锁后若发现已被其他请求刷新则直接复用。
+    pub async fn ensure_fresh_token(
+        &self,
+        account: CodexPoolAccount,
+        force: bool,
+    ) -> Result<CodexPoolAccount, String> {
+        if !force && !account.needs_token_refresh(TOKEN_REFRESH_WINDOW_SECS) {
+            return Ok(account);
+        }
+        let Some(refresher) = self.token_refresher.read().await.clone() else {
+            if force {
+                return Err("Token refresher not configured".to_string());
+            }
+            return Ok(account);
+        };
+        if account.refresh_token.is_none() {
+            return Err("No refresh token available".to_string());
+        }
+
+        let lock = {
+            let mut locks = self.refresh_locks.lock().unwrap();
+            locks.entry(account.id.clone()).or_default().clone()
+        };
+        let _guard = lock.lock().await;
+
+        let current = self
+            .get_account(&account.id)
+            .await
+            .ok_or_else(|| format!("Account not in pool: {}", account.id))?;
+        let refreshed_by_other = current.access_token != account.access_token
+            && !current.needs_token_refresh(TOKEN_REFRESH_WINDOW_SECS);
+        if refreshed_by_other {
+            return Ok(current);
+        }
+        if !force && !current.needs_token_refresh(TOKEN_REFRESH_WINDOW_SECS) {
+            return Ok(current);
+        }
+
+        let token = refresher.refresh(&current).await?;
+        let now = chrono::Utc::now().timestamp();
+
+        let mut pool = self.accounts.write().await;
+        let entry = pool
+            .iter_mut()
+            .find(|a| a.id == account.id)
+            .ok_or_else(|| format!("Account not in pool: {}", account.id))?;
+        entry.access_token = token.access_token;
+        if token.refresh_token.is_some() {
+            entry.refresh_token = token.refresh_token;
+        }
+        if token.id_token.is_some() {
+            entry.id_token = token.id_token;
+        }
+        entry.expires_at = token.expires_at;
+        entry.is_active = true;
+        entry.last_refresh = Some(now);
+        if entry.unavailable_reason.as_deref() == Some("unauthorized") {
+            entry.unavailable_reason = None;
+            entry.cooldown_until = None;
+        }
+        println!("[Codex] Refreshed access token for {}", entry.email);
+        Ok(entry.clone())
+    }
+
     /// 记录 token 使用
     pub async fn record_usage(&self, account_id: &str, tokens: i64) {
         let mut pool = self.accounts.write().await;
@@ -196,12 +502,51 @@ impl CodexPool {
         }
     }
 
+    /// 记录上游响应头中的用量：立即更新账号的 5h/7d 百分比，并加入待写回批次
+    pub async fn record_rate_limits(&self, account_id: &str, usage: CodexUsageSnapshot) {
+        {
+            let mut pool = self.accounts.write().await;
+            let Some(account) = pool.iter_mut().find(|a| a.id == account_id) else {
+                return;
+            };
+            usage.apply_to_account(account);
+        }
+        self.pending_usage
+            .lock()
+            .unwrap()
+            .entry(account_id.to_string())
+            .and_modify(|pending| pending.merge(usage.clone()))
+            .or_insert(usage);
+        self.usage_notify.notify_one();
+    }
+
+    /// 等待新的用量记录，再等 debounce 合并后续更新，取出待写回的批次
+    pub async fn next_usage_batch(
+        &self,
+        debounce: Duration,
+    ) -> HashMap<String, CodexUsageSnapshot> {
+        self.usage_notify.notified().await;
+        tokio::time::sleep(debounce).await;
+        std::mem::take(&mut *self.pending_usage.lock().unwrap())
+    }
+
     /// 记录账号请求失败并进入冷却
     /// 返回 Some(account_id) 如果账号被标记为 forbidden
     pub async fn record_failure(
         &self,
         account_id: &str,
         status_code: Option<u16>,
+    ) -> Option<String> {
+        self.record_failure_with_retry_after(account_id, status_code, None)
+            .await
+    }
+
+    /// 同 record_failure；429 时优先使用上游给出的重置秒数作为冷却时长
+    pub async fn record_failure_with_retry_after(
+        &self,
+        account_id: &str,
+        status_code: Option<u16>,
+        retry_after_secs: Option<i64>,
     ) -> Option<String> {
         let mut pool = self.accounts.write().await;
         let Some(account) = pool.iter_mut().find(|a| a.id == account_id) else {
@@ -224,8 +569,11 @@ impl CodexPool {
                 Some(account_id.to_string())
             }
             Some(429) => {
+                let cooldown = retry_after_secs
+                    .map(|secs| secs.clamp(1, MAX_COOLDOWN_SECS))
+                    .unwrap_or(5 * 60);
                 account.unavailable_reason = Some("quota".to_string());
-                account.cooldown_until = Some(now + 5 * 60);
+                account.cooldown_until = Some(now + cooldown);
                 None
             }
             _ => {
@@ -288,10 +636,20 @@ impl CodexPool {
         }
     }
 
-    /// 获取有效账号数量
+    /// 获取有效账号数量（配置了刷新器时包含可刷新的过期账号）
     pub async fn active_count(&self) -> usize {
+        let can_refresh = self.can_refresh_tokens().await;
         let pool = self.accounts.read().await;
-        pool.iter().filter(|a| a.is_available()).count()
+        pool.iter().filter(|a| a.is_selectable(can_refresh)).count()
+    }
+
+    /// 能服务该模型的有效账号数量，与 next_account_for_model 的候选范围一致
+    pub async fn active_count_for_model(&self, model: Option<&str>) -> usize {
+        let can_refresh = self.can_refresh_tokens().await;
+        let pool = self.accounts.read().await;
+        pool.iter()
+            .filter(|a| a.serves_model(model) && a.is_selectable(can_refresh))
+            .count()
     }
 
     /// 检查是否有可用账号
@@ -299,6 +657,71 @@ impl CodexPool {
         self.active_count().await > 0
     }
 
+    /// 设置全部冷却时的排队配置，None 表示不排队
+    pub async fn set_wait_queue(&self, config: Option<WaitQueueConfig>) {
+        *self.wait_queue.write().await = config;
+    }
+
+    /// 冷却结束后即可重新服务该模型的账号中，最早恢复的时间戳
+    pub async fn earliest_cooldown_end(&self, model: Option<&str>) -> Option<i64> {
+        let can_refresh = self.can_refresh_tokens().await;
+        let pool = self.accounts.read().await;
+        pool.iter()
+            .filter(|a| a.serves_model(model))
+            .filter(|a| a.is_in_cooldown() && a.is_active && !a.is_forbidden)
+            .filter(|a| !a.is_expired() || (can_refresh && a.refresh_token.is_some()))
+            .filter_map(|a| a.cooldown_until)
+            .min()
+    }
+
+    /// 号池没有能服务该模型的可用账号时排队等待其中最早的冷却结束。
+    /// 放弃时返回建议客户端重试的秒数；没有会恢复的账号时返回 Err(None)。
+    pub async fn wait_for_available_account(&self, model: Option<&str>) -> Result<(), Option<u64>> {
+        let retry_after = |until: i64| (until - chrono::Utc::now().timestamp()).max(1) as u64;
+        let Some(until) = self.earliest_cooldown_end(model).await else {
+            return Err(None);
+        };
+        let Some(config) = *self.wait_queue.read().await else {
+            return Err(Some(retry_after(until)));
+        };
+        // 冷却在最长等待时间内不会结束，不必排队
+        if retry_after(until) > config.max_wait_secs {
+            return Err(Some(retry_after(until)));
+        }
+
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= config.max_depth {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            println!("[Codex] Wait queue is full ({}), rejecting request", config.max_depth);
+            return Err(Some(retry_after(until)));
+        }
+        // 请求被取消（如客户端断开）时也要归还排队名额
+        let _slot = WaitSlot(&self.waiting);
+
+        let waited = tokio::time::timeout(Duration::from_secs(config.max_wait_secs), async {
+            // 只有队首请求轮询号池，其余请求在公平锁上按到达顺序排队
+            let _turn = self.wait_turnstile.lock().await;
+            while self.active_count_for_model(model).await == 0 {
+                let now_ms = chrono::Utc::now().timestamp_millis();
+                let wake_ms = match self.earliest_cooldown_end(model).await {
+                    // cooldown_until 为秒级时间戳，过了该秒才算结束
+                    Some(until) => ((until + 1) * 1000 - now_ms).clamp(50, 1000),
+                    None => 1000,
+                };
+                tokio::time::sleep(Duration::from_millis(wake_ms as u64)).await;
+            }
+        })
+        .await;
+
+        match waited {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self
+                .earliest_cooldown_end(model)
+                .await
+                .map(retry_after)
+                .or(Some(1))),
+        }
+    }
+
     /// 清理过期账号
     pub async fn cleanup_expired(&self) -> usize {
         let mut pool = self.accounts.write().await;
@@ -315,6 +738,7 @@ impl CodexPool {
         let mut pool = self.accounts.write().await;
         let existing: HashMap<String, CodexPoolAccount> =
             pool.iter().cloned().map(|a| (a.id.clone(), a)).collect();
+        let pending_usage = self.pending_usage.lock().unwrap().clone();
         *pool = accounts
             .iter()
             .filter_map(|acc| {
@@ -352,12 +776,30 @@ impl CodexPool {
                         next.last_used = prev.last_used;
                     }
                 }
+                // 尚未写回存储的响应头用量比存储中的更新
+                if let Some(usage) = pending_usage.get(&next.id) {
+                    usage.apply_to_account(&mut next);
+                }
                 Some(next)
             })
             .collect();
     }
 }
 
+/// 按权重随机返回下标（rng 必须在 await 之前 drop，因为 ThreadRng 不是 Send）
+fn weighted_pick(weights: &[f64]) -> usize {
+    let total_weight: f64 = weights.iter().sum();
+    let mut rng = rand::thread_rng();
+    let mut roll = rng.gen_range(0.0..total_weight);
+    for (i, w) in weights.iter().enumerate() {
+        roll -= w;
+        if *w > 0.0 && roll <= 0.0 {
+            return i;
+        }
+    }
+    weights.iter().rposition(|w| *w > 0.0).unwrap_or(0)
+}
+
 impl Default for CodexPool {
     fn default() -> Self {
         Self::new()
@@ -366,8 +808,12 @@ impl Default for CodexPool {
 
 #[cfg(test)]
 mod tests {
-    use super::{CodexPool, PoolStrategy};
-    use crate::platforms::openai::codex::models::CodexPoolAccount;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{CodexPool, PoolStrategy, TOKEN_REFRESH_WINDOW_SECS, WaitQueueConfig};
+    use crate::platforms::openai::codex::models::{CodexPoolAccount, CodexUsageSnapshot};
+    use crate::platforms::openai::codex::test_support::MockRefresher;
 
     fn sample_pool_account(id: &str) -> CodexPoolAccount {
         let now = chrono::Utc::now().timestamp();
@@ -395,6 +841,9 @@ mod tests {
             codex_7d_used_percent: None,
             plan_type: None,
             subscription_expires_at: None,
+            priority: 0,
+            weight: 1,
+            reserved_models: Vec::new(),
             tag: None,
             tag_color: None,
         }
@@ -408,9 +857,273 @@ mod tests {
         pool.set_strategy(PoolStrategy::Single).await;
         pool.set_selected_account_id("missing-in-pool".to_string()).await;
 
-        let selected = pool.next_account().await.unwrap();
+        let selected = pool.next_account(None).await.unwrap();
         assert_eq!(selected.id, "primary");
     }
+
+    #[tokio::test]
+    async fn priority_strategy_uses_top_tier_and_honours_model_reservations() {
+        let pool = CodexPool::new();
+        let mut overflow = sample_pool_account("overflow");
+        overflow.priority = 10;
+        let mut light = sample_pool_account("light");
+        light.weight = 0;
+        let mut reserved = sample_pool_account("reserved");
+        reserved.priority = -1;
+        reserved.reserved_models = vec!["gpt-5-codex*".to_string()];
+        for account in [overflow, light, sample_pool_account("heavy"), reserved] {
+            pool.add_account(account).await;
+        }
+        pool.set_strategy(PoolStrategy::Priority).await;
+
+        // 权重为 0 的账号不会被选中，溢出层与预留账号也不参与其他模型
+        for _ in 0..20 {
+            let selected = pool.next_account_for_model(None, Some("gpt-5")).await;
+            assert_eq!(selected.unwrap().id, "heavy");
+        }
+        assert_eq!(
+            pool.next_account_for_model(None, Some("GPT-5-Codex-Mini"))
+                .await
+                .unwrap()
+                .id,
+            "reserved"
+        );
+
+        // 主力层全部冷却后才使用溢出层
+        let cooldown = chrono::Utc::now().timestamp() + 60;
+        for id in ["heavy", "light"] {
+            let mut account = pool.get_account(id).await.unwrap();
+            account.cooldown_until = Some(cooldown);
+            pool.update_account(&account).await;
+        }
+        assert_eq!(pool.next_account(None).await.unwrap().id, "overflow");
+    }
+
+    #[tokio::test]
+    async fn rate_limit_usage_updates_accounts_and_merges_pending_batch() {
+        let pool = CodexPool::new();
+        pool.add_account(sample_pool_account("a")).await;
+
+        pool.record_rate_limits(
+            "a",
+            CodexUsageSnapshot {
+                primary_used_percent: Some(10.0),
+                secondary_used_percent: Some(5.0),
+                secondary_reset_at: Some(2000),
+                observed_at: 100,
+                ..Default::default()
+            },
+        )
+        .await;
+        pool.record_rate_limits(
+            "a",
+            CodexUsageSnapshot {
+                primary_used_percent: Some(20.0),
+                observed_at: 110,
+                ..Default::default()
+            },
+        )
+        .await;
+        // 不在号池中的账号不记录
+        pool.record_rate_limits(
+            "missing",
+            CodexUsageSnapshot {
+                primary_used_percent: Some(99.0),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let account = pool.get_account("a").await.unwrap();
+        assert_eq!(account.codex_5h_used_percent, Some(20.0));
+        assert_eq!(account.codex_7d_used_percent, Some(5.0));
+
+        let batch = pool.next_usage_batch(Duration::from_millis(1)).await;
+        assert_eq!(batch.len(), 1);
+        let usage = &batch["a"];
+        assert_eq!(usage.primary_used_percent, Some(20.0));
+        assert_eq!(usage.secondary_used_percent, Some(5.0));
+        assert_eq!(usage.secondary_reset_at, Some(2000));
+        assert_eq!(usage.observed_at, 110);
+    }
+
+    #[tokio::test]
+    async fn wait_queue_is_bounded_and_first_in_first_out() {
+        // 没有会恢复的账号时不排队
+        assert_eq!(CodexPool::new().wait_for_available_account(None).await, Err(None));
+
+        let pool = Arc::new(CodexPool::new());
+        let mut account = sample_pool_account("a");
+        account.cooldown_until = Some(chrono::Utc::now().timestamp() + 1);
+        pool.add_account(account).await;
+        pool.set_wait_queue(Some(WaitQueueConfig {
+            max_wait_secs: 5,
+            max_depth: 2,
+        }))
+        .await;
+
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        for i in 0..2 {
+            let pool = pool.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                pool.wait_for_available_account(None).await.unwrap();
+                order.lock().unwrap().push(i);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // 队列已满，第三个请求立即放弃并给出重试时间
+        assert!(matches!(
+            pool.wait_for_available_account(None).await,
+            Err(Some(secs)) if secs <= 2
+        ));
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![0, 1]);
+        assert_eq!(pool.active_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn reserved_model_waits_for_its_own_accounts() {
+        let pool = CodexPool::new();
+        pool.add_account(sample_pool_account("general")).await;
+        let mut reserved = sample_pool_account("reserved");
+        reserved.reserved_models = vec!["gpt-5-codex*".to_string()];
+        reserved.cooldown_until = Some(chrono::Utc::now().timestamp() + 1);
+        pool.add_account(reserved).await;
+        pool.set_wait_queue(Some(WaitQueueConfig {
+            max_wait_secs: 5,
+            max_depth: 2,
+        }))
+        .await;
+
+        // 其他账号可用不代表该模型有账号可用
+        assert_eq!(pool.active_count().await, 1);
+        assert_eq!(pool.active_count_for_model(Some("gpt-5")).await, 1);
+        assert_eq!(pool.active_count_for_model(Some("gpt-5-codex")).await, 0);
+
+        pool.wait_for_available_account(Some("gpt-5-codex"))
+            .await
+            .unwrap();
+        assert_eq!(
+            pool.next_account_for_model(None, Some("gpt-5-codex"))
+                .await
+                .unwrap()
+                .id,
+            "reserved"
+        );
+    }
+
+    #[tokio::test]
+    async fn session_affinity_pins_until_account_cools_down() {
+        let pool = CodexPool::new();
+        for id in ["a", "b", "c"] {
+            pool.add_account(sample_pool_account(id)).await;
+        }
+
+        // 轮询会依次选到 a、b；绑定到 b 后同一会话始终选 b
+        pool.pin_session("session:1", "b").await;
+        for _ in 0..4 {
+            let selected = pool.next_account(Some("session:1")).await.unwrap();
+            assert_eq!(selected.id, "b");
+        }
+        // 其他会话仍按轮询
+        assert_eq!(pool.next_account(Some("session:2")).await.unwrap().id, "a");
+        assert_eq!(pool.next_account(None).await.unwrap().id, "b");
+
+        // 绑定账号冷却后回退到正常选号
+        pool.record_failure("b", Some(429)).await;
+        let selected = pool.next_account(Some("session:1")).await.unwrap();
+        assert_ne!(selected.id, "b");
+        assert_eq!(pool.pinned_account_id("session:1").as_deref(), Some("b"));
+
+        // Single 策略不受会话粘性影响
+        pool.record_success("b").await;
+        pool.set_strategy(PoolStrategy::Single).await;
+        pool.set_selected_account_id("c".to_string()).await;
+        assert_eq!(pool.next_account(Some("session:1")).await.unwrap().id, "c");
+
+        // 关闭后清空绑定
+        pool.set_session_affinity_ttl(0).await;
+        pool.pin_session("session:1", "b").await;
+        assert!(pool.pinned_account_id("session:1").is_none());
+    }
+
+    #[tokio::test]
+    async fn record_failure_sets_cooldown_by_status() {
+        let pool = CodexPool::new();
+        for id in ["unauthorized", "forbidden", "quota", "server"] {
+            pool.add_account(sample_pool_account(id)).await;
+        }
+        let now = chrono::Utc::now().timestamp();
+
+        assert_eq!(pool.record_failure("unauthorized", Some(401)).await, None);
+        assert_eq!(
+            pool.record_failure("forbidden", Some(403)).await,
+            Some("forbidden".to_string())
+        );
+        assert_eq!(pool.record_failure("quota", Some(429)).await, None);
+        assert_eq!(pool.record_failure("server", Some(502)).await, None);
+
+        let unauthorized = pool.get_account("unauthorized").await.unwrap();
+        assert_eq!(unauthorized.unavailable_reason.as_deref(), Some("unauthorized"));
+        assert!(unauthorized.cooldown_until.unwrap() >= now + 30 * 60);
+        assert!(!unauthorized.is_available());
+
+        let forbidden = pool.get_account("forbidden").await.unwrap();
+        assert!(forbidden.is_forbidden);
+        assert!(!forbidden.is_available());
+
+        let quota = pool.get_account("quota").await.unwrap();
+        assert_eq!(quota.unavailable_reason.as_deref(), Some("quota"));
+        assert!(quota.cooldown_until.unwrap() >= now + 5 * 60);
+
+        // 5xx 不冷却账号，只记录状态码
+        let server = pool.get_account("server").await.unwrap();
+        assert_eq!(server.last_error_status, Some(502));
+        assert!(server.is_available());
+
+        assert_eq!(pool.active_count().await, 1);
+
+        pool.record_success("quota").await;
+        let quota = pool.get_account("quota").await.unwrap();
+        assert!(quota.cooldown_until.is_none());
+        assert!(quota.is_available());
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_of_one_account_are_single_flight() {
+        let pool = Arc::new(CodexPool::new());
+        let mut account = sample_pool_account("a");
+        account.expires_at = chrono::Utc::now().timestamp() + TOKEN_REFRESH_WINDOW_SECS / 2;
+        pool.add_account(account.clone()).await;
+        pool.add_account(sample_pool_account("b")).await;
+        let refresher = Arc::new(MockRefresher::default());
+        pool.set_token_refresher(refresher.clone()).await;
+
+        let tasks: Vec<_> = (0..5)
+            .map(|_| {
+                let pool = pool.clone();
+                let account = account.clone();
+                tokio::spawn(async move { pool.ensure_fresh_token(account, false).await })
+            })
+            .collect();
+        for task in tasks {
+            let refreshed = task.await.unwrap().unwrap();
+            assert_eq!(refreshed.access_token, "fresh-a-1");
+        }
+        assert_eq!(refresher.calls(), 1);
+
+        // 未临近过期的账号不刷新
+        let b = pool.get_account("b").await.unwrap();
+        let same = pool.ensure_fresh_token(b, false).await.unwrap();
+        assert_eq!(same.access_token, "access");
+        assert_eq!(refresher.calls(), 1);
+    }
 }
 
 // ==================== Codex Server 状态 ====================
@@ -442,8 +1155,32 @@ pub struct CodexServerConfig {
     pub quota_refresh_interval_seconds: u64,
     #[serde(default)]
     pub fast_mode_enabled: bool,
+    /// 上游地址（scheme + host），默认 https://chatgpt.com
+    #[serde(default = "default_upstream_origin")]
+    pub upstream_origin: String,
+    /// 会话粘性保持时长（秒），0 表示关闭
+    #[serde(default = "default_session_affinity_ttl_seconds")]
+    pub session_affinity_ttl_seconds: u64,
+    /// 是否抓包（记录请求体与上游响应，可在之后重放）
+    #[serde(default)]
+    pub capture_enabled: bool,
+    /// 号池全部冷却时排队等待最早恢复的账号，而不是立即返回错误
+    #[serde(default)]
+    pub wait_queue_enabled: bool,
+    /// 排队请求最长等待秒数
+    #[serde(default = "default_wait_queue_max_wait_seconds")]
+    pub wait_queue_max_wait_seconds: u64,
+    /// 最多同时排队的请求数
+    #[serde(default = "default_wait_queue_max_depth")]
+    pub wait_queue_max_depth: usize,
+    /// Smart 策略各打分项的满分
+    #[serde(default)]
+    pub smart_score_weights: SmartScoreWeights,
 }
 
+/// ChatGPT Codex 上游默认地址
+pub const DEFAULT_UPSTREAM_ORIGIN: &str = "https://chatgpt.com";
+
 fn default_codex_enabled() -> bool {
     true
 }
@@ -456,6 +1193,32 @@ fn default_quota_refresh_interval_seconds() -> u64 {
     30 * 60
 }
 
+fn default_upstream_origin() -> String {
+    DEFAULT_UPSTREAM_ORIGIN.to_string()
+}
+
+fn default_session_affinity_ttl_seconds() -> u64 {
+    DEFAULT_SESSION_AFFINITY_TTL_SECS as u64
+}
+
+fn default_wait_queue_max_wait_seconds() -> u64 {
+    60
+}
+
+fn default_wait_queue_max_depth() -> usize {
+    32
+}
+
+impl CodexServerConfig {
+    /// 号池排队配置，未开启时为 None
+    pub fn wait_queue(&self) -> Option<WaitQueueConfig> {
+        self.wait_queue_enabled.then_some(WaitQueueConfig {
+            max_wait_secs: self.wait_queue_max_wait_seconds,
+            max_depth: self.wait_queue_max_depth,
+        })
+    }
+}
+
 impl Default for CodexServerConfig {
     fn default() -> Self {
         Self {
@@ -467,6 +1230,13 @@ impl Default for CodexServerConfig {
             quota_refresh_enabled: true,
             quota_refresh_interval_seconds: 30 * 60,
             fast_mode_enabled: false,
+            upstream_origin: default_upstream_origin(),
+            session_affinity_ttl_seconds: default_session_affinity_ttl_seconds(),
+            capture_enabled: false,
+            wait_queue_enabled: false,
+            wait_queue_max_wait_seconds: default_wait_queue_max_wait_seconds(),
+            wait_queue_max_depth: default_wait_queue_max_depth(),
+            smart_score_weights: SmartScoreWeights::default(),
         }
     }
 }
@@ -0,0 +1,202 @@
+//! Codex 模型价格表
+//!
+//! 按模型配置每百万 token 的输入、缓存输入与输出价格（美元），用于估算请求按 API 计费的成本。
+//! 价格表持久化到 Codex 配置旁的 JSON 文件，文件不存在时使用内置默认价格。
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+const MODEL_PRICES_FILE: &str = "openai_codex_prices.json";
+
+/// 单个模型的价格（美元 / 百万 token）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// 模型名，以 `*` 结尾表示前缀匹配
+    pub model: String,
+    pub input_per_million: f64,
+    #[serde(default)]
+    pub cached_input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    fn new(model: &str, input: f64, cached_input: f64, output: f64) -> Self {
+        Self {
+            model: model.to_string(),
+            input_per_million: input,
+            cached_input_per_million: cached_input,
+            output_per_million: output,
+        }
+    }
+
+    /// 估算成本：缓存命中的输入按缓存价计算，其余按输入价
+    pub fn cost(&self, input_tokens: i64, cached_tokens: i64, output_tokens: i64) -> f64 {
+        let input_tokens = input_tokens.max(0);
+        let cached_tokens = cached_tokens.clamp(0, input_tokens);
+        let uncached = (input_tokens - cached_tokens) as f64;
+        (uncached * self.input_per_million
+            + cached_tokens as f64 * self.cached_input_per_million
+            + output_tokens.max(0) as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// 内置默认价格（OpenAI API 公开价格）
+pub fn default_model_prices() -> Vec<ModelPrice> {
+    vec![
+        ModelPrice::new("gpt-5*", 1.25, 0.125, 10.0),
+        ModelPrice::new("gpt-5-mini*", 0.25, 0.025, 2.0),
+        ModelPrice::new("gpt-5-nano*", 0.05, 0.005, 0.4),
+        ModelPrice::new("gpt-5.1-codex-mini*", 0.25, 0.025, 2.0),
+        ModelPrice::new("gpt-4.1*", 2.0, 0.5, 8.0),
+        ModelPrice::new("gpt-4o*", 2.5, 1.25, 10.0),
+        ModelPrice::new("o3*", 2.0, 0.5, 8.0),
+        ModelPrice::new("o4-mini*", 1.1, 0.275, 4.4),
+    ]
+}
+
+/// 模型价格表
+pub struct ModelPriceTable {
+    prices: RwLock<Vec<ModelPrice>>,
+    file_path: PathBuf,
+}
+
+impl ModelPriceTable {
+    /// 从 JSON 文件加载，文件不存在或损坏时使用默认价格
+    pub fn load(app_data_dir: &std::path::Path) -> Self {
+        let file_path = app_data_dir.join(MODEL_PRICES_FILE);
+        let prices = std::fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<Vec<ModelPrice>>(&s).ok())
+            .unwrap_or_else(default_model_prices);
+
+        Self {
+            prices: RwLock::new(prices),
+            file_path,
+        }
+    }
+
+    /// 列出所有价格
+    pub fn list(&self) -> Vec<ModelPrice> {
+        self.prices.read().unwrap().clone()
+    }
+
+    /// 替换整张价格表并持久化
+    pub fn save(&self, prices: Vec<ModelPrice>) -> Result<Vec<ModelPrice>, String> {
+        let mut normalized = Vec::with_capacity(prices.len());
+        for mut price in prices {
+            price.model = price.model.trim().to_string();
+            if price.model.is_empty() {
+                return Err("Model name is required".to_string());
+            }
+            let values = [
+                price.input_per_million,
+                price.cached_input_per_million,
+                price.output_per_million,
+            ];
+            if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+                return Err(format!("Invalid price for model {}", price.model));
+            }
+            normalized.push(price);
+        }
+
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&normalized)
+            .map_err(|e| format!("Failed to serialize model prices: {}", e))?;
+        std::fs::write(&self.file_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", MODEL_PRICES_FILE, e))?;
+
+        *self.prices.write().unwrap() = normalized.clone();
+        Ok(normalized)
+    }
+
+    /// 恢复默认价格
+    pub fn reset(&self) -> Result<Vec<ModelPrice>, String> {
+        self.save(default_model_prices())
+    }
+
+    /// 查找模型价格：精确匹配优先，其次取最长的前缀匹配
+    pub fn find(&self, model: &str) -> Option<ModelPrice> {
+        let prices = self.prices.read().unwrap();
+        if let Some(exact) = prices.iter().find(|p| p.model.eq_ignore_ascii_case(model)) {
+            return Some(exact.clone());
+        }
+
+        let model = model.to_ascii_lowercase();
+        prices
+            .iter()
+            .filter_map(|p| {
+                let prefix = p.model.strip_suffix('*')?.to_ascii_lowercase();
+                model.starts_with(&prefix).then_some((prefix.len(), p))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, p)| p.clone())
+    }
+
+    /// 估算一次请求的成本（美元），未配置价格的模型为 0
+    pub fn estimate_cost(
+        &self,
+        model: &str,
+        input_tokens: i64,
+        cached_tokens: i64,
+        output_tokens: i64,
+    ) -> f64 {
+        self.find(model)
+            .map(|p| p.cost(input_tokens, cached_tokens, output_tokens))
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModelPrice, ModelPriceTable, default_model_prices};
+
+    #[test]
+    fn longest_prefix_wins_and_cached_input_is_discounted() {
+        let dir = tempfile::tempdir().unwrap();
+        let table = ModelPriceTable::load(dir.path());
+        assert_eq!(table.list(), default_model_prices());
+
+        assert_eq!(table.find("gpt-5-codex").unwrap().model, "gpt-5*");
+        assert_eq!(table.find("GPT-5-mini-2025").unwrap().model, "gpt-5-mini*");
+        assert!(table.find("claude-3").is_none());
+        assert_eq!(table.estimate_cost("claude-3", 1000, 0, 1000), 0.0);
+
+        // 1M 输入（其中 0.4M 命中缓存）+ 0.1M 输出
+        let cost = table.estimate_cost("gpt-5", 1_000_000, 400_000, 100_000);
+        assert!((cost - (0.6 * 1.25 + 0.4 * 0.125 + 0.1 * 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn saved_prices_persist_and_reject_invalid_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let table = ModelPriceTable::load(dir.path());
+        let custom = vec![ModelPrice {
+            model: " my-model ".to_string(),
+            input_per_million: 1.0,
+            cached_input_per_million: 0.5,
+            output_per_million: 2.0,
+        }];
+        table.save(custom).unwrap();
+
+        let reloaded = ModelPriceTable::load(dir.path());
+        assert_eq!(reloaded.list().len(), 1);
+        assert_eq!(reloaded.find("MY-MODEL").unwrap().output_per_million, 2.0);
+
+        let invalid = vec![ModelPrice {
+            model: "bad".to_string(),
+            input_per_million: -1.0,
+            cached_input_per_million: 0.0,
+            output_per_million: 0.0,
+        }];
+        assert!(reloaded.save(invalid).is_err());
+        assert_eq!(reloaded.list().len(), 1);
+
+        reloaded.reset().unwrap();
+        assert_eq!(ModelPriceTable::load(dir.path()).list(), default_model_prices());
+    }
+}
@@ -1,6 +1,7 @@
 //! Codex API 服务器（透传模式）
 //!
-//! 提供本地网关入口，做最小处理后将请求直接透传到 ChatGPT Codex 上游。
+//! 提供本地网关入口，做最小处理后将请求直接透传到 ChatGPT Codex 上游；
+//! Chat Completions 与 Anthropic Messages 请求经 translator 转换为 Responses 格式后再转发。
 
 use bytes::Bytes;
 use futures::{SinkExt, StreamExt};
@@ -12,17 +13,26 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use warp::http::{HeaderMap, Method, StatusCode};
+use warp::filters::BoxedFilter;
 use warp::path::FullPath;
 use warp::{Filter, Rejection, Reply};
 
 use super::{
+    capture::{CaptureBuffer, CaptureRequest},
+    catalog::models_response,
     executor::{CodexExecutor, ForwardMeta, ForwardRequest},
     logger::RequestLogger,
-    models::{CodexError, RequestLog},
+    metrics::{CODEX_METRICS, METRICS_CONTENT_TYPE},
+    models::{CodexError, RequestCapture, RequestLog},
+    client_keys::{ClientApiKey, budget_period_starts},
     pool::CodexPool,
+    pricing::ModelPriceTable,
     storage::CodexLogStorage,
+    translator::{self, ClientProtocol, SseEventParser, StreamTranslator, TranslatedRequest},
 };
 use crate::AppState;
+use crate::core::api_error::{ApiError, ApiErrorCode};
+use crate::core::api_server::RouteTag;
 use crate::data::storage::common::traits::AccountStorage;
 
 // ==================== 不支持参数缓存 ====================
@@ -99,6 +109,11 @@ impl UnsupportedParamCache {
         }
     }
 
+    /// 已缓存的不支持参数数量
+    pub async fn count(&self) -> usize {
+        self.params.read().await.len()
+    }
+
     /// 从 JSON body 中移除所有已知的不支持参数，返回清理后的 body（None 表示无需修改）
     pub async fn strip_known_params(&self, body: &Bytes) -> Option<Bytes> {
         let set = self.params.read().await;
@@ -141,17 +156,68 @@ struct UsageStats {
     input_tokens: i64,
     output_tokens: i64,
     total_tokens: i64,
+    /// 命中提示缓存的输入 token（包含在 input_tokens 中）
+    cached_tokens: i64,
+    /// 推理 token（包含在 output_tokens 中）
+    reasoning_tokens: i64,
+}
+
+impl UsageStats {
+    /// 解析 usage 对象，兼容 Responses 与 Chat Completions 两种字段命名
+    fn from_usage_value(usage: &Value) -> Self {
+        let input_tokens = to_i64(
+            usage
+                .get("input_tokens")
+                .or_else(|| usage.get("prompt_tokens")),
+        );
+        let output_tokens = to_i64(
+            usage
+                .get("output_tokens")
+                .or_else(|| usage.get("completion_tokens")),
+        );
+        let total_tokens = {
+            let explicit_total = to_i64(usage.get("total_tokens"));
+            if explicit_total > 0 {
+                explicit_total
+            } else {
+                input_tokens + output_tokens
+            }
+        };
+        let cached_tokens = to_i64(
+            usage
+                .pointer("/input_tokens_details/cached_tokens")
+                .or_else(|| usage.pointer("/prompt_tokens_details/cached_tokens")),
+        );
+        let reasoning_tokens = to_i64(
+            usage
+                .pointer("/output_tokens_details/reasoning_tokens")
+                .or_else(|| usage.pointer("/completion_tokens_details/reasoning_tokens")),
+        );
+
+        Self {
+            input_tokens,
+            output_tokens,
+            total_tokens,
+            cached_tokens,
+            reasoning_tokens,
+        }
+    }
 }
 
+/// 统一透传入口的路由标记（实际可用路径见 [`is_supported_proxy_path`]）
+pub(crate) const PASSTHROUGH_ROUTE: &str = "* /v1/*, /backend-api/codex/*";
+
 /// Codex API 路由
-pub fn codex_routes_from_state(
-    state: Arc<AppState>,
+///
+/// `tag` 的含义同 [`crate::core::api_server::api_routes`]。
+pub fn codex_routes(
+    state_filter: BoxedFilter<(Arc<AppState>,)>,
+    tag: RouteTag,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-    let state_filter = warp::any().map(move || state.clone());
-
     // 健康检查
     let health = warp::path!("health")
         .and(warp::get())
+        .and(tag("get /health"))
         .and(state_filter.clone())
         .map(|state: Arc<AppState>| {
             warp::reply::json(&serde_json::json!({
@@ -164,15 +230,14 @@ pub fn codex_routes_from_state(
     // GET /v1/models
     let models = warp::path!("v1" / "models")
         .and(warp::get())
+        .and(tag("get /v1/models"))
         .and(state_filter.clone())
-        .and_then(|state: Arc<AppState>| async move {
-            ensure_codex_enabled(&state)?;
-            Result::<_, Rejection>::Ok(warp::reply::json(&get_models()))
-        });
+        .and_then(handle_models);
 
     // GET /pool/status
     let pool_status = warp::path!("pool" / "status")
         .and(warp::get())
+        .and(tag("get /pool/status"))
         .and(state_filter.clone())
         .and_then(|state: Arc<AppState>| async move {
             ensure_codex_enabled(&state)?;
@@ -180,8 +245,16 @@ pub fn codex_routes_from_state(
             Result::<_, Rejection>::Ok(warp::reply::json(&pool.status().await))
         });
 
+    // GET /metrics（Prometheus 文本格式）
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .and(tag("get /metrics"))
+        .and(warp::header::headers_cloned())
+        .and(state_filter.clone())
+        .and_then(handle_metrics);
+
     // 统一透传入口：仅处理 /v1/* 与 /backend-api/codex/*
-    let passthrough = warp::any()
+    let passthrough = tag(PASSTHROUGH_ROUTE)
         .and(warp::path::full())
         .and(warp::method())
         .and(optional_raw_query())
@@ -191,7 +264,36 @@ pub fn codex_routes_from_state(
         .and(state_filter)
         .and_then(handle_passthrough);
 
-    health.or(models).or(pool_status).or(passthrough)
+    health
+        .or(models)
+        .or(pool_status)
+        .or(metrics)
+        .or(passthrough)
+}
+
+/// 导出 Prometheus 指标
+///
+/// 鉴权与 Codex 转发路由相同：管理员 Key 或任一有效的客户端 Key 均可访问，
+/// 两者都未配置时拒绝。指标包含账号邮箱与全局用量，只应把 Key 发给可信的监控方。
+async fn handle_metrics(
+    headers: HeaderMap,
+    state: Arc<AppState>,
+) -> Result<Box<dyn Reply>, Rejection> {
+    authenticate_client(&state, &headers).await?;
+
+    let pool = state.codex_pool.lock().unwrap().clone();
+    let accounts = match pool {
+        Some(pool) => pool.get_accounts().await,
+        None => Vec::new(),
+    };
+    let unsupported_params = state.codex_unsupported_params.count().await;
+    let body = CODEX_METRICS.render(&accounts, unsupported_params);
+
+    Ok(Box::new(warp::reply::with_header(
+        body,
+        "content-type",
+        METRICS_CONTENT_TYPE,
+    )) as Box<dyn Reply>)
 }
 
 fn optional_raw_query() -> impl Filter<Extract = (Option<String>,), Error = Infallible> + Clone {
@@ -216,16 +318,31 @@ async fn handle_passthrough(
     }
 
     ensure_codex_enabled(&state)?;
-    validate_api_key(&state, &headers)?;
+    let client_key = authenticate_client(&state, &headers).await?;
 
     let (pool, executor, logger, storage) = get_runtime_or_reject(&state)?;
+    let prices = state.codex_model_prices.clone();
     let request_format = infer_request_format(&path).to_string();
     let request_model =
         extract_model_from_json_bytes(&body).unwrap_or_else(|| "unknown".to_string());
 
-    let is_responses = request_format == "openai-responses";
+    let protocol = ClientProtocol::from_format(&request_format);
 
-    let (mut body, stream_forced) = if is_responses {
+    // Chat Completions 等协议先转换为 Responses 请求，再走与 Responses 相同的规范化流程
+    let translated: Option<TranslatedRequest> = if protocol.needs_translation() {
+        let result = translator::translate_request(protocol, &body)
+            .map_err(|e| warp::reject::custom(CodexRejection::TranslationError(e)))?;
+        Some(result)
+    } else {
+        None
+    };
+    let forward_path = translator::responses_path_for(protocol, &path);
+    let is_responses = request_format == "openai-responses" || translated.is_some();
+
+    let (mut body, stream_forced) = if let Some(ref t) = translated {
+        let result = normalize_responses_body(&t.body);
+        (result.body.unwrap_or_else(|| t.body.clone()), !t.client_stream)
+    } else if is_responses {
         let result = normalize_responses_body(&body);
         (result.body.unwrap_or(body), result.stream_forced)
     } else {
@@ -243,37 +360,87 @@ async fn handle_passthrough(
         }
     }
 
+    // 按别名改写模型，之后的日志与计费都使用改写后的模型
+    let request_model = match state.codex_model_catalog.resolve_alias(&request_model) {
+        Some(target) if is_responses => {
+            println!("[Codex] Model alias: {} -> {}", request_model, target);
+            body = set_json_key(&body, "model", Value::String(target.clone()));
+            target
+        }
+        _ => request_model,
+    };
+
+    // 白名单校验改写后实际请求的模型，别名不能绕过 Key 的模型限制
+    if let Some(ref key) = client_key {
+        enforce_client_limits(key, &request_model, storage.as_deref())?;
+        // 每分钟请求数只计入转发请求，/metrics 等路由的鉴权不占用
+        if let Err(retry_after) = state
+            .codex_client_keys
+            .check_rate_limit(key, chrono::Utc::now().timestamp_millis())
+        {
+            return Err(warp::reject::custom(CodexRejection::RateLimited(format!(
+                "Rate limit exceeded for API key '{}', retry after {}s",
+                key.name, retry_after
+            ))));
+        }
+    }
+    let client_key_id = client_key.map(|k| k.id);
+
+    let session_key = if is_responses {
+        derive_session_key(&headers, &body)
+    } else {
+        None
+    };
+
     // 最多重试 MAX_UNSUPPORTED_PARAM_RETRIES 次以处理未知的不支持参数
     const MAX_UNSUPPORTED_PARAM_RETRIES: usize = 5;
     let mut retries = 0;
+    // 流式响应在向客户端输出前中断时换号重试，失败的账号不再选用
+    let mut stream_failed_ids: HashSet<String> = HashSet::new();
+    let mut last_stream_error: Option<String> = None;
+    let capture_enabled = storage.is_some() && is_capture_enabled(&state);
 
     loop {
         let forward_request = ForwardRequest {
             method: method.clone(),
-            path: path.clone(),
+            path: forward_path.clone(),
             query: query.clone(),
             headers: headers.clone(),
             body: body.clone(),
             format: request_format.clone(),
             model: request_model.clone(),
+            client_key_id: client_key_id.clone(),
+            excluded_account_ids: stream_failed_ids.clone(),
+            session_key: session_key.clone(),
+            pinned_account_id: None,
         };
 
         let (upstream_response, meta) = match executor.forward(forward_request).await {
             Ok(ok) => ok,
             Err(err) => {
-                let is_no_account = matches!(err, CodexError::NoAvailableAccount);
-                let err_text = err.to_string();
+                // 换号重试后号池已无可用账号时，返回最初的流中断错误
+                let (is_no_account, err_text) = match (&err, last_stream_error.take()) {
+                    (CodexError::NoAvailableAccount, Some(stream_error)) => (false, stream_error),
+                    _ => (matches!(err, CodexError::NoAvailableAccount), err.to_string()),
+                };
                 add_failed_log(
                     logger.clone(),
                     storage.clone(),
                     &request_model,
                     &request_format,
+                    client_key_id.clone(),
                     err_text.clone(),
                 )
                 .await;
 
+                if let CodexError::PoolCoolingDown(retry_after) = err {
+                    return Ok(Box::new(pool_cooling_down_reply(&err_text, retry_after))
+                        as Box<dyn Reply>);
+                }
                 let rejection = if is_no_account {
                     CodexRejection::NoAvailableAccount
+                } else if matches!(err, CodexError::UpstreamTimeout(_)) {
+                    CodexRejection::UpstreamTimeout(err_text)
                 } else {
                     CodexRejection::ExecutionError(err_text)
                 };
@@ -284,6 +451,16 @@ async fn handle_passthrough(
         let upstream_status = StatusCode::from_u16(upstream_response.status().as_u16())
             .unwrap_or(StatusCode::BAD_GATEWAY);
         let upstream_headers = upstream_response.headers().clone();
+        // 抓包记录本次实际发往上游的请求体（已剥离不支持参数、改写别名）
+        let capture = capture_enabled.then(|| {
+            CaptureRequest::new(
+                method.as_str(),
+                &forward_path,
+                query.as_deref(),
+                &headers,
+                &body,
+            )
+        });
 
         // 如果是 402/403，异步更新数据库中的 forbidden 状态
         if upstream_status == StatusCode::PAYMENT_REQUIRED
@@ -311,6 +488,7 @@ async fn handle_passthrough(
                         storage,
                         &request_model,
                         &request_format,
+                        client_key_id.clone(),
                         err_text.clone(),
                     )
                     .await;
@@ -320,15 +498,16 @@ async fn handle_passthrough(
                 }
             };
 
-            if let Some(param) = extract_unsupported_param(&peek_bytes) {
+            if let Some((param, stripped)) =
+                strip_rejected_param(&state.codex_unsupported_params, &body, &peek_bytes).await
+            {
                 println!(
                     "[Codex] Upstream rejected unsupported param '{}', stripping and retrying ({}/{})",
                     param,
                     retries + 1,
                     MAX_UNSUPPORTED_PARAM_RETRIES
                 );
-                state.codex_unsupported_params.add(param.clone()).await;
-                body = remove_json_key(&body, &param);
+                body = stripped;
                 retries += 1;
                 continue;
             }
@@ -360,43 +539,112 @@ async fn handle_passthrough(
                 },
                 usage,
                 error_message,
+                &prices,
             );
+            if let Some(capture) = capture {
+                let response = CaptureBuffer::from_bytes(&peek_bytes);
+                record_capture(
+                    storage.as_deref(),
+                    capture.finish(&log, upstream_status, &upstream_headers, response),
+                );
+            }
             record_log(logger, storage, log).await;
 
-            let response = build_buffered_response(upstream_status, &upstream_headers, peek_bytes)
-                .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e.to_string())))?;
+            let response = build_client_buffered_response(
+                protocol,
+                upstream_status,
+                &upstream_headers,
+                peek_bytes,
+            )
+            .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e.to_string())))?;
             return Ok(Box::new(response) as Box<dyn Reply>);
         }
 
         // 流式响应或非 responses 格式
         if is_event_stream(&upstream_headers) {
-            if stream_forced {
-                let response = destream_responses_sse(
+            let abort_reason = if stream_forced {
+                match destream_responses_sse(
                     upstream_status,
                     upstream_response,
-                    pool,
-                    logger,
-                    storage,
-                    meta,
-                    request_model,
+                    pool.clone(),
+                    logger.clone(),
+                    storage.clone(),
+                    prices.clone(),
+                    meta.clone(),
+                    request_model.clone(),
+                    protocol,
+                    capture,
                 )
                 .await
-                .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e)))?;
-                return Ok(Box::new(response) as Box<dyn Reply>);
+                .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e)))?
+                {
+                    StreamOutcome::Response(response) => {
+                        return Ok(Box::new(response) as Box<dyn Reply>);
+                    }
+                    StreamOutcome::Aborted(reason) => reason,
+                }
+            } else {
+                let mut upstream_stream = upstream_response.bytes_stream().boxed();
+                let prefetched = if upstream_status.is_success() {
+                    prefetch_stream_preamble(&mut upstream_stream).await
+                } else {
+                    Ok(Vec::new())
+                };
+                match prefetched {
+                    Ok(prefetched) => {
+                        let stream_translator = translated.as_ref().and_then(|t| {
+                            translator::new_stream_translator(protocol, t, &request_model)
+                        });
+                        let response = build_streaming_response_with_metrics(
+                            upstream_status,
+                            &upstream_headers,
+                            prefetched,
+                            upstream_stream,
+                            pool,
+                            logger,
+                            storage,
+                            prices,
+                            meta,
+                            request_model,
+                            stream_translator,
+                            capture,
+                        )
+                        .map_err(|e| {
+                            warp::reject::custom(CodexRejection::InternalError(e.to_string()))
+                        })?;
+                        return Ok(Box::new(response) as Box<dyn Reply>);
+                    }
+                    Err(reason) => reason,
+                }
+            };
+
+            // 客户端尚未收到任何内容，换一个账号透明重试
+            pool.record_failure(&meta.account_id, None).await;
+            if stream_failed_ids.len() < MAX_STREAM_FAILOVERS {
+                println!(
+                    "[Codex] Upstream stream from {} aborted before output ({}), failing over ({}/{})",
+                    meta.account_email,
+                    abort_reason,
+                    stream_failed_ids.len() + 1,
+                    MAX_STREAM_FAILOVERS
+                );
+                stream_failed_ids.insert(meta.account_id.clone());
+                last_stream_error = Some(abort_reason);
+                continue;
             }
 
-            let response = build_streaming_response_with_metrics(
-                upstream_status,
-                &upstream_headers,
-                upstream_response,
-                pool,
-                logger,
-                storage,
-                meta,
+            let log = build_request_log(
+                &meta,
                 request_model,
-            )
-            .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e.to_string())))?;
-            return Ok(Box::new(response) as Box<dyn Reply>);
+                "error",
+                UsageStats::default(),
+                Some(abort_reason.clone()),
+                &prices,
+            );
+            record_log(logger, storage, log).await;
+            return Err(warp::reject::custom(CodexRejection::ExecutionError(
+                abort_reason,
+            )));
         }
 
         let upstream_bytes = match upstream_response.bytes().await {
@@ -409,6 +657,7 @@ async fn handle_passthrough(
                     storage,
                     &request_model,
                     &request_format,
+                    client_key_id.clone(),
                     err_text.clone(),
                 )
                 .await;
@@ -444,52 +693,148 @@ async fn handle_passthrough(
             },
             usage,
             error_message,
+            &prices,
         );
+        if let Some(capture) = capture {
+            let response = CaptureBuffer::from_bytes(&upstream_bytes);
+            record_capture(
+                storage.as_deref(),
+                capture.finish(&log, upstream_status, &upstream_headers, response),
+            );
+        }
         record_log(logger, storage, log).await;
 
-        let response = build_buffered_response(upstream_status, &upstream_headers, upstream_bytes)
-            .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e.to_string())))?;
+        let response = build_client_buffered_response(
+            protocol,
+            upstream_status,
+            &upstream_headers,
+            upstream_bytes,
+        )
+        .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e.to_string())))?;
         return Ok(Box::new(response) as Box<dyn Reply>);
     }
 }
 
+/// 流式响应在向客户端输出前最多换号重试的次数
+const MAX_STREAM_FAILOVERS: usize = 2;
+
+/// 开始向客户端输出前最多预读的上游字节数
+const STREAM_PREFETCH_LIMIT: usize = 64 * 1024;
+
+type UpstreamByteStream = futures::stream::BoxStream<'static, Result<Bytes, reqwest::Error>>;
+
+/// 收集上游 SSE 的结果
+enum StreamOutcome {
+    /// 上游正常结束（含 response.failed 等失败事件），已记录日志
+    Response(Response<Body>),
+    /// 上游在结束事件前中断，尚未记录日志，可换号重试
+    Aborted(String),
+}
+
+/// 是否为 Responses 流的结束事件
+fn is_terminal_event(event_type: &str) -> bool {
+    matches!(
+        event_type,
+        "response.completed"
+            | "response.done"
+            | "response.failed"
+            | "response.incomplete"
+            | "error"
+    )
+}
+
+/// 预读上游 SSE 开头的 response.created / response.in_progress 事件。
+/// 这些事件先不发给客户端，上游在产出内容前中断时即可透明地换号重试；
+/// 读到其他事件或超过预读上限后返回已缓冲的 chunk，由调用方接着转发。
+async fn prefetch_stream_preamble(stream: &mut UpstreamByteStream) -> Result<Vec<Bytes>, String> {
+    let mut parser = SseEventParser::default();
+    let mut prefetched = Vec::new();
+    let mut buffered = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let bytes = chunk.map_err(|e| format!("Failed to read upstream stream: {}", e))?;
+        buffered += bytes.len();
+        let events = parser.feed(&bytes);
+        prefetched.push(bytes);
+
+        let has_content = events.iter().any(|(event_type, _)| {
+            !matches!(
+                event_type.as_str(),
+                "response.created" | "response.in_progress"
+            )
+        });
+        if has_content || buffered >= STREAM_PREFETCH_LIMIT {
+            return Ok(prefetched);
+        }
+    }
+
+    // 上游已结束：只有收到结束事件才算完整响应
+    if parser
+        .finish()
+        .iter()
+        .any(|(event_type, _)| is_terminal_event(event_type))
+    {
+        return Ok(prefetched);
+    }
+    Err("Upstream stream ended before response.completed".to_string())
+}
+
+/// 向客户端追加一个流中断错误事件，已有转换器时按客户端协议输出
+fn write_stream_abort_event(
+    stream_translator: Option<&mut Box<dyn StreamTranslator>>,
+    message: &str,
+    out: &mut Vec<u8>,
+) {
+    let data = json!({
+        "type": "error",
+        "code": "stream_aborted",
+        "message": message,
+    });
+    match stream_translator {
+        Some(t) => t.on_event("error", &data, out),
+        None => translator::write_sse_event(out, "error", &data),
+    }
+}
+
 /// 收集上游 SSE 流，提取 response.completed 事件中的完整响应对象，
-/// 以普通 JSON 返回给不需要流式的客户端。
+/// 以普通 JSON 返回给不需要流式的客户端（按客户端协议转换响应结构）。
 async fn destream_responses_sse(
     status: StatusCode,
     response: reqwest::Response,
     pool: Arc<CodexPool>,
     logger: Arc<RwLock<RequestLogger>>,
     storage: Option<Arc<CodexLogStorage>>,
+    prices: Arc<ModelPriceTable>,
     meta: ForwardMeta,
     request_model: String,
-) -> Result<Response<Body>, String> {
+    protocol: ClientProtocol,
+    capture: Option<CaptureRequest>,
+) -> Result<StreamOutcome, String> {
+    let headers = response.headers().clone();
     let mut stream = response.bytes_stream();
     let mut extractor = SseMetricsExtractor::default();
-    let mut all_data = String::new();
+    let mut all_bytes: Vec<u8> = Vec::new();
 
     while let Some(chunk) = stream.next().await {
         match chunk {
             Ok(bytes) => {
                 extractor.ingest_chunk(&bytes);
-                all_data.push_str(&String::from_utf8_lossy(&bytes));
+                all_bytes.extend_from_slice(&bytes);
             }
             Err(err) => {
-                let err_text = format!("Failed to read upstream stream: {}", err);
-                pool.record_failure(&meta.account_id, None).await;
-                add_failed_log(
-                    logger,
-                    storage,
-                    &request_model,
-                    &meta.format,
-                    err_text.clone(),
-                )
-                .await;
-                return Err(err_text);
+                return Ok(StreamOutcome::Aborted(format!(
+                    "Failed to read upstream stream: {}",
+                    err
+                )));
             }
         }
     }
     extractor.finish();
+    if status.is_success() && !extractor.terminal {
+        return Ok(StreamOutcome::Aborted(
+            "Upstream stream ended before response.completed".to_string(),
+        ));
+    }
 
     // 记录 usage
     let usage = extractor.usage;
@@ -497,6 +842,10 @@ async fn destream_responses_sse(
         pool.record_usage(&meta.account_id, usage.total_tokens)
             .await;
     }
+    if let Some(id) = extractor.response_id.as_deref().filter(|_| status.is_success()) {
+        pool.pin_session(&response_session_key(id), &meta.account_id)
+            .await;
+    }
 
     let log_model = if request_model == "unknown" {
         extractor.model.clone().unwrap_or(request_model.clone())
@@ -510,7 +859,7 @@ async fn destream_responses_sse(
     };
     let log = build_request_log(
         &meta,
-        log_model,
+        log_model.clone(),
         if status.is_success() {
             "success"
         } else {
@@ -518,12 +867,24 @@ async fn destream_responses_sse(
         },
         usage,
         error_message,
+        &prices,
     );
+    if let Some(capture) = capture {
+        let response = CaptureBuffer::from_bytes(&all_bytes);
+        record_capture(
+            storage.as_deref(),
+            capture.finish(&log, status, &headers, response),
+        );
+    }
     record_log(logger, storage, log).await;
 
     // 从 SSE 事件中提取 response.completed 的 response 对象
-    let response_json = extract_completed_response(&all_data)
-        .unwrap_or_else(|| json!({"error": "Failed to extract response from SSE stream"}));
+    // 整体拼接后再解码，避免多字节字符被 chunk 边界截断
+    let all_data = String::from_utf8_lossy(&all_bytes);
+    let response_json = match extract_completed_response(&all_data) {
+        Some(resp) => translator::translate_completed_response(protocol, &resp, &log_model),
+        None => json!({"error": "Failed to extract response from SSE stream"}),
+    };
 
     let body_bytes = serde_json::to_vec(&response_json)
         .map_err(|e| format!("Failed to serialize response: {}", e))?;
@@ -532,83 +893,65 @@ async fn destream_responses_sse(
         .status(status)
         .header("content-type", "application/json")
         .body(Body::from(body_bytes))
+        .map(StreamOutcome::Response)
         .map_err(|e| format!("Failed to build destreamed response: {}", e))
 }
 
 /// 从 SSE 文本中提取 response.completed 事件的 response 对象
 fn extract_completed_response(sse_text: &str) -> Option<Value> {
-    // 按双换行分割事件块
-    for block in sse_text.split("\n\n") {
-        let mut event_type = None;
-        let mut data_lines = Vec::new();
+    // 按双换行分割事件块，也尝试 \r\n\r\n 分割
+    sse_text
+        .split("\n\n")
+        .chain(sse_text.split("\r\n\r\n"))
+        .find_map(completed_response_from_block)
+}
 
-        for line in block.lines() {
-            let line = line.trim_start();
-            if let Some(rest) = line.strip_prefix("event:") {
-                event_type = Some(rest.trim());
-            } else if let Some(rest) = line.strip_prefix("data:") {
-                data_lines.push(rest.trim_start());
-            }
-        }
+fn completed_response_from_block(block: &str) -> Option<Value> {
+    let mut event_type = None;
+    let mut data_lines = Vec::new();
 
-        // 查找 response.completed 事件
-        if event_type == Some("response.completed") || event_type == Some("response.done") {
-            if data_lines.is_empty() {
-                continue;
-            }
-            let data = data_lines.join("\n");
-            if let Ok(value) = serde_json::from_str::<Value>(&data) {
-                // response.completed 事件的 data 中有 response 字段
-                if let Some(resp) = value.get("response").cloned() {
-                    return Some(resp);
-                }
-                // 如果没有 response 字段，整个 data 可能就是响应
-                return Some(value);
-            }
+    for line in block.lines() {
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix("event:") {
+            event_type = Some(rest.trim());
+        } else if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start());
         }
     }
 
-    // 也尝试 \r\n\r\n 分割
-    for block in sse_text.split("\r\n\r\n") {
-        let mut event_type = None;
-        let mut data_lines = Vec::new();
-
-        for line in block.lines() {
-            let line = line.trim_start();
-            if let Some(rest) = line.strip_prefix("event:") {
-                event_type = Some(rest.trim());
-            } else if let Some(rest) = line.strip_prefix("data:") {
-                data_lines.push(rest.trim_start());
-            }
-        }
+    if data_lines.is_empty() {
+        return None;
+    }
+    let value = serde_json::from_str::<Value>(&data_lines.join("\n")).ok()?;
 
-        if event_type == Some("response.completed") || event_type == Some("response.done") {
-            if data_lines.is_empty() {
-                continue;
-            }
-            let data = data_lines.join("\n");
-            if let Ok(value) = serde_json::from_str::<Value>(&data) {
-                if let Some(resp) = value.get("response").cloned() {
-                    return Some(resp);
-                }
-                return Some(value);
-            }
-        }
+    // 没有 event 行时以 data 中的 type 字段判断事件类型
+    let event_type = event_type.or_else(|| value.get("type").and_then(|v| v.as_str()));
+    if event_type != Some("response.completed") && event_type != Some("response.done") {
+        return None;
     }
 
-    None
+    // response.completed 事件的 data 中有 response 字段；
+    // 如果没有 response 字段，整个 data 可能就是响应
+    Some(value.get("response").cloned().unwrap_or(value))
 }
 
+/// 转发上游 SSE 流：先发送预读的 chunk，再继续读取上游。
+/// 已向客户端输出内容后上游中断时，补发一个 error 事件让流以合法格式结束，并记为失败。
 fn build_streaming_response_with_metrics(
     status: StatusCode,
     headers: &HeaderMap,
-    response: reqwest::Response,
+    prefetched: Vec<Bytes>,
+    upstream_stream: UpstreamByteStream,
     pool: Arc<CodexPool>,
     logger: Arc<RwLock<RequestLogger>>,
     storage: Option<Arc<CodexLogStorage>>,
+    prices: Arc<ModelPriceTable>,
     meta: ForwardMeta,
     request_model: String,
+    mut stream_translator: Option<Box<dyn StreamTranslator>>,
+    capture: Option<CaptureRequest>,
 ) -> Result<Response<Body>, String> {
+    let upstream_headers = headers.clone();
     let mut builder = Response::builder().status(status);
     for (name, value) in headers.iter() {
         if should_strip_response_header(name.as_str()) {
@@ -617,7 +960,8 @@ fn build_streaming_response_with_metrics(
         builder = builder.header(name, value);
     }
 
-    let mut upstream_stream = response.bytes_stream();
+    let mut upstream_stream =
+        futures::stream::iter(prefetched.into_iter().map(Ok)).chain(upstream_stream);
     let (tx, rx) = futures::channel::mpsc::channel::<Result<Bytes, std::io::Error>>(16);
 
     tokio::spawn(async move {
@@ -626,11 +970,32 @@ fn build_streaming_response_with_metrics(
         // while continuing to drain upstream for usage metrics.
         let mut maybe_tx: Option<futures::channel::mpsc::Sender<Result<Bytes, std::io::Error>>> =
             Some(tx);
+        let mut event_parser = SseEventParser::default();
+        let mut transport_error: Option<String> = None;
+        // 抓包与指标提取读取同一份上游字节（协议转换之前）
+        let mut capture_buffer = capture.is_some().then(CaptureBuffer::default);
 
         while let Some(chunk) = upstream_stream.next().await {
             match chunk {
                 Ok(bytes) => {
                     extractor.ingest_chunk(&bytes);
+                    if let Some(buffer) = capture_buffer.as_mut() {
+                        buffer.push(&bytes);
+                    }
+                    // 需要协议转换时，将上游事件转换为客户端格式后再发送
+                    let bytes = match stream_translator.as_mut() {
+                        Some(t) => {
+                            let mut out = Vec::new();
+                            for (event_type, data) in event_parser.feed(&bytes) {
+                                t.on_event(&event_type, &data, &mut out);
+                            }
+                            if out.is_empty() {
+                                continue;
+                            }
+                            Bytes::from(out)
+                        }
+                        None => bytes,
+                    };
                     if let Some(ref mut sender) = maybe_tx {
                         if sender.send(Ok(bytes)).await.is_err() {
                             // Client disconnected — drop sender but keep draining
@@ -640,22 +1005,39 @@ fn build_streaming_response_with_metrics(
                     }
                 }
                 Err(err) => {
-                    let err_text = format!("Failed to read upstream stream chunk: {}", err);
-                    extractor.error_message.get_or_insert(err_text.clone());
-                    if let Some(ref mut sender) = maybe_tx {
-                        let _ = sender
-                            .send(Err(std::io::Error::new(
-                                std::io::ErrorKind::Other,
-                                err_text,
-                            )))
-                            .await;
-                    }
+                    transport_error =
+                        Some(format!("Failed to read upstream stream chunk: {}", err));
                     break;
                 }
             }
         }
 
+        let mut out = Vec::new();
+        if let Some(t) = stream_translator.as_mut() {
+            for (event_type, data) in event_parser.finish() {
+                t.on_event(&event_type, &data, &mut out);
+            }
+        }
         extractor.finish();
+
+        // 上游中断或未发送结束事件：客户端已收到部分内容，只能以 error 事件收尾
+        let abort_reason = transport_error.or_else(|| {
+            (status.is_success() && !extractor.terminal)
+                .then(|| "Upstream stream ended before response.completed".to_string())
+        });
+        if let Some(ref reason) = abort_reason {
+            write_stream_abort_event(stream_translator.as_mut(), reason, &mut out);
+            extractor.error_message = Some(reason.clone());
+        }
+        if let Some(t) = stream_translator.as_mut() {
+            t.finish(&mut out);
+        }
+        if let Some(ref mut sender) = maybe_tx {
+            if !out.is_empty() {
+                let _ = sender.send(Ok(Bytes::from(out))).await;
+            }
+        }
+
         let usage = extractor.usage;
         let stream_ok = extractor.error_message.is_none();
         if status.is_success() && stream_ok && usage.total_tokens > 0 {
@@ -664,6 +1046,14 @@ fn build_streaming_response_with_metrics(
         } else if !stream_ok {
             pool.record_failure(&meta.account_id, None).await;
         }
+        if let Some(id) = extractor
+            .response_id
+            .as_deref()
+            .filter(|_| status.is_success() && stream_ok)
+        {
+            pool.pin_session(&response_session_key(id), &meta.account_id)
+                .await;
+        }
 
         let log_model = if request_model == "unknown" {
             extractor.model.unwrap_or(request_model)
@@ -686,7 +1076,14 @@ fn build_streaming_response_with_metrics(
             },
             usage,
             error_message,
+            &prices,
         );
+        if let (Some(capture), Some(buffer)) = (capture, capture_buffer) {
+            record_capture(
+                storage.as_deref(),
+                capture.finish(&log, status, &upstream_headers, buffer),
+            );
+        }
         record_log(logger, storage, log).await;
     });
 
@@ -701,6 +1098,9 @@ struct SseMetricsExtractor {
     usage: UsageStats,
     model: Option<String>,
     error_message: Option<String>,
+    response_id: Option<String>,
+    /// 是否收到了结束事件（response.completed / response.failed 等）
+    terminal: bool,
 }
 
 impl SseMetricsExtractor {
@@ -755,6 +1155,14 @@ impl SseMetricsExtractor {
     }
 
     fn extract_fields(&mut self, value: &Value) {
+        if value
+            .get("type")
+            .and_then(|v| v.as_str())
+            .is_some_and(is_terminal_event)
+        {
+            self.terminal = true;
+        }
+
         // 提取 model
         if self.model.is_none() {
             self.model = value
@@ -764,35 +1172,19 @@ impl SseMetricsExtractor {
                 .map(|v| v.to_string());
         }
 
+        if self.response_id.is_none() {
+            self.response_id = value
+                .pointer("/response/id")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string());
+        }
+
         // 提取 usage - ChatGPT Codex 流式响应中 usage 在 response.completed 事件的 response.usage 下
         if let Some(usage) = value
             .pointer("/response/usage")
             .or_else(|| value.get("usage"))
         {
-            let input_tokens = to_i64(
-                usage
-                    .get("input_tokens")
-                    .or_else(|| usage.get("prompt_tokens")),
-            );
-            let output_tokens = to_i64(
-                usage
-                    .get("output_tokens")
-                    .or_else(|| usage.get("completion_tokens")),
-            );
-            let total_tokens = {
-                let explicit_total = to_i64(usage.get("total_tokens"));
-                if explicit_total > 0 {
-                    explicit_total
-                } else {
-                    input_tokens + output_tokens
-                }
-            };
-
-            self.usage = UsageStats {
-                input_tokens,
-                output_tokens,
-                total_tokens,
-            };
+            self.usage = UsageStats::from_usage_value(usage);
         }
 
         // 提取错误信息
@@ -809,6 +1201,26 @@ impl SseMetricsExtractor {
     }
 }
 
+/// 构建缓冲响应；转换协议下的上游错误体改写为客户端协议的错误格式
+fn build_client_buffered_response(
+    protocol: ClientProtocol,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> Result<Response<Body>, String> {
+    if status.is_success() {
+        return build_buffered_response(status, headers, body);
+    }
+    match translator::translate_error_body(protocol, status.as_u16(), &body) {
+        Some(translated) => Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(Body::from(translated))
+            .map_err(|e| format!("Failed to build response: {}", e)),
+        None => build_buffered_response(status, headers, body),
+    }
+}
+
 fn build_buffered_response(
     status: StatusCode,
     headers: &HeaderMap,
@@ -851,7 +1263,7 @@ fn is_event_stream(headers: &HeaderMap) -> bool {
         .unwrap_or(true) // 当 content-type 缺失时，默认假设为 SSE
 }
 
-fn is_supported_proxy_path(path: &str) -> bool {
+pub(crate) fn is_supported_proxy_path(path: &str) -> bool {
     path == "/v1"
         || path.starts_with("/v1/")
         || path == "/backend-api/codex"
@@ -962,6 +1374,31 @@ fn extract_unsupported_param(body: &Bytes) -> Option<String> {
     if param.is_empty() { None } else { Some(param) }
 }
 
+/// 上游以 "Unsupported parameter" 拒绝请求时，缓存该参数并返回 (参数名, 移除该参数后的请求体)
+async fn strip_rejected_param(
+    cache: &UnsupportedParamCache,
+    body: &Bytes,
+    upstream_body: &Bytes,
+) -> Option<(String, Bytes)> {
+    let param = extract_unsupported_param(upstream_body)?;
+    cache.add(param.clone()).await;
+    let stripped = remove_json_key(body, &param);
+    Some((param, stripped))
+}
+
+/// 设置 JSON body 中指定 key 的值（已存在时覆盖），body 不是 JSON 对象时原样返回
+fn set_json_key(body: &Bytes, key: &str, value: Value) -> Bytes {
+    if let Ok(mut root) = serde_json::from_slice::<Value>(body) {
+        if let Some(obj) = root.as_object_mut() {
+            obj.insert(key.to_string(), value);
+            if let Ok(new_body) = serde_json::to_vec(&root) {
+                return Bytes::from(new_body);
+            }
+        }
+    }
+    body.clone()
+}
+
 /// 从 JSON body 中移除指定的 key
 fn remove_json_key(body: &Bytes, key: &str) -> Bytes {
     if let Ok(mut root) = serde_json::from_slice::<Value>(body) {
@@ -975,6 +1412,52 @@ fn remove_json_key(body: &Bytes, key: &str) -> Bytes {
     body.clone()
 }
 
+/// 推导会话粘性 key，优先级：previous_response_id > session_id/conversation_id 请求头
+/// > 系统提示词与首条消息的哈希
+fn derive_session_key(headers: &HeaderMap, body: &Bytes) -> Option<String> {
+    let value = serde_json::from_slice::<Value>(body).ok()?;
+
+    if let Some(previous) = value
+        .get("previous_response_id")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+    {
+        return Some(response_session_key(previous.trim()));
+    }
+
+    for name in ["session_id", "conversation_id"] {
+        if let Some(id) = headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            return Some(format!("session:{}", id));
+        }
+    }
+
+    let instructions = value.get("instructions").filter(|v| !v.is_null());
+    let first_message = match value.get("input") {
+        Some(Value::Array(items)) => items.first(),
+        Some(Value::String(_)) => value.get("input"),
+        _ => None,
+    };
+    if instructions.is_none() && first_message.is_none() {
+        return None;
+    }
+
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    instructions.map(Value::to_string).hash(&mut hasher);
+    first_message.map(Value::to_string).hash(&mut hasher);
+    Some(format!("prompt:{:016x}", hasher.finish()))
+}
+
+/// 上游响应 id 对应的会话 key，后续以 previous_response_id 续接的请求会命中同一账号
+fn response_session_key(response_id: &str) -> String {
+    format!("response:{}", response_id)
+}
+
 fn extract_model_from_json_bytes(body: &Bytes) -> Option<String> {
     serde_json::from_slice::<Value>(body).ok().and_then(|v| {
         v.get("model")
@@ -995,30 +1478,7 @@ fn extract_usage_from_json_bytes(body: &Bytes) -> UsageStats {
         return UsageStats::default();
     };
 
-    let input_tokens = to_i64(
-        usage
-            .get("input_tokens")
-            .or_else(|| usage.get("prompt_tokens")),
-    );
-    let output_tokens = to_i64(
-        usage
-            .get("output_tokens")
-            .or_else(|| usage.get("completion_tokens")),
-    );
-    let total_tokens = {
-        let explicit_total = to_i64(usage.get("total_tokens"));
-        if explicit_total > 0 {
-            explicit_total
-        } else {
-            input_tokens + output_tokens
-        }
-    };
-
-    UsageStats {
-        input_tokens,
-        output_tokens,
-        total_tokens,
-    }
+    UsageStats::from_usage_value(usage)
 }
 
 fn extract_error_message(body: &Bytes) -> Option<String> {
@@ -1074,7 +1534,14 @@ fn build_request_log(
     status: &str,
     usage: UsageStats,
     error_message: Option<String>,
+    prices: &ModelPriceTable,
 ) -> RequestLog {
+    let estimated_cost = prices.estimate_cost(
+        &model,
+        usage.input_tokens,
+        usage.cached_tokens,
+        usage.output_tokens,
+    );
     RequestLog {
         id: uuid::Uuid::new_v4().to_string(),
         timestamp: chrono::Utc::now().timestamp(),
@@ -1082,9 +1549,13 @@ fn build_request_log(
         account_email: meta.account_email.clone(),
         model,
         format: meta.format.clone(),
+        client_key_id: meta.client_key_id.clone(),
         input_tokens: usage.input_tokens,
         output_tokens: usage.output_tokens,
         total_tokens: usage.total_tokens,
+        cached_tokens: usage.cached_tokens,
+        reasoning_tokens: usage.reasoning_tokens,
+        estimated_cost,
         status: status.to_string(),
         error_message,
         request_duration_ms: Some(meta.started_at.elapsed().as_millis() as i64),
@@ -1096,6 +1567,7 @@ async fn record_log(
     storage: Option<Arc<CodexLogStorage>>,
     log: RequestLog,
 ) {
+    CODEX_METRICS.record_request(&log);
     let mut guard = logger.write().await;
     guard.add_log(log.clone());
 
@@ -1105,6 +1577,35 @@ async fn record_log(
     }
 }
 
+/// 号池全部冷却且不再等待时的 429 响应，Retry-After 为最早的冷却结束时间
+fn pool_cooling_down_reply(message: &str, retry_after: u64) -> impl Reply {
+    warp::reply::with_header(
+        ApiError::new(ApiErrorCode::PoolCoolingDown, message).openai_reply(),
+        "retry-after",
+        retry_after.to_string(),
+    )
+}
+
+/// 是否开启了抓包模式
+fn is_capture_enabled(state: &AppState) -> bool {
+    state
+        .codex_server_config
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|c| c.capture_enabled)
+}
+
+/// 写入抓包，失败只打印日志，不影响请求
+fn record_capture(storage: Option<&CodexLogStorage>, capture: RequestCapture) {
+    let Some(s) = storage else {
+        return;
+    };
+    if let Err(e) = s.add_capture(&capture) {
+        eprintln!("[Codex] Failed to store capture {}: {}", capture.id, e);
+    }
+}
+
 /// 更新账户的 forbidden 状态到数据库
 async fn mark_account_forbidden(state: &Arc<AppState>, account_id: &str) {
     let storage = {
@@ -1133,6 +1634,7 @@ async fn add_failed_log(
     storage: Option<Arc<CodexLogStorage>>,
     model: &str,
     format: &str,
+    client_key_id: Option<String>,
     error: String,
 ) {
     let log = RequestLog {
@@ -1142,13 +1644,18 @@ async fn add_failed_log(
         account_email: String::new(),
         model: model.to_string(),
         format: format.to_string(),
+        client_key_id,
         input_tokens: 0,
         output_tokens: 0,
         total_tokens: 0,
+        cached_tokens: 0,
+        reasoning_tokens: 0,
+        estimated_cost: 0.0,
         status: "error".to_string(),
         error_message: Some(error),
         request_duration_ms: None,
     };
+    CODEX_METRICS.record_request(&log);
     let mut guard = logger.write().await;
     guard.add_log(log.clone());
 
@@ -1207,7 +1714,12 @@ fn get_runtime_or_reject(
     Ok((pool, executor, logger, storage))
 }
 
-fn validate_api_key(state: &Arc<AppState>, headers: &HeaderMap) -> Result<(), Rejection> {
+/// 校验请求携带的 API Key。
+/// 管理员 Key（CodexServerConfig.api_key）返回 Ok(None)，客户端 Key 返回 Ok(Some(key))。
+async fn authenticate_client(
+    state: &Arc<AppState>,
+    headers: &HeaderMap,
+) -> Result<Option<ClientApiKey>, Rejection> {
     let configured_key = state
         .codex_server_config
         .lock()
@@ -1217,13 +1729,94 @@ fn validate_api_key(state: &Arc<AppState>, headers: &HeaderMap) -> Result<(), Re
         .map(|v| v.trim().to_string())
         .filter(|v| !v.is_empty());
 
-    // API Key 必须设置，未设置则拒绝请求
-    let Some(expected) = configured_key else {
-        return Err(warp::reject::custom(CodexRejection::ExecutionError(
+    // 管理员 Key 与客户端 Key 至少配置一种，否则拒绝请求
+    if configured_key.is_none() && !state.codex_client_keys.has_keys().await {
+        return Err(warp::reject::custom(CodexRejection::Unauthorized(
             "Unauthorized: API key not configured".to_string(),
         )));
+    }
+
+    let candidates = provided_api_keys(headers);
+
+    if let Some(expected) = configured_key.as_deref() {
+        if candidates.iter().any(|provided| *provided == expected) {
+            return Ok(None);
+        }
+    }
+
+    for provided in candidates {
+        let Some(key) = state.codex_client_keys.find_by_key(provided).await else {
+            continue;
+        };
+        if !key.enabled {
+            return Err(warp::reject::custom(CodexRejection::Unauthorized(
+                "Unauthorized: API key is disabled".to_string(),
+            )));
+        }
+        if key.is_expired(chrono::Utc::now().timestamp()) {
+            return Err(warp::reject::custom(CodexRejection::Unauthorized(
+                "Unauthorized: API key has expired".to_string(),
+            )));
+        }
+        return Ok(Some(key));
+    }
+
+    Err(warp::reject::custom(CodexRejection::Unauthorized(
+        "Unauthorized: invalid API key".to_string(),
+    )))
+}
+
+/// 校验客户端 Key 的模型白名单与 token 预算
+fn enforce_client_limits(
+    key: &ClientApiKey,
+    model: &str,
+    storage: Option<&CodexLogStorage>,
+) -> Result<(), Rejection> {
+    if !key.allows_model(model) {
+        return Err(warp::reject::custom(CodexRejection::Forbidden(format!(
+            "Model '{}' is not allowed for API key '{}'",
+            model, key.name
+        ))));
+    }
+
+    if key.daily_token_limit.is_none() && key.monthly_token_limit.is_none() {
+        return Ok(());
+    }
+    let Some(storage) = storage else {
+        // 没有持久化日志时无法统计用量，设置了预算的 Key 一律拒绝
+        return Err(warp::reject::custom(CodexRejection::ServiceUnavailable(
+            format!(
+                "Token budget for API key '{}' cannot be enforced: request log storage is unavailable",
+                key.name
+            ),
+        )));
     };
 
+    let (day_start, month_start) = budget_period_starts(chrono::Utc::now().timestamp());
+    let budgets = [
+        ("Daily", key.daily_token_limit, day_start),
+        ("Monthly", key.monthly_token_limit, month_start),
+    ];
+    for (period, limit, since) in budgets {
+        let Some(limit) = limit else {
+            continue;
+        };
+        let used = storage
+            .get_client_token_usage(&key.id, since)
+            .map_err(|e| warp::reject::custom(CodexRejection::InternalError(e)))?;
+        if used >= limit {
+            return Err(warp::reject::custom(CodexRejection::RateLimited(format!(
+                "{} token budget exceeded for API key '{}' ({}/{})",
+                period, key.name, used, limit
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+/// 从 Authorization: Bearer 与 x-api-key 头中提取调用方提供的 Key
+fn provided_api_keys(headers: &HeaderMap) -> Vec<&str> {
     let mut candidates: Vec<&str> = Vec::new();
 
     if let Some(auth) = headers.get("authorization").and_then(|v| v.to_str().ok()) {
@@ -1241,13 +1834,25 @@ fn validate_api_key(state: &Arc<AppState>, headers: &HeaderMap) -> Result<(), Re
         candidates.push(key);
     }
 
-    if candidates.into_iter().any(|provided| provided == expected) {
-        return Ok(());
-    }
+    candidates
+}
 
-    Err(warp::reject::custom(CodexRejection::ExecutionError(
-        "Unauthorized: invalid API key".to_string(),
-    )))
+/// 校验请求是否携带 Codex 管理员 Key；未配置管理员 Key 时返回 None
+pub(crate) fn check_admin_key(state: &Arc<AppState>, headers: &HeaderMap) -> Option<bool> {
+    let configured_key = state
+        .codex_server_config
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|cfg| cfg.api_key.as_ref())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())?;
+
+    Some(
+        provided_api_keys(headers)
+            .iter()
+            .any(|provided| *provided == configured_key),
+    )
 }
 
 fn extract_bearer_token(header: &str) -> Option<&str> {
@@ -1265,36 +1870,35 @@ fn extract_bearer_token(header: &str) -> Option<&str> {
     if token.is_empty() { None } else { Some(token) }
 }
 
-fn get_models() -> serde_json::Value {
-    json!({
-        "object": "list",
-        "data": [
-            {
-                "id": "gpt-5",
-                "object": "model",
-                "created": 1728000000,
-                "owned_by": "openai"
-            },
-            {
-                "id": "gpt-5-codex",
-                "object": "model",
-                "created": 1728000000,
-                "owned_by": "openai"
-            },
-            {
-                "id": "gpt-4.1",
-                "object": "model",
-                "created": 1728000000,
-                "owned_by": "openai"
-            },
-            {
-                "id": "gpt-4o",
-                "object": "model",
-                "created": 1728000000,
-                "owned_by": "openai"
-            }
-        ]
-    })
+/// /v1/models：模型目录（按号池账号套餐过滤）+ 近期成功请求的模型 + 别名
+async fn handle_models(state: Arc<AppState>) -> Result<Box<dyn Reply>, Rejection> {
+    ensure_codex_enabled(&state)?;
+    let catalog = state.codex_model_catalog.clone();
+
+    let pool = state.codex_pool.lock().unwrap().clone();
+    let plans: Vec<String> = match pool {
+        Some(pool) => pool
+            .get_accounts()
+            .await
+            .into_iter()
+            .filter(|a| a.is_available())
+            .filter_map(|a| a.plan_type)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let storage = state.codex_log_storage.lock().unwrap().clone();
+    let since = catalog.observed_since(chrono::Utc::now().timestamp());
+    let observed = match (storage, since) {
+        (Some(storage), Some(since)) => storage.get_recent_models(since).unwrap_or_else(|e| {
+            eprintln!("[Codex] Failed to load recent models: {}", e);
+            Vec::new()
+        }),
+        _ => Vec::new(),
+    };
+
+    let ids = catalog.list_models(&plans, &observed);
+    Ok(Box::new(warp::reply::json(&models_response(&ids))) as Box<dyn Reply>)
 }
 
 // ==================== 错误类型 ====================
@@ -1305,8 +1909,565 @@ pub enum CodexRejection {
     InvalidRequest(String),
     TranslationError(String),
     ExecutionError(String),
+    UpstreamTimeout(String),
+    Unauthorized(String),
+    Forbidden(String),
+    RateLimited(String),
     ServiceUnavailable(String),
     InternalError(String),
 }
 
 impl warp::reject::Reject for CodexRejection {}
+
+impl CodexRejection {
+    /// 映射为统一错误码
+    pub fn api_error(&self) -> ApiError {
+        match self {
+            CodexRejection::NoAvailableAccount => ApiError::new(
+                ApiErrorCode::NoAvailableAccount,
+                "No available account in pool",
+            ),
+            CodexRejection::InvalidRequest(msg) => {
+                ApiError::new(ApiErrorCode::InvalidRequest, msg.as_str())
+            }
+            CodexRejection::TranslationError(msg) => {
+                ApiError::new(ApiErrorCode::TranslationError, msg.as_str())
+            }
+            CodexRejection::ExecutionError(msg) => {
+                ApiError::new(ApiErrorCode::ExecutionError, msg.as_str())
+            }
+            CodexRejection::UpstreamTimeout(msg) => {
+                ApiError::new(ApiErrorCode::UpstreamTimeout, msg.as_str())
+            }
+            CodexRejection::Unauthorized(msg) => {
+                ApiError::new(ApiErrorCode::Unauthorized, msg.as_str())
+            }
+            CodexRejection::Forbidden(msg) => ApiError::new(ApiErrorCode::Forbidden, msg.as_str()),
+            CodexRejection::RateLimited(msg) => {
+                ApiError::new(ApiErrorCode::RateLimited, msg.as_str())
+            }
+            CodexRejection::ServiceUnavailable(msg) => {
+                ApiError::new(ApiErrorCode::ServiceUnavailable, msg.as_str())
+            }
+            CodexRejection::InternalError(msg) => {
+                ApiError::new(ApiErrorCode::InternalError, msg.as_str())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use reqwest::{Method, StatusCode};
+    use serde_json::{Value, json};
+    use warp::http::HeaderMap;
+
+    use futures::StreamExt;
+    use tokio::sync::RwLock;
+
+    use super::{
+        CaptureRequest, CodexRejection, SseMetricsExtractor, StreamOutcome, UNSUPPORTED_PARAMS_FILE, UnsupportedParamCache,
+        build_client_buffered_response, build_streaming_response_with_metrics, derive_session_key, destream_responses_sse,
+        extract_usage_from_json_bytes, prefetch_stream_preamble, strip_rejected_param,
+    };
+    use crate::core::api_error::ApiErrorCode;
+    use crate::platforms::openai::codex::executor::{CodexExecutor, ForwardRequest};
+    use crate::platforms::openai::codex::logger::RequestLogger;
+    use crate::platforms::openai::codex::pool::CodexPool;
+    use crate::platforms::openai::codex::pricing::ModelPriceTable;
+    use crate::platforms::openai::codex::storage::CodexLogStorage;
+    use crate::platforms::openai::codex::test_support::{MockReply, MockUpstream, pool_account};
+    use crate::platforms::openai::codex::translator::{self, ClientProtocol};
+
+    fn request_with_body(body: &Bytes) -> ForwardRequest {
+        ForwardRequest {
+            method: Method::POST,
+            path: "/v1/responses".to_string(),
+            query: None,
+            headers: HeaderMap::new(),
+            body: body.clone(),
+            format: "openai-responses".to_string(),
+            model: "gpt-5".to_string(),
+            client_key_id: None,
+            excluded_account_ids: Default::default(),
+            session_key: None,
+            pinned_account_id: None,
+        }
+    }
+
+    async fn setup_stream_test() -> (MockUpstream, Arc<CodexPool>, CodexExecutor) {
+        let upstream = MockUpstream::start().await;
+        let pool = Arc::new(CodexPool::new());
+        pool.add_account(pool_account("a")).await;
+        let executor =
+            CodexExecutor::with_client(pool.clone(), MockUpstream::client(), upstream.origin());
+        (upstream, pool, executor)
+    }
+
+    fn stream_request() -> ForwardRequest {
+        request_with_body(&Bytes::from(
+            json!({"model": "gpt-5", "input": "hi", "stream": true}).to_string(),
+        ))
+    }
+
+    fn created_event() -> Value {
+        json!({"type": "response.created", "response": {"id": "resp_mock", "model": "gpt-5"}})
+    }
+
+    #[tokio::test]
+    async fn unsupported_param_cache_persists_across_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let cache = UnsupportedParamCache::load(dir.path());
+        assert!(dir.path().join(UNSUPPORTED_PARAMS_FILE).exists());
+        cache.add("user_tag".to_string()).await;
+
+        let reloaded = UnsupportedParamCache::load(dir.path());
+        let body = Bytes::from(
+            json!({"model": "gpt-5", "user_tag": "x", "max_output_tokens": 10}).to_string(),
+        );
+        let stripped: Value =
+            serde_json::from_slice(&reloaded.strip_known_params(&body).await.unwrap()).unwrap();
+        assert_eq!(stripped, json!({"model": "gpt-5"}));
+    }
+
+    #[tokio::test]
+    async fn chat_max_tokens_is_not_forwarded_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = UnsupportedParamCache::load(dir.path());
+        let (upstream, _pool, executor) = setup_stream_test().await;
+
+        let chat = Bytes::from(
+            json!({
+                "model": "gpt-5",
+                "messages": [{"role": "user", "content": "hi"}],
+                "max_tokens": 64,
+                "max_completion_tokens": 64,
+            })
+            .to_string(),
+        );
+        let translated = translator::translate_request(ClientProtocol::ChatCompletions, &chat)
+            .unwrap()
+            .body;
+        let body = cache
+            .strip_known_params(&translated)
+            .await
+            .unwrap_or(translated);
+
+        let (response, _) = executor.forward(request_with_body(&body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let forwarded = &upstream.requests()[0].body;
+        assert_eq!(forwarded["model"], "gpt-5");
+        for key in ["max_output_tokens", "max_tokens", "max_completion_tokens"] {
+            assert!(forwarded.get(key).is_none(), "{} was forwarded", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_param_is_cached_and_stripped_before_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = UnsupportedParamCache::load(dir.path());
+
+        let upstream = MockUpstream::start().await;
+        upstream.reject_param("verbosity");
+        let pool = Arc::new(CodexPool::new());
+        pool.add_account(pool_account("a")).await;
+        let executor =
+            CodexExecutor::with_client(pool.clone(), MockUpstream::client(), upstream.origin());
+
+        let body = Bytes::from(json!({"model": "gpt-5", "verbosity": "low"}).to_string());
+        let (response, _) = executor.forward(request_with_body(&body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let upstream_body = response.bytes().await.unwrap();
+
+        let (param, retry_body) = strip_rejected_param(&cache, &body, &upstream_body)
+            .await
+            .unwrap();
+        assert_eq!(param, "verbosity");
+
+        let (response, _) = executor.forward(request_with_body(&retry_body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let requests = upstream.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].body.get("verbosity").is_none());
+
+        // 账号不会因为参数错误被冷却，且参数已写入磁盘
+        assert!(pool.get_account("a").await.unwrap().is_available());
+        let reloaded = UnsupportedParamCache::load(dir.path());
+        assert!(reloaded.strip_known_params(&body).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn upstream_error_reaches_claude_clients_in_anthropic_shape() {
+        let (upstream, _pool, executor) = setup_stream_test().await;
+        upstream.set_default(MockReply::Json(
+            400,
+            json!({"error": {"message": "bad input", "type": "invalid_request_error"}}),
+        ));
+
+        let body = Bytes::from(json!({"model": "gpt-5", "input": "hi"}).to_string());
+        let (response, _) = executor.forward(request_with_body(&body)).await.unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        let headers = response.headers().clone();
+        let upstream_body = response.bytes().await.unwrap();
+
+        let reply = build_client_buffered_response(
+            ClientProtocol::ClaudeMessages,
+            status,
+            &headers,
+            upstream_body.clone(),
+        )
+        .unwrap();
+        assert_eq!(reply.status(), StatusCode::BAD_REQUEST);
+        let bytes = hyper::body::to_bytes(reply.into_body()).await.unwrap();
+        let converted: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(converted["type"], "error");
+        assert_eq!(converted["error"]["type"], "invalid_request_error");
+        assert_eq!(converted["error"]["message"], "bad input");
+
+        // Responses 客户端仍原样收到上游错误体
+        let reply = build_client_buffered_response(
+            ClientProtocol::Responses,
+            status,
+            &headers,
+            upstream_body,
+        )
+        .unwrap();
+        let bytes = hyper::body::to_bytes(reply.into_body()).await.unwrap();
+        let passthrough: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(passthrough["error"]["message"], "bad input");
+    }
+
+    #[test]
+    fn rejection_codes_come_from_variants_not_message_text() {
+        // 上游错误文本中出现 unauthorized / timeout 不影响错误码
+        let upstream = CodexRejection::ExecutionError(
+            "upstream said: Unauthorized request timeout".to_string(),
+        );
+        assert_eq!(upstream.api_error().code, ApiErrorCode::ExecutionError);
+
+        let auth = CodexRejection::Unauthorized("Unauthorized: invalid API key".to_string());
+        assert_eq!(auth.api_error().code, ApiErrorCode::Unauthorized);
+        let timeout = CodexRejection::UpstreamTimeout("Request failed".to_string());
+        assert_eq!(timeout.api_error().code, ApiErrorCode::UpstreamTimeout);
+    }
+
+    #[test]
+    fn usage_includes_cached_and_reasoning_tokens() {
+        let completed = json!({
+            "type": "response.completed",
+            "response": {
+                "model": "gpt-5",
+                "usage": {
+                    "input_tokens": 1200,
+                    "input_tokens_details": {"cached_tokens": 1000},
+                    "output_tokens": 300,
+                    "output_tokens_details": {"reasoning_tokens": 256},
+                    "total_tokens": 1500,
+                },
+            },
+        });
+        let mut extractor = SseMetricsExtractor::default();
+        // 事件跨 chunk 到达
+        let sse = format!("data: {}\n\n", completed);
+        let (head, tail) = sse.split_at(40);
+        extractor.ingest_chunk(&Bytes::from(head.to_string()));
+        extractor.ingest_chunk(&Bytes::from(tail.to_string()));
+        extractor.finish();
+        assert_eq!(extractor.usage.input_tokens, 1200);
+        assert_eq!(extractor.usage.cached_tokens, 1000);
+        assert_eq!(extractor.usage.reasoning_tokens, 256);
+        assert_eq!(extractor.usage.total_tokens, 1500);
+
+        // Chat Completions 风格的 usage
+        let chat = Bytes::from(
+            json!({
+                "usage": {
+                    "prompt_tokens": 50,
+                    "prompt_tokens_details": {"cached_tokens": 20},
+                    "completion_tokens": 10,
+                    "completion_tokens_details": {"reasoning_tokens": 4},
+                },
+            })
+            .to_string(),
+        );
+        let usage = extract_usage_from_json_bytes(&chat);
+        assert_eq!(usage.total_tokens, 60);
+        assert_eq!(usage.cached_tokens, 20);
+        assert_eq!(usage.reasoning_tokens, 4);
+    }
+
+    #[test]
+    fn session_key_prefers_previous_response_then_headers_then_prompt() {
+        let mut headers = HeaderMap::new();
+        headers.insert("session_id", "abc".parse().unwrap());
+        let chained = Bytes::from(
+            json!({"previous_response_id": "resp_1", "input": [{"role": "user", "content": "hi"}]})
+                .to_string(),
+        );
+        assert_eq!(
+            derive_session_key(&headers, &chained).as_deref(),
+            Some("response:resp_1")
+        );
+
+        let body = |instructions: &str, first: &str, second: &str| {
+            Bytes::from(
+                json!({
+                    "instructions": instructions,
+                    "input": [
+                        {"role": "user", "content": first},
+                        {"role": "user", "content": second},
+                    ],
+                })
+                .to_string(),
+            )
+        };
+        assert_eq!(
+            derive_session_key(&headers, &body("sys", "hi", "more")).as_deref(),
+            Some("session:abc")
+        );
+
+        // 无请求头时按系统提示词 + 首条消息哈希，后续消息不影响 key
+        let empty = HeaderMap::new();
+        let key = derive_session_key(&empty, &body("sys", "hi", "more")).unwrap();
+        assert!(key.starts_with("prompt:"));
+        assert_eq!(
+            derive_session_key(&empty, &body("sys", "hi", "other")),
+            Some(key.clone())
+        );
+        assert_ne!(derive_session_key(&empty, &body("sys2", "hi", "more")), Some(key));
+        assert!(derive_session_key(&empty, &Bytes::from("{}")).is_none());
+    }
+
+    #[tokio::test]
+    async fn prefetch_detects_streams_aborted_before_output() {
+        let (upstream, _pool, executor) = setup_stream_test().await;
+        upstream.script(
+            "a",
+            [
+                MockReply::Sse(vec![created_event()]),
+                MockReply::SseAbort(vec![created_event()]),
+            ],
+        );
+
+        // 只有 response.created 就正常结束
+        let (response, _) = executor.forward(stream_request()).await.unwrap();
+        let err = prefetch_stream_preamble(&mut response.bytes_stream().boxed())
+            .await
+            .unwrap_err();
+        assert!(err.contains("ended before response.completed"), "{err}");
+
+        // 连接中断
+        let (response, _) = executor.forward(stream_request()).await.unwrap();
+        let err = prefetch_stream_preamble(&mut response.bytes_stream().boxed())
+            .await
+            .unwrap_err();
+        assert!(err.contains("Failed to read upstream stream"), "{err}");
+
+        // 完整响应：读到内容事件即停止预读
+        let (response, _) = executor.forward(stream_request()).await.unwrap();
+        let mut stream = response.bytes_stream().boxed();
+        let prefetched = prefetch_stream_preamble(&mut stream).await.unwrap();
+        let text: String = prefetched
+            .iter()
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+            .collect();
+        assert!(text.contains("response.output_text.delta"));
+    }
+
+    #[tokio::test]
+    async fn destream_reports_truncated_streams_as_aborted() {
+        let (upstream, pool, executor) = setup_stream_test().await;
+        let dir = tempfile::tempdir().unwrap();
+        let prices = Arc::new(ModelPriceTable::load(dir.path()));
+        let logger = Arc::new(RwLock::new(RequestLogger::new(100)));
+        upstream.script(
+            "a",
+            [MockReply::Sse(vec![
+                created_event(),
+                json!({"type": "response.output_text.delta", "delta": "partial"}),
+            ])],
+        );
+
+        for expect_aborted in [true, false] {
+            let (response, meta) = executor.forward(stream_request()).await.unwrap();
+            let outcome = destream_responses_sse(
+                StatusCode::OK,
+                response,
+                pool.clone(),
+                logger.clone(),
+                None,
+                prices.clone(),
+                meta,
+                "gpt-5".to_string(),
+                ClientProtocol::Responses,
+                None,
+            )
+            .await
+            .unwrap();
+            match outcome {
+                StreamOutcome::Aborted(_) => assert!(expect_aborted),
+                StreamOutcome::Response(response) => {
+                    assert!(!expect_aborted);
+                    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                    let body: Value = serde_json::from_slice(&body).unwrap();
+                    assert_eq!(body["status"], "completed");
+                }
+            }
+        }
+
+        // 完整响应的 id 绑定到账号，供 previous_response_id 续接
+        assert_eq!(
+            pool.pinned_account_id("response:resp_mock").as_deref(),
+            Some("a")
+        );
+
+        // 中断的尝试不记日志，由调用方决定换号还是记为失败
+        let logs = logger.read().await.get_recent_logs(10);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].status, "success");
+    }
+
+    #[tokio::test]
+    async fn stream_aborted_after_output_ends_with_error_event() {
+        let (upstream, pool, executor) = setup_stream_test().await;
+        let dir = tempfile::tempdir().unwrap();
+        let prices = Arc::new(ModelPriceTable::load(dir.path()));
+        let delta = json!({"type": "response.output_text.delta", "delta": "partial"});
+        upstream.script(
+            "a",
+            [
+                MockReply::SseAbort(vec![created_event(), delta.clone()]),
+                MockReply::Sse(vec![created_event(), delta]),
+            ],
+        );
+        let chat_request = translator::translate_request(
+            ClientProtocol::ChatCompletions,
+            &Bytes::from(
+                json!({"model": "gpt-5", "messages": [{"role": "user", "content": "hi"}], "stream": true})
+                    .to_string(),
+            ),
+        )
+        .unwrap();
+
+        // Responses 客户端收到 error 事件；Chat 客户端收到转换后的错误块与 [DONE]
+        for (protocol, expected) in [
+            (ClientProtocol::Responses, "event: error"),
+            (ClientProtocol::ChatCompletions, "data: [DONE]"),
+        ] {
+            let logger = Arc::new(RwLock::new(RequestLogger::new(100)));
+            let (response, meta) = executor.forward(stream_request()).await.unwrap();
+            let mut stream = response.bytes_stream().boxed();
+            let prefetched = prefetch_stream_preamble(&mut stream).await.unwrap();
+            let stream_translator =
+                translator::new_stream_translator(protocol, &chat_request, "gpt-5");
+            let response = build_streaming_response_with_metrics(
+                StatusCode::OK,
+                &HeaderMap::new(),
+                prefetched,
+                stream,
+                pool.clone(),
+                logger.clone(),
+                None,
+                prices.clone(),
+                meta,
+                "gpt-5".to_string(),
+                stream_translator,
+                None,
+            )
+            .unwrap();
+
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let body = String::from_utf8_lossy(&body);
+            assert!(body.contains("partial"), "{body}");
+            assert!(body.contains(expected), "{body}");
+            assert!(body.to_ascii_lowercase().contains("upstream stream"), "{body}");
+
+            // 日志在后台任务中写入
+            let mut logs = Vec::new();
+            for _ in 0..50 {
+                logs = logger.read().await.get_recent_logs(10);
+                if !logs.is_empty() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            assert_eq!(logs.len(), 1);
+            assert_eq!(logs[0].status, "error");
+            assert!(logs[0].error_message.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_stores_upstream_sse_before_translation() {
+        let (_upstream, pool, executor) = setup_stream_test().await;
+        let dir = tempfile::tempdir().unwrap();
+        let prices = Arc::new(ModelPriceTable::load(dir.path()));
+        let storage = Arc::new(CodexLogStorage::new(dir.path().to_path_buf()).unwrap());
+        let logger = Arc::new(RwLock::new(RequestLogger::new(100)));
+        let chat_request = translator::translate_request(
+            ClientProtocol::ChatCompletions,
+            &Bytes::from(
+                json!({"model": "gpt-5", "messages": [{"role": "user", "content": "hi"}], "stream": true})
+                    .to_string(),
+            ),
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer client-secret".parse().unwrap());
+        let capture =
+            CaptureRequest::new("POST", "/v1/responses", None, &headers, &chat_request.body);
+
+        let (response, meta) = executor.forward(stream_request()).await.unwrap();
+        let upstream_headers = response.headers().clone();
+        let mut stream = response.bytes_stream().boxed();
+        let prefetched = prefetch_stream_preamble(&mut stream).await.unwrap();
+        let response = build_streaming_response_with_metrics(
+            StatusCode::OK,
+            &upstream_headers,
+            prefetched,
+            stream,
+            pool.clone(),
+            logger.clone(),
+            Some(storage.clone()),
+            prices,
+            meta,
+            "gpt-5".to_string(),
+            translator::new_stream_translator(
+                ClientProtocol::ChatCompletions,
+                &chat_request,
+                "gpt-5",
+            ),
+            Some(capture),
+        )
+        .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("chat.completion.chunk"));
+
+        let mut captures = Vec::new();
+        for _ in 0..50 {
+            captures = storage.list_captures(10, 0).unwrap();
+            if !captures.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(captures.len(), 1);
+
+        // 抓包 id 与日志一致，响应体是转换前的上游 SSE
+        let log_id = logger.read().await.get_recent_logs(1)[0].id.clone();
+        let capture = storage.get_capture(&log_id).unwrap().unwrap();
+        assert_eq!(capture.account_id, "a");
+        assert_eq!(capture.upstream_status, 200);
+        assert_eq!(capture.request_headers["authorization"], "[REDACTED]");
+        assert!(capture.request_body.contains("\"input\""));
+        assert!(capture.response_body.contains("\"type\":\"response.created\""));
+        assert!(capture.response_body.contains("response.completed"));
+        assert!(!capture.response_body.contains("chat.completion.chunk"));
+        assert!(capture.response_headers.contains_key("content-type"));
+    }
+}
@@ -5,11 +5,17 @@
 use rusqlite::{Connection, params};
 use std::path::PathBuf;
 
+use super::capture::CAPTURE_STORAGE_LIMIT;
 use super::models::{
-    DailyStats, DailyStatsResponse, LogPage, LogQuery, ModelTokenStats, PeriodTokenStats,
-    RequestLog,
+    CaptureSummary, ClientTokenStats, DailyStats, DailyStatsResponse, LogPage, LogQuery,
+    ModelTokenStats, PeriodTokenStats, RequestCapture, RequestLog,
 };
 
+/// query_logs 读取的列，顺序与 row_to_log 中的下标一致
+const LOG_COLUMNS: &str = "id, timestamp, account_id, account_email, model, format, \
+     input_tokens, output_tokens, total_tokens, status, error_message, request_duration_ms, \
+     client_key_id, estimated_cost, cached_tokens, reasoning_tokens";
+
 /// 日志存储管理器
 #[derive(Debug)]
 pub struct CodexLogStorage {
@@ -59,12 +65,22 @@ impl CodexLogStorage {
                 status TEXT NOT NULL,
                 error_message TEXT,
                 request_duration_ms INTEGER,
-                date_key INTEGER NOT NULL
+                date_key INTEGER NOT NULL,
+                client_key_id TEXT,
+                estimated_cost REAL NOT NULL DEFAULT 0,
+                cached_tokens INTEGER NOT NULL DEFAULT 0,
+                reasoning_tokens INTEGER NOT NULL DEFAULT 0
             )",
             [],
         )
         .map_err(|e| format!("Failed to create table: {}", e))?;
 
+        // 旧版本数据库缺少的列
+        ensure_column(conn, "client_key_id", "TEXT")?;
+        ensure_column(conn, "estimated_cost", "REAL NOT NULL DEFAULT 0")?;
+        ensure_column(conn, "cached_tokens", "INTEGER NOT NULL DEFAULT 0")?;
+        ensure_column(conn, "reasoning_tokens", "INTEGER NOT NULL DEFAULT 0")?;
+
         // 创建索引
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_timestamp ON codex_requests(timestamp DESC)",
@@ -86,6 +102,41 @@ impl CodexLogStorage {
             [],
         )
         .map_err(|e| format!("Failed to create model index: {}", e))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_client_key ON codex_requests(client_key_id, timestamp)",
+            [],
+        )
+        .map_err(|e| format!("Failed to create client_key_id index: {}", e))?;
+
+        // 抓包旁表，id 与 codex_requests 相同
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS codex_captures (
+                id TEXT PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                account_id TEXT NOT NULL,
+                account_email TEXT NOT NULL,
+                model TEXT NOT NULL,
+                format TEXT NOT NULL,
+                method TEXT NOT NULL,
+                path TEXT NOT NULL,
+                query TEXT,
+                request_headers TEXT NOT NULL,
+                request_body TEXT NOT NULL,
+                request_truncated INTEGER NOT NULL DEFAULT 0,
+                upstream_status INTEGER NOT NULL,
+                response_headers TEXT NOT NULL,
+                response_body TEXT NOT NULL,
+                response_truncated INTEGER NOT NULL DEFAULT 0,
+                size INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| format!("Failed to create capture table: {}", e))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_capture_timestamp ON codex_captures(timestamp DESC)",
+            [],
+        )
+        .map_err(|e| format!("Failed to create capture timestamp index: {}", e))?;
 
         Ok(())
     }
@@ -108,8 +159,9 @@ impl CodexLogStorage {
                 "INSERT OR REPLACE INTO codex_requests
                  (id, timestamp, account_id, account_email, model, format,
                   input_tokens, output_tokens, total_tokens, status,
-                  error_message, request_duration_ms, date_key)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                  error_message, request_duration_ms, date_key, client_key_id, estimated_cost,
+                  cached_tokens, reasoning_tokens)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                 params![
                     log.id.clone(),
                     log.timestamp,
@@ -124,6 +176,10 @@ impl CodexLogStorage {
                     log.error_message.clone(),
                     log.request_duration_ms,
                     date_key,
+                    log.client_key_id.clone(),
+                    log.estimated_cost,
+                    log.cached_tokens,
+                    log.reasoning_tokens,
                 ],
             )
             .map_err(|e| format!("Failed to insert log: {}", e))?;
@@ -151,7 +207,7 @@ impl CodexLogStorage {
     pub fn query_logs(&self, query: &LogQuery) -> Result<LogPage, String> {
         let conn = self.get_connection()?;
 
-        let mut sql = String::from("SELECT * FROM codex_requests WHERE 1=1");
+        let mut sql = String::from("FROM codex_requests WHERE 1=1");
 
         if let Some(start_ts) = query.start_ts {
             sql.push_str(&format!(" AND timestamp >= {}", start_ts));
@@ -188,15 +244,24 @@ impl CodexLogStorage {
                 ));
             }
         }
+        if let Some(client_key_id) = &query.client_key_id {
+            if !client_key_id.trim().is_empty() {
+                sql.push_str(&format!(
+                    " AND client_key_id = '{}'",
+                    client_key_id.trim().replace('\'', "''")
+                ));
+            }
+        }
 
         // 先获取总数
-        let count_sql = sql.replace("SELECT *", "SELECT COUNT(*)");
+        let count_sql = format!("SELECT COUNT(*) {}", sql);
         let total: i64 = conn
             .query_row(&count_sql, [], |row| row.get(0))
             .map_err(|e| format!("Failed to count logs: {}", e))?;
         let total = total as usize;
 
         // 排序和分页
+        let mut sql = format!("SELECT {} {}", LOG_COLUMNS, sql);
         sql.push_str(&format!(
             " ORDER BY timestamp DESC LIMIT {} OFFSET {}",
             query.limit.unwrap_or(100).max(1),
@@ -209,22 +274,7 @@ impl CodexLogStorage {
 
         let mut items = Vec::new();
         let log_rows = stmt
-            .query_map([], |row| {
-                Ok(RequestLog {
-                    id: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    account_id: row.get(2)?,
-                    account_email: row.get(3)?,
-                    model: row.get(4)?,
-                    format: row.get(5)?,
-                    input_tokens: row.get(6)?,
-                    output_tokens: row.get(7)?,
-                    total_tokens: row.get(8)?,
-                    status: row.get(9)?,
-                    error_message: row.get(10)?,
-                    request_duration_ms: row.get(11)?,
-                })
-            })
+            .query_map([], row_to_log)
             .map_err(|e| format!("Failed to execute query: {}", e))?;
 
         for log in log_rows {
@@ -234,11 +284,12 @@ impl CodexLogStorage {
         Ok(LogPage { total, items })
     }
 
-    /// 获取模型统计
+    /// 获取模型统计（client_key_id 为 Some 时只统计该客户端）
     pub fn get_model_stats(
         &self,
         start_ts: i64,
         end_ts: i64,
+        client_key_id: Option<&str>,
     ) -> Result<Vec<ModelTokenStats>, String> {
         let conn = self.get_connection()?;
 
@@ -248,9 +299,13 @@ impl CodexLogStorage {
                     COUNT(*) as requests,
                     SUM(input_tokens) as input_tokens,
                     SUM(output_tokens) as output_tokens,
-                    SUM(total_tokens) as total_tokens
+                    SUM(total_tokens) as total_tokens,
+                    SUM(estimated_cost) as estimated_cost,
+                    SUM(cached_tokens) as cached_tokens,
+                    SUM(reasoning_tokens) as reasoning_tokens
              FROM codex_requests
              WHERE timestamp >= ?1 AND timestamp <= ?2
+               AND (?3 IS NULL OR client_key_id = ?3)
              GROUP BY model
              ORDER BY total_tokens DESC",
             )
@@ -258,13 +313,16 @@ impl CodexLogStorage {
 
         let mut stats = Vec::new();
         let rows = stmt
-            .query_map([start_ts, end_ts], |row| {
+            .query_map(params![start_ts, end_ts, client_key_id], |row| {
                 Ok(ModelTokenStats {
                     model: row.get(0)?,
                     requests: row.get(1)?,
                     input_tokens: row.get(2)?,
                     output_tokens: row.get(3)?,
                     total_tokens: row.get(4)?,
+                    estimated_cost: row.get(5)?,
+                    cached_tokens: row.get(6)?,
+                    reasoning_tokens: row.get(7)?,
                 })
             })
             .map_err(|e| format!("Failed to execute stats query: {}", e))?;
@@ -276,23 +334,35 @@ impl CodexLogStorage {
         Ok(stats)
     }
 
-    /// 获取周期统计
-    pub fn get_period_stats(&self, now_ts: i64) -> Result<PeriodTokenStats, String> {
+    /// 获取周期统计（client_key_id 为 Some 时只统计该客户端）
+    pub fn get_period_stats(
+        &self,
+        now_ts: i64,
+        client_key_id: Option<&str>,
+    ) -> Result<PeriodTokenStats, String> {
         let conn = self.get_connection()?;
 
-        let calculate_stats = |period_start: i64| -> Result<(u64, u64), String> {
+        let calculate_stats = |period_start: i64| -> Result<(u64, u64, f64), String> {
             let mut stmt = conn
                 .prepare(
-                    "SELECT COUNT(*) as requests, SUM(total_tokens) as tokens
+                    "SELECT COUNT(*) as requests, SUM(total_tokens) as tokens,
+                        SUM(estimated_cost) as cost
                  FROM codex_requests
-                 WHERE timestamp >= ?1 AND timestamp <= ?2",
+                 WHERE timestamp >= ?1 AND timestamp <= ?2
+                   AND (?3 IS NULL OR client_key_id = ?3)",
                 )
                 .map_err(|e| format!("Failed to prepare period query: {}", e))?;
 
-            let mut result = (0u64, 0u64);
+            let mut result = (0u64, 0u64, 0f64);
             let rows = stmt
-                .query_map([period_start, now_ts], |row| {
-                    Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64))
+                .query_map(params![period_start, now_ts, client_key_id], |row| {
+                    let tokens: Option<i64> = row.get(1)?;
+                    let cost: Option<f64> = row.get(2)?;
+                    Ok((
+                        row.get::<_, i64>(0)? as u64,
+                        tokens.unwrap_or(0) as u64,
+                        cost.unwrap_or(0.0),
+                    ))
                 })
                 .map_err(|e| format!("Failed to execute period query: {}", e))?;
 
@@ -322,9 +392,9 @@ impl CodexLogStorage {
             .map(|d| d.and_utc().timestamp())
             .unwrap_or(0);
 
-        let (today_requests, today_tokens) = calculate_stats(today_start)?;
-        let (week_requests, week_tokens) = calculate_stats(week_start)?;
-        let (month_requests, month_tokens) = calculate_stats(month_start)?;
+        let (today_requests, today_tokens, today_cost) = calculate_stats(today_start)?;
+        let (week_requests, week_tokens, week_cost) = calculate_stats(week_start)?;
+        let (month_requests, month_tokens, month_cost) = calculate_stats(month_start)?;
 
         Ok(PeriodTokenStats {
             today_requests,
@@ -333,9 +403,85 @@ impl CodexLogStorage {
             week_tokens,
             month_requests,
             month_tokens,
+            today_cost,
+            week_cost,
+            month_cost,
         })
     }
 
+    /// 按客户端 Key 分组统计
+    pub fn get_client_stats(
+        &self,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<ClientTokenStats>, String> {
+        let conn = self.get_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT client_key_id,
+                    COUNT(*) as requests,
+                    SUM(input_tokens) as input_tokens,
+                    SUM(output_tokens) as output_tokens,
+                    SUM(total_tokens) as total_tokens
+             FROM codex_requests
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             GROUP BY client_key_id
+             ORDER BY total_tokens DESC",
+            )
+            .map_err(|e| format!("Failed to prepare client stats query: {}", e))?;
+
+        let mut stats = Vec::new();
+        let rows = stmt
+            .query_map([start_ts, end_ts], |row| {
+                Ok(ClientTokenStats {
+                    client_key_id: row.get(0)?,
+                    client_name: None,
+                    requests: row.get(1)?,
+                    input_tokens: row.get(2)?,
+                    output_tokens: row.get(3)?,
+                    total_tokens: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to execute client stats query: {}", e))?;
+
+        for stat in rows {
+            stats.push(stat.map_err(|e| format!("Failed to read client stat row: {}", e))?);
+        }
+
+        Ok(stats)
+    }
+
+    /// 获取客户端 Key 自指定时间以来消耗的 token 数（预算校验用）
+    pub fn get_client_token_usage(&self, client_key_id: &str, since_ts: i64) -> Result<i64, String> {
+        let conn = self.get_connection()?;
+        conn.query_row(
+            "SELECT COALESCE(SUM(total_tokens), 0) FROM codex_requests
+             WHERE client_key_id = ?1 AND timestamp >= ?2",
+            params![client_key_id, since_ts],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to get client token usage: {}", e))
+    }
+
+    /// 获取自指定时间以来上游成功处理过的模型（按名称排序）
+    pub fn get_recent_models(&self, since_ts: i64) -> Result<Vec<String>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT model FROM codex_requests
+                 WHERE status = 'success' AND timestamp >= ?1
+                 ORDER BY model",
+            )
+            .map_err(|e| format!("Failed to prepare recent models query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![since_ts], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to execute recent models query: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read model row: {}", e))
+    }
+
     /// 获取每日统计数据（过去30天）
     pub fn get_daily_stats(&self, days: u32) -> Result<DailyStatsResponse, String> {
         let conn = self.get_connection()?;
@@ -358,30 +504,50 @@ impl CodexLogStorage {
 
             let mut stmt = conn
                 .prepare(
-                    "SELECT COUNT(*) as requests, SUM(total_tokens) as tokens
+                    "SELECT COUNT(*) as requests, SUM(total_tokens) as tokens,
+                        SUM(estimated_cost) as cost, SUM(cached_tokens) as cached_tokens,
+                        SUM(reasoning_tokens) as reasoning_tokens
                  FROM codex_requests
                  WHERE timestamp >= ?1 AND timestamp <= ?2",
                 )
                 .map_err(|e| format!("Failed to prepare daily stats query: {}", e))?;
 
-            let mut result = (0u64, 0u64);
+            let mut day = DailyStats {
+                date: date_str,
+                requests: 0,
+                tokens: 0,
+                cached_tokens: 0,
+                reasoning_tokens: 0,
+                cost: 0.0,
+            };
             let rows = stmt
                 .query_map([day_start, day_end], |row| {
                     let requests: i64 = row.get(0)?;
                     let tokens: Option<i64> = row.get(1)?;
-                    Ok((requests as u64, tokens.unwrap_or(0) as u64))
+                    let cost: Option<f64> = row.get(2)?;
+                    let cached_tokens: Option<i64> = row.get(3)?;
+                    let reasoning_tokens: Option<i64> = row.get(4)?;
+                    Ok((
+                        requests as u64,
+                        tokens.unwrap_or(0) as u64,
+                        cost.unwrap_or(0.0),
+                        cached_tokens.unwrap_or(0) as u64,
+                        reasoning_tokens.unwrap_or(0) as u64,
+                    ))
                 })
                 .map_err(|e| format!("Failed to execute daily stats query: {}", e))?;
 
             for row in rows {
-                result = row.map_err(|e| format!("Failed to read daily stats result: {}", e))?;
+                let (requests, tokens, cost, cached_tokens, reasoning_tokens) =
+                    row.map_err(|e| format!("Failed to read daily stats result: {}", e))?;
+                day.requests = requests;
+                day.tokens = tokens;
+                day.cost = cost;
+                day.cached_tokens = cached_tokens;
+                day.reasoning_tokens = reasoning_tokens;
             }
 
-            stats.push(DailyStats {
-                date: date_str,
-                requests: result.0,
-                tokens: result.1,
-            });
+            stats.push(day);
         }
 
         stats.reverse();
@@ -420,6 +586,143 @@ impl CodexLogStorage {
             .map_err(|e| format!("Failed to count logs: {}", e))
     }
 
+    /// 写入抓包，并删除超出总容量上限的最旧抓包
+    pub fn add_capture(&self, capture: &RequestCapture) -> Result<(), String> {
+        self.insert_capture(capture, CAPTURE_STORAGE_LIMIT)
+    }
+
+    fn insert_capture(&self, capture: &RequestCapture, storage_limit: i64) -> Result<(), String> {
+        let request_headers = serde_json::to_string(&capture.request_headers)
+            .map_err(|e| format!("Failed to serialize capture headers: {}", e))?;
+        let response_headers = serde_json::to_string(&capture.response_headers)
+            .map_err(|e| format!("Failed to serialize capture headers: {}", e))?;
+        let size = (capture.request_body.len() + capture.response_body.len()) as i64;
+
+        let mut conn = self.get_connection()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO codex_captures
+             (id, timestamp, account_id, account_email, model, format, method, path, query,
+              request_headers, request_body, request_truncated, upstream_status,
+              response_headers, response_body, response_truncated, size)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                capture.id,
+                capture.timestamp,
+                capture.account_id,
+                capture.account_email,
+                capture.model,
+                capture.format,
+                capture.method,
+                capture.path,
+                capture.query,
+                request_headers,
+                capture.request_body,
+                capture.request_truncated,
+                capture.upstream_status,
+                response_headers,
+                capture.response_body,
+                capture.response_truncated,
+                size,
+            ],
+        )
+        .map_err(|e| format!("Failed to insert capture: {}", e))?;
+        tx.execute(
+            "DELETE FROM codex_captures WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, SUM(size) OVER (ORDER BY timestamp DESC, rowid DESC) AS running
+                    FROM codex_captures
+                ) WHERE running > ?1
+            )",
+            [storage_limit],
+        )
+        .map_err(|e| format!("Failed to trim captures: {}", e))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    /// 抓包列表（按时间倒序，不含请求/响应体）
+    pub fn list_captures(&self, limit: usize, offset: usize) -> Result<Vec<CaptureSummary>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, timestamp, account_email, model, method, path, upstream_status, size
+                 FROM codex_captures
+                 ORDER BY timestamp DESC, rowid DESC
+                 LIMIT ?1 OFFSET ?2",
+            )
+            .map_err(|e| format!("Failed to prepare capture query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![limit.max(1) as i64, offset as i64], |row| {
+                Ok(CaptureSummary {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    account_email: row.get(2)?,
+                    model: row.get(3)?,
+                    method: row.get(4)?,
+                    path: row.get(5)?,
+                    upstream_status: row.get(6)?,
+                    size: row.get(7)?,
+                })
+            })
+            .map_err(|e| format!("Failed to execute capture query: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read capture row: {}", e))
+    }
+
+    /// 获取单个抓包
+    pub fn get_capture(&self, id: &str) -> Result<Option<RequestCapture>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, timestamp, account_id, account_email, model, format, method, path,
+                    query, request_headers, request_body, request_truncated, upstream_status,
+                    response_headers, response_body, response_truncated
+                 FROM codex_captures WHERE id = ?1",
+            )
+            .map_err(|e| format!("Failed to prepare capture query: {}", e))?;
+
+        let mut rows = stmt
+            .query_map([id], |row| {
+                let request_headers: String = row.get(9)?;
+                let response_headers: String = row.get(13)?;
+                Ok(RequestCapture {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    account_id: row.get(2)?,
+                    account_email: row.get(3)?,
+                    model: row.get(4)?,
+                    format: row.get(5)?,
+                    method: row.get(6)?,
+                    path: row.get(7)?,
+                    query: row.get(8)?,
+                    request_headers: serde_json::from_str(&request_headers).unwrap_or_default(),
+                    request_body: row.get(10)?,
+                    request_truncated: row.get(11)?,
+                    upstream_status: row.get(12)?,
+                    response_headers: serde_json::from_str(&response_headers).unwrap_or_default(),
+                    response_body: row.get(14)?,
+                    response_truncated: row.get(15)?,
+                })
+            })
+            .map_err(|e| format!("Failed to execute capture query: {}", e))?;
+
+        rows.next()
+            .transpose()
+            .map_err(|e| format!("Failed to read capture row: {}", e))
+    }
+
+    /// 清空所有抓包
+    pub fn clear_captures(&self) -> Result<usize, String> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM codex_captures", [])
+            .map_err(|e| format!("Failed to clear captures: {}", e))
+    }
+
     /// 获取全时间累计统计
     pub fn get_all_time_stats(&self) -> Result<(u64, u64), String> {
         let conn = self.get_connection()?;
@@ -436,9 +739,208 @@ impl CodexLogStorage {
     }
 }
 
+fn row_to_log(row: &rusqlite::Row<'_>) -> rusqlite::Result<RequestLog> {
+    Ok(RequestLog {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        account_id: row.get(2)?,
+        account_email: row.get(3)?,
+        model: row.get(4)?,
+        format: row.get(5)?,
+        input_tokens: row.get(6)?,
+        output_tokens: row.get(7)?,
+        total_tokens: row.get(8)?,
+        status: row.get(9)?,
+        error_message: row.get(10)?,
+        request_duration_ms: row.get(11)?,
+        client_key_id: row.get(12)?,
+        estimated_cost: row.get(13)?,
+        cached_tokens: row.get(14)?,
+        reasoning_tokens: row.get(15)?,
+    })
+}
+
+/// 为旧版本创建的表补充缺失的列
+fn ensure_column(conn: &Connection, column: &str, definition: &str) -> Result<(), String> {
+    let exists = conn
+        .prepare("SELECT 1 FROM pragma_table_info('codex_requests') WHERE name = ?1")
+        .and_then(|mut stmt| stmt.exists([column]))
+        .map_err(|e| format!("Failed to inspect codex_requests columns: {}", e))?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE codex_requests ADD COLUMN {} {}", column, definition),
+            [],
+        )
+        .map_err(|e| format!("Failed to add column {}: {}", column, e))?;
+    }
+    Ok(())
+}
+
 /// 计算日期键 (YYYYMMDD)
 fn calculate_date_key(timestamp: i64) -> i64 {
     let dt = chrono::DateTime::from_timestamp(timestamp, 0).unwrap_or_else(chrono::Utc::now);
     let date = dt.format("%Y%m%d").to_string();
     date.parse().unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CodexLogStorage;
+    use crate::platforms::openai::codex::models::{LogQuery, RequestCapture, RequestLog};
+    use std::collections::BTreeMap;
+
+    fn sample_log(id: &str, client_key_id: Option<&str>, model: &str, tokens: i64) -> RequestLog {
+        RequestLog {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            account_id: "acct".to_string(),
+            account_email: "acct@example.com".to_string(),
+            model: model.to_string(),
+            format: "openai-responses".to_string(),
+            client_key_id: client_key_id.map(str::to_string),
+            input_tokens: tokens / 2,
+            output_tokens: tokens - tokens / 2,
+            total_tokens: tokens,
+            cached_tokens: tokens / 4,
+            reasoning_tokens: tokens / 10,
+            estimated_cost: tokens as f64 / 1000.0,
+            status: "success".to_string(),
+            error_message: None,
+            request_duration_ms: Some(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn migrates_legacy_schema_and_splits_stats_by_client() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs_dir = dir.path().join("logs");
+        std::fs::create_dir_all(&logs_dir).unwrap();
+        {
+            // 旧版本的表结构，没有 client_key_id 列
+            let conn = rusqlite::Connection::open(logs_dir.join("codex_logs.db")).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE codex_requests (
+                    id TEXT PRIMARY KEY, timestamp INTEGER NOT NULL, account_id TEXT NOT NULL,
+                    account_email TEXT NOT NULL, model TEXT NOT NULL, format TEXT NOT NULL,
+                    input_tokens INTEGER NOT NULL DEFAULT 0, output_tokens INTEGER NOT NULL DEFAULT 0,
+                    total_tokens INTEGER NOT NULL DEFAULT 0, status TEXT NOT NULL,
+                    error_message TEXT, request_duration_ms INTEGER, date_key INTEGER NOT NULL
+                );
+                INSERT INTO codex_requests VALUES
+                    ('legacy', strftime('%s','now'), 'acct', 'acct@example.com', 'gpt-5',
+                     'openai-responses', 1, 1, 2, 'success', NULL, 5, 20250101);",
+            )
+            .unwrap();
+        }
+
+        let storage = CodexLogStorage::new(dir.path().to_path_buf()).unwrap();
+        storage.add_log(sample_log("a1", Some("alice"), "gpt-5", 100)).await;
+        storage.add_log(sample_log("a2", Some("alice"), "gpt-4o", 50)).await;
+        storage.add_log(sample_log("b1", Some("bob"), "gpt-5", 10)).await;
+
+        let now = chrono::Utc::now().timestamp();
+        let alice_models = storage.get_model_stats(0, now + 1, Some("alice")).unwrap();
+        assert_eq!(alice_models.len(), 2);
+        assert_eq!(alice_models[0].model, "gpt-5");
+        assert_eq!(alice_models[0].total_tokens, 100);
+        assert!((alice_models[0].estimated_cost - 0.1).abs() < 1e-9);
+        assert_eq!(alice_models[0].cached_tokens, 25);
+        assert_eq!(alice_models[0].reasoning_tokens, 10);
+
+        let clients = storage.get_client_stats(0, now + 1).unwrap();
+        assert_eq!(clients[0].client_key_id.as_deref(), Some("alice"));
+        assert_eq!(clients[0].total_tokens, 150);
+        assert!(clients.iter().any(|c| c.client_key_id.is_none() && c.total_tokens == 2));
+
+        let bob_period = storage.get_period_stats(now, Some("bob")).unwrap();
+        assert_eq!(bob_period.today_requests, 1);
+        assert_eq!(bob_period.today_tokens, 10);
+        assert!((bob_period.today_cost - 0.01).abs() < 1e-9);
+
+        // 旧数据迁移后成本与缓存/推理 token 均为 0
+        let daily = storage.get_daily_stats(1).unwrap();
+        assert_eq!(daily.stats[0].requests, 4);
+        assert!((daily.stats[0].cost - 0.16).abs() < 1e-9);
+        assert_eq!(daily.stats[0].cached_tokens, 25 + 12 + 2);
+        assert_eq!(daily.stats[0].reasoning_tokens, 10 + 5 + 1);
+        assert_eq!(storage.get_client_token_usage("alice", 0).unwrap(), 150);
+
+        let page = storage
+            .query_logs(&LogQuery {
+                limit: None,
+                offset: None,
+                start_ts: None,
+                end_ts: None,
+                model: None,
+                format: None,
+                status: None,
+                account_id: None,
+                client_key_id: Some("bob".to_string()),
+            })
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].id, "b1");
+        assert_eq!(page.items[0].cached_tokens, 2);
+        assert_eq!(page.items[0].reasoning_tokens, 1);
+
+        // 近期模型只包含成功的请求
+        let mut failed = sample_log("f1", None, "gpt-9", 0);
+        failed.status = "error".to_string();
+        storage.add_log(failed).await;
+        assert_eq!(storage.get_recent_models(0).unwrap(), vec!["gpt-4o", "gpt-5"]);
+        assert!(storage.get_recent_models(now + 10).unwrap().is_empty());
+    }
+
+    fn sample_capture(id: &str, timestamp: i64, body_len: usize) -> RequestCapture {
+        RequestCapture {
+            id: id.to_string(),
+            timestamp,
+            account_id: "acct".to_string(),
+            account_email: "acct@example.com".to_string(),
+            model: "gpt-5".to_string(),
+            format: "openai-responses".to_string(),
+            method: "POST".to_string(),
+            path: "/v1/responses".to_string(),
+            query: None,
+            request_headers: BTreeMap::from([(
+                "authorization".to_string(),
+                "[REDACTED]".to_string(),
+            )]),
+            request_body: "r".repeat(body_len),
+            request_truncated: false,
+            upstream_status: 200,
+            response_headers: BTreeMap::new(),
+            response_body: "data: {}\n\n".to_string(),
+            response_truncated: false,
+        }
+    }
+
+    #[test]
+    fn captures_are_trimmed_oldest_first_to_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = CodexLogStorage::new(dir.path().to_path_buf()).unwrap();
+
+        // 每条约 108 字节，上限 250 字节只能保留最新的两条
+        for (i, id) in ["c1", "c2", "c3"].iter().enumerate() {
+            storage
+                .insert_capture(&sample_capture(id, 100 + i as i64, 100), 250)
+                .unwrap();
+        }
+        let ids: Vec<String> = storage
+            .list_captures(10, 0)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["c3", "c2"]);
+        assert!(storage.get_capture("c1").unwrap().is_none());
+
+        let capture = storage.get_capture("c3").unwrap().unwrap();
+        assert_eq!(capture.request_headers["authorization"], "[REDACTED]");
+        assert_eq!(capture.request_body.len(), 100);
+        assert_eq!(capture.upstream_status, 200);
+
+        assert_eq!(storage.clear_captures().unwrap(), 2);
+        assert!(storage.list_captures(10, 0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+//! 测试辅助：进程内 mock 上游与号池账号构造
+//!
+//! MockUpstream 按 chatgpt-account-id 为每个账号编排响应序列，可模拟 401/402/403/429/5xx、
+//! SSE 流（含中途断开）以及 "Unsupported parameter" 错误，并记录收到的请求供断言。
+//! MockRefresher 为号池提供可计数的 token 刷新。
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use hyper::Body;
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+use warp::Filter;
+use warp::http::{HeaderMap, Response, StatusCode};
+use warp::path::FullPath;
+
+use super::models::CodexPoolAccount;
+use super::pool::{RefreshedToken, TokenRefresher};
+use crate::proxy_helper::ProxyClient;
+
+/// 构造一个可用的号池账号，chatgpt_account_id 与 id 相同
+pub fn pool_account(id: &str) -> CodexPoolAccount {
+    let now = chrono::Utc::now().timestamp();
+    CodexPoolAccount {
+        id: id.to_string(),
+        email: format!("{id}@example.com"),
+        access_token: format!("access-{id}"),
+        refresh_token: Some("refresh".to_string()),
+        id_token: None,
+        expires_at: now + 3600,
+        chatgpt_account_id: id.to_string(),
+        chatgpt_user_id: None,
+        organization_id: None,
+        is_active: true,
+        is_forbidden: false,
+        last_used: Some(now),
+        last_refresh: None,
+        cooldown_until: None,
+        unavailable_reason: None,
+        last_error_status: None,
+        daily_quota: None,
+        used_quota: 0,
+        total_tokens_used: 0,
+        codex_5h_used_percent: None,
+        codex_7d_used_percent: None,
+        plan_type: None,
+        subscription_expires_at: None,
+        priority: 0,
+        weight: 1,
+        reserved_models: Vec::new(),
+        tag: None,
+        tag_color: None,
+    }
+}
+
+/// 测试用 token 刷新器：第 n 次刷新返回 `fresh-{id}-{n}`，有效期 1 小时
+#[derive(Default)]
+pub struct MockRefresher {
+    calls: AtomicUsize,
+}
+
+impl MockRefresher {
+    /// 已调用刷新的次数
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenRefresher for MockRefresher {
+    async fn refresh(&self, account: &CodexPoolAccount) -> Result<RefreshedToken, String> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        // 模拟网络耗时，便于并发请求在刷新期间排队
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(RefreshedToken {
+            access_token: format!("fresh-{}-{}", account.id, n),
+            refresh_token: Some(format!("refresh-{}", n)),
+            id_token: None,
+            expires_at: chrono::Utc::now().timestamp() + 3600,
+        })
+    }
+}
+
+/// mock 上游的一次响应
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    /// 指定状态码 + 通用 JSON 错误体
+    Status(u16),
+    /// 指定状态码 + 自定义 JSON 体
+    Json(u16, Value),
+    /// 指定状态码 + 通用 JSON 错误体 + 一个额外响应头（如 retry-after）
+    StatusWithHeader(u16, &'static str, String),
+    /// 200 + SSE 流，每个元素为一个 data 事件
+    Sse(Vec<Value>),
+    /// 200 + SSE 流，发送完给定事件后连接异常中断
+    SseAbort(Vec<Value>),
+}
+
+impl MockReply {
+    /// 一次完整的 Responses SSE：created → 文本增量 → completed（带 usage）
+    pub fn completed(text: &str, input_tokens: i64, output_tokens: i64) -> Self {
+        Self::Sse(vec![
+            json!({"type": "response.created", "response": {"id": "resp_mock", "model": "gpt-5"}}),
+            json!({"type": "response.output_text.delta", "delta": text}),
+            json!({
+                "type": "response.completed",
+                "response": {
+                    "id": "resp_mock",
+                    "model": "gpt-5",
+                    "status": "completed",
+                    "output": [{
+                        "type": "message",
+                        "role": "assistant",
+                        "content": [{"type": "output_text", "text": text}],
+                    }],
+                    "usage": {
+                        "input_tokens": input_tokens,
+                        "output_tokens": output_tokens,
+                        "total_tokens": input_tokens + output_tokens,
+                    },
+                },
+            }),
+        ])
+    }
+}
+
+/// mock 上游收到的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub account_id: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub authorization: Option<String>,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    scripts: HashMap<String, VecDeque<MockReply>>,
+    default_reply: Option<MockReply>,
+    rejected_params: Vec<String>,
+    response_headers: HashMap<String, Vec<(String, String)>>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// 进程内 mock 上游，drop 时自动关闭
+pub struct MockUpstream {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockUpstream {
+    /// 在 127.0.0.1 的随机端口上启动
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let route_state = state.clone();
+
+        let route = warp::any()
+            .and(warp::path::full())
+            .and(
+                warp::query::raw()
+                    .map(Some)
+                    .or(warp::any().map(|| None))
+                    .unify(),
+            )
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .map(
+                move |path: FullPath, query: Option<String>, headers: HeaderMap, body: Bytes| {
+                    respond(&route_state, path.as_str(), query, &headers, &body)
+                },
+            );
+
+        let (tx, rx) = oneshot::channel::<()>();
+        let (addr, server) =
+            warp::serve(route).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                let _ = rx.await;
+            });
+        tokio::spawn(server);
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(tx),
+        }
+    }
+
+    /// 供 CodexExecutor 使用的上游地址
+    pub fn origin(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 不走系统代理的客户端，保证请求直达 mock
+    pub fn client() -> ProxyClient {
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("failed to build test client");
+        ProxyClient::new(client, None)
+    }
+
+    /// 为指定账号追加响应序列，用完后回退到默认响应
+    pub fn script(&self, account_id: &str, replies: impl IntoIterator<Item = MockReply>) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(account_id.to_string())
+            .or_default()
+            .extend(replies);
+    }
+
+    /// 设置未编排时的默认响应（缺省为一次成功的 SSE）
+    pub fn set_default(&self, reply: MockReply) {
+        self.state.lock().unwrap().default_reply = Some(reply);
+    }
+
+    /// 请求体包含该参数时返回 400 "Unsupported parameter"
+    pub fn reject_param(&self, param: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .rejected_params
+            .push(param.to_string());
+    }
+
+    /// 为指定账号的每个响应附加响应头（如 x-codex-* 用量）
+    pub fn set_response_headers(&self, account_id: &str, headers: &[(&str, &str)]) {
+        self.state.lock().unwrap().response_headers.insert(
+            account_id.to_string(),
+            headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        );
+    }
+
+    /// 已收到的请求（按到达顺序）
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn respond(
+    state: &Mutex<MockState>,
+    path: &str,
+    query: Option<String>,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Response<Body> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let body = serde_json::from_slice::<Value>(body).unwrap_or(Value::Null);
+    let account_id = header("chatgpt-account-id").unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    state.requests.push(RecordedRequest {
+        account_id: account_id.clone(),
+        path: path.to_string(),
+        query,
+        authorization: header("authorization"),
+        body: body.clone(),
+    });
+
+    if let Some(param) = state
+        .rejected_params
+        .iter()
+        .find(|p| body.get(p.as_str()).is_some())
+    {
+        return json_response(
+            400,
+            &json!({"detail": format!("Unsupported parameter: {}", param)}),
+        );
+    }
+
+    let reply = state
+        .scripts
+        .get_mut(&account_id)
+        .and_then(|queue| queue.pop_front())
+        .or_else(|| state.default_reply.clone())
+        .unwrap_or_else(|| MockReply::completed("ok", 10, 5));
+    let extra_headers = state
+        .response_headers
+        .get(&account_id)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut response = match reply {
+        MockReply::Status(status) => json_response(
+            status,
+            &json!({"error": {"message": format!("mock upstream status {}", status)}}),
+        ),
+        MockReply::Json(status, value) => json_response(status, &value),
+        MockReply::StatusWithHeader(status, name, value) => {
+            let mut response = json_response(
+                status,
+                &json!({"error": {"message": format!("mock upstream status {}", status)}}),
+            );
+            response
+                .headers_mut()
+                .insert(name, value.parse().unwrap());
+            response
+        }
+        MockReply::Sse(events) => sse_response(Body::from(sse_text(&events))),
+        MockReply::SseAbort(events) => {
+            let head = futures::stream::iter([Ok::<_, std::io::Error>(sse_text(&events))]);
+            // 稍作等待，保证响应头与已发送的事件先到达客户端
+            let abort = futures::stream::once(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionReset,
+                    "mock upstream aborted",
+                ))
+            });
+            sse_response(Body::wrap_stream(head.chain(abort)))
+        }
+    };
+    for (name, value) in extra_headers {
+        response.headers_mut().insert(
+            warp::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+    }
+    response
+}
+
+fn sse_text(events: &[Value]) -> String {
+    let mut out = String::new();
+    for event in events {
+        out.push_str("data: ");
+        out.push_str(&event.to_string());
+        out.push_str("\n\n");
+    }
+    out
+}
+
+fn sse_response(body: Body) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .body(body)
+        .unwrap()
+}
+
+fn json_response(status: u16, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
+        .header("content-type", "application/json")
+        .body(Body::from(value.to_string()))
+        .unwrap()
+}
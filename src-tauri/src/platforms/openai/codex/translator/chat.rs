@@ -0,0 +1,750 @@
+//! Chat Completions ⇄ Responses 转换
+//!
+//! - 请求：messages / tools / tool_choice / response_format → Responses input 结构
+//! - 响应：Responses SSE → chat.completion.chunk，或 response.completed → chat.completion
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use serde_json::{Map, Value, json};
+
+use super::{StreamTranslator, TranslatedRequest, usage_field, write_sse_data};
+
+// ==================== 请求转换 ====================
+
+/// 将 Chat Completions 请求体转换为 Responses 请求体
+pub fn translate_request(body: &Bytes) -> Result<TranslatedRequest, String> {
+    let root: Value =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON body: {}", e))?;
+    let obj = root
+        .as_object()
+        .ok_or_else(|| "Request body must be a JSON object".to_string())?;
+    let messages = obj
+        .get("messages")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "`messages` must be an array".to_string())?;
+
+    let mut instructions: Vec<String> = Vec::new();
+    let mut input: Vec<Value> = Vec::new();
+
+    for message in messages {
+        let role = message
+            .get("role")
+            .and_then(|v| v.as_str())
+            .unwrap_or("user");
+        match role {
+            // Codex 后端通过 instructions 接收系统提示
+            "system" | "developer" => {
+                let text = content_to_text(message.get("content"));
+                if !text.is_empty() {
+                    instructions.push(text);
+                }
+            }
+            "user" => {
+                input.push(json!({
+                    "type": "message",
+                    "role": "user",
+                    "content": user_content_parts(message.get("content"))?,
+                }));
+            }
+            "assistant" => {
+                let text = content_to_text(message.get("content"));
+                if !text.is_empty() {
+                    input.push(json!({
+                        "type": "message",
+                        "role": "assistant",
+                        "content": [{"type": "output_text", "text": text}],
+                    }));
+                }
+                if let Some(calls) = message.get("tool_calls").and_then(|v| v.as_array()) {
+                    for call in calls {
+                        input.push(function_call_item(
+                            call.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
+                            call.get("function"),
+                        ));
+                    }
+                }
+                // 旧版 function_call 字段
+                if let Some(call) = message.get("function_call") {
+                    let name = call.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+                    input.push(function_call_item(name, Some(call)));
+                }
+            }
+            "tool" | "function" => {
+                let call_id = message
+                    .get("tool_call_id")
+                    .or_else(|| message.get("name"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                input.push(json!({
+                    "type": "function_call_output",
+                    "call_id": call_id,
+                    "output": content_to_text(message.get("content")),
+                }));
+            }
+            other => return Err(format!("Unsupported message role: {}", other)),
+        }
+    }
+
+    let mut out = Map::new();
+    if let Some(model) = obj.get("model") {
+        out.insert("model".to_string(), model.clone());
+    }
+    if !instructions.is_empty() {
+        out.insert("instructions".to_string(), json!(instructions.join("\n\n")));
+    }
+    out.insert("input".to_string(), Value::Array(input));
+
+    if let Some(tools) = obj.get("tools").and_then(|v| v.as_array()) {
+        let converted = tools
+            .iter()
+            .map(convert_tool)
+            .collect::<Result<Vec<_>, _>>()?;
+        if !converted.is_empty() {
+            out.insert("tools".to_string(), Value::Array(converted));
+        }
+    } else if let Some(functions) = obj.get("functions").and_then(|v| v.as_array()) {
+        let converted: Vec<Value> = functions.iter().map(function_definition).collect();
+        out.insert("tools".to_string(), Value::Array(converted));
+    }
+
+    if let Some(choice) = obj.get("tool_choice").or_else(|| obj.get("function_call")) {
+        out.insert("tool_choice".to_string(), convert_tool_choice(choice));
+    }
+    if let Some(parallel) = obj.get("parallel_tool_calls") {
+        out.insert("parallel_tool_calls".to_string(), parallel.clone());
+    }
+    if let Some(format) = obj.get("response_format") {
+        out.insert(
+            "text".to_string(),
+            json!({"format": convert_response_format(format)?}),
+        );
+    }
+    if let Some(effort) = obj.get("reasoning_effort").and_then(|v| v.as_str()) {
+        out.insert(
+            "reasoning".to_string(),
+            json!({"effort": effort, "summary": "auto"}),
+        );
+    }
+    for key in ["temperature", "top_p"] {
+        if let Some(v) = obj.get(key) {
+            out.insert(key.to_string(), v.clone());
+        }
+    }
+    // max_tokens / max_completion_tokens 不转换：Codex 后端不支持 max_output_tokens，
+    // 该参数在转发前总会被移除（见 BUILTIN_UNSUPPORTED_PARAMS），输出长度由上游决定
+
+    let client_stream = obj.get("stream").and_then(|v| v.as_bool()).unwrap_or(false);
+    let include_usage = root
+        .pointer("/stream_options/include_usage")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    // ChatGPT Codex 后端要求 stream: true 且不存储会话
+    out.insert("stream".to_string(), json!(true));
+    out.insert("store".to_string(), json!(false));
+
+    let body = serde_json::to_vec(&Value::Object(out))
+        .map_err(|e| format!("Failed to serialize translated request: {}", e))?;
+
+    Ok(TranslatedRequest {
+        body: Bytes::from(body),
+        client_stream,
+        include_usage,
+    })
+}
+
+/// 将 content（字符串或 part 数组）拼接为纯文本
+fn content_to_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn user_content_parts(content: Option<&Value>) -> Result<Vec<Value>, String> {
+    match content {
+        Some(Value::String(s)) => Ok(vec![json!({"type": "input_text", "text": s})]),
+        Some(Value::Array(parts)) => parts.iter().map(convert_user_part).collect(),
+        Some(Value::Null) | None => Ok(vec![]),
+        Some(_) => Err("Message content must be a string or an array".to_string()),
+    }
+}
+
+fn convert_user_part(part: &Value) -> Result<Value, String> {
+    let part_type = part.get("type").and_then(|v| v.as_str()).unwrap_or("text");
+    match part_type {
+        "text" => Ok(json!({
+            "type": "input_text",
+            "text": part.get("text").and_then(|v| v.as_str()).unwrap_or_default(),
+        })),
+        "image_url" => {
+            let image = part.get("image_url");
+            let url = image
+                .and_then(|v| v.get("url").or(Some(v)))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "image_url part is missing url".to_string())?;
+            let mut converted = json!({"type": "input_image", "image_url": url});
+            if let Some(detail) = image.and_then(|v| v.get("detail")) {
+                converted["detail"] = detail.clone();
+            }
+            Ok(converted)
+        }
+        "file" => {
+            let file = part.get("file").cloned().unwrap_or(Value::Null);
+            let mut converted = json!({"type": "input_file"});
+            for key in ["file_id", "file_data", "filename"] {
+                if let Some(v) = file.get(key) {
+                    converted[key] = v.clone();
+                }
+            }
+            Ok(converted)
+        }
+        other => Err(format!("Unsupported content part type: {}", other)),
+    }
+}
+
+fn function_call_item(call_id: &str, function: Option<&Value>) -> Value {
+    let name = function
+        .and_then(|f| f.get("name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let arguments = function
+        .and_then(|f| f.get("arguments"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("{}");
+    json!({
+        "type": "function_call",
+        "call_id": call_id,
+        "name": name,
+        "arguments": arguments,
+    })
+}
+
+fn convert_tool(tool: &Value) -> Result<Value, String> {
+    let tool_type = tool.get("type").and_then(|v| v.as_str()).unwrap_or("function");
+    if tool_type != "function" {
+        return Err(format!("Unsupported tool type: {}", tool_type));
+    }
+    let function = tool
+        .get("function")
+        .ok_or_else(|| "Function tool is missing `function`".to_string())?;
+    Ok(function_definition(function))
+}
+
+/// Chat 的 {name, description, parameters, strict} → Responses 的扁平 function 工具
+fn function_definition(function: &Value) -> Value {
+    let mut converted = json!({
+        "type": "function",
+        "name": function.get("name").cloned().unwrap_or(Value::Null),
+        "parameters": function
+            .get("parameters")
+            .cloned()
+            .unwrap_or_else(|| json!({"type": "object", "properties": {}})),
+    });
+    for key in ["description", "strict"] {
+        if let Some(v) = function.get(key) {
+            converted[key] = v.clone();
+        }
+    }
+    converted
+}
+
+fn convert_tool_choice(choice: &Value) -> Value {
+    match choice {
+        Value::String(_) => choice.clone(),
+        Value::Object(obj) => {
+            // {"type":"function","function":{"name":...}} 或旧版 {"name":...}
+            let name = obj
+                .get("function")
+                .and_then(|f| f.get("name"))
+                .or_else(|| obj.get("name"))
+                .cloned()
+                .unwrap_or(Value::Null);
+            json!({"type": "function", "name": name})
+        }
+        _ => json!("auto"),
+    }
+}
+
+fn convert_response_format(format: &Value) -> Result<Value, String> {
+    let format_type = format.get("type").and_then(|v| v.as_str()).unwrap_or("text");
+    match format_type {
+        "text" | "json_object" => Ok(json!({"type": format_type})),
+        "json_schema" => {
+            let schema = format
+                .get("json_schema")
+                .ok_or_else(|| "json_schema response_format is missing `json_schema`".to_string())?;
+            let mut converted = json!({
+                "type": "json_schema",
+                "name": schema.get("name").cloned().unwrap_or_else(|| json!("response")),
+                "schema": schema.get("schema").cloned().unwrap_or_else(|| json!({})),
+            });
+            for key in ["description", "strict"] {
+                if let Some(v) = schema.get(key) {
+                    converted[key] = v.clone();
+                }
+            }
+            Ok(converted)
+        }
+        other => Err(format!("Unsupported response_format type: {}", other)),
+    }
+}
+
+// ==================== 响应转换 ====================
+
+/// 将 Responses 完整响应对象转换为 chat.completion
+pub fn response_to_chat_completion(response: &Value, fallback_model: &str) -> Value {
+    // 上游返回失败时保持 OpenAI 错误结构
+    if let Some(error) = response.get("error").filter(|e| {
+        !e.is_null()
+            && (response.get("output").is_none() || response.get("status") == Some(&json!("failed")))
+    }) {
+        return json!({"error": error});
+    }
+
+    let mut content = String::new();
+    let mut reasoning = String::new();
+    let mut refusal = String::new();
+    let mut tool_calls = Vec::new();
+
+    for item in response
+        .get("output")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("message") => {
+                for part in item
+                    .get("content")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    match part.get("type").and_then(|v| v.as_str()) {
+                        Some("output_text") => {
+                            content.push_str(part.get("text").and_then(|v| v.as_str()).unwrap_or(""))
+                        }
+                        Some("refusal") => refusal
+                            .push_str(part.get("refusal").and_then(|v| v.as_str()).unwrap_or("")),
+                        _ => {}
+                    }
+                }
+            }
+            Some("function_call") => tool_calls.push(json!({
+                "id": item.get("call_id").cloned().unwrap_or(Value::Null),
+                "type": "function",
+                "function": {
+                    "name": item.get("name").cloned().unwrap_or(Value::Null),
+                    "arguments": item.get("arguments").cloned().unwrap_or_else(|| json!("{}")),
+                },
+            })),
+            Some("reasoning") => {
+                for summary in item
+                    .get("summary")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    reasoning.push_str(summary.get("text").and_then(|v| v.as_str()).unwrap_or(""));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut message = json!({
+        "role": "assistant",
+        "content": if content.is_empty() && !tool_calls.is_empty() {
+            Value::Null
+        } else {
+            json!(content)
+        },
+    });
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = json!(reasoning);
+    }
+    if !refusal.is_empty() {
+        message["refusal"] = json!(refusal);
+    }
+    let has_tool_calls = !tool_calls.is_empty();
+    if has_tool_calls {
+        message["tool_calls"] = Value::Array(tool_calls);
+    }
+
+    json!({
+        "id": chat_completion_id(response.get("id").and_then(|v| v.as_str())),
+        "object": "chat.completion",
+        "created": response
+            .get("created_at")
+            .and_then(|v| v.as_i64())
+            .unwrap_or_else(|| chrono::Utc::now().timestamp()),
+        "model": response
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or(fallback_model),
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": finish_reason(response, has_tool_calls),
+            "logprobs": null,
+        }],
+        "usage": chat_usage(response.get("usage")),
+    })
+}
+
+fn chat_completion_id(response_id: Option<&str>) -> String {
+    match response_id {
+        Some(id) => format!("chatcmpl-{}", id.trim_start_matches("resp_")),
+        None => format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+    }
+}
+
+fn finish_reason(response: &Value, has_tool_calls: bool) -> &'static str {
+    if has_tool_calls {
+        return "tool_calls";
+    }
+    match response
+        .pointer("/incomplete_details/reason")
+        .and_then(|v| v.as_str())
+    {
+        Some("max_output_tokens") => "length",
+        Some("content_filter") => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Responses usage → Chat usage
+fn chat_usage(usage: Option<&Value>) -> Value {
+    let prompt_tokens = usage_field(usage, "/input_tokens");
+    let completion_tokens = usage_field(usage, "/output_tokens");
+    let total_tokens = match usage_field(usage, "/total_tokens") {
+        0 => prompt_tokens + completion_tokens,
+        total => total,
+    };
+    json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": total_tokens,
+        "prompt_tokens_details": {
+            "cached_tokens": usage_field(usage, "/input_tokens_details/cached_tokens"),
+        },
+        "completion_tokens_details": {
+            "reasoning_tokens": usage_field(usage, "/output_tokens_details/reasoning_tokens"),
+        },
+    })
+}
+
+/// Responses SSE → chat.completion.chunk 流式转换器
+pub struct ChatStreamTranslator {
+    id: String,
+    model: String,
+    created: i64,
+    include_usage: bool,
+    role_sent: bool,
+    /// function_call 输出项 id → tool_calls 下标
+    tool_indices: HashMap<String, usize>,
+    /// 每个 tool call 是否已经发送过 arguments 增量
+    tool_args_sent: Vec<bool>,
+    finished: bool,
+}
+
+impl ChatStreamTranslator {
+    pub fn new(fallback_model: &str, include_usage: bool) -> Self {
+        Self {
+            id: chat_completion_id(None),
+            model: fallback_model.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            include_usage,
+            role_sent: false,
+            tool_indices: HashMap::new(),
+            tool_args_sent: Vec::new(),
+            finished: false,
+        }
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+                "logprobs": null,
+            }],
+        })
+    }
+
+    fn emit_delta(&mut self, delta: Value, out: &mut Vec<u8>) {
+        if !self.role_sent {
+            self.role_sent = true;
+            write_sse_data(out, &self.chunk(json!({"role": "assistant", "content": ""}), None));
+        }
+        write_sse_data(out, &self.chunk(delta, None));
+    }
+
+    fn capture_response_meta(&mut self, response: Option<&Value>) {
+        let Some(response) = response else {
+            return;
+        };
+        if let Some(id) = response.get("id").and_then(|v| v.as_str()) {
+            self.id = chat_completion_id(Some(id));
+        }
+        if let Some(model) = response.get("model").and_then(|v| v.as_str()) {
+            self.model = model.to_string();
+        }
+        if let Some(created) = response.get("created_at").and_then(|v| v.as_i64()) {
+            self.created = created;
+        }
+    }
+
+    fn tool_index(&self, data: &Value) -> Option<usize> {
+        data.get("item_id")
+            .and_then(|v| v.as_str())
+            .and_then(|id| self.tool_indices.get(id).copied())
+    }
+}
+
+impl StreamTranslator for ChatStreamTranslator {
+    fn on_event(&mut self, event_type: &str, data: &Value, out: &mut Vec<u8>) {
+        if self.finished {
+            return;
+        }
+
+        match event_type {
+            "response.created" | "response.in_progress" => {
+                self.capture_response_meta(data.get("response"));
+                if !self.role_sent {
+                    self.role_sent = true;
+                    write_sse_data(
+                        out,
+                        &self.chunk(json!({"role": "assistant", "content": ""}), None),
+                    );
+                }
+            }
+            "response.output_text.delta" => {
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if !delta.is_empty() {
+                    self.emit_delta(json!({"content": delta}), out);
+                }
+            }
+            "response.reasoning_summary_text.delta" | "response.reasoning_text.delta" => {
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if !delta.is_empty() {
+                    self.emit_delta(json!({"reasoning_content": delta}), out);
+                }
+            }
+            "response.output_item.added" => {
+                let Some(item) = data.get("item") else {
+                    return;
+                };
+                if item.get("type").and_then(|v| v.as_str()) != Some("function_call") {
+                    return;
+                }
+                let index = self.tool_args_sent.len();
+                if let Some(item_id) = item.get("id").and_then(|v| v.as_str()) {
+                    self.tool_indices.insert(item_id.to_string(), index);
+                }
+                self.tool_args_sent.push(false);
+                self.emit_delta(
+                    json!({"tool_calls": [{
+                        "index": index,
+                        "id": item.get("call_id").cloned().unwrap_or(Value::Null),
+                        "type": "function",
+                        "function": {
+                            "name": item.get("name").cloned().unwrap_or(Value::Null),
+                            "arguments": "",
+                        },
+                    }]}),
+                    out,
+                );
+            }
+            "response.function_call_arguments.delta" => {
+                let Some(index) = self.tool_index(data) else {
+                    return;
+                };
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if delta.is_empty() {
+                    return;
+                }
+                self.tool_args_sent[index] = true;
+                self.emit_delta(
+                    json!({"tool_calls": [{"index": index, "function": {"arguments": delta}}]}),
+                    out,
+                );
+            }
+            "response.output_item.done" => {
+                // 上游未发送增量时，用完整的 arguments 补发一次
+                let Some(item) = data.get("item") else {
+                    return;
+                };
+                let Some(index) = item
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| self.tool_indices.get(id).copied())
+                else {
+                    return;
+                };
+                if self.tool_args_sent[index] {
+                    return;
+                }
+                if let Some(arguments) = item
+                    .get("arguments")
+                    .and_then(|v| v.as_str())
+                    .filter(|v| !v.is_empty())
+                {
+                    self.tool_args_sent[index] = true;
+                    self.emit_delta(
+                        json!({"tool_calls": [{"index": index, "function": {"arguments": arguments}}]}),
+                        out,
+                    );
+                }
+            }
+            "response.completed" | "response.incomplete" => {
+                let response = data.get("response").cloned().unwrap_or(Value::Null);
+                self.capture_response_meta(Some(&response));
+                let reason = finish_reason(&response, !self.tool_args_sent.is_empty());
+                write_sse_data(out, &self.chunk(json!({}), Some(reason)));
+                if self.include_usage {
+                    write_sse_data(
+                        out,
+                        &json!({
+                            "id": self.id,
+                            "object": "chat.completion.chunk",
+                            "created": self.created,
+                            "model": self.model,
+                            "choices": [],
+                            "usage": chat_usage(response.get("usage")),
+                        }),
+                    );
+                }
+                out.extend_from_slice(b"data: [DONE]\n\n");
+                self.finished = true;
+            }
+            "response.failed" | "error" => {
+                let message = data
+                    .pointer("/response/error/message")
+                    .or_else(|| data.pointer("/error/message"))
+                    .or_else(|| data.get("message"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Upstream response failed");
+                write_sse_data(
+                    out,
+                    &json!({"error": {"message": message, "type": "upstream_error"}}),
+                );
+                out.extend_from_slice(b"data: [DONE]\n\n");
+                self.finished = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, _out: &mut Vec<u8>) {
+        // 未收到 response.completed 时不补发 [DONE]，让客户端感知到流被截断
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::openai::codex::translator::SseEventParser;
+
+    #[test]
+    fn translate_request_maps_messages_tools_and_format() {
+        let body = Bytes::from(
+            json!({
+                "model": "gpt-5",
+                "messages": [
+                    {"role": "system", "content": "be brief"},
+                    {"role": "user", "content": "weather?"},
+                    {"role": "assistant", "content": null, "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"x\"}"}
+                    }]},
+                    {"role": "tool", "tool_call_id": "call_1", "content": "sunny"}
+                ],
+                "tools": [{"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}],
+                "tool_choice": {"type": "function", "function": {"name": "get_weather"}},
+                "response_format": {"type": "json_object"},
+                "stream": true,
+                "stream_options": {"include_usage": true}
+            })
+            .to_string(),
+        );
+
+        let translated = translate_request(&body).unwrap();
+        assert!(translated.client_stream);
+        assert!(translated.include_usage);
+
+        let v: Value = serde_json::from_slice(&translated.body).unwrap();
+        assert_eq!(v["instructions"], "be brief");
+        assert_eq!(v["stream"], true);
+        assert_eq!(v["input"][0]["content"][0]["type"], "input_text");
+        assert_eq!(v["input"][1]["type"], "function_call");
+        assert_eq!(v["input"][1]["call_id"], "call_1");
+        assert_eq!(v["input"][2]["type"], "function_call_output");
+        assert_eq!(v["tools"][0]["name"], "get_weather");
+        assert_eq!(v["tool_choice"]["name"], "get_weather");
+        assert_eq!(v["text"]["format"]["type"], "json_object");
+    }
+
+    #[test]
+    fn response_to_chat_completion_collects_text_and_tool_calls() {
+        let response = json!({
+            "id": "resp_abc",
+            "model": "gpt-5",
+            "created_at": 1,
+            "status": "completed",
+            "output": [
+                {"type": "message", "content": [{"type": "output_text", "text": "hi"}]},
+                {"type": "function_call", "call_id": "call_1", "name": "f", "arguments": "{}"}
+            ],
+            "usage": {"input_tokens": 3, "output_tokens": 2, "total_tokens": 5}
+        });
+
+        let chat = response_to_chat_completion(&response, "unknown");
+        assert_eq!(chat["id"], "chatcmpl-abc");
+        assert_eq!(chat["choices"][0]["message"]["content"], "hi");
+        assert_eq!(chat["choices"][0]["message"]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(chat["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(chat["usage"]["total_tokens"], 5);
+    }
+
+    #[test]
+    fn stream_translator_emits_chunks_and_done() {
+        let sse = concat!(
+            "event: response.created\n",
+            "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_1\",\"model\":\"gpt-5\"}}\n\n",
+            "event: response.output_text.delta\n",
+            "data: {\"type\":\"response.output_text.delta\",\"delta\":\"你好\"}\n\n",
+            "event: response.completed\n",
+            "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_1\",\"usage\":{\"input_tokens\":1,\"output_tokens\":1}}}\n\n",
+        );
+
+        let mut parser = SseEventParser::default();
+        let mut translator = ChatStreamTranslator::new("gpt-5", true);
+        let mut out = Vec::new();
+        // 按单字节切分，验证多字节字符不会被截断
+        for byte in sse.as_bytes() {
+            for (event_type, data) in parser.feed(std::slice::from_ref(byte)) {
+                translator.on_event(&event_type, &data, &mut out);
+            }
+        }
+
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("\"content\":\"你好\""));
+        assert!(text.contains("\"finish_reason\":\"stop\""));
+        assert!(text.contains("\"prompt_tokens\":1"));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+}
@@ -0,0 +1,823 @@
+//! Anthropic Messages ⇄ Responses 转换
+//!
+//! - 请求：system / content blocks / tool_use / tool_result → Responses input 结构
+//! - 响应：Responses SSE → message_start / content_block_* / message_delta / message_stop，
+//!   或 response.completed → Anthropic message 对象
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use serde_json::{Map, Value, json};
+
+use super::{StreamTranslator, TranslatedRequest, usage_field, write_sse_event};
+
+// ==================== 请求转换 ====================
+
+/// 将 Anthropic Messages 请求体转换为 Responses 请求体
+pub fn translate_request(body: &Bytes) -> Result<TranslatedRequest, String> {
+    let root: Value =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON body: {}", e))?;
+    let obj = root
+        .as_object()
+        .ok_or_else(|| "Request body must be a JSON object".to_string())?;
+    let messages = obj
+        .get("messages")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "`messages` must be an array".to_string())?;
+
+    let mut input: Vec<Value> = Vec::new();
+    for message in messages {
+        let role = message
+            .get("role")
+            .and_then(|v| v.as_str())
+            .unwrap_or("user");
+        match role {
+            "user" => convert_user_message(message.get("content"), &mut input)?,
+            "assistant" => convert_assistant_message(message.get("content"), &mut input),
+            other => return Err(format!("Unsupported message role: {}", other)),
+        }
+    }
+
+    let mut out = Map::new();
+    if let Some(model) = obj.get("model") {
+        out.insert("model".to_string(), model.clone());
+    }
+    let system = blocks_to_text(obj.get("system"));
+    if !system.is_empty() {
+        out.insert("instructions".to_string(), json!(system));
+    }
+    out.insert("input".to_string(), Value::Array(input));
+
+    if let Some(tools) = obj.get("tools").and_then(|v| v.as_array()) {
+        let converted = tools
+            .iter()
+            .map(convert_tool)
+            .collect::<Result<Vec<_>, _>>()?;
+        if !converted.is_empty() {
+            out.insert("tools".to_string(), Value::Array(converted));
+        }
+    }
+    if let Some(choice) = obj.get("tool_choice") {
+        if let Some(converted) = convert_tool_choice(choice) {
+            out.insert("tool_choice".to_string(), converted);
+        }
+        if choice
+            .get("disable_parallel_tool_use")
+            .and_then(|v| v.as_bool())
+            == Some(true)
+        {
+            out.insert("parallel_tool_calls".to_string(), json!(false));
+        }
+    }
+    if let Some(effort) = thinking_effort(obj.get("thinking")) {
+        out.insert(
+            "reasoning".to_string(),
+            json!({"effort": effort, "summary": "auto"}),
+        );
+    }
+    for key in ["temperature", "top_p"] {
+        if let Some(v) = obj.get(key) {
+            out.insert(key.to_string(), v.clone());
+        }
+    }
+    // max_tokens 不转换：Codex 后端不支持 max_output_tokens，
+    // 该参数在转发前总会被移除（见 BUILTIN_UNSUPPORTED_PARAMS），输出长度由上游决定
+
+    let client_stream = obj.get("stream").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    // ChatGPT Codex 后端要求 stream: true 且不存储会话
+    out.insert("stream".to_string(), json!(true));
+    out.insert("store".to_string(), json!(false));
+
+    let body = serde_json::to_vec(&Value::Object(out))
+        .map_err(|e| format!("Failed to serialize translated request: {}", e))?;
+
+    Ok(TranslatedRequest {
+        body: Bytes::from(body),
+        client_stream,
+        include_usage: false,
+    })
+}
+
+/// system / tool_result 等字段可以是字符串或 text block 数组
+fn blocks_to_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|v| v.as_str()).unwrap_or("text") == "text")
+            .filter_map(|b| b.get("text").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// user 消息中的 tool_result 转为 function_call_output，其余 block 合并为一条 user 消息
+fn convert_user_message(content: Option<&Value>, input: &mut Vec<Value>) -> Result<(), String> {
+    let blocks = match content {
+        Some(Value::String(s)) => {
+            input.push(json!({
+                "type": "message",
+                "role": "user",
+                "content": [{"type": "input_text", "text": s}],
+            }));
+            return Ok(());
+        }
+        Some(Value::Array(blocks)) => blocks,
+        _ => return Ok(()),
+    };
+
+    let mut parts = Vec::new();
+    for block in blocks {
+        match block.get("type").and_then(|v| v.as_str()).unwrap_or("text") {
+            "text" => parts.push(json!({
+                "type": "input_text",
+                "text": block.get("text").and_then(|v| v.as_str()).unwrap_or_default(),
+            })),
+            "image" => parts.push(convert_image_block(block)?),
+            "document" => {
+                // 仅支持纯文本文档，其余类型交由上游报错意义不大，直接拒绝
+                let text = block
+                    .pointer("/source/data")
+                    .and_then(|v| v.as_str())
+                    .filter(|_| block.pointer("/source/type") == Some(&json!("text")))
+                    .ok_or_else(|| "Only text document blocks are supported".to_string())?;
+                parts.push(json!({"type": "input_text", "text": text}));
+            }
+            "tool_result" => {
+                let mut output = blocks_to_text(block.get("content"));
+                if block.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+                    output = format!("[tool error] {}", output);
+                }
+                input.push(json!({
+                    "type": "function_call_output",
+                    "call_id": block.get("tool_use_id").cloned().unwrap_or(Value::Null),
+                    "output": output,
+                }));
+            }
+            other => return Err(format!("Unsupported content block type: {}", other)),
+        }
+    }
+
+    if !parts.is_empty() {
+        input.push(json!({"type": "message", "role": "user", "content": parts}));
+    }
+    Ok(())
+}
+
+fn convert_image_block(block: &Value) -> Result<Value, String> {
+    let source = block
+        .get("source")
+        .ok_or_else(|| "Image block is missing `source`".to_string())?;
+    let url = match source.get("type").and_then(|v| v.as_str()) {
+        Some("base64") => format!(
+            "data:{};base64,{}",
+            source
+                .get("media_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("image/png"),
+            source
+                .get("data")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+        ),
+        Some("url") => source
+            .get("url")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        other => return Err(format!("Unsupported image source type: {:?}", other)),
+    };
+    Ok(json!({"type": "input_image", "image_url": url}))
+}
+
+/// assistant 消息：text → output_text，tool_use → function_call；thinking 无法回传给上游，直接丢弃
+fn convert_assistant_message(content: Option<&Value>, input: &mut Vec<Value>) {
+    let blocks = match content {
+        Some(Value::String(s)) => {
+            if !s.is_empty() {
+                input.push(json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "output_text", "text": s}],
+                }));
+            }
+            return;
+        }
+        Some(Value::Array(blocks)) => blocks,
+        _ => return,
+    };
+
+    for block in blocks {
+        match block.get("type").and_then(|v| v.as_str()) {
+            Some("text") => {
+                let text = block
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                if !text.is_empty() {
+                    input.push(json!({
+                        "type": "message",
+                        "role": "assistant",
+                        "content": [{"type": "output_text", "text": text}],
+                    }));
+                }
+            }
+            Some("tool_use") => {
+                let arguments = block
+                    .get("input")
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "{}".to_string());
+                input.push(json!({
+                    "type": "function_call",
+                    "call_id": block.get("id").cloned().unwrap_or(Value::Null),
+                    "name": block.get("name").cloned().unwrap_or(Value::Null),
+                    "arguments": arguments,
+                }));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_tool(tool: &Value) -> Result<Value, String> {
+    // 自定义工具没有 type 或 type 为 custom；web_search 等服务端工具无法映射
+    match tool.get("type").and_then(|v| v.as_str()) {
+        None | Some("custom") => {}
+        Some(other) => return Err(format!("Unsupported tool type: {}", other)),
+    }
+    let mut converted = json!({
+        "type": "function",
+        "name": tool.get("name").cloned().unwrap_or(Value::Null),
+        "parameters": tool
+            .get("input_schema")
+            .cloned()
+            .unwrap_or_else(|| json!({"type": "object", "properties": {}})),
+    });
+    if let Some(description) = tool.get("description") {
+        converted["description"] = description.clone();
+    }
+    Ok(converted)
+}
+
+fn convert_tool_choice(choice: &Value) -> Option<Value> {
+    match choice.get("type").and_then(|v| v.as_str())? {
+        "auto" => Some(json!("auto")),
+        "any" => Some(json!("required")),
+        "none" => Some(json!("none")),
+        "tool" => Some(json!({
+            "type": "function",
+            "name": choice.get("name").cloned().unwrap_or(Value::Null),
+        })),
+        _ => None,
+    }
+}
+
+/// thinking.budget_tokens → reasoning.effort
+fn thinking_effort(thinking: Option<&Value>) -> Option<&'static str> {
+    let thinking = thinking?;
+    if thinking.get("type").and_then(|v| v.as_str()) != Some("enabled") {
+        return None;
+    }
+    let budget = thinking
+        .get("budget_tokens")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+    Some(if budget < 4096 {
+        "low"
+    } else if budget < 16384 {
+        "medium"
+    } else {
+        "high"
+    })
+}
+
+// ==================== 响应转换 ====================
+
+/// 将 Responses 完整响应对象转换为 Anthropic message
+pub fn response_to_message(response: &Value, fallback_model: &str) -> Value {
+    if let Some(error) = response.get("error").filter(|e| {
+        !e.is_null()
+            && (response.get("output").is_none()
+                || response.get("status") == Some(&json!("failed")))
+    }) {
+        return error_body(
+            "api_error",
+            error
+                .get("message")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Upstream response failed"),
+        );
+    }
+
+    let mut content = Vec::new();
+    let mut has_tool_use = false;
+
+    for item in response
+        .get("output")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("reasoning") => {
+                let thinking: String = item
+                    .get("summary")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.get("text").and_then(|v| v.as_str()))
+                    .collect();
+                if !thinking.is_empty() {
+                    content
+                        .push(json!({"type": "thinking", "thinking": thinking, "signature": ""}));
+                }
+            }
+            Some("message") => {
+                for part in item
+                    .get("content")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let text = match part.get("type").and_then(|v| v.as_str()) {
+                        Some("output_text") => part.get("text"),
+                        Some("refusal") => part.get("refusal"),
+                        _ => None,
+                    };
+                    if let Some(text) = text.and_then(|v| v.as_str()) {
+                        content.push(json!({"type": "text", "text": text}));
+                    }
+                }
+            }
+            Some("function_call") => {
+                has_tool_use = true;
+                content.push(json!({
+                    "type": "tool_use",
+                    "id": item.get("call_id").cloned().unwrap_or(Value::Null),
+                    "name": item.get("name").cloned().unwrap_or(Value::Null),
+                    "input": parse_arguments(item.get("arguments").and_then(|v| v.as_str())),
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    json!({
+        "id": message_id(response.get("id").and_then(|v| v.as_str())),
+        "type": "message",
+        "role": "assistant",
+        "model": response
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or(fallback_model),
+        "content": content,
+        "stop_reason": stop_reason(response, has_tool_use),
+        "stop_sequence": null,
+        "usage": message_usage(response.get("usage")),
+    })
+}
+
+fn error_body(error_type: &str, message: &str) -> Value {
+    json!({
+        "type": "error",
+        "error": {"type": error_type, "message": message},
+    })
+}
+
+/// 将上游非 2xx 的错误响应体转换为 Anthropic 错误体，错误类型按状态码归类
+pub fn error_response(status: u16, body: &[u8]) -> Value {
+    let error_type = match status {
+        400 | 422 => "invalid_request_error",
+        401 => "authentication_error",
+        402 => "billing_error",
+        403 => "permission_error",
+        404 => "not_found_error",
+        413 => "request_too_large",
+        429 => "rate_limit_error",
+        504 => "timeout_error",
+        529 => "overloaded_error",
+        _ => "api_error",
+    };
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|root| {
+            ["/error/message", "/message", "/detail"]
+                .iter()
+                .find_map(|pointer| root.pointer(pointer).and_then(|v| v.as_str()))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        })
+        .or_else(|| {
+            let text = String::from_utf8_lossy(body).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        })
+        .unwrap_or_else(|| format!("Upstream returned HTTP {}", status));
+    error_body(error_type, &message)
+}
+
+fn parse_arguments(arguments: Option<&str>) -> Value {
+    arguments
+        .filter(|s| !s.trim().is_empty())
+        .and_then(|s| serde_json::from_str::<Value>(s).ok())
+        .unwrap_or_else(|| json!({}))
+}
+
+fn message_id(response_id: Option<&str>) -> String {
+    match response_id {
+        Some(id) => format!("msg_{}", id.trim_start_matches("resp_")),
+        None => format!("msg_{}", uuid::Uuid::new_v4().simple()),
+    }
+}
+
+fn stop_reason(response: &Value, has_tool_use: bool) -> &'static str {
+    if has_tool_use {
+        return "tool_use";
+    }
+    match response
+        .pointer("/incomplete_details/reason")
+        .and_then(|v| v.as_str())
+    {
+        Some("max_output_tokens") => "max_tokens",
+        _ => "end_turn",
+    }
+}
+
+/// Responses usage → Anthropic usage（Anthropic 的 input_tokens 不含缓存命中部分）
+fn message_usage(usage: Option<&Value>) -> Value {
+    let input_tokens = usage_field(usage, "/input_tokens");
+    let cached_tokens = usage_field(usage, "/input_tokens_details/cached_tokens");
+    json!({
+        "input_tokens": (input_tokens - cached_tokens).max(0),
+        "output_tokens": usage_field(usage, "/output_tokens"),
+        "cache_read_input_tokens": cached_tokens,
+        "cache_creation_input_tokens": 0,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Text,
+    Thinking,
+    ToolUse,
+}
+
+/// Responses SSE → Anthropic Messages SSE 流式转换器
+pub struct ClaudeStreamTranslator {
+    id: String,
+    model: String,
+    message_started: bool,
+    /// 当前打开的 content block (index, 类型)
+    open_block: Option<(usize, BlockKind)>,
+    next_index: usize,
+    /// function_call 输出项 id → content block 下标
+    tool_blocks: HashMap<String, usize>,
+    /// 已经发送过 arguments 增量的 tool block
+    tool_args_sent: HashMap<usize, bool>,
+    has_tool_use: bool,
+    finished: bool,
+}
+
+impl ClaudeStreamTranslator {
+    pub fn new(fallback_model: &str) -> Self {
+        Self {
+            id: message_id(None),
+            model: fallback_model.to_string(),
+            message_started: false,
+            open_block: None,
+            next_index: 0,
+            tool_blocks: HashMap::new(),
+            tool_args_sent: HashMap::new(),
+            has_tool_use: false,
+            finished: false,
+        }
+    }
+
+    fn capture_response_meta(&mut self, response: Option<&Value>) {
+        let Some(response) = response else {
+            return;
+        };
+        if let Some(id) = response.get("id").and_then(|v| v.as_str()) {
+            self.id = message_id(Some(id));
+        }
+        if let Some(model) = response.get("model").and_then(|v| v.as_str()) {
+            self.model = model.to_string();
+        }
+    }
+
+    fn ensure_message_start(&mut self, out: &mut Vec<u8>) {
+        if self.message_started {
+            return;
+        }
+        self.message_started = true;
+        write_sse_event(
+            out,
+            "message_start",
+            &json!({
+                "type": "message_start",
+                "message": {
+                    "id": self.id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": self.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0},
+                },
+            }),
+        );
+    }
+
+    fn close_block(&mut self, out: &mut Vec<u8>) {
+        if let Some((index, _)) = self.open_block.take() {
+            write_sse_event(
+                out,
+                "content_block_stop",
+                &json!({"type": "content_block_stop", "index": index}),
+            );
+        }
+    }
+
+    /// 确保当前打开的是指定类型的 block，必要时关闭旧 block 并开启新 block
+    fn ensure_block(&mut self, kind: BlockKind, content_block: Value, out: &mut Vec<u8>) -> usize {
+        self.ensure_message_start(out);
+        // tool_use 每次都开启新 block，文本/思考连续增量复用当前 block
+        if let Some((index, _)) = self
+            .open_block
+            .filter(|(_, open_kind)| *open_kind == kind && kind != BlockKind::ToolUse)
+        {
+            return index;
+        }
+        self.close_block(out);
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open_block = Some((index, kind));
+        write_sse_event(
+            out,
+            "content_block_start",
+            &json!({
+                "type": "content_block_start",
+                "index": index,
+                "content_block": content_block,
+            }),
+        );
+        index
+    }
+
+    fn emit_block_delta(&self, index: usize, delta: Value, out: &mut Vec<u8>) {
+        write_sse_event(
+            out,
+            "content_block_delta",
+            &json!({"type": "content_block_delta", "index": index, "delta": delta}),
+        );
+    }
+
+    fn emit_tool_arguments(&mut self, index: usize, partial_json: &str, out: &mut Vec<u8>) {
+        self.tool_args_sent.insert(index, true);
+        self.emit_block_delta(
+            index,
+            json!({"type": "input_json_delta", "partial_json": partial_json}),
+            out,
+        );
+    }
+}
+
+impl StreamTranslator for ClaudeStreamTranslator {
+    fn on_event(&mut self, event_type: &str, data: &Value, out: &mut Vec<u8>) {
+        if self.finished {
+            return;
+        }
+
+        match event_type {
+            "response.created" | "response.in_progress" => {
+                self.capture_response_meta(data.get("response"));
+                self.ensure_message_start(out);
+            }
+            "response.output_text.delta" => {
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if delta.is_empty() {
+                    return;
+                }
+                let index =
+                    self.ensure_block(BlockKind::Text, json!({"type": "text", "text": ""}), out);
+                self.emit_block_delta(index, json!({"type": "text_delta", "text": delta}), out);
+            }
+            "response.reasoning_summary_text.delta" | "response.reasoning_text.delta" => {
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if delta.is_empty() {
+                    return;
+                }
+                let index = self.ensure_block(
+                    BlockKind::Thinking,
+                    json!({"type": "thinking", "thinking": ""}),
+                    out,
+                );
+                self.emit_block_delta(
+                    index,
+                    json!({"type": "thinking_delta", "thinking": delta}),
+                    out,
+                );
+            }
+            "response.output_item.added" => {
+                let Some(item) = data.get("item") else {
+                    return;
+                };
+                if item.get("type").and_then(|v| v.as_str()) != Some("function_call") {
+                    return;
+                }
+                self.has_tool_use = true;
+                let index = self.ensure_block(
+                    BlockKind::ToolUse,
+                    json!({
+                        "type": "tool_use",
+                        "id": item.get("call_id").cloned().unwrap_or(Value::Null),
+                        "name": item.get("name").cloned().unwrap_or(Value::Null),
+                        "input": {},
+                    }),
+                    out,
+                );
+                if let Some(item_id) = item.get("id").and_then(|v| v.as_str()) {
+                    self.tool_blocks.insert(item_id.to_string(), index);
+                }
+            }
+            "response.function_call_arguments.delta" => {
+                let Some(index) = data
+                    .get("item_id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| self.tool_blocks.get(id).copied())
+                else {
+                    return;
+                };
+                let delta = data.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+                if !delta.is_empty() {
+                    self.emit_tool_arguments(index, delta, out);
+                }
+            }
+            "response.output_item.done" => {
+                // 上游未发送增量时，用完整的 arguments 补发一次
+                let Some(item) = data.get("item") else {
+                    return;
+                };
+                let Some(index) = item
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| self.tool_blocks.get(id).copied())
+                else {
+                    return;
+                };
+                if self.tool_args_sent.get(&index).copied().unwrap_or(false) {
+                    return;
+                }
+                if let Some(arguments) = item
+                    .get("arguments")
+                    .and_then(|v| v.as_str())
+                    .filter(|v| !v.is_empty())
+                {
+                    self.emit_tool_arguments(index, arguments, out);
+                }
+            }
+            "response.completed" | "response.incomplete" => {
+                let response = data.get("response").cloned().unwrap_or(Value::Null);
+                self.ensure_message_start(out);
+                self.close_block(out);
+                write_sse_event(
+                    out,
+                    "message_delta",
+                    &json!({
+                        "type": "message_delta",
+                        "delta": {
+                            "stop_reason": stop_reason(&response, self.has_tool_use),
+                            "stop_sequence": null,
+                        },
+                        "usage": message_usage(response.get("usage")),
+                    }),
+                );
+                write_sse_event(out, "message_stop", &json!({"type": "message_stop"}));
+                self.finished = true;
+            }
+            "response.failed" | "error" => {
+                let message = data
+                    .pointer("/response/error/message")
+                    .or_else(|| data.pointer("/error/message"))
+                    .or_else(|| data.get("message"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Upstream response failed");
+                write_sse_event(out, "error", &error_body("api_error", message));
+                self.finished = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, _out: &mut Vec<u8>) {
+        // 未收到 response.completed 时不补发 message_stop，让客户端感知到流被截断
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::openai::codex::translator::SseEventParser;
+
+    #[test]
+    fn translate_request_maps_system_blocks_and_tool_results() {
+        let body = Bytes::from(
+            json!({
+                "model": "gpt-5",
+                "system": [{"type": "text", "text": "be brief"}],
+                "max_tokens": 1024,
+                "messages": [
+                    {"role": "user", "content": "weather?"},
+                    {"role": "assistant", "content": [
+                        {"type": "text", "text": "checking"},
+                        {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "x"}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_1", "content": "sunny"}
+                    ]}
+                ],
+                "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}],
+                "tool_choice": {"type": "any"},
+                "stream": true
+            })
+            .to_string(),
+        );
+
+        let translated = translate_request(&body).unwrap();
+        assert!(translated.client_stream);
+
+        let v: Value = serde_json::from_slice(&translated.body).unwrap();
+        assert_eq!(v["instructions"], "be brief");
+        assert_eq!(v["input"][1]["content"][0]["text"], "checking");
+        assert_eq!(v["input"][2]["type"], "function_call");
+        assert_eq!(v["input"][2]["arguments"], "{\"city\":\"x\"}");
+        assert_eq!(v["input"][3]["type"], "function_call_output");
+        assert_eq!(v["input"][3]["call_id"], "toolu_1");
+        assert_eq!(v["tools"][0]["parameters"]["type"], "object");
+        assert!(v.get("max_output_tokens").is_none());
+        assert_eq!(v["tool_choice"], "required");
+    }
+
+    #[test]
+    fn stream_translator_emits_anthropic_events_with_usage() {
+        let sse = concat!(
+            "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_1\",\"model\":\"gpt-5\"}}\n\n",
+            "data: {\"type\":\"response.output_text.delta\",\"delta\":\"hi\"}\n\n",
+            "data: {\"type\":\"response.output_item.added\",\"item\":{\"type\":\"function_call\",\"id\":\"fc_1\",\"call_id\":\"call_1\",\"name\":\"f\"}}\n\n",
+            "data: {\"type\":\"response.function_call_arguments.delta\",\"item_id\":\"fc_1\",\"delta\":\"{}\"}\n\n",
+            "data: {\"type\":\"response.completed\",\"response\":{\"usage\":{\"input_tokens\":10,\"output_tokens\":4,\"input_tokens_details\":{\"cached_tokens\":6}}}}\n\n",
+        );
+
+        let mut parser = SseEventParser::default();
+        let mut translator = ClaudeStreamTranslator::new("gpt-5");
+        let mut out = Vec::new();
+        for (event_type, data) in parser.feed(sse.as_bytes()) {
+            translator.on_event(&event_type, &data, &mut out);
+        }
+
+        let text = String::from_utf8(out).unwrap();
+        let events: Vec<&str> = text
+            .lines()
+            .filter_map(|l| l.strip_prefix("event: "))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert!(text.contains("\"stop_reason\":\"tool_use\""));
+        assert!(text.contains("\"input_tokens\":4"));
+        assert!(text.contains("\"cache_read_input_tokens\":6"));
+    }
+
+    #[test]
+    fn response_to_message_builds_content_blocks() {
+        let response = json!({
+            "id": "resp_abc",
+            "model": "gpt-5",
+            "output": [
+                {"type": "message", "content": [{"type": "output_text", "text": "hi"}]}
+            ],
+            "incomplete_details": {"reason": "max_output_tokens"},
+            "usage": {"input_tokens": 3, "output_tokens": 2}
+        });
+
+        let message = response_to_message(&response, "unknown");
+        assert_eq!(message["id"], "msg_abc");
+        assert_eq!(message["content"][0]["text"], "hi");
+        assert_eq!(message["stop_reason"], "max_tokens");
+        assert_eq!(message["usage"]["output_tokens"], 2);
+    }
+}
@@ -0,0 +1,222 @@
+//! 协议转换层
+//!
+//! 将非 Responses 协议的客户端请求转换为 ChatGPT Codex 后端可接受的 Responses 请求，
+//! 并将上游 Responses SSE 事件还原为客户端期望的响应格式。
+//! 目前支持 OpenAI Chat Completions 与 Anthropic Messages 两种客户端协议。
+
+pub mod chat;
+pub mod claude;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+/// 客户端使用的协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientProtocol {
+    /// 原生 Responses API（透传）
+    Responses,
+    /// OpenAI Chat Completions API
+    ChatCompletions,
+    /// Anthropic Messages API
+    ClaudeMessages,
+}
+
+impl ClientProtocol {
+    /// 根据请求格式（infer_request_format 的结果）确定客户端协议
+    pub fn from_format(format: &str) -> Self {
+        match format {
+            "openai-chat" => Self::ChatCompletions,
+            "claude" => Self::ClaudeMessages,
+            _ => Self::Responses,
+        }
+    }
+
+    /// 是否需要做协议转换
+    pub fn needs_translation(&self) -> bool {
+        !matches!(self, Self::Responses)
+    }
+}
+
+/// 转换后的请求
+#[derive(Debug, Clone)]
+pub struct TranslatedRequest {
+    /// Responses 格式的请求体（stream 已强制为 true）
+    pub body: Bytes,
+    /// 客户端是否要求流式响应
+    pub client_stream: bool,
+    /// 流式响应结束时是否附带 usage 块（仅 Chat Completions）
+    pub include_usage: bool,
+}
+
+/// 将客户端请求体转换为 Responses 请求体
+pub fn translate_request(protocol: ClientProtocol, body: &Bytes) -> Result<TranslatedRequest, String> {
+    match protocol {
+        ClientProtocol::Responses => Err("Responses requests do not need translation".to_string()),
+        ClientProtocol::ChatCompletions => chat::translate_request(body),
+        ClientProtocol::ClaudeMessages => claude::translate_request(body),
+    }
+}
+
+/// 将上游路径中的客户端端点替换为 Responses 端点
+pub fn responses_path_for(protocol: ClientProtocol, path: &str) -> String {
+    let suffix = match protocol {
+        ClientProtocol::Responses => return path.to_string(),
+        ClientProtocol::ChatCompletions => "/chat/completions",
+        ClientProtocol::ClaudeMessages => "/messages",
+    };
+    match path.strip_suffix(suffix) {
+        Some(prefix) => format!("{}/responses", prefix),
+        None => path.to_string(),
+    }
+}
+
+/// 将 response.completed 中的完整 Responses 对象转换为客户端的非流式响应
+pub fn translate_completed_response(
+    protocol: ClientProtocol,
+    response: &Value,
+    fallback_model: &str,
+) -> Value {
+    match protocol {
+        ClientProtocol::Responses => response.clone(),
+        ClientProtocol::ChatCompletions => {
+            chat::response_to_chat_completion(response, fallback_model)
+        }
+        ClientProtocol::ClaudeMessages => claude::response_to_message(response, fallback_model),
+    }
+}
+
+/// 将上游非 2xx 的错误响应体转换为客户端协议的错误格式（无需转换时返回 None）
+pub fn translate_error_body(protocol: ClientProtocol, status: u16, body: &[u8]) -> Option<Bytes> {
+    match protocol {
+        ClientProtocol::ClaudeMessages => {
+            Some(Bytes::from(claude::error_response(status, body).to_string()))
+        }
+        // 上游错误体本身就是 OpenAI 风格
+        ClientProtocol::Responses | ClientProtocol::ChatCompletions => None,
+    }
+}
+
+/// 为流式响应创建事件转换器（Responses 协议返回 None，表示原样透传）
+pub fn new_stream_translator(
+    protocol: ClientProtocol,
+    request: &TranslatedRequest,
+    fallback_model: &str,
+) -> Option<Box<dyn StreamTranslator>> {
+    match protocol {
+        ClientProtocol::Responses => None,
+        ClientProtocol::ChatCompletions => Some(Box::new(chat::ChatStreamTranslator::new(
+            fallback_model,
+            request.include_usage,
+        ))),
+        ClientProtocol::ClaudeMessages => {
+            Some(Box::new(claude::ClaudeStreamTranslator::new(fallback_model)))
+        }
+    }
+}
+
+/// 流式响应转换器：逐个消费上游 Responses SSE 事件，输出客户端协议的 SSE 字节
+pub trait StreamTranslator: Send {
+    /// 处理一个上游事件，将需要发给客户端的内容追加到 out
+    fn on_event(&mut self, event_type: &str, data: &Value, out: &mut Vec<u8>);
+
+    /// 上游流结束，输出收尾内容
+    fn finish(&mut self, out: &mut Vec<u8>);
+}
+
+/// 按字节缓冲的 SSE 事件解析器。
+/// 与 SseMetricsExtractor 不同，这里保留完整的 UTF-8 字节序列，避免多字节字符被 chunk 边界截断。
+#[derive(Default)]
+pub struct SseEventParser {
+    pending: Vec<u8>,
+}
+
+impl SseEventParser {
+    /// 输入一个 chunk，返回其中完整的事件 (event_type, data)
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<(String, Value)> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some((idx, sep_len)) = find_event_boundary(&self.pending) {
+            let block: Vec<u8> = self.pending.drain(..idx + sep_len).collect();
+            if let Some(event) = parse_event_block(&block[..idx]) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    /// 上游结束时解析残留的最后一个事件
+    pub fn finish(&mut self) -> Vec<(String, Value)> {
+        let block = std::mem::take(&mut self.pending);
+        parse_event_block(&block).into_iter().collect()
+    }
+}
+
+fn find_event_boundary(buf: &[u8]) -> Option<(usize, usize)> {
+    let lf = buf.windows(2).position(|w| w == b"\n\n").map(|i| (i, 2));
+    let crlf = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| (i, 4));
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(if a.0 <= b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+fn parse_event_block(block: &[u8]) -> Option<(String, Value)> {
+    let text = String::from_utf8_lossy(block);
+    let mut event_type: Option<String> = None;
+    let mut data_lines = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix("event:") {
+            event_type = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start());
+        }
+    }
+
+    if data_lines.is_empty() {
+        return None;
+    }
+    let data = data_lines.join("\n");
+    if data.trim() == "[DONE]" {
+        return None;
+    }
+    let value = serde_json::from_str::<Value>(&data).ok()?;
+
+    // Responses 事件的 data 中自带 type 字段，优先使用
+    let event_type = value
+        .get("type")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .or(event_type)
+        .unwrap_or_default();
+
+    Some((event_type, value))
+}
+
+/// 以 `data: {json}\n\n` 的形式写入一个 SSE 事件
+pub(crate) fn write_sse_data(out: &mut Vec<u8>, value: &Value) {
+    out.extend_from_slice(b"data: ");
+    if let Ok(json) = serde_json::to_vec(value) {
+        out.extend_from_slice(&json);
+    }
+    out.extend_from_slice(b"\n\n");
+}
+
+/// 以 `event: name\ndata: {json}\n\n` 的形式写入一个具名 SSE 事件
+pub(crate) fn write_sse_event(out: &mut Vec<u8>, event: &str, value: &Value) {
+    out.extend_from_slice(b"event: ");
+    out.extend_from_slice(event.as_bytes());
+    out.extend_from_slice(b"\n");
+    write_sse_data(out, value);
+}
+
+/// 从 Responses usage 对象中读取整数字段
+pub(crate) fn usage_field(usage: Option<&Value>, pointer: &str) -> i64 {
+    usage
+        .and_then(|u| u.pointer(pointer))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0)
+}
@@ -6,6 +6,10 @@ fn default_reverse_proxy_enabled() -> bool {
     true
 }
 
+fn default_pool_weight() -> u32 {
+    1
+}
+
 /// 账号类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -50,6 +54,15 @@ pub struct Account {
     pub email: String,
     #[serde(default = "default_reverse_proxy_enabled")]
     pub reverse_proxy_enabled: bool,
+    /// Codex 号池优先级层级，数值越小越优先（Priority 策略）
+    #[serde(default)]
+    pub pool_priority: i32,
+    /// Codex 号池同层内的选择权重（Priority 策略）
+    #[serde(default = "default_pool_weight")]
+    pub pool_weight: u32,
+    /// 仅用于这些模型的请求（支持 `*` 结尾的前缀匹配），为空表示不限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pool_reserved_models: Vec<String>,
     /// 账号类型
     #[serde(default)]
     pub account_type: AccountType,
@@ -124,6 +137,17 @@ impl SyncableAccount for Account {
         "openai"
     }
 
+    fn machine_maintained_fields() -> &'static [&'static str] {
+        &[
+            "token",
+            "quota",
+            "last_used",
+            "openai_auth_json",
+            "rt_invalid",
+            "rt_invalid_reason",
+        ]
+    }
+
     fn merge_missing_fields(&mut self, source: &Self) {
         if self.quota.is_none() && source.quota.is_some() {
             self.quota = source.quota.clone();
@@ -156,6 +180,9 @@ impl Account {
             id,
             email,
             reverse_proxy_enabled: true,
+            pool_priority: 0,
+            pool_weight: default_pool_weight(),
+            pool_reserved_models: Vec::new(),
             account_type: AccountType::OAuth,
             token: Some(token),
             api_config: None,
@@ -184,6 +211,9 @@ impl Account {
             id,
             email,
             reverse_proxy_enabled: true,
+            pool_priority: 0,
+            pool_weight: default_pool_weight(),
+            pool_reserved_models: Vec::new(),
             account_type: AccountType::API,
             token: None,
             api_config: Some(api_config),
@@ -294,6 +324,9 @@ mod tests {
 
         let account: Account = serde_json::from_value(legacy).unwrap();
         assert!(account.reverse_proxy_enabled);
+        assert_eq!(account.pool_priority, 0);
+        assert_eq!(account.pool_weight, 1);
+        assert!(account.pool_reserved_models.is_empty());
     }
 
     #[test]
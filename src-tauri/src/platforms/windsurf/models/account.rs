@@ -117,6 +117,10 @@ impl SyncableAccount for Account {
     fn platform_name() -> &'static str {
         "windsurf"
     }
+
+    fn machine_maintained_fields() -> &'static [&'static str] {
+        &["token", "quota", "last_used"]
+    }
 }
 
 impl Account {
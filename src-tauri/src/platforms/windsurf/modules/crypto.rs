@@ -227,8 +227,7 @@ pub mod platform {
     }
 
     /// Windows DPAPI 加密
-    #[allow(dead_code)]
-    fn dpapi_encrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    pub(crate) fn dpapi_encrypt(data: &[u8]) -> Result<Vec<u8>, String> {
         use std::ptr;
         use windows::Win32::Foundation::{HLOCAL, LocalFree};
         use windows::Win32::Security::Cryptography::{
@@ -270,7 +269,7 @@ pub mod platform {
         }
     }
 
-    fn dpapi_decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    pub(crate) fn dpapi_decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
         use std::ptr;
         use windows::Win32::Foundation::{HLOCAL, LocalFree};
         use windows::Win32::Security::Cryptography::{
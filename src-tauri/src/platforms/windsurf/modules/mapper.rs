@@ -1,4 +1,7 @@
 use crate::data::storage::common::{AccountDbMapper, StorageError};
+use crate::database::sync_crypto::{
+    decrypt_optional_secret, decrypt_secret, encrypt_optional_secret, encrypt_secret,
+};
 use crate::platforms::windsurf::models::{Account, QuotaData, TokenData};
 use tokio_postgres::Row;
 
@@ -21,13 +24,13 @@ impl AccountDbMapper<Account> for WindsurfAccountMapper {
             email: email.clone(),
             name: row.get(2),
             token: TokenData {
-                access_token: row.get(3),
-                refresh_token: row.get(4),
+                access_token: decrypt_secret(row.get(3))?,
+                refresh_token: decrypt_secret(row.get(4))?,
                 expiry_timestamp: row.get(5),
                 email: Some(email),
                 user_id: row.get(6),
             },
-            api_key: row.get(7),
+            api_key: decrypt_optional_secret(row.get(7))?,
             api_server_url: row.get(8),
             quota,
             tag: row.get(15),
@@ -41,7 +44,7 @@ impl AccountDbMapper<Account> for WindsurfAccountMapper {
             version: row.get(18),
             deleted: false,
             auth_provider: row.get(19),
-            devin_auth1_token: row.get(20),
+            devin_auth1_token: decrypt_optional_secret(row.get(20))?,
             devin_account_id: row.get(21),
             devin_primary_org_id: row.get(22),
         })
@@ -101,11 +104,11 @@ impl AccountDbMapper<Account> for WindsurfAccountMapper {
             Box::new(account.id.clone()),
             Box::new(account.email.clone()),
             Box::new(account.name.clone()),
-            Box::new(account.token.access_token.clone()),
-            Box::new(account.token.refresh_token.clone()),
+            Box::new(encrypt_secret(&account.token.access_token)),
+            Box::new(encrypt_secret(&account.token.refresh_token)),
             Box::new(account.token.expiry_timestamp),
             Box::new(account.token.user_id.clone()),
-            Box::new(account.api_key.clone()),
+            Box::new(encrypt_optional_secret(&account.api_key)),
             Box::new(account.api_server_url.clone()),
             Box::new(quota_json),
             Box::new(account.tag.clone()),
@@ -119,7 +122,7 @@ impl AccountDbMapper<Account> for WindsurfAccountMapper {
             Box::new(version),
             Box::new(account.deleted),
             Box::new(account.auth_provider.clone()),
-            Box::new(account.devin_auth1_token.clone()),
+            Box::new(encrypt_optional_secret(&account.devin_auth1_token)),
             Box::new(account.devin_account_id.clone()),
             Box::new(account.devin_primary_org_id.clone()),
         ]
@@ -982,6 +982,46 @@ export default {
       chatId: 'Enter User ID or Group Chat ID'
     }
   },
+  securityConfig: {
+    title: 'Security',
+    masterKey: 'Master Key',
+    unlocked: 'Unlocked',
+    locked: 'Locked',
+    notEnabled: 'Not enabled',
+    keyringBackend: 'The master key is stored in the system secret store.',
+    fileBackend: 'The system secret store is unavailable. The master key is protected by a passphrase that must be entered after each start. Database, proxy, Telegram and Raindrop secrets cannot be saved until it is unlocked.',
+    keyringError: 'Secret store error',
+    retryKeyring: 'Retry System Secret Store',
+    unlock: 'Unlock',
+    setPassphrase: 'Set Passphrase',
+    changePassphrase: 'Change Passphrase',
+    setPassphraseHelp: 'Set a master passphrase (at least 8 characters) to enable saving secrets on this device.',
+    changePassphraseHelp: 'Enter the current passphrase and a new one (at least 8 characters).',
+    storageEncryption: 'Encrypt Local Storage',
+    storageEncryptionHelp: 'Encrypt local account files with the master key',
+    syncPassphrase: 'Sync Passphrase',
+    syncPassphraseHelp: 'Encrypts account tokens before they are written to the sync database. Every device needs the same passphrase.',
+    enableSyncEncryption: 'Enable',
+    databaseRequired: 'Configure the database connection first',
+    placeholders: {
+      masterPassphrase: 'Enter master passphrase',
+      currentPassphrase: 'Current passphrase',
+      newPassphrase: 'New passphrase',
+      syncPassphrase: 'Enter sync passphrase'
+    },
+    messages: {
+      unlocked: 'Master key unlocked',
+      unlockFailed: 'Failed to unlock',
+      retryFailed: 'System secret store unavailable',
+      passphraseSaved: 'Master passphrase saved',
+      passphraseFailed: 'Failed to save master passphrase',
+      storageEncryptionSaved: 'Local storage encryption updated',
+      storageEncryptionFailed: 'Failed to update local storage encryption',
+      syncPassphraseSaved: 'Sync passphrase saved',
+      syncPassphraseFailed: 'Failed to save sync passphrase',
+      lockedOnStartup: 'The master key is locked. Open Settings > Security to unlock it or set a passphrase.'
+    }
+  },
   credit: {
     title: 'Credit Usage Statistics',
     viewUsage: 'View Usage Details',
@@ -1759,6 +1799,8 @@ export default {
         strategySingleDesc: 'Use a fixed account',
         strategySmart: 'Smart',
         strategySmartDesc: 'Weighted random by quota & expiry',
+        strategyPriority: 'Priority',
+        strategyPriorityDesc: 'Primary tier first, weighted within a tier',
         selectAccount: 'Select Account',
         noMatchingAccounts: 'No matching accounts',
         totalAccounts: 'Total Accounts',
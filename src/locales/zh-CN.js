@@ -984,6 +984,46 @@ export default {
       chatId: '请输入 User ID 或 Group Chat ID'
     }
   },
+  securityConfig: {
+    title: '安全',
+    masterKey: '主密钥',
+    unlocked: '已解锁',
+    locked: '已锁定',
+    notEnabled: '未启用',
+    keyringBackend: '主密钥保存在系统密钥库中。',
+    fileBackend: '系统密钥库不可用，主密钥由口令保护，每次启动后需要输入口令解锁。解锁前无法保存数据库、代理、Telegram 和 Raindrop 的密钥。',
+    keyringError: '密钥库错误',
+    retryKeyring: '重试系统密钥库',
+    unlock: '解锁',
+    setPassphrase: '设置口令',
+    changePassphrase: '更换口令',
+    setPassphraseHelp: '设置主密钥口令（至少 8 个字符）后即可在本机保存密钥。',
+    changePassphraseHelp: '输入当前口令和新口令（至少 8 个字符）。',
+    storageEncryption: '加密本地存储',
+    storageEncryptionHelp: '用主密钥加密本地账号文件',
+    syncPassphrase: '同步口令',
+    syncPassphraseHelp: '账号令牌写入同步数据库前先加密，每台设备都需要输入同一口令。',
+    enableSyncEncryption: '启用',
+    databaseRequired: '请先配置数据库连接',
+    placeholders: {
+      masterPassphrase: '输入主密钥口令',
+      currentPassphrase: '当前口令',
+      newPassphrase: '新口令',
+      syncPassphrase: '输入同步口令'
+    },
+    messages: {
+      unlocked: '主密钥已解锁',
+      unlockFailed: '解锁失败',
+      retryFailed: '系统密钥库不可用',
+      passphraseSaved: '主密钥口令已保存',
+      passphraseFailed: '保存主密钥口令失败',
+      storageEncryptionSaved: '本地存储加密设置已更新',
+      storageEncryptionFailed: '更新本地存储加密设置失败',
+      syncPassphraseSaved: '同步口令已保存',
+      syncPassphraseFailed: '保存同步口令失败',
+      lockedOnStartup: '主密钥已锁定，请在 设置 > 安全 中解锁或设置口令。'
+    }
+  },
   credit: {
     title: 'Credit 使用统计',
     viewUsage: '查看使用详情',
@@ -1752,6 +1792,8 @@ export default {
         strategySingleDesc: '固定使用指定账号',
         strategySmart: '智能',
         strategySmartDesc: '按配额和到期时间加权随机分配',
+        strategyPriority: '分层',
+        strategyPriorityDesc: '优先使用主力层级，层内按权重分配',
         selectAccount: '选择账号',
         noMatchingAccounts: '无匹配账号',
         totalAccounts: '总账号',